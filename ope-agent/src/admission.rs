@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use ope::{Context, Enforcer, Error, Matcher, PolicyStore, Request};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionReview {
+    pub api_version: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<AdmissionRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<AdmissionResponse>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionRequest {
+    pub uid: String,
+    #[serde(default)]
+    pub kind: GroupVersionKind,
+    #[serde(default)]
+    pub resource: GroupVersionResource,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sub_resource: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub namespace: String,
+    pub operation: String,
+    #[serde(default)]
+    pub user_info: UserInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_object: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GroupVersionKind {
+    #[serde(default)]
+    pub group: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GroupVersionResource {
+    #[serde(default)]
+    pub group: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub resource: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserInfo {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub uid: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionResponse {
+    pub uid: String,
+    pub allowed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub audit_annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Status {
+    pub code: u16,
+    pub message: String,
+}
+
+pub struct AdmissionWebhook<M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+}
+
+impl<M, S> AdmissionWebhook<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self { enforcer }
+    }
+
+    pub fn request(&self, admission: &AdmissionRequest) -> Request {
+        let group = match admission.resource.group.as_str() {
+            "" => "core",
+            group => group,
+        };
+        let mut resource = format!("{group}/{}", admission.resource.resource);
+        if !admission.sub_resource.is_empty() {
+            resource = format!("{resource}/{}", admission.sub_resource);
+        }
+
+        let mut context = Context::new();
+        context.insert("namespace", admission.namespace.clone());
+        context.insert("groups", admission.user_info.groups.clone());
+        if !admission.user_info.uid.is_empty() {
+            context.insert("uid", admission.user_info.uid.clone());
+        }
+        context.insert("kind", admission.kind.kind.clone());
+        context.insert("dryRun", admission.dry_run.unwrap_or_default());
+        if let Some(object) = &admission.object {
+            context.insert("object", object.clone());
+        }
+        if let Some(object) = &admission.old_object {
+            context.insert("oldObject", object.clone());
+        }
+
+        Request {
+            resource: format!("{resource}:{}:{}", admission.namespace, admission.name),
+            action: admission.operation.to_ascii_lowercase(),
+            subject: admission.user_info.username.clone(),
+            context,
+        }
+    }
+}
+
+impl<M: Matcher, S: PolicyStore> AdmissionWebhook<M, S> {
+    pub fn admit(&self, admission: &AdmissionRequest) -> AdmissionResponse {
+        let input = self.request(admission);
+        let result = self.enforcer.is_allowed(&input);
+        let status = match &result {
+            Ok(()) => None,
+            Err(err @ (Error::Deny { .. } | Error::NotMatched { .. })) => Some(Status {
+                code: 403,
+                message: err.to_string(),
+            }),
+            Err(err) => {
+                tracing::error!("admit {:?} failed: {}", input, err);
+                Some(Status {
+                    code: 500,
+                    message: err.to_string(),
+                })
+            }
+        };
+        let obligations = match &result {
+            Ok(()) | Err(Error::Deny { .. }) => {
+                let decision = self.enforcer.explain(&input);
+                match (&decision.result, &result) {
+                    (Ok(()), Ok(())) | (Err(Error::Deny { .. }), Err(_)) => decision.obligations(),
+                    _ => BTreeMap::new(),
+                }
+            }
+            Err(_) => BTreeMap::new(),
+        };
+        let audit_annotations = obligations
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect();
+        AdmissionResponse {
+            uid: admission.uid.clone(),
+            allowed: status.is_none(),
+            status,
+            audit_annotations,
+        }
+    }
+
+    pub fn review(&self, review: AdmissionReview) -> AdmissionReview {
+        let response = review.request.as_ref().map(|request| self.admit(request));
+        AdmissionReview {
+            api_version: review.api_version,
+            kind: review.kind,
+            request: None,
+            response,
+        }
+    }
+
+    pub fn review_json(&self, body: &[u8]) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(&self.review(serde_json::from_slice(body)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ope::{PolicyBuilder, Regexp};
+    use serde_json::json;
+
+    use super::*;
+
+    fn review(username: &str, operation: &str, namespace: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "apiVersion": "admission.k8s.io/v1",
+            "kind": "AdmissionReview",
+            "request": {
+                "uid": "705ab4f5-6393-11e8-b7cc-42010a800002",
+                "kind": {"group": "apps", "version": "v1", "kind": "Deployment"},
+                "resource": {"group": "apps", "version": "v1", "resource": "deployments"},
+                "name": "web",
+                "namespace": namespace,
+                "operation": operation,
+                "userInfo": {"username": username, "groups": ["system:authenticated"]},
+                "object": {"metadata": {"labels": {"team": "payments"}}},
+                "dryRun": false
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn admission() {
+        let policy = PolicyBuilder::new("deployers")
+            .allow()
+            .subject("system:serviceaccount:ci:<.*>")
+            .actions(["create", "update"])
+            .resource("apps/deployments:<staging|prod>:<.*>")
+            .obligation("ope/policy", "deployers")
+            .build()
+            .unwrap();
+        let webhook = AdmissionWebhook::new(Arc::new(Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy],
+        )));
+
+        let allowed: Value = serde_json::from_slice(
+            &webhook
+                .review_json(&review(
+                    "system:serviceaccount:ci:deployer",
+                    "CREATE",
+                    "prod",
+                ))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(allowed["apiVersion"], "admission.k8s.io/v1");
+        assert_eq!(
+            allowed["response"]["uid"],
+            "705ab4f5-6393-11e8-b7cc-42010a800002"
+        );
+        assert_eq!(allowed["response"]["allowed"], true);
+        assert_eq!(
+            allowed["response"]["auditAnnotations"]["ope/policy"],
+            "deployers"
+        );
+        assert!(allowed.get("request").is_none());
+
+        let request: AdmissionReview =
+            serde_json::from_slice(&review("alice", "DELETE", "prod")).unwrap();
+        let input = webhook.request(request.request.as_ref().unwrap());
+        assert_eq!(input.resource, "apps/deployments:prod:web");
+        assert_eq!(input.action, "delete");
+        assert_eq!(input.context.get_str("kind").unwrap(), "Deployment");
+        let response = webhook.review(request).response.unwrap();
+        assert!(!response.allowed);
+        assert_eq!(response.status.unwrap().code, 403);
+
+        let denied: Value = serde_json::from_slice(
+            &webhook
+                .review_json(&review(
+                    "system:serviceaccount:ci:deployer",
+                    "CREATE",
+                    "dev",
+                ))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(denied["response"]["allowed"], false);
+        assert!(denied["response"]["status"]["message"].is_string());
+        assert!(webhook.review_json(b"{").is_err());
+    }
+}
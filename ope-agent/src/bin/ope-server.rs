@@ -0,0 +1,68 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use ope::{Enforcer, FileStore, Regexp};
+use ope_agent::{ExtAuthz, HttpAuthz};
+
+const USAGE: &str = "usage: ope-server <policy-dir> [--listen <addr>] [--http-listen <addr>] \
+    [--subject-header <name>] [--trusted-proxies <n>] [--cache-size <n>]";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut dir = None;
+    let mut listen: SocketAddr = "0.0.0.0:9191".parse()?;
+    let mut http_listen: Option<SocketAddr> = None;
+    let mut subject_header = None;
+    let mut trusted_proxies = 0;
+    let mut cache_size = 1024;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().ok_or(USAGE)?.parse()?,
+            "--http-listen" => http_listen = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--subject-header" => subject_header = Some(args.next().ok_or(USAGE)?),
+            "--trusted-proxies" => trusted_proxies = args.next().ok_or(USAGE)?.parse()?,
+            "--cache-size" => cache_size = args.next().ok_or(USAGE)?.parse()?,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if dir.is_none() && !arg.starts_with('-') => dir = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let store = FileStore::open(dir.ok_or(USAGE)?)?;
+    let _watcher = store.watch()?;
+    let enforcer = Enforcer::with_store(Regexp::new(cache_size)?, store);
+    enforcer.warm()?;
+    let enforcer = Arc::new(enforcer);
+    if let Some(addr) = http_listen {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let mut handler = HttpAuthz::new(enforcer.clone()).with_trusted_proxies(trusted_proxies);
+        if let Some(header) = &subject_header {
+            handler = handler.with_subject_header(header);
+        }
+        println!("ope-server http ext_authz listening on {addr}");
+        tokio::spawn(async move {
+            if let Err(err) = handler.serve(listener).await {
+                eprintln!("http ext_authz server stopped: {err}");
+            }
+        });
+    }
+    let mut service = ExtAuthz::new(enforcer);
+    if let Some(header) = subject_header {
+        service = service.with_subject_header(header);
+    }
+    let service = service.into_service();
+
+    println!("ope-server listening on {listen}");
+    tonic::transport::Server::builder()
+        .add_service(service)
+        .serve_with_shutdown(listen, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
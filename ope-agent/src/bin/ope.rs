@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use ope::{Context, Enforcer, Fixture, Policy, Regexp, Request, Severity};
+use ope_agent::cli;
+use serde_json::Value;
+
+#[derive(Parser)]
+#[command(name = "ope", about = "Test, lint and format ope policies")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Check {
+        #[arg(short, long = "policies", required = true)]
+        policies: Vec<PathBuf>,
+        subject: String,
+        action: String,
+        resource: String,
+        #[arg(short, long = "context", value_parser = cli::parse_context)]
+        context: Vec<(String, Value)>,
+        #[arg(long)]
+        explain: bool,
+    },
+    Test {
+        #[arg(short, long = "policies", required = true)]
+        policies: Vec<PathBuf>,
+        #[arg(required = true)]
+        fixtures: Vec<PathBuf>,
+    },
+    Lint {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    Fmt {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        #[arg(long)]
+        check: bool,
+    },
+    Schema,
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse().command) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(command: Command) -> ope::Result<bool> {
+    match command {
+        Command::Check {
+            policies,
+            subject,
+            action,
+            resource,
+            context,
+            explain,
+        } => {
+            let enforcer = Enforcer::new(Regexp::new(256)?, cli::load(&policies)?);
+            let input = Request {
+                resource,
+                action,
+                subject,
+                context: context.into_iter().collect::<Context>(),
+            };
+            let decision = enforcer.explain(&input);
+            if explain {
+                for policy in decision.policies.iter() {
+                    println!("{}: {:?}", policy.id, policy.effect);
+                    for statement in policy.statements.iter() {
+                        println!("  {statement:?}");
+                    }
+                }
+            }
+            match &decision.result {
+                Ok(()) => println!("allowed"),
+                Err(err) => println!("denied: {err}"),
+            }
+            Ok(decision.is_allowed())
+        }
+        Command::Test { policies, fixtures } => {
+            let enforcer = Enforcer::new(Regexp::new(256)?, cli::load(&policies)?);
+            let (mut total, mut failed) = (0, 0);
+            for (path, format) in cli::policy_files(&fixtures)? {
+                let fixture = Fixture::parse(&fs::read_to_string(&path)?, format)?;
+                for (i, outcome) in fixture.run(&enforcer).iter().enumerate() {
+                    total += 1;
+                    if outcome.passed {
+                        continue;
+                    }
+                    failed += 1;
+                    let case = outcome.case;
+                    let name = if case.name.is_empty() {
+                        format!("case {i}")
+                    } else {
+                        case.name.clone()
+                    };
+                    let actual = match &outcome.decision {
+                        Ok(()) => "allowed".to_owned(),
+                        Err(err) => format!("denied: {err}"),
+                    };
+                    println!(
+                        "FAIL {}: {name}: {} {} {} expected {:?}, got {actual}",
+                        path.display(),
+                        case.subject,
+                        case.action,
+                        case.resource,
+                        case.expect
+                    );
+                }
+            }
+            println!("{} passed, {failed} failed", total - failed);
+            Ok(failed == 0)
+        }
+        Command::Lint {
+            paths,
+            deny_warnings,
+        } => {
+            let mut passed = true;
+            for (path, format) in cli::policy_files(&paths)? {
+                for policy in cli::parse(&path, format)? {
+                    for diagnostic in policy.lint() {
+                        if diagnostic.severity == Severity::Error || deny_warnings {
+                            passed = false;
+                        }
+                        println!(
+                            "{}: {:?} {:?} {}/{}: {}",
+                            path.display(),
+                            diagnostic.severity,
+                            diagnostic.code,
+                            policy.id,
+                            diagnostic.path,
+                            diagnostic.message
+                        );
+                    }
+                }
+            }
+            Ok(passed)
+        }
+        Command::Fmt { paths, check } => {
+            let mut formatted = true;
+            for (path, format) in cli::policy_files(&paths)? {
+                let content = fs::read_to_string(&path)?;
+                let canonical = cli::canonical(&content, format)?;
+                if canonical == content {
+                    continue;
+                }
+                if check {
+                    println!("{} is not formatted", path.display());
+                    formatted = false;
+                } else {
+                    fs::write(&path, canonical)?;
+                    println!("formatted {}", path.display());
+                }
+            }
+            Ok(formatted)
+        }
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&Policy::json_schema())?);
+            Ok(true)
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ope::{Error, Format, Policy, Result};
+use serde_json::{json, Value};
+
+pub fn policy_files(paths: &[PathBuf]) -> Result<Vec<(PathBuf, Format)>> {
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        if path.is_dir() {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(path)? {
+                let path = entry?.path();
+                if let (true, Some(format)) = (path.is_file(), Format::from_path(&path)) {
+                    entries.push((path, format));
+                }
+            }
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            files.extend(entries);
+        } else {
+            files.push((path.clone(), format_of(path)?));
+        }
+    }
+    Ok(files)
+}
+
+pub fn load(paths: &[PathBuf]) -> Result<Vec<Policy>> {
+    let (mut ids, mut policies) = (BTreeSet::new(), Vec::new());
+    for (path, format) in policy_files(paths)? {
+        for policy in parse(&path, format)? {
+            if !ids.insert(policy.id.clone()) {
+                return Err(Error::PolicyAlreadyExists(policy.id));
+            }
+            policies.push(policy);
+        }
+    }
+    Ok(policies)
+}
+
+pub fn parse(path: &Path, format: Format) -> Result<Vec<Policy>> {
+    Policy::parse_all(&fs::read_to_string(path)?, format)
+}
+
+pub fn parse_context(pair: &str) -> Result<(String, Value), String> {
+    let (key, value) = pair
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got {pair}"))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+    Ok((key.to_owned(), value))
+}
+
+pub fn canonical(content: &str, format: Format) -> Result<String> {
+    let policies = Policy::parse_all(content, format)?;
+    let document = match serde_json::from_str(&format.to_json(content)?)? {
+        Value::Array(_) => serde_json::to_value(&policies)?,
+        Value::Object(document)
+            if document.contains_key("policies") && !document.contains_key("id") =>
+        {
+            json!({ "policies": policies })
+        }
+        _ => serde_json::to_value(&policies[0])?,
+    };
+    #[allow(unreachable_patterns)]
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(&document)? + "\n"),
+        Format::Yaml => Ok(serde_yaml::to_string(&document)?),
+        _ => Err(unsupported(format)),
+    }
+}
+
+fn format_of(path: &Path) -> Result<Format> {
+    Format::from_path(path).ok_or_else(|| {
+        Error::IoError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported policy file {}", path.display()),
+        ))
+    })
+}
+
+fn unsupported(format: Format) -> Error {
+    Error::IoError(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("cannot format {format:?} policies"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_and_context() {
+        let raw = r#"{"statements": [{"resources": ["articles:<.*>"], "actions": ["get"], "subjects": ["peter"], "effect": "Allow",
+            "conditions": {"ip": {"type": "CIDR", "options": {"cidr": ["10.0.0.0/8"]}}}, "meta": null}],
+            "id": "a", "meta": {"owner": "cms"}}"#;
+        let formatted = canonical(raw, Format::Json).unwrap();
+        assert_eq!(canonical(&formatted, Format::Json).unwrap(), formatted);
+        assert!(formatted.starts_with("{\n  \"description\": \"\",\n"));
+        let yaml = canonical(&formatted, Format::Yaml).unwrap();
+        assert_eq!(canonical(&yaml, Format::Yaml).unwrap(), yaml);
+        assert_eq!(
+            serde_json::to_value(Policy::parse_all(&yaml, Format::Yaml).unwrap()).unwrap(),
+            serde_json::to_value(Policy::parse_all(raw, Format::Json).unwrap()).unwrap()
+        );
+        let set = canonical(&format!(r#"{{"policies": [{raw}]}}"#), Format::Json).unwrap();
+        assert!(set.starts_with("{\n  \"policies\": ["));
+        assert!(canonical(
+            r#"{"id": "", "statements": [], "meta": null}"#,
+            Format::Json
+        )
+        .is_err());
+
+        assert_eq!(
+            parse_context("count=5").unwrap(),
+            ("count".to_owned(), json!(5))
+        );
+        assert_eq!(
+            parse_context("ip=10.0.0.1").unwrap(),
+            ("ip".to_owned(), json!("10.0.0.1"))
+        );
+        assert!(parse_context("ip").is_err());
+    }
+}
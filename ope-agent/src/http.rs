@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderName, HeaderValue, CONTENT_TYPE, HOST};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use ope::{Context, Enforcer, Error, Matcher, PolicyStore, Request};
+use serde_json::Value;
+use tokio::net::TcpListener;
+
+pub struct HttpAuthz<M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+    subject_header: Option<String>,
+    path_prefix: String,
+    trusted_proxies: usize,
+}
+
+impl<M, S> HttpAuthz<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self {
+            enforcer,
+            subject_header: None,
+            path_prefix: String::new(),
+            trusted_proxies: 0,
+        }
+    }
+
+    pub fn with_subject_header(mut self, header: impl Into<String>) -> Self {
+        self.subject_header = Some(header.into().to_ascii_lowercase());
+        self
+    }
+
+    pub fn with_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefix = prefix.into();
+        self
+    }
+
+    pub fn with_trusted_proxies(mut self, trusted_proxies: usize) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    pub fn request<B>(&self, req: &hyper::Request<B>) -> Request {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned)
+        };
+        let path = req.uri().path();
+        let resource = match path.strip_prefix(self.path_prefix.as_str()) {
+            Some(rest) if !self.path_prefix.is_empty() && rest.starts_with('/') => rest,
+            _ => path,
+        };
+
+        let mut context = Context::new();
+        let peer = req
+            .extensions()
+            .get::<SocketAddr>()
+            .map(|addr| addr.ip().to_string());
+        let client = match self.trusted_proxies {
+            0 => peer,
+            trusted => {
+                let forwarded = header("x-forwarded-for").unwrap_or_default();
+                let hops: Vec<_> = forwarded
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .collect();
+                hops.get(hops.len().saturating_sub(trusted))
+                    .map(|v| (*v).to_owned())
+                    .or(peer)
+            }
+        };
+        if let Some(address) = client {
+            context.insert("clientIP", address);
+        }
+        let host = header(HOST.as_str()).or_else(|| req.uri().host().map(str::to_owned));
+        if let Some(host) = host {
+            context.insert("host", host);
+        }
+        let headers: serde_json::Map<String, Value> = req
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| {
+                let value = v.to_str().ok()?.to_owned();
+                Some((k.to_string(), Value::String(value)))
+            })
+            .collect();
+        context.insert("headers", headers);
+
+        Request {
+            resource: resource.to_owned(),
+            action: req.method().as_str().to_ascii_lowercase(),
+            subject: self
+                .subject_header
+                .as_deref()
+                .and_then(header)
+                .unwrap_or_default(),
+            context,
+        }
+    }
+}
+
+impl<M: Matcher, S: PolicyStore> HttpAuthz<M, S> {
+    pub fn respond<B>(&self, req: &hyper::Request<B>) -> Response<String> {
+        let input = self.request(req);
+        let result = self.enforcer.is_allowed(&input);
+        let (status, body) = match &result {
+            Ok(()) => (StatusCode::OK, ""),
+            Err(Error::Deny { .. } | Error::NotMatched { .. }) => {
+                (StatusCode::FORBIDDEN, "forbidden")
+            }
+            Err(err) => {
+                tracing::error!("check {:?} failed: {}", input, err);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+            }
+        };
+        let obligations = match &result {
+            Ok(()) | Err(Error::Deny { .. }) => {
+                let decision = self.enforcer.explain(&input);
+                match (&decision.result, &result) {
+                    (Ok(()), Ok(())) | (Err(Error::Deny { .. }), Err(_)) => decision.obligations(),
+                    _ => BTreeMap::new(),
+                }
+            }
+            Err(_) => BTreeMap::new(),
+        };
+        let mut response = Response::new(body.to_owned());
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        for (key, value) in obligations {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            match (
+                HeaderName::try_from(key.as_str()),
+                HeaderValue::try_from(value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => tracing::warn!("obligation {} is not a valid header", key),
+            }
+        }
+        response
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()>
+    where
+        M: Send + Sync + 'static,
+        S: Send + Sync + 'static,
+    {
+        let handler = Arc::new(self);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |mut req: hyper::Request<Incoming>| {
+                    req.extensions_mut().insert(peer);
+                    let response = handler.respond(&req).map(Full::<Bytes>::from);
+                    async move { Ok::<_, Infallible>(response) }
+                });
+                if let Err(err) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::warn!("serve ext_authz connection failed: {}", err);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ope::{PolicyBuilder, Regexp};
+
+    use super::*;
+    use crate::SUBJECT_HEADER;
+
+    fn check(subject: &str, method: &str, path: &str) -> hyper::Request<()> {
+        hyper::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(HOST, "api.local")
+            .header(SUBJECT_HEADER, subject)
+            .header("x-forwarded-for", "10.0.0.7, 172.16.0.1")
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn authorize() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("<peter|ken>")
+            .action("get")
+            .resource("/articles/<[0-9]+>")
+            .condition(
+                "clientIP",
+                "CIDR",
+                serde_json::json!({"cidr": ["10.0.0.0/8"]}),
+            )
+            .obligation("x-ope-tier", "gold")
+            .obligation("x-ope-mask", ["ssn"])
+            .build()
+            .unwrap();
+        let enforcer = Arc::new(Enforcer::new(Regexp::new(16).unwrap(), vec![policy]));
+        let direct = HttpAuthz::new(enforcer.clone());
+        let mut req = check("ken", "GET", "/articles/1");
+        assert_eq!(direct.request(&req).subject, "");
+        assert!(direct.request(&req).context.get("clientIP").is_none());
+        req.extensions_mut()
+            .insert(SocketAddr::from(([192, 168, 0, 9], 4711)));
+        assert_eq!(
+            direct.request(&req).context.get_str("clientIP").unwrap(),
+            "192.168.0.9"
+        );
+        let one = HttpAuthz::new(enforcer.clone()).with_trusted_proxies(1);
+        assert_eq!(
+            one.request(&req).context.get_str("clientIP").unwrap(),
+            "172.16.0.1"
+        );
+        let server = HttpAuthz::new(enforcer)
+            .with_subject_header(SUBJECT_HEADER)
+            .with_path_prefix("/authz")
+            .with_trusted_proxies(2);
+
+        let input = server.request(&check("ken", "GET", "/authz/articles/1?page=2"));
+        assert_eq!(input.subject, "ken");
+        assert_eq!(input.action, "get");
+        assert_eq!(input.resource, "/articles/1");
+        assert_eq!(input.context.get_str("clientIP").unwrap(), "10.0.0.7");
+        assert_eq!(input.context.get_str("host").unwrap(), "api.local");
+        assert_eq!(
+            server.request(&check("ken", "GET", "/authzx")).resource,
+            "/authzx"
+        );
+
+        let allowed = server.respond(&check("peter", "GET", "/authz/articles/1"));
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(allowed.headers()["x-ope-tier"], "gold");
+        assert_eq!(allowed.headers()["x-ope-mask"], r#"["ssn"]"#);
+
+        let denied = server.respond(&check("max", "GET", "/authz/articles/1"));
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        assert!(denied.headers().get("x-ope-tier").is_none());
+        assert_eq!(denied.body(), "forbidden");
+        assert_eq!(
+            server
+                .respond(&check("peter", "DELETE", "/authz/articles/1"))
+                .status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
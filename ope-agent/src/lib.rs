@@ -1,3 +1,30 @@
+#[cfg(feature = "admission")]
+mod admission;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "server")]
+mod http;
+mod middleware;
+#[cfg(feature = "server")]
+mod server;
+
+#[cfg(feature = "admission")]
+pub use admission::{
+    AdmissionRequest, AdmissionResponse, AdmissionReview, AdmissionWebhook, GroupVersionKind,
+    GroupVersionResource, Status, UserInfo,
+};
+#[cfg(feature = "actix")]
+pub use middleware::actix::{OpeMiddleware, OpeMiddlewareService};
+#[cfg(feature = "axum")]
+pub use middleware::axum::{OpeLayer, OpeService};
+#[cfg(feature = "async-graphql")]
+pub use middleware::graphql::{GraphqlSubject, OpeExtension};
+pub use middleware::{Extractors, Guard, Rejection, Subject};
+#[cfg(feature = "server")]
+pub use {http::HttpAuthz, server::ExtAuthz};
+
+pub const SUBJECT_HEADER: &str = "x-ope-subject";
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
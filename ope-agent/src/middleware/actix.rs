@@ -0,0 +1,157 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use ope::{Enforcer, Matcher, PolicyStore};
+
+use super::{extractors, Extractors, Guard, Rejection, Subject};
+
+impl Default for Extractors<HttpRequest> {
+    fn default() -> Self {
+        extractors(
+            |req| req.method().as_str(),
+            |req| req.path(),
+            |req| req.extensions().get::<Subject>().map(|s| s.0.clone()),
+        )
+    }
+}
+
+impl Rejection {
+    fn into_http_response(self) -> HttpResponse {
+        let mut response = HttpResponse::build(
+            StatusCode::from_u16(self.status()).unwrap_or(StatusCode::FORBIDDEN),
+        );
+        match self {
+            Rejection::Unauthenticated => response.finish(),
+            Rejection::Forbidden(reason) | Rejection::Internal(reason) => response.body(reason),
+        }
+    }
+}
+
+pub struct OpeMiddleware<M, S> {
+    guard: Guard<HttpRequest, M, S>,
+}
+
+impl<M, S> OpeMiddleware<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self::with_extractors(enforcer, Extractors::default())
+    }
+
+    pub fn with_extractors(
+        enforcer: Arc<Enforcer<M, S>>,
+        extractors: Extractors<HttpRequest>,
+    ) -> Self {
+        Self {
+            guard: Guard::with_extractors(enforcer, extractors),
+        }
+    }
+}
+
+impl<I, B, M, S> Transform<I, ServiceRequest> for OpeMiddleware<M, S>
+where
+    I: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+    M: Matcher + 'static,
+    S: PolicyStore + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = OpeMiddlewareService<I, M, S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, inner: I) -> Self::Future {
+        ready(Ok(OpeMiddlewareService {
+            inner: Rc::new(inner),
+            guard: self.guard.clone(),
+        }))
+    }
+}
+
+pub struct OpeMiddlewareService<I, M, S> {
+    inner: Rc<I>,
+    guard: Guard<HttpRequest, M, S>,
+}
+
+impl<I, B, M, S> Service<ServiceRequest> for OpeMiddlewareService<I, M, S>
+where
+    I: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+    M: Matcher,
+    S: PolicyStore,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(inner);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        match self.guard.check(req.request()) {
+            Ok(input) => {
+                req.extensions_mut().insert(input);
+                let inner = self.inner.clone();
+                Box::pin(async move { Ok(inner.call(req).await?.map_into_left_body()) })
+            }
+            Err(rejection) => {
+                let response = req
+                    .into_response(rejection.into_http_response())
+                    .map_into_right_body();
+                Box::pin(async move { Ok(response) })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+    use ope::{PolicyBuilder, Regexp};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn middleware() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("/articles/<[0-9]+>")
+            .build()
+            .unwrap();
+        let enforcer = Arc::new(Enforcer::new(Regexp::new(16).unwrap(), vec![policy]));
+        let app = test::init_service(
+            App::new()
+                .wrap(OpeMiddleware::new(enforcer))
+                .route("/articles/{id}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let status = |subject: Option<&'static str>, path: &'static str| {
+            let req = test::TestRequest::get()
+                .uri(path)
+                .insert_header((crate::SUBJECT_HEADER, "peter"))
+                .to_request();
+            if let Some(subject) = subject {
+                req.extensions_mut().insert(Subject(subject.to_owned()));
+            }
+            let app = &app;
+            async move { test::call_service(app, req).await.status() }
+        };
+        assert_eq!(status(Some("peter"), "/articles/1").await, StatusCode::OK);
+        assert_eq!(
+            status(Some("max"), "/articles/1").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(Some("peter"), "/articles/x").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(None, "/articles/1").await, StatusCode::UNAUTHORIZED);
+    }
+}
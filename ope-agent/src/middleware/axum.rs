@@ -0,0 +1,173 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::Body;
+use axum::http::{request::Parts, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use ope::Enforcer;
+use tower::{Layer, Service};
+
+use super::{extractors, Extractors, Guard, Rejection, Subject};
+
+impl Default for Extractors<Parts> {
+    fn default() -> Self {
+        extractors(
+            |parts| parts.method.as_str(),
+            |parts| parts.uri.path(),
+            |parts| parts.extensions.get::<Subject>().map(|s| s.0.clone()),
+        )
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::FORBIDDEN);
+        match self {
+            Rejection::Unauthenticated => status.into_response(),
+            Rejection::Forbidden(reason) | Rejection::Internal(reason) => {
+                (status, reason).into_response()
+            }
+        }
+    }
+}
+
+pub struct OpeLayer<M, S> {
+    guard: Guard<Parts, M, S>,
+}
+
+impl<M, S> Clone for OpeLayer<M, S> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+        }
+    }
+}
+
+impl<M, S> OpeLayer<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self::with_extractors(enforcer, Extractors::default())
+    }
+
+    pub fn with_extractors(enforcer: Arc<Enforcer<M, S>>, extractors: Extractors<Parts>) -> Self {
+        Self {
+            guard: Guard::with_extractors(enforcer, extractors),
+        }
+    }
+}
+
+impl<I, M, S> Layer<I> for OpeLayer<M, S> {
+    type Service = OpeService<I, M, S>;
+
+    fn layer(&self, inner: I) -> Self::Service {
+        OpeService {
+            inner,
+            guard: self.guard.clone(),
+        }
+    }
+}
+
+pub struct OpeService<I, M, S> {
+    inner: I,
+    guard: Guard<Parts, M, S>,
+}
+
+impl<I: Clone, M, S> Clone for OpeService<I, M, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            guard: self.guard.clone(),
+        }
+    }
+}
+
+impl<I, M, S> Service<Request<Body>> for OpeService<I, M, S>
+where
+    I: Service<Request<Body>, Response = Response> + Send + 'static,
+    I::Future: Send + 'static,
+    M: ope::Matcher,
+    S: ope::PolicyStore,
+{
+    type Response = Response;
+    type Error = I::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, I::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let (mut parts, body) = req.into_parts();
+        match self.guard.check(&parts) {
+            Ok(input) => {
+                parts.extensions.insert(input);
+                Box::pin(self.inner.call(Request::from_parts(parts, body)))
+            }
+            Err(rejection) => Box::pin(async move { Ok(rejection.into_response()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use ope::{PolicyBuilder, Regexp};
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn layer() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("/articles/<[0-9]+>")
+            .build()
+            .unwrap();
+        let enforcer = Arc::new(Enforcer::new(Regexp::new(16).unwrap(), vec![policy]));
+        let handler = service_fn(|req: Request<Body>| async move {
+            let subject = req
+                .extensions()
+                .get::<ope::Request>()
+                .unwrap()
+                .subject
+                .clone();
+            Ok::<_, Infallible>(subject.into_response())
+        });
+        let service = OpeLayer::new(enforcer.clone()).layer(handler);
+        let forged = Request::get("/articles/1")
+            .header(crate::SUBJECT_HEADER, "peter")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            service.clone().oneshot(forged).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let status = |subject: Option<&str>, path: &str| {
+            let mut req = Request::get(path);
+            if let Some(subject) = subject {
+                req = req.extension(Subject(subject.to_owned()));
+            }
+            let service = service.clone();
+            let req = req.body(Body::empty()).unwrap();
+            async move { service.oneshot(req).await.unwrap().status() }
+        };
+        assert_eq!(status(Some("peter"), "/articles/1").await, StatusCode::OK);
+        assert_eq!(
+            status(Some("max"), "/articles/1").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(None, "/articles/1").await, StatusCode::UNAUTHORIZED);
+
+        let extractors = Extractors::default()
+            .subject(|_: &Parts| Some("peter".to_owned()))
+            .resource(|parts: &Parts| Some(format!("/articles/{}", parts.uri.query()?)));
+        let service = OpeLayer::with_extractors(enforcer, extractors).layer(handler);
+        let req = Request::get("/?7").body(Body::empty()).unwrap();
+        assert_eq!(service.oneshot(req).await.unwrap().status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo,
+};
+use async_graphql::{PathSegment, QueryPathSegment, ServerError, ServerResult, Value};
+use ope::{Context, Enforcer, Error, Matcher, PolicyStore, Request};
+
+#[derive(Debug, Clone)]
+pub struct GraphqlSubject(pub String);
+
+pub struct OpeExtension<M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+}
+
+impl<M, S> OpeExtension<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self { enforcer }
+    }
+}
+
+impl<M, S> ExtensionFactory for OpeExtension<M, S>
+where
+    M: Matcher + Send + Sync + 'static,
+    S: PolicyStore + Send + Sync + 'static,
+{
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(FieldAuthz {
+            enforcer: self.enforcer.clone(),
+            state: Mutex::default(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct State {
+    operation: Option<String>,
+    fields: HashMap<String, String>,
+    decisions: HashMap<String, Result<(), String>>,
+}
+
+struct FieldAuthz<M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+    state: Mutex<State>,
+}
+
+impl<M: Matcher, S: PolicyStore> FieldAuthz<M, S> {
+    fn authorize(&self, ctx: &ExtensionContext<'_>, info: &ResolveInfo<'_>) -> Result<(), String> {
+        let subject = ctx
+            .data_opt::<GraphqlSubject>()
+            .ok_or_else(|| "unauthenticated".to_owned())?;
+
+        let mut keys = Vec::new();
+        let mut node = Some(info.path_node);
+        while let Some(current) = node {
+            if let QueryPathSegment::Name(name) = current.segment {
+                keys.push(name);
+            }
+            node = current.parent;
+        }
+        keys.reverse();
+        let key = keys.join(".");
+        let parent = keys[..keys.len().saturating_sub(1)].join(".");
+
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let action = state
+            .operation
+            .get_or_insert_with(|| info.parent_type.to_ascii_lowercase())
+            .clone();
+        let resource = match state.fields.get(&parent) {
+            Some(parent) => format!("{parent}.{}", info.name),
+            None => info.name.to_owned(),
+        };
+        state.fields.insert(key, resource.clone());
+        if let Some(decision) = state.decisions.get(&resource) {
+            return decision.clone();
+        }
+
+        let input = Request {
+            resource: resource.clone(),
+            action,
+            subject: subject.0.clone(),
+            context: ctx.data_opt::<Context>().cloned().unwrap_or_default(),
+        };
+        let decision = match self.enforcer.is_allowed(&input) {
+            Ok(()) => Ok(()),
+            Err(err @ (Error::Deny { .. } | Error::NotMatched { .. })) => Err(err.to_string()),
+            Err(err) => {
+                tracing::error!("check {:?} failed: {}", input, err);
+                Err(err.to_string())
+            }
+        };
+        state.decisions.insert(resource, decision.clone());
+        decision
+    }
+}
+
+#[async_graphql::async_trait::async_trait]
+impl<M, S> Extension for FieldAuthz<M, S>
+where
+    M: Matcher + Send + Sync + 'static,
+    S: PolicyStore + Send + Sync + 'static,
+{
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if !info.is_for_introspection {
+            self.authorize(ctx, &info).map_err(|reason| {
+                let mut path = Vec::new();
+                let mut node = Some(info.path_node);
+                while let Some(current) = node {
+                    path.push(match current.segment {
+                        QueryPathSegment::Name(name) => PathSegment::Field(name.to_owned()),
+                        QueryPathSegment::Index(index) => PathSegment::Index(index),
+                    });
+                    node = current.parent;
+                }
+                path.reverse();
+                ServerError {
+                    path,
+                    ..ServerError::new(reason, None)
+                }
+            })?;
+        }
+        next.run(ctx, info).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+    use ope::{PolicyBuilder, Regexp};
+
+    use super::*;
+
+    #[derive(SimpleObject)]
+    struct User {
+        name: String,
+        email: String,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn users(&self) -> Vec<Option<User>> {
+            ["peter", "ken"]
+                .into_iter()
+                .map(|name| {
+                    Some(User {
+                        name: name.to_owned(),
+                        email: format!("{name}@example.com"),
+                    })
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn field_authz() {
+        let policy = PolicyBuilder::new("users")
+            .allow()
+            .subject("<.*>")
+            .action("query")
+            .resources(["users", "users.name"])
+            .allow()
+            .subject("admin")
+            .action("query")
+            .resource("users.email")
+            .build()
+            .unwrap();
+        let enforcer = Arc::new(Enforcer::new(Regexp::new(16).unwrap(), vec![policy]));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(OpeExtension::new(enforcer))
+            .finish();
+        let query = "{ users { name mail: email } }";
+
+        let response = schema
+            .execute(async_graphql::Request::new(query).data(GraphqlSubject("admin".to_owned())))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let response = schema
+            .execute(
+                async_graphql::Request::new("{ users { name } }")
+                    .data(GraphqlSubject("peter".to_owned())),
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap()["users"][1]["name"],
+            "ken"
+        );
+
+        let response = schema
+            .execute(async_graphql::Request::new(query).data(GraphqlSubject("peter".to_owned())))
+            .await;
+        assert_eq!(response.errors.len(), 2);
+        assert!(response.data.into_json().unwrap()["users"][1].is_null());
+
+        let response = schema.execute(query).await;
+        assert_eq!(response.errors[0].message, "unauthenticated");
+
+        let response = schema
+            .execute(
+                async_graphql::Request::new(
+                    "{ people: users { handle: name } staff: users { email } }",
+                )
+                .data(GraphqlSubject("peter".to_owned())),
+            )
+            .await;
+        let paths: Vec<_> = response.errors.iter().map(|err| err.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [0, 1].map(|index| vec![
+                PathSegment::Field("staff".to_owned()),
+                PathSegment::Index(index),
+                PathSegment::Field("email".to_owned()),
+            ])
+        );
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["people"][0]["handle"], "peter");
+        assert_eq!(data["people"][1]["handle"], "ken");
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(feature = "actix")]
+pub(crate) mod actix;
+#[cfg(feature = "axum")]
+pub(crate) mod axum;
+#[cfg(feature = "async-graphql")]
+pub(crate) mod graphql;
+
+use std::sync::Arc;
+
+use ope::{Context, Enforcer, Error, Matcher, PolicyStore, Request};
+
+type Extract<R> = Arc<dyn Fn(&R) -> Option<String> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub struct Subject(pub String);
+
+pub struct Extractors<R> {
+    subject: Extract<R>,
+    action: Extract<R>,
+    resource: Extract<R>,
+    context: Arc<dyn Fn(&R) -> Context + Send + Sync>,
+}
+
+impl<R> Clone for Extractors<R> {
+    fn clone(&self) -> Self {
+        Self {
+            subject: self.subject.clone(),
+            action: self.action.clone(),
+            resource: self.resource.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<R> Extractors<R> {
+    pub fn subject<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&R) -> Option<String> + Send + Sync + 'static,
+    {
+        self.subject = Arc::new(f);
+        self
+    }
+
+    pub fn action<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&R) -> Option<String> + Send + Sync + 'static,
+    {
+        self.action = Arc::new(f);
+        self
+    }
+
+    pub fn resource<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&R) -> Option<String> + Send + Sync + 'static,
+    {
+        self.resource = Arc::new(f);
+        self
+    }
+
+    pub fn context<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&R) -> Context + Send + Sync + 'static,
+    {
+        self.context = Arc::new(f);
+        self
+    }
+
+    pub fn request(&self, req: &R) -> Option<Request> {
+        Some(Request {
+            resource: (self.resource)(req)?,
+            action: (self.action)(req)?,
+            subject: (self.subject)(req)?,
+            context: (self.context)(req),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Rejection {
+    Unauthenticated,
+    Forbidden(String),
+    Internal(String),
+}
+
+impl Rejection {
+    pub fn status(&self) -> u16 {
+        match self {
+            Rejection::Unauthenticated => 401,
+            Rejection::Forbidden(_) => 403,
+            Rejection::Internal(_) => 500,
+        }
+    }
+}
+
+pub struct Guard<R, M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+    extractors: Extractors<R>,
+}
+
+impl<R, M, S> Clone for Guard<R, M, S> {
+    fn clone(&self) -> Self {
+        Self {
+            enforcer: self.enforcer.clone(),
+            extractors: self.extractors.clone(),
+        }
+    }
+}
+
+impl<R, M, S> Guard<R, M, S> {
+    pub fn with_extractors(enforcer: Arc<Enforcer<M, S>>, extractors: Extractors<R>) -> Self {
+        Self {
+            enforcer,
+            extractors,
+        }
+    }
+}
+
+impl<R, M: Matcher, S: PolicyStore> Guard<R, M, S> {
+    pub fn check(&self, req: &R) -> Result<Request, Rejection> {
+        let input = self
+            .extractors
+            .request(req)
+            .ok_or(Rejection::Unauthenticated)?;
+        match self.enforcer.is_allowed(&input) {
+            Ok(()) => Ok(input),
+            Err(err @ (Error::Deny { .. } | Error::NotMatched { .. })) => {
+                Err(Rejection::Forbidden(err.to_string()))
+            }
+            Err(err) => {
+                tracing::error!("check {:?} failed: {}", input, err);
+                Err(Rejection::Internal(err.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "axum", feature = "actix"))]
+fn extractors<R: 'static>(
+    method: fn(&R) -> &str,
+    path: fn(&R) -> &str,
+    subject: fn(&R) -> Option<String>,
+) -> Extractors<R> {
+    Extractors {
+        subject: Arc::new(subject),
+        action: Arc::new(move |req| Some(method(req).to_ascii_lowercase())),
+        resource: Arc::new(move |req| Some(path(req).to_owned())),
+        context: Arc::new(|_| Context::new()),
+    }
+}
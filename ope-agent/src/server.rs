@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use envoy_types::ext_authz::v3::pb::{
+    Authorization, AuthorizationServer, CheckRequest, CheckResponse, HttpStatusCode,
+};
+use envoy_types::ext_authz::v3::{CheckRequestExt, CheckResponseExt, DeniedHttpResponseBuilder};
+use ope::{Context, Enforcer, Error, Matcher, PolicyStore, Request};
+use serde_json::Value;
+use tonic::{Response, Status};
+
+pub struct ExtAuthz<M, S> {
+    enforcer: Arc<Enforcer<M, S>>,
+    subject_header: Option<String>,
+}
+
+impl<M, S> ExtAuthz<M, S> {
+    pub fn new(enforcer: Arc<Enforcer<M, S>>) -> Self {
+        Self {
+            enforcer,
+            subject_header: None,
+        }
+    }
+
+    /// Falls back to `header` when the peer has no authenticated principal.
+    /// Clients can set any header, so only enable this behind a trusted proxy
+    /// that overwrites it.
+    pub fn with_subject_header(mut self, header: impl Into<String>) -> Self {
+        self.subject_header = Some(header.into().to_ascii_lowercase());
+        self
+    }
+
+    pub fn into_service(self) -> AuthorizationServer<Self>
+    where
+        M: Matcher + Send + Sync + 'static,
+        S: PolicyStore + Send + Sync + 'static,
+    {
+        AuthorizationServer::new(self)
+    }
+
+    pub fn request(&self, check: &CheckRequest) -> Request {
+        let attributes = check.attributes.clone().unwrap_or_default();
+        let http = attributes
+            .request
+            .and_then(|request| request.http)
+            .unwrap_or_default();
+
+        let principal = attributes
+            .source
+            .map(|source| source.principal)
+            .unwrap_or_default();
+        let subject = match &self.subject_header {
+            Some(header) if principal.is_empty() => {
+                http.headers.get(header).cloned().unwrap_or_default()
+            }
+            _ => principal,
+        };
+        let resource = match http.path.split_once('?') {
+            Some((path, _)) => path.to_owned(),
+            None => http.path.clone(),
+        };
+
+        let mut context: Context = attributes.context_extensions.into_iter().collect();
+        if let Some(address) = check.get_client_address() {
+            context.insert("clientIP", address.clone());
+        }
+        if !http.host.is_empty() {
+            context.insert("host", http.host);
+        }
+        let headers: serde_json::Map<String, Value> = http
+            .headers
+            .into_iter()
+            .map(|(k, v)| (k, Value::String(v)))
+            .collect();
+        context.insert("headers", headers);
+
+        Request {
+            resource,
+            action: http.method.to_ascii_lowercase(),
+            subject,
+            context,
+        }
+    }
+}
+
+fn denied(reason: String) -> CheckResponse {
+    let mut http = DeniedHttpResponseBuilder::new();
+    http.set_http_status(HttpStatusCode::Forbidden)
+        .set_body(reason.clone());
+    let mut response = CheckResponse::with_status(Status::permission_denied(reason));
+    response.set_http_response(http);
+    response
+}
+
+#[tonic::async_trait]
+impl<M, S> Authorization for ExtAuthz<M, S>
+where
+    M: Matcher + Send + Sync + 'static,
+    S: PolicyStore + Send + Sync + 'static,
+{
+    async fn check(
+        &self,
+        request: tonic::Request<CheckRequest>,
+    ) -> Result<Response<CheckResponse>, Status> {
+        let input = self.request(request.get_ref());
+        let response = match self.enforcer.is_allowed(&input) {
+            Ok(()) => CheckResponse::with_status(Status::ok("")),
+            Err(err @ (Error::Deny { .. } | Error::NotMatched { .. })) => denied(err.to_string()),
+            Err(err) => {
+                tracing::error!("check {:?} failed: {}", input, err);
+                return Err(Status::internal(err.to_string()));
+            }
+        };
+        Ok(Response::new(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use envoy_types::pb::envoy::config::core::v3::{address, Address, SocketAddress};
+    use envoy_types::pb::envoy::service::auth::v3::{attribute_context, AttributeContext};
+    use ope::{PolicyBuilder, Regexp};
+
+    use super::*;
+    use crate::SUBJECT_HEADER;
+
+    fn check(principal: &str, method: &str, path: &str) -> CheckRequest {
+        CheckRequest {
+            attributes: Some(AttributeContext {
+                source: Some(attribute_context::Peer {
+                    address: Some(Address {
+                        address: Some(address::Address::SocketAddress(SocketAddress {
+                            address: "10.0.0.7".to_owned(),
+                            ..Default::default()
+                        })),
+                    }),
+                    principal: principal.to_owned(),
+                    ..Default::default()
+                }),
+                request: Some(attribute_context::Request {
+                    time: None,
+                    http: Some(attribute_context::HttpRequest {
+                        method: method.to_owned(),
+                        path: path.to_owned(),
+                        host: "api.local".to_owned(),
+                        headers: HashMap::from([(SUBJECT_HEADER.to_owned(), "ken".to_owned())]),
+                        ..Default::default()
+                    }),
+                }),
+                context_extensions: HashMap::from([("tenant".to_owned(), "acme".to_owned())]),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn authorize() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("<peter|ken>")
+            .action("get")
+            .resource("/articles/<[0-9]+>")
+            .condition(
+                "clientIP",
+                "CIDR",
+                serde_json::json!({"cidr": ["10.0.0.0/8"]}),
+            )
+            .build()
+            .unwrap();
+        let server = ExtAuthz::new(Arc::new(Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy],
+        )));
+        assert_eq!(server.request(&check("", "GET", "/")).subject, "");
+        let server = server.with_subject_header(SUBJECT_HEADER);
+
+        let input = server.request(&check("", "GET", "/articles/1?page=2"));
+        assert_eq!(input.subject, "ken");
+        assert_eq!(input.action, "get");
+        assert_eq!(input.resource, "/articles/1");
+        assert_eq!(input.context.get_str("tenant").unwrap(), "acme");
+        assert_eq!(input.context.get_str("host").unwrap(), "api.local");
+        assert_eq!(server.request(&check("peter", "GET", "/")).subject, "peter");
+
+        let code = |check| {
+            let server = &server;
+            async move {
+                server
+                    .check(tonic::Request::new(check))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .status
+                    .unwrap()
+                    .code
+            }
+        };
+        assert_eq!(code(check("peter", "GET", "/articles/1")).await, 0);
+        assert_eq!(
+            code(check("max", "GET", "/articles/1")).await,
+            tonic::Code::PermissionDenied as i32
+        );
+        assert_eq!(
+            code(check("peter", "DELETE", "/articles/1")).await,
+            tonic::Code::PermissionDenied as i32
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnbalancedBraces(String),
+    InvalidRegex(String, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnbalancedBraces(tpl) => write!(f, "Unbalanced braces in {tpl}"),
+            Error::InvalidRegex(pattern, err) => write!(f, "invalid regex {pattern}: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
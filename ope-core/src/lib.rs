@@ -0,0 +1,13 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod err;
+mod template;
+
+pub use err::Error;
+pub use template::{delimiter_indices, escape, matches, segments, split_name, Segment, Template};
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,206 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use regex_automata::meta::Regex;
+
+use crate::{Error, Result};
+
+pub enum Segment {
+    Literal(String),
+    Template(String),
+}
+
+pub fn delimiter_indices(
+    s: &str,
+    delimiter_start: char,
+    delimiter_end: char,
+) -> Result<Vec<usize>> {
+    let (mut level, mut idx, mut escaped) = (0, 0, false);
+    let mut idxs: Vec<usize> = Vec::new();
+    for (i, value) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            if value == delimiter_start || value == delimiter_end || value == '\\' {
+                continue;
+            }
+        }
+        if value == '\\' {
+            escaped = true;
+        } else if value == delimiter_start {
+            level += 1;
+            if level == 1 {
+                idx = i;
+            }
+        } else if value == delimiter_end {
+            level -= 1;
+            match level.cmp(&0) {
+                Ordering::Less => {
+                    return Err(Error::UnbalancedBraces(s.to_owned()));
+                }
+                Ordering::Equal => {
+                    idxs.push(idx);
+                    idxs.push(i + value.len_utf8());
+                }
+                Ordering::Greater => {}
+            }
+        }
+    }
+    if level != 0 {
+        return Err(Error::UnbalancedBraces(s.to_owned()));
+    }
+    Ok(idxs)
+}
+
+pub fn escape(text: &str) -> String {
+    regex_syntax::escape(text)
+}
+
+pub fn segments(tpl: &str, delimiter_start: &str, delimiter_end: &str) -> Result<Vec<Segment>> {
+    let (mut level, mut current, mut segments) = (0usize, String::new(), Vec::new());
+    let mut rest = tpl;
+    while let Some(c) = rest.chars().next() {
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            if let Some(after) = escaped.strip_prefix('\\') {
+                match level {
+                    0 => current.push('\\'),
+                    _ => current.push_str("\\\\"),
+                }
+                rest = after;
+                continue;
+            }
+            if let Some(delimiter) = [delimiter_start, delimiter_end]
+                .into_iter()
+                .find(|d| escaped.starts_with(*d))
+            {
+                match level {
+                    0 => current.push_str(delimiter),
+                    _ => current.push_str(&escape(delimiter)),
+                }
+                rest = &escaped[delimiter.len()..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix(delimiter_start) {
+            match level {
+                0 if !current.is_empty() => {
+                    segments.push(Segment::Literal(core::mem::take(&mut current)))
+                }
+                0 => {}
+                _ => current.push_str(delimiter_start),
+            }
+            level += 1;
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix(delimiter_end) {
+            match level {
+                0 => return Err(Error::UnbalancedBraces(tpl.to_owned())),
+                1 => segments.push(Segment::Template(core::mem::take(&mut current))),
+                _ => current.push_str(delimiter_end),
+            }
+            level -= 1;
+            rest = after;
+            continue;
+        }
+        current.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if level != 0 {
+        return Err(Error::UnbalancedBraces(tpl.to_owned()));
+    }
+    if !current.is_empty() {
+        segments.push(Segment::Literal(current));
+    }
+    Ok(segments)
+}
+
+pub fn split_name(patt: &str) -> (Option<&str>, &str) {
+    if let Some((name, inner)) = patt.split_once(':') {
+        let mut chars = name.chars();
+        let valid = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if valid {
+            return (Some(name), inner);
+        }
+    }
+    (None, patt)
+}
+
+pub struct Template {
+    regex: Regex,
+}
+
+impl Template {
+    pub fn new(tpl: &str, delimiter_start: char, delimiter_end: char) -> Result<Self> {
+        let (start, end) = (delimiter_start.to_string(), delimiter_end.to_string());
+        let mut pattern = String::from("^");
+        for segment in segments(tpl, &start, &end)? {
+            match segment {
+                Segment::Literal(raw) => pattern.push_str(&escape(&raw)),
+                Segment::Template(patt) => {
+                    let (_, patt) = split_name(&patt);
+                    pattern.push_str(&format!("(?:{patt})"));
+                }
+            }
+        }
+        pattern.push('$');
+        let regex =
+            Regex::new(&pattern).map_err(|err| Error::InvalidRegex(pattern, err.to_string()))?;
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, needle: &str) -> bool {
+        self.regex.is_match(needle)
+    }
+}
+
+pub fn matches(
+    delimiter_start: char,
+    delimiter_end: char,
+    haystack: &[impl AsRef<str>],
+    needle: &str,
+) -> Result<bool> {
+    for tpl in haystack.iter().map(AsRef::as_ref) {
+        if !tpl.contains(delimiter_start) {
+            if tpl == needle {
+                return Ok(true);
+            }
+            continue;
+        }
+        if Template::new(tpl, delimiter_start, delimiter_end)?.is_match(needle) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template() {
+        assert_eq!(
+            delimiter_indices("资源:文章:<[0-9]+>:ü<.*>", '<', '>').unwrap(),
+            [14, 22, 25, 29]
+        );
+        let haystack = ["articles:<[0-9]+>", r"users:<id:[a-z]+>\<x\>", "static"];
+        assert!(matches('<', '>', &haystack, "articles:12").unwrap());
+        assert!(matches('<', '>', &haystack, "users:max<x>").unwrap());
+        assert!(matches('<', '>', &haystack, "static").unwrap());
+        assert!(!matches('<', '>', &haystack, "articles:x").unwrap());
+        assert!(matches!(
+            matches('<', '>', &["<a"], "a"),
+            Err(Error::UnbalancedBraces(_))
+        ));
+        assert!(matches!(
+            matches('<', '>', &["<(>"], "a"),
+            Err(Error::InvalidRegex(..))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, value::to_raw_value, Value};
+
+use ope::{ConditionRegistry, Enforcer, JsonCondition, Matcher, Policy, Regexp, Request};
+
+fn policies(count: usize) -> Vec<Policy> {
+    (0..count)
+        .map(|i| {
+            Policy::builder(format!("policy-{i}"))
+                .allow()
+                .subject(format!("user-{i}"))
+                .subject("group:<admins|editors>")
+                .action("<get|list>")
+                .resource(format!("articles:{i}:<.*>"))
+                .build()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn matcher(c: &mut Criterion) {
+    let mut group = c.benchmark_group("matcher");
+    let literal = ["articles:1:comments"];
+    let templated = ["articles:<[0-9]+>:<comments|likes>"];
+
+    let regexp = Regexp::new(1024).unwrap();
+    group.bench_function("literal", |b| {
+        b.iter(|| {
+            regexp
+                .matches(
+                    '<',
+                    '>',
+                    black_box(&literal),
+                    black_box("articles:1:comments"),
+                )
+                .unwrap()
+        })
+    });
+    group.bench_function("templated/cache-hit", |b| {
+        b.iter(|| {
+            regexp
+                .matches(
+                    '<',
+                    '>',
+                    black_box(&templated),
+                    black_box("articles:1:likes"),
+                )
+                .unwrap()
+        })
+    });
+
+    let patterns: Vec<_> = (0..4096)
+        .map(|i| format!("articles:{i}:<comments|likes>"))
+        .collect();
+    let missing = Regexp::new(16).unwrap();
+    let mut next = 0;
+    group.bench_function("templated/cache-miss", |b| {
+        b.iter(|| {
+            next = (next + 1) % patterns.len();
+            missing
+                .matches(
+                    '<',
+                    '>',
+                    black_box(&patterns[next..=next]),
+                    black_box("articles:1:likes"),
+                )
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn condition(c: &mut Criterion) {
+    let mut group = c.benchmark_group("condition");
+    let registry = ConditionRegistry::new();
+    let request = Request::new("peter", "get", "articles:1");
+    let cases: [(&str, &str, Value, Value); 3] = [
+        (
+            "cidr",
+            "CIDR",
+            json!({"cidr": ["10.0.0.0/8", "192.168.1.0/24"]}),
+            json!("192.168.1.67"),
+        ),
+        (
+            "string-equal",
+            "StringEqual",
+            json!({"equals": "2023"}),
+            json!("2023"),
+        ),
+        (
+            "numeric-cmp",
+            "NumericCmp",
+            json!({"symbol": ">", "value": 5.0}),
+            json!(6.0),
+        ),
+    ];
+    for (name, jtype, options, input) in cases {
+        let condition = registry
+            .build(&JsonCondition {
+                jtype: jtype.to_owned(),
+                options: to_raw_value(&options).unwrap(),
+            })
+            .unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| condition.evaluate(black_box(&input), black_box(&request)))
+        });
+    }
+    group.finish();
+}
+
+fn evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluation");
+    for count in [1_000, 10_000, 100_000] {
+        let enforcer = Enforcer::new(Regexp::new(count * 2).unwrap(), policies(count));
+        enforcer.warm().unwrap();
+        let allowed = Request::new("user-7", "get", "articles:7:comments");
+        let denied = Request::new("user-7", "delete", "articles:7:comments");
+        group.bench_with_input(BenchmarkId::new("allowed", count), &allowed, |b, input| {
+            b.iter(|| enforcer.is_allowed(black_box(input)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("not-matched", count),
+            &denied,
+            |b, input| b.iter(|| enforcer.is_allowed(black_box(input))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, matcher, condition, evaluation);
+criterion_main!(benches);
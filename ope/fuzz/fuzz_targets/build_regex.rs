@@ -0,0 +1,26 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use ope::fuzzing::{build_regex, delimiter_indices};
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    template: &'a str,
+    start: char,
+    end: char,
+}
+
+fuzz_target!(|input: Input<'_>| {
+    if let Ok(indices) = delimiter_indices(input.template, input.start, input.end) {
+        assert_eq!(indices.len() % 2, 0);
+        for pair in indices.chunks(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(input.template.is_char_boundary(pair[0]));
+            assert!(input.template.is_char_boundary(pair[1]));
+        }
+    }
+    if let Ok(pattern) = build_regex(input.template, input.start, input.end) {
+        let _ = regex::Regex::new(&pattern);
+    }
+});
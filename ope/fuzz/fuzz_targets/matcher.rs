@@ -0,0 +1,20 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use ope::{Matcher, Regexp};
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    patterns: Vec<&'a str>,
+    needle: &'a str,
+    start: char,
+    end: char,
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let matcher = Regexp::new(16).unwrap();
+    let _ = matcher.matches(input.start, input.end, &input.patterns, input.needle);
+    let _ = matcher.named_captures(input.start, input.end, &input.patterns, input.needle);
+    let _ = matcher.spans(input.start, input.end, &input.patterns, input.needle);
+});
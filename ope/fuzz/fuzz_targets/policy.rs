@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ope::{Enforcer, Format, Policy, Regexp, Request};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    for format in [Format::Json, Format::Yaml, Format::Toml] {
+        let Ok(policies) = Policy::parse_all(content, format) else {
+            continue;
+        };
+        for policy in policies.iter() {
+            let _ = policy.lint();
+            let _ = serde_json::to_string(policy);
+        }
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies);
+        let _ = enforcer.is_allowed(&Request::new("peter", "get", "articles:1"));
+    }
+});
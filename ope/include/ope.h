@@ -0,0 +1,31 @@
+#ifndef OPE_H
+#define OPE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define OPE_OK 0
+#define OPE_DENY 1
+#define OPE_NOT_MATCHED 2
+#define OPE_MULTIPLE_APPLICABLE 3
+#define OPE_ERR_INVALID_ARGUMENT -1
+#define OPE_ERR_PARSE -2
+#define OPE_ERR_STORE -3
+#define OPE_ERR_PATTERN -4
+#define OPE_ERR_CONDITION -5
+#define OPE_ERR_INTERNAL -99
+
+typedef struct OpeEnforcer OpeEnforcer;
+
+OpeEnforcer *ope_enforcer_new(void);
+void ope_enforcer_free(OpeEnforcer *enforcer);
+int ope_load_policies_json(OpeEnforcer *enforcer, const char *policies);
+int ope_is_allowed(const OpeEnforcer *enforcer, const char *request);
+const char *ope_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,173 @@
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::context::Context;
+use crate::req::Request;
+use crate::Result;
+
+pub struct AuditEvent<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub request: &'a Request,
+    pub decision: &'a Result<()>,
+    pub policies: &'a [String],
+    pub latency: Duration,
+    pub cached: bool,
+    pub shadow: Option<&'a Result<()>>,
+}
+
+impl AuditEvent<'_> {
+    pub fn allowed(&self) -> bool {
+        self.decision.is_ok()
+    }
+
+    pub fn diverged(&self) -> bool {
+        match self.shadow {
+            Some(shadow) => shadow.is_ok() != self.decision.is_ok(),
+            None => false,
+        }
+    }
+}
+
+pub trait AuditSink {
+    fn record(&self, event: &AuditEvent<'_>);
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: DateTime<Utc>,
+    subject: &'a str,
+    action: &'a str,
+    resource: &'a str,
+    context: &'a Context,
+    allowed: bool,
+    error: Option<String>,
+    policies: &'a [String],
+    latency_us: u128,
+    cached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shadow_allowed: Option<bool>,
+}
+
+impl<'a> From<&AuditEvent<'a>> for Line<'a> {
+    fn from(event: &AuditEvent<'a>) -> Self {
+        Self {
+            timestamp: event.timestamp,
+            subject: &event.request.subject,
+            action: &event.request.action,
+            resource: &event.request.resource,
+            context: &event.request.context,
+            allowed: event.allowed(),
+            error: event.decision.as_ref().err().map(|err| err.to_string()),
+            policies: event.policies,
+            latency_us: event.latency.as_micros(),
+            cached: event.cached,
+            shadow_allowed: event.shadow.map(|shadow| shadow.is_ok()),
+        }
+    }
+}
+
+pub struct JsonLinesSink {
+    writer: Mutex<LineWriter<File>>,
+}
+
+impl JsonLinesSink {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: Mutex::new(LineWriter::new(file)),
+        })
+    }
+
+    fn write(&self, event: &AuditEvent<'_>) -> std::result::Result<(), String> {
+        let mut line = serde_json::to_vec(&Line::from(event)).map_err(|err| err.to_string())?;
+        line.push(b'\n');
+        let mut writer = self.writer.lock().map_err(|err| err.to_string())?;
+        writer.write_all(&line).map_err(|err| err.to_string())
+    }
+}
+
+impl AuditSink for JsonLinesSink {
+    fn record(&self, event: &AuditEvent<'_>) {
+        if let Err(err) = self.write(event) {
+            tracing::warn!("write audit event failed: {}", err);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TracingSink;
+
+impl AuditSink for TracingSink {
+    fn record(&self, event: &AuditEvent<'_>) {
+        tracing::info!(
+            target: "ope::audit",
+            subject = %event.request.subject,
+            action = %event.request.action,
+            resource = %event.request.resource,
+            allowed = event.allowed(),
+            policies = ?event.policies,
+            latency_us = event.latency.as_micros() as u64,
+            cached = event.cached,
+            "authorization decision"
+        );
+        if event.diverged() {
+            tracing::warn!(
+                target: "ope::audit",
+                subject = %event.request.subject,
+                action = %event.request.action,
+                resource = %event.request.resource,
+                allowed = event.allowed(),
+                "shadow policies diverged"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn json_lines() {
+        let path = std::env::temp_dir().join(format!("ope-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sink = JsonLinesSink::open(&path).unwrap();
+        let request = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([("ip", "10.0.0.1")]),
+        };
+        let policies = vec!["readers".to_owned()];
+        for decision in [Ok(()), Err(Error::not_matched())] {
+            sink.record(&AuditEvent {
+                timestamp: Utc::now(),
+                request: &request,
+                decision: &decision,
+                policies: &policies,
+                latency: Duration::from_micros(42),
+                cached: false,
+                shadow: None,
+            });
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["allowed"], true);
+        assert_eq!(lines[0]["policies"][0], "readers");
+        assert_eq!(lines[0]["context"]["ip"], "10.0.0.1");
+        assert_eq!(lines[1]["allowed"], false);
+        assert!(lines[1]["error"].is_string());
+        let _ = std::fs::remove_file(path);
+    }
+}
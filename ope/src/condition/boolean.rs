@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::Value;
 
 use super::Condition;
 use crate::req::Request;
@@ -7,13 +7,90 @@ use crate::req::Request;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Boolean {
     pub value: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub coerce: bool,
+}
+
+impl Boolean {
+    fn coerce(input: &Value) -> Option<bool> {
+        match input {
+            Value::Bool(v) => Some(*v),
+            Value::Number(v) => match v.as_i64() {
+                Some(0) => Some(false),
+                Some(1) => Some(true),
+                _ => None,
+            },
+            Value::String(v) => match v.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Some(true),
+                "false" | "0" | "no" | "off" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl Condition for Boolean {
-    fn evaluate(&self, input: Box<RawValue>, _req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<bool>(input.get()) {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let v = if self.coerce {
+            Self::coerce(input)
+        } else {
+            input.as_bool()
+        };
+        if let Some(v) = v {
             return self.value == v;
         }
         false
     }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn coerce() {
+        let req = Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Default::default(),
+        };
+        let strict: Boolean = serde_json::from_value(json!({"value": true})).unwrap();
+        assert!(strict.evaluate(&json!(true), &req));
+        assert!(!strict.evaluate(&json!("true"), &req));
+
+        let loose: Boolean =
+            serde_json::from_value(json!({"value": true, "coerce": true})).unwrap();
+        for input in [
+            json!(true),
+            json!("True"),
+            json!(" yes "),
+            json!("on"),
+            json!(1),
+        ] {
+            assert!(loose.evaluate(&input, &req), "{input}");
+        }
+        for input in [
+            json!(false),
+            json!("false"),
+            json!("maybe"),
+            json!(2),
+            json!(null),
+        ] {
+            assert!(!loose.evaluate(&input, &req), "{input}");
+        }
+        let off: Boolean = serde_json::from_value(json!({"value": false, "coerce": true})).unwrap();
+        assert!(off.evaluate(&json!("0"), &req));
+        assert_eq!(
+            serde_json::to_value(&strict).unwrap(),
+            json!({"value": true})
+        );
+    }
 }
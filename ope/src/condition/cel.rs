@@ -0,0 +1,126 @@
+use std::sync::{Arc, OnceLock};
+
+use cel_interpreter::{to_value, Context, Program, Value as CelValue};
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+
+use super::Condition;
+use crate::matcher::cache::ShardedCache;
+use crate::req::Request;
+use crate::{Error, Result};
+
+const PROGRAMS: usize = 1024;
+
+#[derive(Deserialize)]
+struct Options {
+    expression: String,
+}
+
+pub struct CelCondition {
+    expression: String,
+    program: Arc<Program>,
+}
+
+impl CelCondition {
+    pub fn new(expression: impl Into<String>) -> Result<Self> {
+        let expression = expression.into();
+        let program = programs()?.get(&expression);
+        let program = match program {
+            Some(program) => program,
+            None => {
+                let program = Arc::new(Program::compile(&expression).map_err(|err| {
+                    Error::InvalidExpression(format!("{expression}: {}", err.msg))
+                })?);
+                programs()?.put(expression.clone(), program.clone());
+                program
+            }
+        };
+        Ok(Self {
+            expression,
+            program,
+        })
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    pub(crate) fn build(options: &RawValue) -> Result<Box<dyn Condition>> {
+        let options: Options = serde_json::from_str(options.get())?;
+        Ok(Box::new(Self::new(options.expression)?))
+    }
+
+    fn execute(&self, input: &Value, req: &Request) -> Option<bool> {
+        let mut context = Context::default();
+        context.add_variable_from_value("value", to_value(input).ok()?);
+        context.add_variable_from_value("context", to_value(&req.context).ok()?);
+        context.add_variable_from_value("subject", req.subject.clone());
+        context.add_variable_from_value("action", req.action.clone());
+        context.add_variable_from_value("resource", req.resource.clone());
+        match self.program.execute(&context) {
+            Ok(CelValue::Bool(v)) => Some(v),
+            Ok(_) => None,
+            Err(err) => {
+                tracing::debug!("cel expression {} failed: {}", self.expression, err);
+                None
+            }
+        }
+    }
+}
+
+fn programs() -> Result<&'static ShardedCache<String, Arc<Program>>> {
+    static PROGRAMS_CACHE: OnceLock<ShardedCache<String, Arc<Program>>> = OnceLock::new();
+    if let Some(cache) = PROGRAMS_CACHE.get() {
+        return Ok(cache);
+    }
+    let cache = ShardedCache::new(PROGRAMS, 16)?;
+    Ok(PROGRAMS_CACHE.get_or_init(|| cache))
+}
+
+impl Condition for CelCondition {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        self.execute(input, req).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn expressions() {
+        let req = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([("labels", json!({"team": "eng"})), ("level", json!(3))]),
+        };
+        let cel = CelCondition::new(
+            "value >= 3 && context.labels.team in ['eng', 'ops'] && subject == 'peter'",
+        )
+        .unwrap();
+        assert!(cel.evaluate(&json!(3), &req));
+        assert!(!cel.evaluate(&json!(2), &req));
+        assert!(!CelCondition::new("value")
+            .unwrap()
+            .evaluate(&json!(1), &req));
+        assert!(!CelCondition::new("missing.key")
+            .unwrap()
+            .evaluate(&json!(1), &req));
+        assert!(matches!(
+            CelCondition::new("value >="),
+            Err(Error::InvalidExpression(_))
+        ));
+        let again = CelCondition::new(cel.expression()).unwrap();
+        assert!(Arc::ptr_eq(&cel.program, &again.program));
+
+        let policy = r#"{"id": "cel", "statements": [{"effect": "Allow", "subjects": ["peter"], "actions": ["get"], "resources": ["articles:1"], "conditions": {"level": {"type": "CEL", "options": {"expression": "value >="}}}}]}"#;
+        assert!(matches!(
+            crate::Policy::parse(policy, crate::Format::Json),
+            Err(Error::ValidationError(_))
+        ));
+    }
+}
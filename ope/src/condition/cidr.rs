@@ -1,11 +1,11 @@
-use std::net::IpAddr;
 use std::str::FromStr;
 
 use cidr_utils::cidr::IpCidr;
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::Value;
 
 use super::Condition;
+use crate::context::as_ip;
 use crate::req::Request;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -14,21 +14,23 @@ pub struct Cidr {
 }
 
 impl Condition for Cidr {
-    fn evaluate(&self, input: Box<RawValue>, _req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<String>(input.get()) {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        if let Some(ip) = as_ip(input) {
             let mut matched = false;
             for value in self.cidr.iter() {
                 if let Ok(cidr) = IpCidr::from_str(value) {
-                    if let Ok(ip) = v.parse::<IpAddr>() {
-                        if !cidr.contains(&ip) {
-                            return false;
-                        }
-                        matched = true;
+                    if !cidr.contains(&ip) {
+                        return false;
                     }
+                    matched = true;
                 }
             }
             return matched;
         }
         false
     }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,276 @@
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+
+use super::{registry::ConditionRegistry, Condition, JsonCondition};
+use crate::req::Request;
+use crate::Result;
+
+const ALL_OF: &str = "AllOf";
+const ANY_OF: &str = "AnyOf";
+const NOT: &str = "Not";
+
+#[derive(Deserialize)]
+struct Operand {
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(rename = "type")]
+    jtype: String,
+    options: Box<RawValue>,
+}
+
+#[derive(Deserialize)]
+struct ListOptions {
+    conditions: Vec<Operand>,
+}
+
+#[derive(Deserialize)]
+struct NotOptions {
+    condition: Operand,
+}
+
+enum Node {
+    Leaf(Box<dyn Condition>),
+    AllOf(Vec<Built>),
+    AnyOf(Vec<Built>),
+    Not(Box<Built>),
+}
+
+struct Built {
+    key: Option<String>,
+    condition: Node,
+}
+
+impl Built {
+    fn new(registry: &ConditionRegistry, operand: Operand) -> Result<Self> {
+        let condition = match operand.jtype.as_str() {
+            ALL_OF => Node::AllOf(list(registry, &operand.options)?),
+            ANY_OF => Node::AnyOf(list(registry, &operand.options)?),
+            NOT => Node::Not(Box::new(not(registry, &operand.options)?)),
+            _ => Node::Leaf(registry.build(&JsonCondition {
+                jtype: operand.jtype,
+                options: operand.options,
+            })?),
+        };
+        Ok(Self {
+            key: operand.key,
+            condition,
+        })
+    }
+
+    fn evaluate(&self, input: &Value, req: &Request) -> Option<bool> {
+        let input = match &self.key {
+            Some(key) => super::lookup(req, key, self.node_reads_context())?,
+            None => input,
+        };
+        match &self.condition {
+            Node::Leaf(condition) => Some(condition.evaluate(input, req)),
+            Node::AllOf(operands) => all_of(operands, input, req),
+            Node::AnyOf(operands) => any_of(operands, input, req),
+            Node::Not(operand) => operand.evaluate(input, req).map(|v| !v),
+        }
+    }
+
+    fn reads_input(&self) -> bool {
+        self.key.is_none() && self.node_reads_context()
+    }
+
+    fn node_reads_context(&self) -> bool {
+        match &self.condition {
+            Node::Leaf(condition) => condition.reads_context(),
+            Node::AllOf(operands) | Node::AnyOf(operands) => {
+                operands.iter().any(Built::reads_input)
+            }
+            Node::Not(operand) => operand.reads_input(),
+        }
+    }
+
+    fn reads_request(&self) -> bool {
+        self.key.is_some()
+            || match &self.condition {
+                Node::Leaf(condition) => condition.reads_request(),
+                Node::AllOf(operands) | Node::AnyOf(operands) => {
+                    operands.iter().any(Built::reads_request)
+                }
+                Node::Not(operand) => operand.reads_request(),
+            }
+    }
+}
+
+fn all_of(operands: &[Built], input: &Value, req: &Request) -> Option<bool> {
+    let mut known = true;
+    for operand in operands.iter() {
+        match operand.evaluate(input, req) {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => known = false,
+        }
+    }
+    known.then_some(true)
+}
+
+fn any_of(operands: &[Built], input: &Value, req: &Request) -> Option<bool> {
+    let mut known = true;
+    for operand in operands.iter() {
+        match operand.evaluate(input, req) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => known = false,
+        }
+    }
+    known.then_some(false)
+}
+
+pub struct AllOf {
+    operands: Vec<Built>,
+}
+
+impl Condition for AllOf {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        all_of(&self.operands, input, req).unwrap_or_default()
+    }
+
+    fn reads_request(&self) -> bool {
+        self.operands.iter().any(Built::reads_request)
+    }
+
+    fn reads_context(&self) -> bool {
+        self.operands.iter().any(Built::reads_input)
+    }
+}
+
+pub struct AnyOf {
+    operands: Vec<Built>,
+}
+
+impl Condition for AnyOf {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        any_of(&self.operands, input, req).unwrap_or_default()
+    }
+
+    fn reads_request(&self) -> bool {
+        self.operands.iter().any(Built::reads_request)
+    }
+
+    fn reads_context(&self) -> bool {
+        self.operands.iter().any(Built::reads_input)
+    }
+}
+
+pub struct Not {
+    operand: Built,
+}
+
+impl Condition for Not {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        self.operand
+            .evaluate(input, req)
+            .is_some_and(|matched| !matched)
+    }
+
+    fn reads_request(&self) -> bool {
+        self.operand.reads_request()
+    }
+
+    fn reads_context(&self) -> bool {
+        self.operand.reads_input()
+    }
+}
+
+fn list(registry: &ConditionRegistry, options: &RawValue) -> Result<Vec<Built>> {
+    let options: ListOptions = serde_json::from_str(options.get())?;
+    options
+        .conditions
+        .into_iter()
+        .map(|operand| Built::new(registry, operand))
+        .collect()
+}
+
+fn not(registry: &ConditionRegistry, options: &RawValue) -> Result<Built> {
+    let options: NotOptions = serde_json::from_str(options.get())?;
+    Built::new(registry, options.condition)
+}
+
+pub(crate) fn is_combinator(name: &str) -> bool {
+    matches!(name, ALL_OF | ANY_OF | NOT)
+}
+
+pub(crate) fn build(
+    registry: &ConditionRegistry,
+    condition: &JsonCondition,
+) -> Option<Result<Box<dyn Condition>>> {
+    let built: Result<Box<dyn Condition>> = match condition.jtype.as_str() {
+        ALL_OF => {
+            list(registry, &condition.options).map(|operands| Box::new(AllOf { operands }) as _)
+        }
+        ANY_OF => {
+            list(registry, &condition.options).map(|operands| Box::new(AnyOf { operands }) as _)
+        }
+        NOT => not(registry, &condition.options).map(|operand| Box::new(Not { operand }) as _),
+        _ => return None,
+    };
+    Some(built)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Context;
+    use crate::Error;
+
+    #[test]
+    fn combinators() {
+        let condition: JsonCondition = serde_json::from_value(json!({
+            "type": "AllOf",
+            "options": {"conditions": [
+                {"type": "CIDR", "options": {"cidr": ["10.0.0.0/8"]}},
+                {"type": "AnyOf", "options": {"conditions": [
+                    {"key": "mfa", "type": "Boolean", "options": {"value": true}},
+                    {"type": "Not", "options": {"condition":
+                        {"key": "role", "type": "StringEqual", "options": {"equals": "guest"}}
+                    }}
+                ]}}
+            ]}
+        }))
+        .unwrap();
+        let registry = ConditionRegistry::new();
+        let built = registry.build(&condition).unwrap();
+        let mut req = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([("mfa", json!(false)), ("role", json!("guest"))]),
+        };
+        assert!(!built.evaluate(&json!("10.1.2.3"), &req));
+        req.context.insert("mfa", true);
+        assert!(built.evaluate(&json!("10.1.2.3"), &req));
+        assert!(!built.evaluate(&json!("192.168.0.1"), &req));
+        req.context.insert("mfa", false);
+        req.context.insert("role", "admin");
+        assert!(built.evaluate(&json!("10.1.2.3"), &req));
+        req.context = Context::new();
+        assert!(!built.evaluate(&json!("10.1.2.3"), &req));
+        req.context.insert("mfa", true);
+        assert!(built.evaluate(&json!("10.1.2.3"), &req));
+        let guest: JsonCondition = serde_json::from_value(json!({
+            "type": "Not",
+            "options": {"condition": {"key": "role", "type": "StringEqual", "options": {"equals": "guest"}}}
+        }))
+        .unwrap();
+        assert!(!registry.build(&guest).unwrap().evaluate(&json!(null), &req));
+
+        let roundtrip: JsonCondition =
+            serde_json::from_str(&serde_json::to_string(&condition).unwrap()).unwrap();
+        assert_eq!(roundtrip, condition);
+        let unknown: JsonCondition = serde_json::from_value(json!({
+            "type": "Not",
+            "options": {"condition": {"type": "Missing", "options": {}}}
+        }))
+        .unwrap();
+        assert!(matches!(
+            registry.build(&unknown),
+            Err(Error::NotFoundConditionType(_))
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Condition;
+use crate::req::Request;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EqualsAttribute {
+    pub attribute: String,
+}
+
+impl Condition for EqualsAttribute {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        let field = match self.attribute.as_str() {
+            "subject" => &req.subject,
+            "action" => &req.action,
+            "resource" => &req.resource,
+            attribute => return req.context.lookup(attribute) == Some(input),
+        };
+        input.as_str() == Some(field.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn attributes() {
+        let req = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([
+                ("subject.id", json!("peter")),
+                ("level", json!(3)),
+                ("subject", json!("admin")),
+            ]),
+        };
+        let owner = EqualsAttribute {
+            attribute: "subject.id".to_owned(),
+        };
+        assert!(owner.evaluate(&json!("peter"), &req));
+        assert!(!owner.evaluate(&json!("max"), &req));
+
+        let level = EqualsAttribute {
+            attribute: "level".to_owned(),
+        };
+        assert!(level.evaluate(&json!(3), &req));
+        assert!(!level.evaluate(&json!("3"), &req));
+
+        let subject = EqualsAttribute {
+            attribute: "subject".to_owned(),
+        };
+        assert!(subject.evaluate(&json!("peter"), &req));
+        assert!(!subject.evaluate(&json!("admin"), &req));
+        assert!(!subject.evaluate(&json!(1), &req));
+
+        let missing = EqualsAttribute {
+            attribute: "missing".to_owned(),
+        };
+        assert!(!missing.evaluate(&json!("peter"), &req));
+    }
+}
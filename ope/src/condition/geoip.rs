@@ -0,0 +1,207 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+
+use super::Condition;
+use crate::context::as_ip;
+use crate::req::Request;
+use crate::Result;
+
+pub trait GeoIpResolver: Send + Sync {
+    fn country(&self, ip: IpAddr) -> Option<String>;
+
+    fn asn(&self, ip: IpAddr) -> Option<u32>;
+}
+
+#[derive(Deserialize)]
+struct Options {
+    #[serde(default)]
+    countries: Vec<String>,
+    #[serde(default)]
+    asns: Vec<u32>,
+}
+
+pub struct GeoIpCondition {
+    countries: Vec<String>,
+    asns: Vec<u32>,
+    resolver: Arc<dyn GeoIpResolver>,
+}
+
+impl GeoIpCondition {
+    pub fn new(resolver: Arc<dyn GeoIpResolver>) -> Self {
+        Self {
+            countries: Vec::new(),
+            asns: Vec::new(),
+            resolver,
+        }
+    }
+
+    pub fn with_countries<I, S>(mut self, countries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.countries = countries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_asns(mut self, asns: impl IntoIterator<Item = u32>) -> Self {
+        self.asns = asns.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn factory(
+        resolver: Arc<dyn GeoIpResolver>,
+    ) -> impl Fn(&RawValue) -> Result<Box<dyn Condition>> + Send + Sync + 'static {
+        move |options| {
+            let options: Options = serde_json::from_str(options.get())?;
+            Ok(Box::new(
+                Self::new(resolver.clone())
+                    .with_countries(options.countries)
+                    .with_asns(options.asns),
+            ))
+        }
+    }
+}
+
+impl Condition for GeoIpCondition {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let Some(ip) = as_ip(input) else {
+            return false;
+        };
+        if !self.countries.is_empty() {
+            match self.resolver.country(ip) {
+                Some(country) => {
+                    if !self
+                        .countries
+                        .iter()
+                        .any(|v| v.eq_ignore_ascii_case(&country))
+                    {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        if !self.asns.is_empty() {
+            match self.resolver.asn(ip) {
+                Some(asn) => return self.asns.contains(&asn),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "geoip")]
+pub struct MaxMindResolver {
+    country: Option<maxminddb::Reader<Vec<u8>>>,
+    asn: Option<maxminddb::Reader<Vec<u8>>>,
+}
+
+#[cfg(feature = "geoip")]
+impl MaxMindResolver {
+    pub fn new() -> Self {
+        Self {
+            country: None,
+            asn: None,
+        }
+    }
+
+    pub fn with_country_db(mut self, path: impl AsRef<std::path::Path>) -> Result<Self> {
+        self.country = Some(maxminddb::Reader::open_readfile(path)?);
+        Ok(self)
+    }
+
+    pub fn with_asn_db(mut self, path: impl AsRef<std::path::Path>) -> Result<Self> {
+        self.asn = Some(maxminddb::Reader::open_readfile(path)?);
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "geoip")]
+impl Default for MaxMindResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "geoip")]
+impl GeoIpResolver for MaxMindResolver {
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let country: maxminddb::geoip2::Country = self.country.as_ref()?.lookup(ip).ok()?;
+        country
+            .country
+            .and_then(|v| v.iso_code)
+            .map(ToOwned::to_owned)
+    }
+
+    fn asn(&self, ip: IpAddr) -> Option<u32> {
+        let asn: maxminddb::geoip2::Asn = self.asn.as_ref()?.lookup(ip).ok()?;
+        asn.autonomous_system_number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::condition::{ConditionRegistry, JsonCondition};
+    use crate::context::Context;
+
+    struct Table(HashMap<IpAddr, (&'static str, u32)>);
+
+    impl GeoIpResolver for Table {
+        fn country(&self, ip: IpAddr) -> Option<String> {
+            self.0.get(&ip).map(|(country, _)| country.to_string())
+        }
+
+        fn asn(&self, ip: IpAddr) -> Option<u32> {
+            self.0.get(&ip).map(|(_, asn)| *asn)
+        }
+    }
+
+    #[test]
+    fn geoip() {
+        let table = Table(HashMap::from([
+            ("1.1.1.1".parse().unwrap(), ("AU", 13335)),
+            ("8.8.8.8".parse().unwrap(), ("US", 15169)),
+        ]));
+        let registry = ConditionRegistry::new();
+        registry.register_geoip(Arc::new(table)).unwrap();
+        let condition: JsonCondition = serde_json::from_value(json!({
+            "type": "GeoIP",
+            "options": {"countries": ["us", "DE"]}
+        }))
+        .unwrap();
+        let built = registry.build(&condition).unwrap();
+        let req = Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Context::new(),
+        };
+        assert!(built.evaluate(&json!("8.8.8.8"), &req));
+        assert!(!built.evaluate(&json!("1.1.1.1"), &req));
+        assert!(!built.evaluate(&json!("9.9.9.9"), &req));
+        assert!(!built.evaluate(&json!("not an ip"), &req));
+
+        let condition: JsonCondition = serde_json::from_value(json!({
+            "type": "GeoIP",
+            "options": {"asns": [13335]}
+        }))
+        .unwrap();
+        let built = registry.build(&condition).unwrap();
+        assert!(built.evaluate(&json!("1.1.1.1"), &req));
+        assert!(!built.evaluate(&json!("8.8.8.8"), &req));
+    }
+}
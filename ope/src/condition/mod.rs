@@ -1,21 +1,40 @@
 pub(crate) mod boolean;
+#[cfg(feature = "cel")]
+pub(crate) mod cel;
 pub(crate) mod cidr;
+pub(crate) mod combinator;
+pub(crate) mod equals_attribute;
+pub(crate) mod geoip;
+pub(crate) mod number;
 pub(crate) mod numeric_cmp;
+pub(crate) mod registry;
 pub(crate) mod resource_contains;
+pub(crate) mod schedule;
 pub(crate) mod string_cmp;
+pub(crate) mod string_equal;
+pub(crate) mod string_list;
 pub(crate) mod string_match;
 pub(crate) mod time_cmp;
 
-use crate::{Error, Result};
+use crate::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::{value::RawValue, Value};
 
 use crate::req::Request;
 
+#[cfg(feature = "cel")]
+pub use cel::CelCondition;
+#[cfg(feature = "geoip")]
+pub use geoip::MaxMindResolver;
+pub use geoip::{GeoIpCondition, GeoIpResolver};
+pub use registry::ConditionRegistry;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct JsonCondition {
     #[serde(rename = "type")]
     pub jtype: String,
+    #[cfg_attr(feature = "schema", schemars(with = "serde_json::Value"))]
     pub options: Box<RawValue>,
 }
 
@@ -30,44 +49,26 @@ impl PartialEq for JsonCondition {
 
 impl JsonCondition {
     pub fn into(&self) -> Result<Box<dyn Condition>> {
-        match self.jtype.as_str() {
-            "StringCmp" => {
-                let result: string_cmp::StringCmp =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-                Ok(Box::new(result))
-            }
-            "StringMatch" => {
-                let result: string_match::StringMatch =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-                Ok(Box::new(result))
-            }
-            "CIDR" => {
-                let result: cidr::Cidr =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-
-                Ok(Box::new(result))
-            }
-            "Boolean" => {
-                let result: boolean::Boolean =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-                Ok(Box::new(result))
-            }
-            "NumericCmp" => {
-                let result: numeric_cmp::NumericCmp =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-                Ok(Box::new(result))
-            }
-            "TimeCmp" => {
-                let result: time_cmp::TimeCmp =
-                    serde_json::from_str(self.options.get()).map_err(Error::SerdeError)?;
-                Ok(Box::new(result))
-            }
-            "ResourceContains" => Ok(Box::new(resource_contains::ResourceContains)),
-            v => Err(Error::NotFoundConditionType(v.to_string())),
-        }
+        registry::default_registry().build(self)
     }
 }
 
 pub trait Condition {
-    fn evaluate(&self, input: Box<RawValue>, req: &Request) -> bool;
+    fn evaluate(&self, input: &Value, req: &Request) -> bool;
+
+    fn reads_request(&self) -> bool {
+        true
+    }
+
+    fn reads_context(&self) -> bool {
+        true
+    }
+}
+
+pub(crate) fn lookup<'a>(req: &'a Request, key: &str, reads_context: bool) -> Option<&'a Value> {
+    static NULL: Value = Value::Null;
+    match req.context.lookup(key) {
+        Some(value) => Some(value),
+        None => (!reads_context).then_some(&NULL),
+    }
 }
@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use super::Condition;
+use crate::req::Request;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NumberEquals {
+    pub value: Number,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GreaterThan {
+    pub value: Number,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LessThanOrEqual {
+    pub value: Number,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Between {
+    pub min: Number,
+    pub max: Number,
+}
+
+impl Condition for NumberEquals {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        compare(input, &self.value) == Some(Ordering::Equal)
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+impl Condition for GreaterThan {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        compare(input, &self.value) == Some(Ordering::Greater)
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+impl Condition for LessThanOrEqual {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        matches!(
+            compare(input, &self.value),
+            Some(Ordering::Less | Ordering::Equal)
+        )
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+impl Condition for Between {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        matches!(
+            compare(input, &self.min),
+            Some(Ordering::Greater | Ordering::Equal)
+        ) && matches!(
+            compare(input, &self.max),
+            Some(Ordering::Less | Ordering::Equal)
+        )
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+fn parse(input: &Value) -> Option<Number> {
+    match input {
+        Value::Number(v) => Some(v.clone()),
+        Value::String(v) => serde_json::from_str::<Number>(v.trim()).ok(),
+        _ => None,
+    }
+}
+
+pub(super) fn compare(input: &Value, dest: &Number) -> Option<Ordering> {
+    let src = parse(input)?;
+    if let (Some(src), Some(dest)) = (src.as_i64(), dest.as_i64()) {
+        return Some(src.cmp(&dest));
+    }
+    if let (Some(src), Some(dest)) = (src.as_u64(), dest.as_u64()) {
+        return Some(src.cmp(&dest));
+    }
+    src.as_f64()?.partial_cmp(&dest.as_f64()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn thresholds() {
+        let req = Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Context::new(),
+        };
+        let gt = GreaterThan {
+            value: Number::from(1024),
+        };
+        assert!(gt.evaluate(&json!(2048), &req));
+        assert!(gt.evaluate(&json!("1024.5"), &req));
+        assert!(!gt.evaluate(&json!(1024), &req));
+        assert!(!gt.evaluate(&json!("big"), &req));
+
+        let le = LessThanOrEqual {
+            value: Number::from_f64(1.5).unwrap(),
+        };
+        assert!(le.evaluate(&json!(1), &req));
+        assert!(le.evaluate(&json!(1.5), &req));
+        assert!(!le.evaluate(&json!(2), &req));
+
+        let eq = NumberEquals {
+            value: Number::from(i64::MAX),
+        };
+        assert!(eq.evaluate(&json!(i64::MAX), &req));
+        assert!(!eq.evaluate(&json!(i64::MAX - 1), &req));
+
+        let between = Between {
+            min: Number::from(-5),
+            max: Number::from(5),
+        };
+        assert!(between.evaluate(&json!(-5), &req));
+        assert!(between.evaluate(&json!(4.9), &req));
+        assert!(!between.evaluate(&json!(6), &req));
+    }
+}
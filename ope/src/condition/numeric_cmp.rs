@@ -1,6 +1,9 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::Value;
 
+use super::number::compare;
 use super::Condition;
 use crate::req::Request;
 
@@ -11,52 +14,22 @@ pub struct NumericCmp {
 }
 
 impl Condition for NumericCmp {
-    fn evaluate(&self, input: Box<RawValue>, _req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<serde_json::Number>(input.get()) {
-            if v.is_f64() {
-                if let Some(src) = v.as_f64() {
-                    if let Some(dest) = self.value.as_f64() {
-                        match self.symbol.as_str() {
-                            "==" => return src == dest,
-                            "!=" => return src != dest,
-                            ">" => return src > dest,
-                            ">=" => return src >= dest,
-                            "<" => return src < dest,
-                            "<=" => return src <= dest,
-                            _ => {}
-                        }
-                    }
-                }
-            } else if v.is_i64() {
-                if let Some(src) = v.as_i64() {
-                    if let Some(dest) = self.value.as_i64() {
-                        match self.symbol.as_str() {
-                            "==" => return src == dest,
-                            "!=" => return src != dest,
-                            ">" => return src > dest,
-                            ">=" => return src >= dest,
-                            "<" => return src < dest,
-                            "<=" => return src <= dest,
-                            _ => {}
-                        }
-                    }
-                }
-            } else if v.is_u64() {
-                if let Some(src) = v.as_u64() {
-                    if let Some(dest) = self.value.as_u64() {
-                        match self.symbol.as_str() {
-                            "==" => return src == dest,
-                            "!=" => return src != dest,
-                            ">" => return src > dest,
-                            ">=" => return src >= dest,
-                            "<" => return src < dest,
-                            "<=" => return src <= dest,
-                            _ => {}
-                        }
-                    }
-                }
-            }
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let Some(ordering) = compare(input, &self.value) else {
+            return false;
+        };
+        match self.symbol.as_str() {
+            "==" => ordering == Ordering::Equal,
+            "!=" => ordering != Ordering::Equal,
+            ">" => ordering == Ordering::Greater,
+            ">=" => ordering != Ordering::Less,
+            "<" => ordering == Ordering::Less,
+            "<=" => ordering != Ordering::Greater,
+            _ => false,
         }
+    }
+
+    fn reads_request(&self) -> bool {
         false
     }
 }
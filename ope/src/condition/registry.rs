@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+
+use super::{
+    boolean, cidr, combinator, equals_attribute, geoip, number, numeric_cmp, resource_contains,
+    schedule, string_cmp, string_equal, string_list, string_match, time_cmp, Condition,
+    JsonCondition,
+};
+use crate::{Error, Result};
+
+type Factory = Box<dyn Fn(&RawValue) -> Result<Box<dyn Condition>> + Send + Sync>;
+
+pub struct ConditionRegistry {
+    factories: RwLock<HashMap<String, Factory>>,
+}
+
+impl Default for ConditionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConditionRegistry {
+    pub fn new() -> Self {
+        let mut factories: HashMap<String, Factory> = HashMap::new();
+        factories.insert("StringCmp".to_owned(), factory::<string_cmp::StringCmp>());
+        factories.insert(
+            "StringEqual".to_owned(),
+            factory::<string_equal::StringEqual>(),
+        );
+        factories.insert(
+            "StringMatch".to_owned(),
+            factory::<string_match::StringMatch>(),
+        );
+        factories.insert("StringIn".to_owned(), factory::<string_list::StringIn>());
+        factories.insert(
+            "StringNotIn".to_owned(),
+            factory::<string_list::StringNotIn>(),
+        );
+        factories.insert(
+            "ListContains".to_owned(),
+            factory::<string_list::ListContains>(),
+        );
+        factories.insert(
+            "EqualsAttribute".to_owned(),
+            factory::<equals_attribute::EqualsAttribute>(),
+        );
+        factories.insert("CIDR".to_owned(), factory::<cidr::Cidr>());
+        factories.insert("Boolean".to_owned(), factory::<boolean::Boolean>());
+        factories.insert(
+            "NumericCmp".to_owned(),
+            factory::<numeric_cmp::NumericCmp>(),
+        );
+        factories.insert("NumberEquals".to_owned(), factory::<number::NumberEquals>());
+        factories.insert("GreaterThan".to_owned(), factory::<number::GreaterThan>());
+        factories.insert(
+            "LessThanOrEqual".to_owned(),
+            factory::<number::LessThanOrEqual>(),
+        );
+        factories.insert("Between".to_owned(), factory::<number::Between>());
+        factories.insert("TimeOfDay".to_owned(), Box::new(schedule::TimeOfDay::build));
+        factories.insert("DayOfWeek".to_owned(), Box::new(schedule::DayOfWeek::build));
+        factories.insert("DateRange".to_owned(), Box::new(schedule::DateRange::build));
+        factories.insert("TimeCmp".to_owned(), factory::<time_cmp::TimeCmp>());
+        factories.insert(
+            "ResourceContains".to_owned(),
+            Box::new(|_| Ok(Box::new(resource_contains::ResourceContains))),
+        );
+        #[cfg(feature = "cel")]
+        factories.insert("CEL".to_owned(), Box::new(super::cel::CelCondition::build));
+        Self {
+            factories: RwLock::new(factories),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            factories: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn register<C>(&self, name: &str) -> Result<()>
+    where
+        C: Condition + DeserializeOwned + 'static,
+    {
+        let mut factories = self
+            .factories
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        factories.insert(name.to_owned(), factory::<C>());
+        Ok(())
+    }
+
+    pub fn register_fn<F>(&self, name: &str, factory: F) -> Result<()>
+    where
+        F: Fn(&RawValue) -> Result<Box<dyn Condition>> + Send + Sync + 'static,
+    {
+        let mut factories = self
+            .factories
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        factories.insert(name.to_owned(), Box::new(factory));
+        Ok(())
+    }
+
+    pub fn register_geoip(&self, resolver: Arc<dyn geoip::GeoIpResolver>) -> Result<()> {
+        self.register_fn("GeoIP", geoip::GeoIpCondition::factory(resolver))
+    }
+
+    pub fn contains(&self, name: &str) -> Result<bool> {
+        let factories = self
+            .factories
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        Ok(combinator::is_combinator(name) || factories.contains_key(name))
+    }
+
+    pub fn build(&self, condition: &JsonCondition) -> Result<Box<dyn Condition>> {
+        if let Some(built) = combinator::build(self, condition) {
+            return built;
+        }
+        let factories = self
+            .factories
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        match factories.get(condition.jtype.as_str()) {
+            Some(factory) => factory(&condition.options),
+            None => Err(Error::NotFoundConditionType(condition.jtype.clone())),
+        }
+    }
+}
+
+fn factory<C>() -> Factory
+where
+    C: Condition + DeserializeOwned + 'static,
+{
+    Box::new(|options| {
+        let result: C = serde_json::from_str(options.get()).map_err(Error::SerdeError)?;
+        Ok(Box::new(result))
+    })
+}
+
+pub(crate) fn default_registry() -> &'static ConditionRegistry {
+    static REGISTRY: OnceLock<ConditionRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(ConditionRegistry::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::req::Request;
+
+    #[derive(Deserialize)]
+    struct Even {
+        enabled: bool,
+    }
+
+    impl Condition for Even {
+        fn evaluate(&self, input: &serde_json::Value, _req: &Request) -> bool {
+            match input.as_i64() {
+                Some(v) => !self.enabled || v % 2 == 0,
+                None => false,
+            }
+        }
+    }
+
+    #[test]
+    fn register() {
+        let registry = ConditionRegistry::new();
+        let condition = JsonCondition {
+            jtype: "Even".to_owned(),
+            options: serde_json::value::to_raw_value(&serde_json::json!({"enabled": true}))
+                .unwrap(),
+        };
+        assert!(matches!(
+            registry.build(&condition),
+            Err(Error::NotFoundConditionType(_))
+        ));
+        registry.register::<Even>("Even").unwrap();
+        let even = registry.build(&condition).unwrap();
+        let req = Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Default::default(),
+        };
+        assert!(even.evaluate(&serde_json::json!(4), &req));
+        assert!(!even.evaluate(&serde_json::json!(3), &req));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use serde_json::value::RawValue;
+use serde::Deserialize;
+use serde_json::Value;
 
 use super::Condition;
 use crate::req::Request;
@@ -9,8 +10,8 @@ use crate::req::Request;
 pub struct ResourceContains;
 
 impl Condition for ResourceContains {
-    fn evaluate(&self, input: Box<RawValue>, req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<HashMap<String, String>>(input.get()) {
+    fn evaluate(&self, input: &Value, req: &Request) -> bool {
+        if let Ok(v) = HashMap::<String, String>::deserialize(input) {
             let value = match v.get("value") {
                 Some(value_string) => {
                     if value_string.is_empty() {
@@ -0,0 +1,311 @@
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use chrono_tz::Tz;
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+
+use super::Condition;
+use crate::req::Request;
+use crate::Result;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimeOfDay {
+    pub start: String,
+    pub end: String,
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub from_context: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DayOfWeek {
+    pub days: Vec<String>,
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub from_context: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DateRange {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub from_context: bool,
+}
+
+impl TimeOfDay {
+    pub(crate) fn build(options: &RawValue) -> Result<Box<dyn Condition>> {
+        let hours: Self = serde_json::from_str(options.get())?;
+        validate_timezone(&hours.timezone)?;
+        let start = parse_time(&hours.start).ok_or_else(|| invalid("time", &hours.start))?;
+        let end = parse_time(&hours.end).ok_or_else(|| invalid("time", &hours.end))?;
+        if start == end {
+            return Err(serde_json::Error::custom(format!(
+                "TimeOfDay start and end are both {}",
+                hours.start
+            ))
+            .into());
+        }
+        Ok(Box::new(hours))
+    }
+}
+
+impl DayOfWeek {
+    pub(crate) fn build(options: &RawValue) -> Result<Box<dyn Condition>> {
+        let days: Self = serde_json::from_str(options.get())?;
+        validate_timezone(&days.timezone)?;
+        if let Some(day) = days.days.iter().find(|v| Weekday::from_str(v).is_err()) {
+            return Err(invalid("day", day));
+        }
+        Ok(Box::new(days))
+    }
+}
+
+impl DateRange {
+    pub(crate) fn build(options: &RawValue) -> Result<Box<dyn Condition>> {
+        let range: Self = serde_json::from_str(options.get())?;
+        let tz = validate_timezone(&range.timezone)?;
+        for (bound, end) in [(&range.start, false), (&range.end, true)] {
+            if let Some(bound) = bound {
+                parse_bound(bound, &tz, end).ok_or_else(|| invalid("date", bound))?;
+            }
+        }
+        Ok(Box::new(range))
+    }
+}
+
+impl Condition for TimeOfDay {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let (Some(tz), Some(start), Some(end)) = (
+            timezone(&self.timezone),
+            parse_time(&self.start),
+            parse_time(&self.end),
+        ) else {
+            return false;
+        };
+        let Some(now) = now(self.from_context, input) else {
+            return false;
+        };
+        let time = now.with_timezone(&tz).time();
+        if start <= end {
+            return start <= time && time < end;
+        }
+        time >= start || time < end
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+
+    fn reads_context(&self) -> bool {
+        self.from_context
+    }
+}
+
+impl Condition for DayOfWeek {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let (Some(tz), Some(now)) = (timezone(&self.timezone), now(self.from_context, input))
+        else {
+            return false;
+        };
+        let weekday = now.with_timezone(&tz).weekday();
+        self.days
+            .iter()
+            .filter_map(|v| Weekday::from_str(v).ok())
+            .any(|v| v == weekday)
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+
+    fn reads_context(&self) -> bool {
+        self.from_context
+    }
+}
+
+impl Condition for DateRange {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        let (Some(tz), Some(now)) = (timezone(&self.timezone), now(self.from_context, input))
+        else {
+            return false;
+        };
+        if let Some(start) = &self.start {
+            match parse_bound(start, &tz, false) {
+                Some(start) if now >= start => {}
+                _ => return false,
+            }
+        }
+        if let Some(end) = &self.end {
+            match parse_bound(end, &tz, true) {
+                Some(end) if now < end => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+
+    fn reads_context(&self) -> bool {
+        self.from_context
+    }
+}
+
+fn timezone(name: &Option<String>) -> Option<Tz> {
+    match name {
+        Some(name) => Tz::from_str(name).ok(),
+        None => Some(Tz::UTC),
+    }
+}
+
+fn validate_timezone(name: &Option<String>) -> Result<Tz> {
+    match name {
+        Some(name) => Tz::from_str(name).map_err(|_| invalid("timezone", name)),
+        None => Ok(Tz::UTC),
+    }
+}
+
+fn invalid(kind: &str, value: &str) -> crate::Error {
+    serde_json::Error::custom(format!("invalid {kind} {value}")).into()
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .ok()
+}
+
+fn now(from_context: bool, input: &Value) -> Option<DateTime<Utc>> {
+    match from_context {
+        true => instant(input),
+        false => Some(Utc::now()),
+    }
+}
+
+fn instant(input: &Value) -> Option<DateTime<Utc>> {
+    if let Some(v) = input.as_i64() {
+        return DateTime::from_timestamp(v, 0);
+    }
+    DateTime::parse_from_rfc3339(input.as_str()?)
+        .ok()
+        .map(|v| v.with_timezone(&Utc))
+}
+
+fn parse_bound(value: &str, tz: &Tz, end: bool) -> Option<DateTime<Utc>> {
+    if let Ok(v) = DateTime::parse_from_rfc3339(value) {
+        return Some(v.with_timezone(&Utc));
+    }
+    let naive = match NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        Ok(v) => v,
+        Err(_) => {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+            let date = if end { date.succ_opt()? } else { date };
+            date.and_hms_opt(0, 0, 0)?
+        }
+    };
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|v| v.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+
+    fn raw(v: &str) -> Value {
+        Value::from(v)
+    }
+
+    fn req() -> Request {
+        Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Context::new(),
+        }
+    }
+
+    #[test]
+    fn office_hours() {
+        let hours = TimeOfDay {
+            start: "09:00".to_owned(),
+            end: "17:00".to_owned(),
+            timezone: Some("Asia/Shanghai".to_owned()),
+            from_context: true,
+        };
+        assert!(hours.evaluate(&raw("2024-03-04T02:30:00Z"), &req()));
+        assert!(!hours.evaluate(&raw("2024-03-04T09:30:00Z"), &req()));
+
+        let night = TimeOfDay {
+            start: "22:00".to_owned(),
+            end: "06:00".to_owned(),
+            timezone: None,
+            from_context: true,
+        };
+        assert!(night.evaluate(&raw("2024-03-04T23:00:00Z"), &req()));
+        assert!(night.evaluate(&raw("2024-03-04T05:59:59Z"), &req()));
+        assert!(!night.evaluate(&raw("2024-03-04T12:00:00Z"), &req()));
+
+        let options = |start: &str, end: &str| {
+            serde_json::value::to_raw_value(&serde_json::json!({"start": start, "end": end}))
+                .unwrap()
+        };
+        assert!(TimeOfDay::build(&options("09:00", "09:00:00")).is_err());
+        assert!(TimeOfDay::build(&options("9am", "17:00")).is_err());
+        let hours = TimeOfDay::build(&options("00:00", "23:59:59")).unwrap();
+        assert!(!hours.reads_context());
+    }
+
+    #[test]
+    fn weekdays() {
+        let weekdays = DayOfWeek {
+            days: ["Mon", "Tue", "Wed", "Thu", "Fri"]
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            timezone: Some("America/New_York".to_owned()),
+            from_context: true,
+        };
+        // Monday 01:00 UTC is still Sunday in New York
+        assert!(!weekdays.evaluate(&raw("2024-03-04T01:00:00Z"), &req()));
+        assert!(weekdays.evaluate(&raw("2024-03-04T15:00:00Z"), &req()));
+
+        let options = |days: &[&str], timezone: &str| {
+            serde_json::value::to_raw_value(
+                &serde_json::json!({"days": days, "timezone": timezone}),
+            )
+            .unwrap()
+        };
+        assert!(DayOfWeek::build(&options(&["Mon", "Funday"], "UTC")).is_err());
+        assert!(DayOfWeek::build(&options(&["Mon"], "Mars/Olympus")).is_err());
+        assert!(DayOfWeek::build(&options(&["Mon"], "Europe/Paris")).is_ok());
+    }
+
+    #[test]
+    fn date_range() {
+        let range = DateRange {
+            start: Some("2024-01-01".to_owned()),
+            end: Some("2024-01-31".to_owned()),
+            timezone: Some("UTC".to_owned()),
+            from_context: true,
+        };
+        assert!(range.evaluate(&raw("2024-01-31T23:59:59Z"), &req()));
+        assert!(!range.evaluate(&raw("2024-02-01T00:00:00Z"), &req()));
+        assert!(!range.evaluate(&raw("2023-12-31T23:59:59Z"), &req()));
+
+        let options = |start: &str| {
+            serde_json::value::to_raw_value(&serde_json::json!({"start": start})).unwrap()
+        };
+        assert!(DateRange::build(&options("January")).is_err());
+        // without from_context the caller cannot pick "now"
+        let range = DateRange::build(&options("2000-01-01")).unwrap();
+        assert!(range.evaluate(&raw("1999-01-01T00:00:00Z"), &req()));
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::Value;
 
 use super::Condition;
 use crate::req::Request;
@@ -17,11 +17,11 @@ pub struct StringCmpInner {
 }
 
 impl Condition for StringCmp {
-    fn evaluate(&self, input: Box<RawValue>, _req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<String>(input.get()) {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        if let Some(v) = input.as_str() {
             let mut matched = false;
             for dest_value in self.values.iter() {
-                if !self.cmp(&v, dest_value) {
+                if !self.cmp(v, dest_value) {
                     return false;
                 }
                 matched = true;
@@ -30,6 +30,10 @@ impl Condition for StringCmp {
         }
         false
     }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
 }
 
 impl StringCmp {
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Condition;
+use crate::req::Request;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StringEqual {
+    pub equals: String,
+}
+
+impl Condition for StringEqual {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        if let Some(v) = input.as_str() {
+            return v == self.equals;
+        }
+        false
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
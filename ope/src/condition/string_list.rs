@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Condition;
+use crate::req::Request;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StringIn {
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StringNotIn {
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListContains {
+    pub value: String,
+}
+
+impl Condition for StringIn {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        match strings(input) {
+            Some(items) => !items.is_empty() && items.iter().all(|v| self.values.contains(v)),
+            None => false,
+        }
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+impl Condition for StringNotIn {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        match strings(input) {
+            Some(items) => !items.iter().any(|v| self.values.contains(v)),
+            None => false,
+        }
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+impl Condition for ListContains {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        match input {
+            Value::Array(items) => items.iter().any(|v| v.as_str() == Some(&self.value)),
+            _ => false,
+        }
+    }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
+}
+
+fn strings(input: &Value) -> Option<Vec<String>> {
+    match input {
+        Value::String(v) => Some(vec![v.clone()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| v.as_str().map(ToOwned::to_owned))
+            .collect(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn membership() {
+        let req = Request {
+            resource: String::new(),
+            action: String::new(),
+            subject: String::new(),
+            context: Context::new(),
+        };
+        let values = vec!["eng".to_owned(), "ops".to_owned()];
+        let within = StringIn {
+            values: values.clone(),
+        };
+        assert!(within.evaluate(&json!("eng"), &req));
+        assert!(within.evaluate(&json!(["eng", "ops"]), &req));
+        assert!(!within.evaluate(&json!(["eng", "sales"]), &req));
+        assert!(!within.evaluate(&json!([]), &req));
+        assert!(!within.evaluate(&json!(1), &req));
+
+        let outside = StringNotIn { values };
+        assert!(outside.evaluate(&json!("sales"), &req));
+        assert!(outside.evaluate(&json!([]), &req));
+        assert!(!outside.evaluate(&json!(["sales", "ops"]), &req));
+        assert!(!outside.evaluate(&json!([1]), &req));
+
+        let contains = ListContains {
+            value: "admin".to_owned(),
+        };
+        assert!(contains.evaluate(&json!(["user", "admin"]), &req));
+        assert!(!contains.evaluate(&json!(["user"]), &req));
+        assert!(!contains.evaluate(&json!("admin"), &req));
+    }
+}
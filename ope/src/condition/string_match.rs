@@ -1,8 +1,8 @@
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::Value;
 
 use super::Condition;
+use crate::matcher::backend::Regex;
 use crate::req::Request;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -11,12 +11,16 @@ pub struct StringMatch {
 }
 
 impl Condition for StringMatch {
-    fn evaluate(&self, input: Box<RawValue>, _req: &Request) -> bool {
-        if let Ok(v) = serde_json::from_str::<String>(input.get()) {
+    fn evaluate(&self, input: &Value, _req: &Request) -> bool {
+        if let Some(v) = input.as_str() {
             if let Ok(matcher) = Regex::new(&self.matches) {
-                return matcher.is_match(&v);
+                return matcher.is_match(v).unwrap_or_default();
             }
         }
         false
     }
+
+    fn reads_request(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Context(HashMap<String, Value>);
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    pub fn lookup(&self, key: &str) -> Option<&Value> {
+        if let Some(value) = self.0.get(key) {
+            return Some(value);
+        }
+        let pointer = key.strip_prefix('/')?;
+        let (head, rest) = match pointer.find('/') {
+            Some(i) => pointer.split_at(i),
+            None => (pointer, ""),
+        };
+        let head = head.replace("~1", "/").replace("~0", "~");
+        self.0.get(&head)?.pointer(rest)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.0.iter()
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<&str> {
+        self.value(key)?
+            .as_str()
+            .ok_or_else(|| mismatch(key, "string"))
+    }
+
+    pub fn get_i64(&self, key: &str) -> Result<i64> {
+        as_i64(self.value(key)?).ok_or_else(|| mismatch(key, "i64"))
+    }
+
+    pub fn get_f64(&self, key: &str) -> Result<f64> {
+        as_f64(self.value(key)?).ok_or_else(|| mismatch(key, "f64"))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        as_bool(self.value(key)?).ok_or_else(|| mismatch(key, "bool"))
+    }
+
+    pub fn get_ip(&self, key: &str) -> Result<IpAddr> {
+        as_ip(self.value(key)?).ok_or_else(|| mismatch(key, "ip address"))
+    }
+
+    fn value(&self, key: &str) -> Result<&Value> {
+        self.0
+            .get(key)
+            .ok_or_else(|| Error::ContextKeyNotFound(key.to_owned()))
+    }
+}
+
+pub(crate) fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(v) => v.as_i64(),
+        Value::String(v) => v.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(v) => v.as_f64(),
+        Value::String(v) => v.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(v) => Some(*v),
+        Value::String(v) => v.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn as_ip(value: &Value) -> Option<IpAddr> {
+    value.as_str().and_then(|v| v.trim().parse().ok())
+}
+
+fn mismatch(key: &str, expected: &str) -> Error {
+    Error::ContextTypeMismatch {
+        key: key.to_owned(),
+        expected: expected.to_owned(),
+    }
+}
+
+impl From<HashMap<String, Value>> for Context {
+    fn from(value: HashMap<String, Value>) -> Self {
+        Self(value)
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Context {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>, const N: usize> From<[(K, V); N]> for Context {
+    fn from(value: [(K, V); N]) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed() {
+        let ctx = Context::from([
+            ("name", Value::from("peter")),
+            ("count", Value::from(6)),
+            ("size", Value::from("1.5")),
+            ("mfa", Value::from("true")),
+            ("clientIP", Value::from("192.168.1.67")),
+        ]);
+        assert_eq!(ctx.get_str("name").unwrap(), "peter");
+        assert_eq!(ctx.get_i64("count").unwrap(), 6);
+        assert_eq!(ctx.get_f64("size").unwrap(), 1.5);
+        assert!(ctx.get_bool("mfa").unwrap());
+        assert_eq!(
+            ctx.get_ip("clientIP").unwrap(),
+            "192.168.1.67".parse::<IpAddr>().unwrap()
+        );
+        assert!(matches!(
+            ctx.get_i64("name"),
+            Err(Error::ContextTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            ctx.get_str("missing"),
+            Err(Error::ContextKeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn lookup() {
+        let ctx = Context::from([
+            ("team", serde_json::json!("flat")),
+            (
+                "request",
+                serde_json::json!({"object": {"metadata": {"labels": {"team": "eng"}}, "items": [1, 2]}}),
+            ),
+            ("a/b", serde_json::json!({"c": true})),
+        ]);
+        assert_eq!(ctx.lookup("team"), Some(&Value::from("flat")));
+        assert_eq!(
+            ctx.lookup("/request/object/metadata/labels/team"),
+            Some(&Value::from("eng"))
+        );
+        assert_eq!(ctx.lookup("/request/object/items/1"), Some(&Value::from(2)));
+        assert_eq!(ctx.lookup("/a~1b/c"), Some(&Value::from(true)));
+        assert!(ctx.lookup("/team").is_some());
+        assert!(ctx.lookup("/request/object/missing").is_none());
+        assert!(ctx.lookup("request.object").is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use validator::Validate;
+
+use super::literal;
+use crate::policy::Policy;
+use crate::resolver::RoleGraph;
+use crate::statement::{Effect, Statement};
+use crate::{Error, Result};
+
+const ANY: &str = "<.*>";
+const FIELDS: [&str; 3] = ["sub", "obj", "act"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Role,
+    KeyMatch,
+    KeyMatch2,
+    RegexMatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Effects {
+    AllowOverride,
+    DenyOverride,
+}
+
+#[derive(Debug, Clone)]
+pub struct CasbinModel {
+    policy: Vec<String>,
+    ops: HashMap<String, Op>,
+    effects: Effects,
+}
+
+pub struct CasbinImport {
+    pub policies: Vec<Policy>,
+    pub roles: RoleGraph,
+}
+
+impl CasbinModel {
+    pub fn from_conf(content: &str) -> Result<Self> {
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut section = String::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                section = name.trim().to_owned();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(unsupported(format!("line {line}")));
+            };
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_owned(), value.trim().to_owned());
+        }
+        let definition = |section: &str, key: &str| {
+            sections
+                .get(section)
+                .and_then(|v| v.get(key))
+                .ok_or_else(|| unsupported(format!("missing {section} {key}")))
+        };
+        let request = tokens(definition("request_definition", "r")?);
+        if request != FIELDS {
+            return Err(unsupported(format!(
+                "request definition {}",
+                request.join(", ")
+            )));
+        }
+        let policy = tokens(definition("policy_definition", "p")?);
+        if FIELDS.iter().any(|v| !policy.iter().any(|p| p == v))
+            || policy
+                .iter()
+                .any(|v| !FIELDS.contains(&v.as_str()) && v != "eft")
+        {
+            return Err(unsupported(format!(
+                "policy definition {}",
+                policy.join(", ")
+            )));
+        }
+        if let Some(roles) = sections.get("role_definition") {
+            if roles.keys().any(|v| v != "g") || tokens(&roles["g"]) != ["_", "_"] {
+                return Err(unsupported("role definition other than g = _, _"));
+            }
+        }
+        let effects = match compact(definition("policy_effect", "e")?).as_str() {
+            "some(where(p.eft==allow))" => Effects::AllowOverride,
+            "some(where(p.eft==allow))&&!some(where(p.eft==deny))" => Effects::DenyOverride,
+            effect => return Err(unsupported(format!("policy effect {effect}"))),
+        };
+        let mut ops = HashMap::new();
+        for term in compact(definition("matchers", "m")?).split("&&") {
+            let (field, op) = matcher(term)?;
+            if op == Op::Role && (field != "sub" || !sections.contains_key("role_definition")) {
+                return Err(unsupported(format!("matcher {term}")));
+            }
+            if ops.insert(field.clone(), op).is_some() {
+                return Err(unsupported(format!("multiple matchers on {field}")));
+            }
+        }
+        Ok(Self {
+            policy,
+            ops,
+            effects,
+        })
+    }
+
+    pub fn import(&self, policy_csv: &str) -> Result<CasbinImport> {
+        let roles = RoleGraph::new();
+        let mut policies = Vec::new();
+        for line in policy_csv.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let columns = columns(line);
+            match columns.split_first() {
+                Some((kind, values)) if kind == "p" => {
+                    if values.len() != self.policy.len() {
+                        return Err(unsupported(format!("policy line {line}")));
+                    }
+                    let row: HashMap<_, _> = self
+                        .policy
+                        .iter()
+                        .map(String::as_str)
+                        .zip(values.iter().map(String::as_str))
+                        .collect();
+                    let effect = match row.get("eft").copied() {
+                        None | Some("allow") => Effect::Allow,
+                        Some("deny") if self.effects == Effects::DenyOverride => Effect::Deny,
+                        Some("deny") => continue,
+                        Some(effect) => return Err(unsupported(format!("effect {effect}"))),
+                    };
+                    let field = |name: &str| self.pattern(name, row[name]);
+                    let policy = Policy {
+                        id: format!("p{}", policies.len()),
+                        description: String::new(),
+                        statements: vec![Statement {
+                            effect,
+                            subjects: vec![field("sub")?],
+                            actions: vec![field("act")?],
+                            resources: vec![field("obj")?],
+                            conditions: None,
+                            meta: None,
+                            obligations: None,
+                            not_subjects: Vec::new(),
+                            not_actions: Vec::new(),
+                            not_resources: Vec::new(),
+                            subject_matcher: None,
+                            action_matcher: None,
+                            resource_matcher: None,
+                            compiled: Default::default(),
+                        }],
+                        meta: None,
+                        priority: 0,
+                    };
+                    policy.validate()?;
+                    policies.push(policy);
+                }
+                Some((kind, [member, role])) if kind == "g" => roles.add(member, role)?,
+                _ => return Err(unsupported(format!("policy line {line}"))),
+            }
+        }
+        Ok(CasbinImport { policies, roles })
+    }
+
+    fn pattern(&self, field: &str, value: &str) -> Result<String> {
+        Ok(match self.ops.get(field) {
+            None => ANY.to_owned(),
+            Some(Op::Equal | Op::Role) => literal(value),
+            Some(Op::KeyMatch) => key_match(value),
+            Some(Op::KeyMatch2) => value
+                .split('/')
+                .map(|segment| match segment.starts_with(':') {
+                    true => "<[^/]+>".to_owned(),
+                    false => key_match(segment),
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+            Some(Op::RegexMatch) if value.contains(['<', '>']) => {
+                return Err(unsupported(format!("regex {value} with delimiters")))
+            }
+            Some(Op::RegexMatch) => format!("<.*(?:{value}).*>"),
+        })
+    }
+}
+
+fn unsupported(construct: impl Into<String>) -> Error {
+    Error::UnsupportedCasbin(construct.into())
+}
+
+fn tokens(definition: &str) -> Vec<String> {
+    definition.split(',').map(|v| v.trim().to_owned()).collect()
+}
+
+fn compact(expression: &str) -> String {
+    expression.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn matcher(term: &str) -> Result<(String, Op)> {
+    let field = |r: &str, p: &str| match (r.strip_prefix("r."), p.strip_prefix("p.")) {
+        (Some(r), Some(p)) if r == p && FIELDS.contains(&r) => Ok(r.to_owned()),
+        _ => Err(unsupported(format!("matcher {term}"))),
+    };
+    if let Some((r, p)) = term.split_once("==") {
+        return Ok((field(r, p)?, Op::Equal));
+    }
+    let Some((name, args)) = term.strip_suffix(')').and_then(|v| v.split_once('(')) else {
+        return Err(unsupported(format!("matcher {term}")));
+    };
+    let Some((r, p)) = args.split_once(',') else {
+        return Err(unsupported(format!("matcher {term}")));
+    };
+    let op = match name {
+        "g" => Op::Role,
+        "keyMatch" => Op::KeyMatch,
+        "keyMatch2" => Op::KeyMatch2,
+        "regexMatch" => Op::RegexMatch,
+        _ => return Err(unsupported(format!("matcher function {name}"))),
+    };
+    Ok((field(r, p)?, op))
+}
+
+fn key_match(value: &str) -> String {
+    value.split('*').map(literal).collect::<Vec<_>>().join(ANY)
+}
+
+fn columns(line: &str) -> Vec<String> {
+    let (mut columns, mut current, mut quoted) = (Vec::new(), String::new(), false);
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => columns.push(std::mem::take(&mut current).trim().to_owned()),
+            c => current.push(c),
+        }
+    }
+    columns.push(current.trim().to_owned());
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Enforcer, Regexp, Request};
+
+    #[test]
+    fn rbac() {
+        let model = CasbinModel::from_conf(
+            r#"
+            [request_definition]
+            r = sub, obj, act
+
+            [policy_definition]
+            p = sub, obj, act, eft
+
+            [role_definition]
+            g = _, _
+
+            [policy_effect]
+            e = some(where (p.eft == allow)) && !some(where (p.eft == deny))
+
+            [matchers]
+            m = g(r.sub, p.sub) && keyMatch2(r.obj, p.obj) && regexMatch(r.act, p.act)
+            "#,
+        )
+        .unwrap();
+        let import = model
+            .import(
+                r#"
+                p, data_admin, /data/:id, "(GET)|(POST)", allow
+                p, alice, /data/secret, GET, deny
+                p, bob, /files/*, GET, allow
+                g, alice, data_admin
+                "#,
+            )
+            .unwrap();
+        assert_eq!(import.policies.len(), 3);
+        assert_eq!(
+            import.policies[0].statements[0].resources,
+            vec!["/data/<[^/]+>"]
+        );
+
+        let enforcer =
+            Enforcer::new(Regexp::new(16).unwrap(), import.policies).with_resolver(import.roles);
+        let request = |subject: &str, resource: &str, action: &str| Request {
+            resource: resource.to_owned(),
+            action: action.to_owned(),
+            subject: subject.to_owned(),
+            context: Context::new(),
+        };
+        assert!(enforcer
+            .is_allowed(&request("alice", "/data/1", "POST"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&request("alice", "/data/1", "DELETE"))
+            .is_err());
+        assert!(enforcer
+            .is_allowed(&request("alice", "/data/1/x", "GET"))
+            .is_err());
+        assert!(matches!(
+            enforcer.is_allowed(&request("alice", "/data/secret", "GET")),
+            Err(Error::Deny { .. })
+        ));
+        assert!(enforcer
+            .is_allowed(&request("bob", "/files/a/b", "GET"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&request("bob", "/data/1", "GET"))
+            .is_err());
+    }
+
+    #[test]
+    fn acl() {
+        let model = CasbinModel::from_conf(
+            r#"
+            [request_definition]
+            r = sub, obj, act
+            [policy_definition]
+            p = sub, obj, act
+            [policy_effect]
+            e = some(where (p.eft == allow))
+            [matchers]
+            m = r.sub == p.sub && r.obj == p.obj && r.act == p.act
+            "#,
+        )
+        .unwrap();
+        let import = model.import("p, alice, data1, read\n").unwrap();
+        assert_eq!(import.policies[0].statements[0].subjects, vec!["alice"]);
+        assert!(matches!(
+            model.import("g, alice, admin, domain1"),
+            Err(Error::UnsupportedCasbin(_))
+        ));
+        assert!(matches!(
+            CasbinModel::from_conf(
+                "[request_definition]\nr = sub, dom, obj, act\n[policy_definition]\np = sub, obj, act\n"
+            ),
+            Err(Error::UnsupportedCasbin(_))
+        ));
+    }
+}
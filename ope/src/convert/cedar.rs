@@ -0,0 +1,534 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, value::to_raw_value, Value};
+use validator::Validate;
+
+use super::{context_key, literal, merge_conditions};
+use crate::condition::JsonCondition;
+use crate::matcher::backend;
+use crate::policy::Policy;
+use crate::statement::{Effect, Statement};
+use crate::{Error, Result};
+
+const ANY: &str = "<.*>";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(i64),
+    Punct(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub struct CedarPolicySet {
+    policies: Vec<Policy>,
+}
+
+impl CedarPolicySet {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(content)?,
+            pos: 0,
+        };
+        let mut policies = Vec::new();
+        while parser.peek().is_some() {
+            let policy = parser.policy(policies.len())?;
+            policy.validate()?;
+            policies.push(policy);
+        }
+        Ok(Self { policies })
+    }
+
+    pub fn policies(&self) -> &[Policy] {
+        &self.policies
+    }
+
+    pub fn into_policies(self) -> Vec<Policy> {
+        self.policies
+    }
+}
+
+fn unsupported(construct: impl Into<String>) -> Error {
+    Error::UnsupportedCedar(construct.into())
+}
+
+fn tokenize(content: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '/' => {
+                chars.next();
+                if chars.next() != Some('/') {
+                    return Err(unsupported("character /"));
+                }
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '"' => {
+                chars.next();
+                let mut buffer = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => buffer.push('\n'),
+                            Some('t') => buffer.push('\t'),
+                            Some('*') => buffer.push_str("\\*"),
+                            Some(c) => buffer.push(c),
+                            None => return Err(unsupported("unterminated string")),
+                        },
+                        Some(c) => buffer.push(c),
+                        None => return Err(unsupported("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(buffer));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut buffer = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '-') {
+                    buffer.push(c);
+                }
+                let number = buffer
+                    .parse()
+                    .map_err(|_| unsupported(format!("number {buffer}")))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut buffer = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    buffer.push(c);
+                }
+                tokens.push(Token::Ident(buffer));
+            }
+            _ => {
+                chars.next();
+                let punct = match (c, chars.peek()) {
+                    (':', Some(':')) => "::",
+                    ('=', Some('=')) => "==",
+                    ('!', Some('=')) => "!=",
+                    ('<', Some('=')) => "<=",
+                    ('>', Some('=')) => ">=",
+                    ('&', Some('&')) => "&&",
+                    ('|', Some('|')) => "||",
+                    _ => "",
+                };
+                if !punct.is_empty() {
+                    chars.next();
+                    tokens.push(Token::Punct(punct));
+                    continue;
+                }
+                let punct = match c {
+                    '(' => "(",
+                    ')' => ")",
+                    '[' => "[",
+                    ']' => "]",
+                    '{' => "{",
+                    '}' => "}",
+                    ',' => ",",
+                    ';' => ";",
+                    '.' => ".",
+                    '<' => "<",
+                    '>' => ">",
+                    '!' => "!",
+                    '@' => "@",
+                    c => return Err(unsupported(format!("character {c}"))),
+                };
+                tokens.push(Token::Punct(punct));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(v)) if v == keyword) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(unsupported(format!(
+                "expected {punct} near {:?}",
+                self.peek()
+            ))),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => Err(unsupported(format!(
+                "expected {keyword} near {:?}",
+                self.peek()
+            ))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Ident(v)) => Ok(v),
+            token => Err(unsupported(format!("expected identifier near {token:?}"))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Str(v)) => Ok(v),
+            token => Err(unsupported(format!("expected string near {token:?}"))),
+        }
+    }
+
+    fn policy(&mut self, index: usize) -> Result<Policy> {
+        let mut annotations = BTreeMap::new();
+        while self.eat("@") {
+            let name = self.ident()?;
+            self.expect("(")?;
+            let value = self.string()?;
+            self.expect(")")?;
+            annotations.insert(name, value);
+        }
+        let effect = match self.ident()?.as_str() {
+            "permit" => Effect::Allow,
+            "forbid" => Effect::Deny,
+            effect => return Err(unsupported(format!("effect {effect}"))),
+        };
+        self.expect("(")?;
+        self.expect_keyword("principal")?;
+        let subjects = self.scope("principal")?;
+        self.expect(",")?;
+        self.expect_keyword("action")?;
+        let actions = self.scope("action")?;
+        self.expect(",")?;
+        self.expect_keyword("resource")?;
+        let resources = self.scope("resource")?;
+        self.expect(")")?;
+
+        let mut conditions: HashMap<String, Vec<JsonCondition>> = HashMap::new();
+        loop {
+            if self.eat_keyword("when") {
+                for (key, condition) in self.clause()? {
+                    conditions.entry(key).or_default().push(condition);
+                }
+            } else if self.eat_keyword("unless") {
+                let mut clause = self.clause()?;
+                let Some((key, _)) = clause.first() else {
+                    return Err(unsupported("empty unless clause"));
+                };
+                if clause.iter().any(|(k, _)| k != key) {
+                    return Err(unsupported("unless over multiple context keys"));
+                }
+                let key = key.clone();
+                let negated = match clause.len() {
+                    1 => clause.remove(0).1,
+                    _ => condition(
+                        "AllOf",
+                        json!({ "conditions": clause.into_iter().map(|(_, v)| v).collect::<Vec<_>>() }),
+                    )?,
+                };
+                conditions
+                    .entry(key)
+                    .or_default()
+                    .push(condition("Not", json!({ "condition": negated }))?);
+            } else {
+                break;
+            }
+        }
+        self.expect(";")?;
+
+        Ok(Policy {
+            id: annotations
+                .get("id")
+                .cloned()
+                .unwrap_or_else(|| format!("policy{index}")),
+            description: String::new(),
+            statements: vec![Statement {
+                effect,
+                subjects,
+                actions,
+                resources,
+                conditions: merge_conditions(conditions)?,
+                meta: None,
+                obligations: None,
+                not_subjects: Vec::new(),
+                not_actions: Vec::new(),
+                not_resources: Vec::new(),
+                subject_matcher: None,
+                action_matcher: None,
+                resource_matcher: None,
+                compiled: Default::default(),
+            }],
+            meta: match annotations.is_empty() {
+                true => None,
+                false => Some(to_raw_value(&json!({ "annotations": annotations }))?),
+            },
+            priority: 0,
+        })
+    }
+
+    fn scope(&mut self, variable: &str) -> Result<Vec<String>> {
+        if matches!(self.peek(), Some(Token::Punct("," | ")"))) {
+            return Ok(vec![ANY.to_owned()]);
+        }
+        if self.eat("==") {
+            return Ok(vec![self.entity()?]);
+        }
+        if self.eat_keyword("is") {
+            let kind = self.path()?;
+            if self.eat_keyword("in") {
+                return Err(unsupported(format!("{variable} is {kind} in")));
+            }
+            return Ok(vec![format!("{}::\"{ANY}\"", literal(&kind))]);
+        }
+        self.expect_keyword("in")
+            .map_err(|_| unsupported(format!("{variable} constraint {:?}", self.peek())))?;
+        match variable {
+            "resource" => Err(unsupported("resource in")),
+            "action" if self.eat("[") => {
+                let mut entities = Vec::new();
+                while !self.eat("]") {
+                    entities.push(self.entity()?);
+                    if !self.eat(",") {
+                        self.expect("]")?;
+                        break;
+                    }
+                }
+                Ok(entities)
+            }
+            _ => Ok(vec![self.entity()?]),
+        }
+    }
+
+    fn path(&mut self) -> Result<String> {
+        let mut path = self.ident()?;
+        while matches!(
+            self.tokens.get(self.pos..self.pos + 2),
+            Some([Token::Punct("::"), Token::Ident(_)])
+        ) {
+            self.pos += 1;
+            path.push_str("::");
+            path.push_str(&self.ident()?);
+        }
+        Ok(path)
+    }
+
+    fn entity(&mut self) -> Result<String> {
+        let kind = self.path()?;
+        self.expect("::")?;
+        let id = self.string()?;
+        Ok(literal(&format!("{kind}::\"{id}\"")))
+    }
+
+    fn clause(&mut self) -> Result<Vec<(String, JsonCondition)>> {
+        self.expect("{")?;
+        let mut conditions = vec![self.condition()?];
+        while self.eat("&&") {
+            conditions.push(self.condition()?);
+        }
+        self.expect("}")?;
+        Ok(conditions)
+    }
+
+    fn condition(&mut self) -> Result<(String, JsonCondition)> {
+        match self.ident()?.as_str() {
+            "context" => {}
+            variable => return Err(unsupported(format!("condition on {variable}"))),
+        }
+        let mut path = Vec::new();
+        while self.eat(".") {
+            let name = self.ident()?;
+            if self.eat("(") {
+                let built = self.method(&name)?;
+                self.expect(")")?;
+                return Ok((context_key(&path), built));
+            }
+            path.push(name);
+        }
+        if path.is_empty() {
+            return Err(unsupported("condition on the whole context"));
+        }
+        let key = context_key(&path);
+        if self.eat_keyword("like") {
+            let pattern = self.string()?;
+            return Ok((
+                key,
+                condition("StringMatch", json!({ "matches": like(&pattern) }))?,
+            ));
+        }
+        let op = ["==", "!=", "<=", ">=", "<", ">"]
+            .into_iter()
+            .find(|op| self.eat(op));
+        let Some(op) = op else {
+            return Ok((key, condition("Boolean", json!({ "value": true }))?));
+        };
+        let built = match (op, self.next()) {
+            ("==", Some(Token::Str(v))) => condition("StringEqual", json!({ "equals": v }))?,
+            ("!=", Some(Token::Str(v))) => condition("StringNotIn", json!({ "values": [v] }))?,
+            ("==", Some(Token::Ident(v))) if v == "true" || v == "false" => {
+                condition("Boolean", json!({ "value": v == "true" }))?
+            }
+            (op, Some(Token::Number(v))) => {
+                condition("NumericCmp", json!({ "symbol": op, "value": v }))?
+            }
+            (op, token) => return Err(unsupported(format!("{op} {token:?}"))),
+        };
+        Ok((key, built))
+    }
+
+    fn method(&mut self, name: &str) -> Result<JsonCondition> {
+        match name {
+            "isInRange" => {
+                self.expect_keyword("ip")?;
+                self.expect("(")?;
+                let cidr = self.string()?;
+                self.expect(")")?;
+                condition("CIDR", json!({ "cidr": [cidr] }))
+            }
+            "contains" => condition("ListContains", json!({ "value": self.string()? })),
+            name => Err(unsupported(format!("method {name}"))),
+        }
+    }
+}
+
+fn condition(jtype: &str, options: Value) -> Result<JsonCondition> {
+    Ok(JsonCondition {
+        jtype: jtype.to_owned(),
+        options: to_raw_value(&options)?,
+    })
+}
+
+fn like(pattern: &str) -> String {
+    let mut buffer = String::from("^");
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("\\*") {
+            buffer.push_str("\\*");
+            rest = after;
+            continue;
+        }
+        match c {
+            '*' => buffer.push_str(".*"),
+            c => buffer.push_str(&backend::escape(&c.to_string())),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    buffer.push('$');
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Enforcer, Regexp, Request};
+
+    #[test]
+    fn convert() {
+        let raw = r#"
+            // editors may update documents from the office network
+            @id("edit-docs")
+            permit (
+                principal in Group::"editors",
+                action in [Action::"view", Action::"edit"],
+                resource is Document
+            ) when {
+                context.ip.isInRange(ip("10.0.0.0/8")) && context.level >= 2
+            } unless { context.device.kind like "kiosk*" };
+
+            forbid (principal, action == Action::"edit", resource == Document::"handbook");
+        "#;
+        let set = CedarPolicySet::parse(raw).unwrap();
+        let policies = set.into_policies();
+        assert_eq!(policies[0].id, "edit-docs");
+        assert_eq!(policies[1].id, "policy1");
+        assert_eq!(
+            policies[0].statements[0].subjects,
+            vec![r#"Group::"editors""#]
+        );
+        assert_eq!(
+            policies[0].statements[0].resources,
+            vec![r#"Document::"<.*>""#]
+        );
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies);
+        let mut request = Request {
+            resource: r#"Document::"roadmap""#.to_owned(),
+            action: r#"Action::"edit""#.to_owned(),
+            subject: r#"Group::"editors""#.to_owned(),
+            context: Context::from([
+                ("ip", json!("10.1.2.3")),
+                ("level", json!(3)),
+                ("device", json!({"kind": "laptop"})),
+            ]),
+        };
+        assert!(enforcer.is_allowed(&request).is_ok());
+        request
+            .context
+            .insert("device", json!({"kind": "kiosk-lobby"}));
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("device", json!({"kind": "laptop"}));
+        request.context.insert("level", 1);
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("level", 2);
+        request.resource = r#"Document::"handbook""#.to_owned();
+        assert!(matches!(
+            enforcer.is_allowed(&request),
+            Err(Error::Deny { .. })
+        ));
+
+        let mfa = CedarPolicySet::parse(
+            "permit (principal, action, resource) when { context.mfa == true };",
+        )
+        .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), mfa.into_policies());
+        request.context = Context::new();
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("mfa", true);
+        assert!(enforcer.is_allowed(&request).is_ok());
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        for raw in [
+            "permit (principal, action, resource in Folder::\"a\");",
+            "permit (principal, action, resource) when { principal.admin == true };",
+            "permit (principal, action, resource) when { context.a == 1 || context.b == 2 };",
+            "permit (principal, action, resource) unless { context.a == 1 && context.b == 2 };",
+        ] {
+            assert!(matches!(
+                CedarPolicySet::parse(raw),
+                Err(Error::UnsupportedCedar(_))
+            ));
+        }
+    }
+}
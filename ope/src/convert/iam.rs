@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+use serde_json::{json, value::to_raw_value, Value};
+use validator::Validate;
+
+use crate::condition::JsonCondition;
+use crate::matcher::backend;
+use crate::policy::Policy;
+use crate::statement::{Effect, Statement};
+use crate::{Error, Result};
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct IamPolicy {
+    pub version: Option<String>,
+    pub id: Option<String>,
+    pub statement: OneOrMany<IamStatement>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct IamStatement {
+    pub sid: Option<String>,
+    pub effect: Effect,
+    pub principal: Option<Principal>,
+    pub not_principal: Option<Value>,
+    pub action: Option<OneOrMany<String>>,
+    pub not_action: Option<Value>,
+    pub resource: Option<OneOrMany<String>>,
+    pub not_resource: Option<Value>,
+    pub condition: Option<BTreeMap<String, BTreeMap<String, OneOrMany<Value>>>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T: Clone> OneOrMany<T> {
+    fn to_vec(&self) -> Vec<T> {
+        match self {
+            OneOrMany::One(v) => vec![v.clone()],
+            OneOrMany::Many(v) => v.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Principal {
+    Any(String),
+    Typed(BTreeMap<String, OneOrMany<String>>),
+}
+
+impl IamPolicy {
+    pub fn from_json(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    pub fn into_policy(self, id: impl Into<String>) -> Result<Policy> {
+        let statements = self
+            .statement
+            .to_vec()
+            .iter()
+            .map(convert_statement)
+            .collect::<Result<Vec<_>>>()?;
+        let policy = Policy {
+            id: self.id.unwrap_or_else(|| id.into()),
+            description: String::new(),
+            statements,
+            meta: match self.version {
+                Some(version) => Some(to_raw_value(&json!({ "version": version }))?),
+                None => None,
+            },
+            priority: 0,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+fn unsupported(element: impl Into<String>) -> Error {
+    Error::UnsupportedIam(element.into())
+}
+
+fn convert_statement(statement: &IamStatement) -> Result<Statement> {
+    if statement.not_principal.is_some() {
+        return Err(unsupported("NotPrincipal"));
+    }
+    if statement.not_action.is_some() {
+        return Err(unsupported("NotAction"));
+    }
+    if statement.not_resource.is_some() {
+        return Err(unsupported("NotResource"));
+    }
+    let subjects = match &statement.principal {
+        None => vec![wildcard("*")],
+        Some(Principal::Any(v)) if v == "*" => vec![wildcard("*")],
+        Some(Principal::Any(v)) => return Err(unsupported(format!("Principal {v}"))),
+        Some(Principal::Typed(principals)) => principals
+            .values()
+            .flat_map(|v| v.to_vec())
+            .map(|v| wildcard(&v))
+            .collect(),
+    };
+    let field = |name: &str, values: &Option<OneOrMany<String>>| match values {
+        Some(values) => Ok(values.to_vec().iter().map(|v| wildcard(v)).collect()),
+        None => Err(unsupported(format!("statement without {name}"))),
+    };
+    let conditions = match &statement.condition {
+        Some(condition) => Some(convert_conditions(condition)?),
+        None => None,
+    };
+    Ok(Statement {
+        effect: statement.effect.clone(),
+        subjects,
+        actions: field("Action", &statement.action)?,
+        resources: field("Resource", &statement.resource)?,
+        conditions,
+        meta: match &statement.sid {
+            Some(sid) => Some(to_raw_value(&json!({ "sid": sid }))?),
+            None => None,
+        },
+        obligations: None,
+        not_subjects: Vec::new(),
+        not_actions: Vec::new(),
+        not_resources: Vec::new(),
+        subject_matcher: None,
+        action_matcher: None,
+        resource_matcher: None,
+        compiled: Default::default(),
+    })
+}
+
+fn wildcard(pattern: &str) -> String {
+    let mut buffer = String::new();
+    for c in pattern.chars() {
+        match c {
+            '*' => buffer.push_str("<.*>"),
+            '?' => buffer.push_str("<.>"),
+            c => buffer.push(c),
+        }
+    }
+    buffer
+}
+
+fn convert_conditions(
+    block: &BTreeMap<String, BTreeMap<String, OneOrMany<Value>>>,
+) -> Result<HashMap<String, JsonCondition>> {
+    let mut conditions = HashMap::new();
+    for (operator, keys) in block.iter() {
+        for (key, values) in keys.iter() {
+            let values = values.to_vec();
+            let condition = convert_condition(operator, &values)?;
+            if conditions.insert(key.clone(), condition).is_some() {
+                return Err(unsupported(format!("multiple operators on {key}")));
+            }
+        }
+    }
+    Ok(conditions)
+}
+
+fn convert_condition(operator: &str, values: &[Value]) -> Result<JsonCondition> {
+    let strings = values
+        .iter()
+        .map(|v| match v {
+            Value::String(v) => Ok(v.clone()),
+            Value::Number(_) | Value::Bool(_) => Ok(v.to_string()),
+            _ => Err(unsupported(format!("{operator} value {v}"))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let alternatives =
+        |f: fn(&str) -> String| strings.iter().map(|v| f(v)).collect::<Vec<_>>().join("|");
+    let (jtype, options) = match operator {
+        "StringEquals" if strings.len() == 1 => ("StringEqual", json!({ "equals": strings[0] })),
+        "StringEquals" => (
+            "StringMatch",
+            json!({ "matches": format!("^(?:{})$", alternatives(backend::escape)) }),
+        ),
+        "StringEqualsIgnoreCase" => (
+            "StringMatch",
+            json!({ "matches": format!("(?i)^(?:{})$", alternatives(backend::escape)) }),
+        ),
+        "StringNotEquals" | "StringNotEqualsIgnoreCase" => (
+            "StringCmp",
+            json!({ "values": strings.iter().map(|v| json!({
+                "equal": false,
+                "ignore_case": operator == "StringNotEqualsIgnoreCase",
+                "value": v,
+            })).collect::<Vec<_>>() }),
+        ),
+        "StringLike" => (
+            "StringMatch",
+            json!({ "matches": format!("^(?:{})$", alternatives(like)) }),
+        ),
+        "NumericEquals"
+        | "NumericNotEquals"
+        | "NumericLessThan"
+        | "NumericLessThanEquals"
+        | "NumericGreaterThan"
+        | "NumericGreaterThanEquals" => {
+            let [value] = strings.as_slice() else {
+                return Err(unsupported(format!("{operator} with multiple values")));
+            };
+            let value: serde_json::Number = value
+                .parse()
+                .map_err(|_| unsupported(format!("{operator} value {value}")))?;
+            (
+                "NumericCmp",
+                json!({ "symbol": symbol(operator, "Numeric"), "value": value }),
+            )
+        }
+        "DateEquals"
+        | "DateNotEquals"
+        | "DateLessThan"
+        | "DateLessThanEquals"
+        | "DateGreaterThan"
+        | "DateGreaterThanEquals" => {
+            let [value] = strings.as_slice() else {
+                return Err(unsupported(format!("{operator} with multiple values")));
+            };
+            (
+                "TimeCmp",
+                json!({ "values": [{
+                    "symbol": symbol(operator, "Date"),
+                    "value": value,
+                    "format": DATE_FORMAT,
+                    "location": "UTC",
+                }] }),
+            )
+        }
+        "Bool" => {
+            let [value] = strings.as_slice() else {
+                return Err(unsupported("Bool with multiple values"));
+            };
+            let value: bool = value
+                .parse()
+                .map_err(|_| unsupported(format!("Bool value {value}")))?;
+            ("Boolean", json!({ "value": value }))
+        }
+        "IpAddress" => ("CIDR", json!({ "cidr": strings })),
+        _ => return Err(unsupported(format!("condition operator {operator}"))),
+    };
+    Ok(JsonCondition {
+        jtype: jtype.to_owned(),
+        options: to_raw_value(&options)?,
+    })
+}
+
+fn like(pattern: &str) -> String {
+    pattern
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(backend::escape)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>()
+        .join(".*")
+}
+
+fn symbol(operator: &str, family: &str) -> &'static str {
+    match operator.trim_start_matches(family) {
+        "Equals" => "==",
+        "NotEquals" => "!=",
+        "LessThan" => "<",
+        "LessThanEquals" => "<=",
+        "GreaterThan" => ">",
+        _ => ">=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Enforcer, Regexp, Request};
+
+    #[test]
+    fn convert() {
+        let raw = r#"{
+            "Version": "2012-10-17",
+            "Statement": [{
+                "Sid": "ReadReports",
+                "Effect": "Allow",
+                "Principal": {"AWS": ["arn:aws:iam::123456789012:user/*"]},
+                "Action": ["s3:Get*", "s3:List?ucket"],
+                "Resource": "arn:aws:s3:::reports/*",
+                "Condition": {
+                    "IpAddress": {"aws:SourceIp": ["10.0.0.0/8"]},
+                    "StringLike": {"s3:prefix": "home/*"},
+                    "NumericLessThan": {"s3:max-keys": "10"},
+                    "NumericGreaterThanEquals": {"s3:min-keys": "0.5"},
+                    "Bool": {"aws:SecureTransport": "true"}
+                }
+            }, {
+                "Effect": "Deny",
+                "Action": "s3:GetObject",
+                "Resource": "arn:aws:s3:::reports/secret"
+            }]
+        }"#;
+        let policy = IamPolicy::from_json(raw)
+            .unwrap()
+            .into_policy("reports")
+            .unwrap();
+        assert_eq!(policy.id, "reports");
+        assert_eq!(
+            policy.statements[0].actions,
+            vec!["s3:Get<.*>", "s3:List<.>ucket"]
+        );
+        assert_eq!(policy.statements[1].subjects, vec!["<.*>"]);
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let mut request = Request {
+            resource: "arn:aws:s3:::reports/2024.csv".to_owned(),
+            action: "s3:GetObject".to_owned(),
+            subject: "arn:aws:iam::123456789012:user/alice".to_owned(),
+            context: Context::from([
+                ("aws:SourceIp", json!("10.1.2.3")),
+                ("s3:prefix", json!("home/alice")),
+                ("s3:max-keys", json!(5)),
+                ("s3:min-keys", json!(1)),
+                ("aws:SecureTransport", json!(true)),
+            ]),
+        };
+        assert!(enforcer.is_allowed(&request).is_ok());
+        request.context.insert("s3:max-keys", "9.5");
+        assert!(enforcer.is_allowed(&request).is_ok());
+        request.context.insert("s3:max-keys", 10);
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("s3:max-keys", 5);
+        request.context.insert("aws:SourceIp", "192.168.0.1");
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("aws:SourceIp", "10.1.2.3");
+        request.resource = "arn:aws:s3:::reports/secret".to_owned();
+        assert!(matches!(
+            enforcer.is_allowed(&request),
+            Err(Error::Deny { .. })
+        ));
+    }
+
+    #[test]
+    fn unsupported_elements() {
+        for raw in [
+            r#"{"Statement": {"Effect": "Allow", "NotAction": "s3:*", "Resource": "*"}}"#,
+            r#"{"Statement": {"Effect": "Allow", "Action": "s3:*", "Resource": "*",
+                "Condition": {"StringNotLike": {"s3:prefix": "a*"}}}}"#,
+        ] {
+            assert!(matches!(
+                IamPolicy::from_json(raw).unwrap().into_policy("p"),
+                Err(Error::UnsupportedIam(_))
+            ));
+        }
+    }
+}
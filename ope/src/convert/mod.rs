@@ -0,0 +1,46 @@
+pub(crate) mod casbin;
+#[cfg(feature = "cedar")]
+pub(crate) mod cedar;
+pub(crate) mod iam;
+pub(crate) mod rego;
+
+use std::collections::HashMap;
+
+use serde_json::{json, value::to_raw_value};
+
+use crate::condition::JsonCondition;
+use crate::Result;
+
+pub(crate) fn literal(value: &str) -> String {
+    value.replace('<', "\\<").replace('>', "\\>")
+}
+
+pub(crate) fn context_key(path: &[impl AsRef<str>]) -> String {
+    match path {
+        [key] => key.as_ref().to_owned(),
+        keys => keys
+            .iter()
+            .map(|v| format!("/{}", v.as_ref().replace('~', "~0").replace('/', "~1")))
+            .collect(),
+    }
+}
+
+pub(crate) fn merge_conditions(
+    conditions: HashMap<String, Vec<JsonCondition>>,
+) -> Result<Option<HashMap<String, JsonCondition>>> {
+    let mut merged = HashMap::new();
+    for (key, mut values) in conditions.into_iter() {
+        let condition = match values.len() {
+            1 => values.remove(0),
+            _ => JsonCondition {
+                jtype: "AllOf".to_owned(),
+                options: to_raw_value(&json!({ "conditions": values }))?,
+            },
+        };
+        merged.insert(key, condition);
+    }
+    Ok(match merged.is_empty() {
+        true => None,
+        false => Some(merged),
+    })
+}
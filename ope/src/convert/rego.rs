@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+
+use serde_json::{json, value::to_raw_value, Number, Value};
+use validator::Validate;
+
+use super::{context_key, literal, merge_conditions};
+use crate::condition::JsonCondition;
+use crate::matcher::backend;
+use crate::policy::Policy;
+use crate::statement::{Effect, Statement};
+use crate::{Error, Result};
+
+const ANY: &str = "<.*>";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(Number),
+    Punct(&'static str),
+    Newline,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Value(Value),
+    Ref(Vec<String>),
+    Call(String, Vec<Term>),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Term, &'static str, Term),
+    In(Term, Vec<Value>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    effect: Effect,
+    body: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegoPolicy {
+    pub package: Option<String>,
+    rules: Vec<Rule>,
+}
+
+impl RegoPolicy {
+    pub fn parse(content: &str) -> Result<Self> {
+        Parser {
+            tokens: tokenize(content)?,
+            pos: 0,
+        }
+        .module()
+    }
+
+    pub fn into_policy(self, id: impl Into<String>) -> Result<Policy> {
+        let mut statements = Vec::new();
+        for rule in self.rules.iter() {
+            if let Some(statement) = convert_rule(rule)? {
+                statements.push(statement);
+            }
+        }
+        let policy = Policy {
+            id: id.into(),
+            description: String::new(),
+            statements,
+            meta: match self.package {
+                Some(package) => Some(to_raw_value(&json!({ "package": package }))?),
+                None => None,
+            },
+            priority: 0,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+fn unsupported(construct: impl Into<String>) -> Error {
+    Error::UnsupportedRego(construct.into())
+}
+
+fn tokenize(content: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                chars.next();
+                tokens.push(Token::Newline);
+            }
+            '#' => while chars.next_if(|c| *c != '\n').is_some() {},
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut buffer = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => buffer.push('\n'),
+                            Some('t') => buffer.push('\t'),
+                            Some(c) => buffer.push(c),
+                            None => return Err(unsupported("unterminated string")),
+                        },
+                        Some(c) => buffer.push(c),
+                        None => return Err(unsupported("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(buffer));
+            }
+            '`' => {
+                chars.next();
+                let mut buffer = String::new();
+                loop {
+                    match chars.next() {
+                        Some('`') => break,
+                        Some(c) => buffer.push(c),
+                        None => return Err(unsupported("unterminated raw string")),
+                    }
+                }
+                tokens.push(Token::Str(buffer));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut buffer = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '.'))
+                {
+                    buffer.push(c);
+                }
+                let number = buffer
+                    .parse()
+                    .map_err(|_| unsupported(format!("number {buffer}")))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut buffer = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '.'))
+                {
+                    buffer.push(c);
+                }
+                tokens.push(Token::Ident(buffer));
+            }
+            _ => {
+                chars.next();
+                let punct = match (c, chars.peek()) {
+                    ('=', Some('=')) => "==",
+                    ('!', Some('=')) => "!=",
+                    (':', Some('=')) => ":=",
+                    _ => "",
+                };
+                if !punct.is_empty() {
+                    chars.next();
+                    tokens.push(Token::Punct(punct));
+                    continue;
+                }
+                let punct = match c {
+                    '=' => "=",
+                    '{' => "{",
+                    '}' => "}",
+                    '(' => "(",
+                    ')' => ")",
+                    '[' => "[",
+                    ']' => "]",
+                    ',' => ",",
+                    ';' => ";",
+                    c => return Err(unsupported(format!("character {c}"))),
+                };
+                tokens.push(Token::Punct(punct));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(unsupported(format!(
+                "expected {punct} near {:?}",
+                self.peek()
+            ))),
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&Token::Newline) {
+            self.pos += 1;
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Ident(v)) => Ok(v),
+            token => Err(unsupported(format!("expected identifier near {token:?}"))),
+        }
+    }
+
+    fn module(&mut self) -> Result<RegoPolicy> {
+        let mut policy = RegoPolicy {
+            package: None,
+            rules: Vec::new(),
+        };
+        loop {
+            self.skip_newlines();
+            let Some(token) = self.next() else {
+                return Ok(policy);
+            };
+            match token {
+                Token::Ident(v) if v == "package" => policy.package = Some(self.ident()?),
+                Token::Ident(v) if v == "import" => {
+                    while !matches!(self.peek(), None | Some(Token::Newline)) {
+                        self.pos += 1;
+                    }
+                }
+                Token::Ident(v) if v == "default" => {
+                    let name = self.ident()?;
+                    if !self.eat("=") {
+                        self.expect(":=")?;
+                    }
+                    match self.term()? {
+                        Term::Value(Value::Bool(false)) => {}
+                        _ => return Err(unsupported(format!("default {name}"))),
+                    }
+                }
+                Token::Ident(name) => policy.rules.push(self.rule(&name)?),
+                token => return Err(unsupported(format!("{token:?} at top level"))),
+            }
+        }
+    }
+
+    fn rule(&mut self, name: &str) -> Result<Rule> {
+        let effect = match name {
+            "allow" => Effect::Allow,
+            "deny" => Effect::Deny,
+            _ => return Err(unsupported(format!("rule {name}"))),
+        };
+        if self.eat("=") || self.eat(":=") {
+            match self.term()? {
+                Term::Value(Value::Bool(true)) => {}
+                _ => return Err(unsupported(format!("non-boolean rule {name}"))),
+            }
+        }
+        if matches!(self.peek(), Some(Token::Ident(v)) if v == "if") {
+            self.pos += 1;
+        }
+        self.expect("{")?;
+        let mut body = Vec::new();
+        loop {
+            while matches!(self.peek(), Some(Token::Newline | Token::Punct(";"))) {
+                self.pos += 1;
+            }
+            if self.eat("}") {
+                return Ok(Rule { effect, body });
+            }
+            body.push(self.expr()?);
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let left = self.term()?;
+        for op in ["==", "!="] {
+            if self.eat(op) {
+                return Ok(Expr::Compare(left, op, self.term()?));
+            }
+        }
+        if matches!(self.peek(), Some(Token::Ident(v)) if v == "in") {
+            self.pos += 1;
+            return Ok(Expr::In(left, self.collection()?));
+        }
+        Ok(Expr::Term(left))
+    }
+
+    fn collection(&mut self) -> Result<Vec<Value>> {
+        let close = match self.next() {
+            Some(Token::Punct("{")) => "}",
+            Some(Token::Punct("[")) => "]",
+            token => return Err(unsupported(format!("collection {token:?}"))),
+        };
+        let mut values = Vec::new();
+        loop {
+            self.skip_newlines();
+            if self.eat(close) {
+                return Ok(values);
+            }
+            match self.term()? {
+                Term::Value(v) => values.push(v),
+                term => return Err(unsupported(format!("collection element {term:?}"))),
+            }
+            self.skip_newlines();
+            if !self.eat(",") {
+                self.skip_newlines();
+                self.expect(close)?;
+                return Ok(values);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Term> {
+        match self.next() {
+            Some(Token::Str(v)) => Ok(Term::Value(Value::String(v))),
+            Some(Token::Number(v)) => Ok(Term::Value(Value::Number(v))),
+            Some(Token::Ident(v)) if v == "true" => Ok(Term::Value(Value::Bool(true))),
+            Some(Token::Ident(v)) if v == "false" => Ok(Term::Value(Value::Bool(false))),
+            Some(Token::Ident(v)) if v == "null" => Ok(Term::Value(Value::Null)),
+            Some(Token::Ident(v)) => {
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    while !self.eat(")") {
+                        args.push(self.term()?);
+                        if !self.eat(",") {
+                            self.expect(")")?;
+                            break;
+                        }
+                    }
+                    return Ok(Term::Call(v, args));
+                }
+                let mut path: Vec<String> = v.split('.').map(ToOwned::to_owned).collect();
+                while self.eat("[") {
+                    match self.next() {
+                        Some(Token::Str(key)) => path.push(key),
+                        token => return Err(unsupported(format!("reference index {token:?}"))),
+                    }
+                    self.expect("]")?;
+                }
+                Ok(Term::Ref(path))
+            }
+            token => Err(unsupported(format!("term {token:?}"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Subject,
+    Action,
+    Resource,
+}
+
+enum Target {
+    Field(Field),
+    Context(String),
+}
+
+fn target(term: &Term) -> Result<Target> {
+    let Term::Ref(path) = term else {
+        return Err(unsupported(format!("operand {term:?}")));
+    };
+    match path
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["input", "subject"] => Ok(Target::Field(Field::Subject)),
+        ["input", "action"] => Ok(Target::Field(Field::Action)),
+        ["input", "resource"] => Ok(Target::Field(Field::Resource)),
+        ["input", "context", keys @ ..] if !keys.is_empty() => {
+            Ok(Target::Context(context_key(keys)))
+        }
+        _ => Err(unsupported(format!("reference {}", path.join(".")))),
+    }
+}
+
+fn string(term: &Term) -> Result<&str> {
+    match term {
+        Term::Value(Value::String(v)) => Ok(v),
+        term => Err(unsupported(format!("expected string, got {term:?}"))),
+    }
+}
+
+#[derive(Default)]
+struct Draft {
+    fields: HashMap<Field, Vec<String>>,
+    excluded: HashMap<Field, Vec<String>>,
+    conditions: HashMap<String, Vec<JsonCondition>>,
+}
+
+impl Draft {
+    fn constrain(&mut self, field: Field, patterns: Vec<String>) -> Result<()> {
+        if self.fields.insert(field, patterns).is_some() {
+            return Err(unsupported(format!("multiple constraints on {field:?}")));
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, key: String, jtype: &str, options: Value) -> Result<()> {
+        self.conditions.entry(key).or_default().push(JsonCondition {
+            jtype: jtype.to_owned(),
+            options: to_raw_value(&options)?,
+        });
+        Ok(())
+    }
+
+    fn apply(&mut self, expr: &Expr) -> Result<bool> {
+        match expr {
+            Expr::Term(Term::Value(Value::Bool(v))) => return Ok(*v),
+            Expr::Compare(left @ Term::Value(_), op, right @ Term::Ref(_)) => {
+                return self.apply(&Expr::Compare(right.clone(), op, left.clone()));
+            }
+            Expr::Compare(left, op, Term::Value(value)) => match (target(left)?, *op, value) {
+                (Target::Field(field), "==", Value::String(v)) => {
+                    self.constrain(field, vec![literal(v)])?
+                }
+                (Target::Field(field), "!=", Value::String(v)) => {
+                    self.excluded.entry(field).or_default().push(literal(v))
+                }
+                (Target::Context(key), "==", Value::String(v)) => {
+                    self.condition(key, "StringEqual", json!({ "equals": v }))?
+                }
+                (Target::Context(key), "==", Value::Bool(v)) => {
+                    self.condition(key, "Boolean", json!({ "value": v }))?
+                }
+                (Target::Context(key), "==", Value::Number(v)) => {
+                    self.condition(key, "NumberEquals", json!({ "value": v }))?
+                }
+                (Target::Context(key), "!=", Value::String(v)) => {
+                    self.condition(key, "StringNotIn", json!({ "values": [v] }))?
+                }
+                _ => return Err(unsupported(format!("comparison {expr:?}"))),
+            },
+            Expr::In(left, values) => {
+                let values = values
+                    .iter()
+                    .map(|v| match v {
+                        Value::String(v) => Ok(v.clone()),
+                        v => Err(unsupported(format!("collection element {v}"))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                match target(left)? {
+                    Target::Field(field) => {
+                        self.constrain(field, values.iter().map(|v| literal(v)).collect())?
+                    }
+                    Target::Context(key) => {
+                        self.condition(key, "StringIn", json!({ "values": values }))?
+                    }
+                }
+            }
+            Expr::Term(Term::Call(name, args)) => self.call(name, args)?,
+            _ => return Err(unsupported(format!("expression {expr:?}"))),
+        }
+        Ok(true)
+    }
+
+    fn call(&mut self, name: &str, args: &[Term]) -> Result<()> {
+        match (name, args) {
+            ("startswith", [subject, prefix]) => match target(subject)? {
+                Target::Field(field) => {
+                    self.constrain(field, vec![format!("{}{ANY}", literal(string(prefix)?))])
+                }
+                Target::Context(key) => self.condition(
+                    key,
+                    "StringMatch",
+                    json!({ "matches": format!("^{}", backend::escape(string(prefix)?)) }),
+                ),
+            },
+            ("endswith", [subject, suffix]) => match target(subject)? {
+                Target::Field(field) => {
+                    self.constrain(field, vec![format!("{ANY}{}", literal(string(suffix)?))])
+                }
+                Target::Context(key) => self.condition(
+                    key,
+                    "StringMatch",
+                    json!({ "matches": format!("{}$", backend::escape(string(suffix)?)) }),
+                ),
+            },
+            ("regex.match", [pattern, subject]) => {
+                let pattern = string(pattern)?;
+                match target(subject)? {
+                    Target::Field(_) if pattern.contains(['<', '>']) => {
+                        Err(unsupported(format!("regex {pattern} with delimiters")))
+                    }
+                    Target::Field(field) => self.constrain(field, vec![format!("<(?:{pattern})>")]),
+                    Target::Context(key) => {
+                        self.condition(key, "StringMatch", json!({ "matches": pattern }))
+                    }
+                }
+            }
+            ("net.cidr_contains", [cidr, subject]) => match target(subject)? {
+                Target::Context(key) => {
+                    self.condition(key, "CIDR", json!({ "cidr": [string(cidr)?] }))
+                }
+                Target::Field(field) => Err(unsupported(format!("cidr on {field:?}"))),
+            },
+            _ => Err(unsupported(format!("function {name}"))),
+        }
+    }
+}
+
+fn convert_rule(rule: &Rule) -> Result<Option<Statement>> {
+    let mut draft = Draft::default();
+    for expr in rule.body.iter() {
+        if !draft.apply(expr)? {
+            return Ok(None);
+        }
+    }
+    let conditions = merge_conditions(draft.conditions)?;
+    let mut field = |field: Field| {
+        draft
+            .fields
+            .remove(&field)
+            .unwrap_or_else(|| vec![ANY.to_owned()])
+    };
+    let (subjects, actions, resources) = (
+        field(Field::Subject),
+        field(Field::Action),
+        field(Field::Resource),
+    );
+    let mut excluded = |field: Field| draft.excluded.remove(&field).unwrap_or_default();
+    Ok(Some(Statement {
+        effect: rule.effect.clone(),
+        subjects,
+        actions,
+        resources,
+        conditions,
+        meta: None,
+        obligations: None,
+        not_subjects: excluded(Field::Subject),
+        not_actions: excluded(Field::Action),
+        not_resources: excluded(Field::Resource),
+        subject_matcher: None,
+        action_matcher: None,
+        resource_matcher: None,
+        compiled: Default::default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Enforcer, Regexp, Request};
+
+    #[test]
+    fn convert() {
+        let raw = r#"
+            package authz.articles
+
+            import rego.v1
+
+            default allow := false
+
+            # editors may read and write articles from the office
+            allow if {
+                input.subject in {"peter", "max"}
+                input.action in ["get", "put"]
+                startswith(input.resource, "articles:")
+                net.cidr_contains("10.0.0.0/8", input.context.ip)
+                input.context.mfa == true
+            }
+
+            allow { input.subject == "admin"; input.resource != "articles:<root>" }
+
+            deny if {
+                regex.match(`articles:[0-9]+:secret`, input.resource)
+                input.context.user["tier"] != "gold"
+            }
+        "#;
+        let policy = RegoPolicy::parse(raw)
+            .unwrap()
+            .into_policy("articles")
+            .unwrap();
+        assert_eq!(policy.statements.len(), 3);
+        assert_eq!(policy.statements[0].resources, vec!["articles:<.*>"]);
+        assert_eq!(policy.statements[1].actions, vec!["<.*>"]);
+        assert_eq!(
+            policy.statements[1].not_resources,
+            vec!["articles:\\<root\\>"]
+        );
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let mut request = Request {
+            resource: "articles:1".to_owned(),
+            action: "put".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([
+                ("ip", json!("10.1.2.3")),
+                ("mfa", json!(true)),
+                ("user", json!({"tier": "silver"})),
+            ]),
+        };
+        assert!(enforcer.is_allowed(&request).is_ok());
+        let mut missing = request.clone();
+        missing.context = Context::from([("ip", json!("10.1.2.3"))]);
+        assert!(enforcer.is_allowed(&missing).is_err());
+        request.context.insert("ip", "192.168.0.1");
+        assert!(enforcer.is_allowed(&request).is_err());
+        request.context.insert("ip", "10.1.2.3");
+        request.resource = "articles:7:secret".to_owned();
+        assert!(matches!(
+            enforcer.is_allowed(&request),
+            Err(Error::Deny { .. })
+        ));
+        request.context.insert("user", json!({"tier": "gold"}));
+        assert!(enforcer.is_allowed(&request).is_ok());
+        request.subject = "admin".to_owned();
+        request.resource = "articles:<root>".to_owned();
+        assert!(enforcer.is_allowed(&request).is_err());
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        for raw in [
+            "default allow := true",
+            "allow { count(input.roles) > 0 }",
+            "allow { input.subject == \"a\"; startswith(input.subject, \"b\") }",
+            "grant { true }",
+        ] {
+            assert!(matches!(
+                RegoPolicy::parse(raw).and_then(|v| v.into_policy("p")),
+                Err(Error::UnsupportedRego(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Arc;
+
+use tracing::Instrument;
+
+use super::{combining::Combiner, decision::Located, merge, options::Deadline, Enforcer};
+use crate::instrument::{self, Instant, Timings};
+use crate::matcher::asynchronous::AsyncMatcher;
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::statement::{Effect, Field, Statement};
+use crate::store::{asynchronous::AsyncPolicyStore, may_match_with, union};
+use crate::Result;
+
+pub trait AsyncEnforcer {
+    fn is_allowed(&self, input: &Request) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl<M: AsyncMatcher + Sync, S: AsyncPolicyStore + Sync> AsyncEnforcer for Enforcer<M, S> {
+    async fn is_allowed(&self, input: &Request) -> Result<()> {
+        async {
+            let started = Instant::now();
+            let mut matched = Vec::new();
+            let deadline = self.options.deadline(started);
+            let decision = self.evaluate_async(input, deadline, &mut matched).await;
+            self.audit(input, &decision, &matched, started.elapsed(), false, None);
+            decision
+        }
+        .instrument(instrument::evaluation(input))
+        .await
+    }
+}
+
+impl<M: AsyncMatcher + Sync, S: AsyncPolicyStore + Sync> Enforcer<M, S> {
+    async fn evaluate_async(
+        &self,
+        input: &Request,
+        deadline: Deadline,
+        matched: &mut Vec<String>,
+    ) -> Result<()> {
+        tracing::debug!("input = {:?}, combining = {:?}", input, self.combining);
+        let mut combiner = Combiner::new(self.combining);
+        let requests = self.expand(input)?;
+        let mut candidates = self.candidates_async(&requests).await?;
+        self.prioritize(&mut candidates);
+        let (mut decision, mut located) = (None, Located::default());
+        for policy in candidates.iter() {
+            deadline.check()?;
+            let span = instrument::policy(&policy.id);
+            let (mut effect, mut timings) = (None, Timings::default());
+            for request in requests.iter() {
+                effect = merge(
+                    effect,
+                    self.policy_effect_async(policy, request, &mut located, &mut timings)
+                        .instrument(span.clone())
+                        .await?,
+                );
+            }
+            instrument::policy_effect(&span, effect.as_ref(), &timings);
+            if effect.is_some() {
+                matched.push(policy.id.clone());
+            }
+            if let Some(result) = combiner.push(&policy.id, effect) {
+                decision = Some(result);
+                break;
+            }
+        }
+        let decision = decision.unwrap_or_else(|| combiner.finish());
+        located.locate(decision)
+    }
+
+    async fn candidates_async(&self, requests: &[Cow<'_, Request>]) -> Result<Vec<Arc<Policy>>> {
+        let matcher = &self.ope.matcher;
+        if !matcher.indexable() {
+            return Ok(self
+                .store
+                .all()
+                .await?
+                .into_iter()
+                .filter(|policy| {
+                    requests.iter().any(|input| {
+                        may_match_with(
+                            policy,
+                            &input.subject,
+                            &input.resource,
+                            &input.action,
+                            |delimiter, pattern| matcher.is_literal(delimiter, pattern),
+                        )
+                    })
+                })
+                .collect());
+        }
+        let mut lists = Vec::with_capacity(requests.len());
+        for request in requests.iter() {
+            lists.push(
+                self.store
+                    .find_candidates(&request.subject, &request.resource, &request.action)
+                    .await?,
+            );
+        }
+        Ok(union(lists))
+    }
+}
+
+impl<M: AsyncMatcher + Sync, S> Enforcer<M, S> {
+    async fn policy_effect_async(
+        &self,
+        policy: &Policy,
+        input: &Request,
+        located: &mut Located,
+        timings: &mut Timings,
+    ) -> Result<Option<Effect>> {
+        let mut effect = None;
+        for (index, statement) in policy.statements.iter().enumerate() {
+            let started = Instant::now();
+            let matched = self.fields_match_async(statement, input).await?;
+            timings.matched_since(started);
+            if !matched {
+                continue;
+            }
+            if let Some(condition) =
+                timings.conditions(|| self.ope.rejected_condition(statement, input))?
+            {
+                located.miss(&policy.id, index, condition);
+                continue;
+            }
+            if let Effect::Deny = statement.effect {
+                located.deny(&policy.id, index);
+                return Ok(Some(Effect::Deny));
+            }
+            effect = Some(Effect::Allow);
+        }
+        Ok(effect)
+    }
+
+    async fn fields_match_async(&self, statement: &Statement, input: &Request) -> Result<bool> {
+        let (start, end) = (
+            statement.get_start_delimiter(),
+            statement.get_end_delimiter(),
+        );
+        let fields = [
+            (Field::Action, &statement.actions, &input.action, false),
+            (Field::Subject, &statement.subjects, &input.subject, false),
+            (
+                Field::Resource,
+                &statement.resources,
+                &input.resource,
+                false,
+            ),
+            (Field::Action, &statement.not_actions, &input.action, true),
+            (
+                Field::Subject,
+                &statement.not_subjects,
+                &input.subject,
+                true,
+            ),
+            (
+                Field::Resource,
+                &statement.not_resources,
+                &input.resource,
+                true,
+            ),
+        ];
+        for (field, patterns, needle, excluded) in fields {
+            if excluded && patterns.is_empty() {
+                continue;
+            }
+            let matched = match self.options.shortcut(patterns) {
+                Some(matched) => matched,
+                None => match self.ope.field_matcher(statement, field)? {
+                    Some(matcher) => {
+                        let patterns = statement.resolve(patterns, &input.context, |value| {
+                            matcher.escape_literal(start, end, value)
+                        });
+                        matcher.matches(start, end, &patterns, needle)?
+                    }
+                    None => {
+                        let matcher = &self.ope.matcher;
+                        let patterns = statement.resolve(patterns, &input.context, |value| {
+                            matcher.escape_literal(start, end, value)
+                        });
+                        matcher.matches(start, end, &patterns, needle).await?
+                    }
+                },
+            };
+            if matched == excluded {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context as TaskContext, Poll, Waker};
+
+    use super::*;
+    use crate::{Context, Error, Regexp};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = TaskContext::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(v) = future.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    #[test]
+    fn async_enforcer() {
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![Policy {
+                id: "readers".to_owned(),
+                description: String::new(),
+                statements: vec![Statement {
+                    effect: Effect::Allow,
+                    subjects: vec!["peter".to_owned()],
+                    actions: vec!["get".to_owned()],
+                    resources: vec!["articles:<[0-9]+>".to_owned()],
+                    conditions: None,
+                    meta: None,
+                    obligations: None,
+                    not_subjects: Vec::new(),
+                    not_actions: Vec::new(),
+                    not_resources: Vec::new(),
+                    subject_matcher: None,
+                    action_matcher: None,
+                    resource_matcher: None,
+                    compiled: Default::default(),
+                }],
+                meta: None,
+                priority: 0,
+            }],
+        );
+        let mut request = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::new(),
+        };
+        assert!(block_on(AsyncEnforcer::is_allowed(&enforcer, &request)).is_ok());
+        request.resource = "articles:x".to_owned();
+        assert!(matches!(
+            block_on(AsyncEnforcer::is_allowed(&enforcer, &request)),
+            Err(Error::NotMatched { .. })
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use super::{combine, decision::Located, merge, Enforcer};
+use crate::instrument::{Instant, Timings};
+use crate::matcher::Matcher;
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::statement::{Effect, Field, Statement};
+use crate::store::PolicyStore;
+use crate::{Error, Outcome, Result};
+
+type Memo<'a> = HashMap<(usize, usize, Field, &'a str), bool>;
+
+impl<M: Matcher, S: PolicyStore> Enforcer<M, S> {
+    pub fn is_allowed_batch(&self, inputs: &[Request]) -> Vec<Result<()>> {
+        let mut policies = match self.store.all() {
+            Ok(policies) => policies,
+            Err(err) => {
+                let message = format!("{err}");
+                return inputs
+                    .iter()
+                    .map(|_| Err(Error::StoreError(message.clone())))
+                    .collect();
+            }
+        };
+        self.prioritize(&mut policies);
+        let mut errors = HashMap::new();
+        let expanded: Vec<_> = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                self.expand(input).unwrap_or_else(|err| {
+                    errors.insert(i, err);
+                    Vec::new()
+                })
+            })
+            .collect();
+        let mut memo: Memo = HashMap::new();
+        let mut results = Vec::with_capacity(inputs.len());
+        for (i, requests) in expanded.iter().enumerate() {
+            let started = Instant::now();
+            let deadline = self.options.deadline(started);
+            let (mut matched, mut located) = (Vec::new(), Located::default());
+            let decision = match errors.remove(&i) {
+                Some(err) => Err(err),
+                None => combine(
+                    self.combining,
+                    policies.iter().enumerate().map(|(p, policy)| {
+                        deadline.check()?;
+                        let mut effect = None;
+                        for request in requests.iter() {
+                            effect = merge(
+                                effect,
+                                self.memo_policy_effect(
+                                    &mut memo,
+                                    &mut located,
+                                    p,
+                                    policy,
+                                    request,
+                                )?,
+                            );
+                        }
+                        if effect.is_some() {
+                            matched.push(policy.id.clone());
+                        }
+                        Ok((policy.id.as_str(), effect))
+                    }),
+                ),
+            };
+            let decision = located.locate(decision);
+            let latency = started.elapsed();
+            let shadow = self.shadow_decision(&inputs[i]);
+            self.audit(
+                &inputs[i],
+                &decision,
+                &matched,
+                latency,
+                false,
+                shadow.as_ref(),
+            );
+            results.push(decision);
+        }
+        self.prune_stats();
+        results
+    }
+
+    fn memo_policy_effect<'a>(
+        &self,
+        memo: &mut Memo<'a>,
+        located: &mut Located,
+        p: usize,
+        policy: &Policy,
+        input: &'a Request,
+    ) -> Result<Option<Effect>> {
+        let mut effect = None;
+        for (s, statement) in policy.statements.iter().enumerate() {
+            if statement.has_placeholders()
+                || statement.has_exclusions()
+                || statement.conditions.as_ref().is_some_and(|c| !c.is_empty())
+            {
+                match self
+                    .ope
+                    .statement_outcome(statement, input, &mut Timings::default())?
+                {
+                    Outcome::Matched => {}
+                    Outcome::Missed => continue,
+                    Outcome::Rejected(condition) => {
+                        located.miss(&policy.id, s, condition);
+                        continue;
+                    }
+                }
+                if let Effect::Deny = statement.effect {
+                    located.deny(&policy.id, s);
+                    return Ok(Some(Effect::Deny));
+                }
+                effect = Some(Effect::Allow);
+                continue;
+            }
+            let fields = [
+                (Field::Action, &statement.actions, input.action.as_str()),
+                (Field::Subject, &statement.subjects, input.subject.as_str()),
+                (
+                    Field::Resource,
+                    &statement.resources,
+                    input.resource.as_str(),
+                ),
+            ];
+            let mut matched = true;
+            for (field, haystack, needle) in fields {
+                if !self.memo_matches(memo, (p, s, field, needle), statement, haystack)? {
+                    matched = false;
+                    break;
+                }
+            }
+            if !matched {
+                continue;
+            }
+            if let Some(condition) = self.ope.rejected_condition(statement, input)? {
+                located.miss(&policy.id, s, condition);
+                continue;
+            }
+            if let Effect::Deny = statement.effect {
+                located.deny(&policy.id, s);
+                return Ok(Some(Effect::Deny));
+            }
+            effect = Some(Effect::Allow);
+        }
+        Ok(effect)
+    }
+
+    fn memo_matches<'a>(
+        &self,
+        memo: &mut Memo<'a>,
+        key: (usize, usize, Field, &'a str),
+        statement: &Statement,
+        haystack: &[String],
+    ) -> Result<bool> {
+        if let Some(matched) = self.options.shortcut(haystack) {
+            return Ok(matched);
+        }
+        if let Some(matched) = memo.get(&key) {
+            return Ok(*matched);
+        }
+        let matched = self.ope.matches_with(statement, key.2, haystack, key.3)?;
+        memo.insert(key, matched);
+        Ok(matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Context, Effect, Enforcer, Error, Policy, Regexp, Request, Statement};
+
+    #[test]
+    fn batch() {
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![Policy {
+                id: "readers".to_owned(),
+                description: String::new(),
+                statements: vec![Statement {
+                    effect: Effect::Allow,
+                    subjects: vec!["peter".to_owned()],
+                    actions: vec!["<get|list>".to_owned()],
+                    resources: vec!["articles:<[0-9]+>".to_owned()],
+                    conditions: None,
+                    meta: None,
+                    obligations: None,
+                    not_subjects: Vec::new(),
+                    not_actions: Vec::new(),
+                    not_resources: Vec::new(),
+                    subject_matcher: None,
+                    action_matcher: None,
+                    resource_matcher: None,
+                    compiled: Default::default(),
+                }],
+                meta: None,
+                priority: 0,
+            }],
+        );
+        let requests: Vec<Request> = ["get", "list", "delete"]
+            .iter()
+            .flat_map(|action| {
+                ["articles:1", "articles:x"].iter().map(|resource| Request {
+                    resource: resource.to_string(),
+                    action: action.to_string(),
+                    subject: "peter".to_owned(),
+                    context: Context::new(),
+                })
+            })
+            .collect();
+        let results = enforcer.is_allowed_batch(&requests);
+        assert_eq!(results.len(), requests.len());
+        for (request, result) in requests.iter().zip(results.iter()) {
+            let expected = enforcer.is_allowed(request);
+            assert_eq!(result.is_ok(), expected.is_ok());
+        }
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::NotMatched { .. })));
+        assert!(matches!(results[4], Err(Error::NotMatched { .. })));
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::instrument::{self, Instant};
+use crate::matcher::cache::ShardedCache;
+use crate::{Error, Result};
+
+#[derive(Clone)]
+enum Cached {
+    Allow,
+    Deny(String, Option<usize>),
+    NotMatched(Option<String>, Option<usize>, Option<String>),
+    MultipleApplicable(Vec<String>),
+}
+
+#[derive(Clone)]
+struct Entry {
+    decision: Cached,
+    revision: u64,
+    expires: Instant,
+}
+
+pub struct DecisionCache {
+    cache: ShardedCache<String, Entry>,
+    ttl: Duration,
+}
+
+impl DecisionCache {
+    pub fn new(cache_size: usize, ttl: Duration) -> Result<Self> {
+        Ok(Self {
+            cache: ShardedCache::new(cache_size, 16)?,
+            ttl,
+        })
+    }
+
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
+    pub(crate) fn get(&self, key: &str, revision: u64) -> Option<Result<()>> {
+        let decision = self.fetch(key, revision);
+        instrument::cache_lookup(decision.is_some());
+        decision
+    }
+
+    fn fetch(&self, key: &str, revision: u64) -> Option<Result<()>> {
+        let entry = self.cache.get(key)?;
+        if entry.revision != revision || entry.expires <= Instant::now() {
+            return None;
+        }
+        Some(match entry.decision {
+            Cached::Allow => Ok(()),
+            Cached::Deny(policy, statement) => Err(Error::Deny { policy, statement }),
+            Cached::NotMatched(policy, statement, condition) => Err(Error::NotMatched {
+                policy,
+                statement,
+                condition,
+            }),
+            Cached::MultipleApplicable(ids) => Err(Error::MultipleApplicable(ids)),
+        })
+    }
+
+    pub(crate) fn put(&self, key: String, revision: u64, decision: &Result<()>) {
+        let decision = match decision {
+            Ok(()) => Cached::Allow,
+            Err(Error::Deny { policy, statement }) => Cached::Deny(policy.clone(), *statement),
+            Err(Error::NotMatched {
+                policy,
+                statement,
+                condition,
+            }) => Cached::NotMatched(policy.clone(), *statement, condition.clone()),
+            Err(Error::MultipleApplicable(ids)) => Cached::MultipleApplicable(ids.clone()),
+            Err(_) => return,
+        };
+        self.cache.put(
+            key,
+            Entry {
+                decision,
+                revision,
+                expires: Instant::now() + self.ttl,
+            },
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::statement::Effect;
+use crate::{Error, Result};
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum Combining {
+    #[default]
+    DenyOverrides,
+    AllowOverrides,
+    FirstApplicable,
+    OnlyOneApplicable,
+}
+
+pub(crate) struct Combiner<'a> {
+    combining: Combining,
+    allowed: Option<&'a str>,
+    denied: Option<&'a str>,
+}
+
+impl<'a> Combiner<'a> {
+    pub(crate) fn new(combining: Combining) -> Self {
+        Self {
+            combining,
+            allowed: None,
+            denied: None,
+        }
+    }
+
+    pub(crate) fn push(&mut self, id: &'a str, effect: Option<Effect>) -> Option<Result<()>> {
+        let effect = effect?;
+        match (self.combining, &effect) {
+            (Combining::DenyOverrides, Effect::Deny)
+            | (Combining::FirstApplicable, Effect::Deny) => {
+                return Some(Err(Error::deny(id)));
+            }
+            (Combining::AllowOverrides, Effect::Allow)
+            | (Combining::FirstApplicable, Effect::Allow) => return Some(Ok(())),
+            (Combining::OnlyOneApplicable, _) => {
+                if let Some(other) = self.allowed.or(self.denied) {
+                    return Some(Err(Error::MultipleApplicable(vec![
+                        other.to_owned(),
+                        id.to_owned(),
+                    ])));
+                }
+            }
+            _ => {}
+        }
+        match effect {
+            Effect::Allow => self.allowed = self.allowed.or(Some(id)),
+            Effect::Deny => self.denied = self.denied.or(Some(id)),
+        }
+        None
+    }
+
+    pub(crate) fn finish(self) -> Result<()> {
+        if let Some(id) = self.denied {
+            return Err(Error::deny(id));
+        }
+        if self.allowed.is_none() {
+            return Err(Error::not_matched());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use serde_json::Value;
+
+use crate::condition;
+use crate::matcher::Matcher;
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::statement::{Effect, Field, Statement};
+use crate::{Error, Ope, Result};
+
+#[derive(Debug)]
+pub struct Decision {
+    pub result: Result<()>,
+    pub policies: Vec<PolicyTrace>,
+}
+
+impl Decision {
+    pub fn is_allowed(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    pub fn matched(&self) -> impl Iterator<Item = &PolicyTrace> {
+        self.policies.iter().filter(|v| v.effect.is_some())
+    }
+
+    pub fn obligations(&self) -> BTreeMap<String, Value> {
+        let effect = match &self.result {
+            Ok(()) => Effect::Allow,
+            Err(Error::Deny { .. }) => Effect::Deny,
+            Err(_) => return BTreeMap::new(),
+        };
+        let mut obligations = BTreeMap::new();
+        for policy in self.matched() {
+            for statement in policy.statements.iter() {
+                if statement.effect != effect || !statement.applicable() {
+                    continue;
+                }
+                for (key, value) in statement.obligations.iter().flatten() {
+                    obligations
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+        obligations
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyTrace {
+    pub id: String,
+    pub effect: Option<Effect>,
+    pub statements: Vec<StatementTrace>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementTrace {
+    pub index: usize,
+    pub effect: Effect,
+    pub action: Option<String>,
+    pub subject: Option<String>,
+    pub resource: Option<String>,
+    pub spans: BTreeMap<String, Vec<Range<usize>>>,
+    pub excluded: Option<String>,
+    pub conditions: Vec<ConditionTrace>,
+    pub obligations: Option<BTreeMap<String, Value>>,
+}
+
+impl StatementTrace {
+    pub fn applicable(&self) -> bool {
+        self.action.is_some()
+            && self.subject.is_some()
+            && self.resource.is_some()
+            && self.excluded.is_none()
+            && self.conditions.iter().all(|v| v.passed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionTrace {
+    pub key: String,
+    pub condition_type: String,
+    pub evaluated: bool,
+    pub passed: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Located {
+    denied: HashMap<String, usize>,
+    missed: Option<(String, usize, String)>,
+}
+
+impl Located {
+    pub(crate) fn deny(&mut self, policy: &str, statement: usize) {
+        let index = self.denied.entry(policy.to_owned()).or_insert(statement);
+        *index = statement.min(*index);
+    }
+
+    pub(crate) fn miss(&mut self, policy: &str, statement: usize, condition: String) {
+        match &self.missed {
+            Some((id, index, _)) if id != policy || *index <= statement => {}
+            _ => self.missed = Some((policy.to_owned(), statement, condition)),
+        }
+    }
+
+    pub(crate) fn trace(&mut self, policy: &PolicyTrace) {
+        for statement in policy.statements.iter() {
+            if statement.applicable() {
+                if statement.effect == Effect::Deny {
+                    self.deny(&policy.id, statement.index);
+                }
+                continue;
+            }
+            let fields = statement.action.is_some()
+                && statement.subject.is_some()
+                && statement.resource.is_some()
+                && statement.excluded.is_none();
+            let rejected = statement
+                .conditions
+                .iter()
+                .filter(|v| !v.passed)
+                .map(|v| &v.key)
+                .min();
+            if let (true, Some(condition)) = (fields, rejected) {
+                self.miss(&policy.id, statement.index, condition.clone());
+            }
+        }
+    }
+
+    pub(crate) fn locate(self, decision: Result<()>) -> Result<()> {
+        match decision {
+            Err(Error::Deny {
+                policy,
+                statement: None,
+            }) => {
+                let statement = self.denied.get(&policy).copied();
+                Err(Error::Deny { policy, statement })
+            }
+            Err(Error::NotMatched { policy: None, .. }) => Err(match self.missed {
+                Some((policy, statement, condition)) => Error::NotMatched {
+                    policy: Some(policy),
+                    statement: Some(statement),
+                    condition: Some(condition),
+                },
+                None => Error::not_matched(),
+            }),
+            decision => decision,
+        }
+    }
+}
+
+pub(crate) fn trace_policy<M: Matcher>(
+    ope: &Ope<M>,
+    policy: &Policy,
+    input: &Request,
+) -> Result<PolicyTrace> {
+    let mut effect = None;
+    let mut statements = Vec::with_capacity(policy.statements.len());
+    for (index, statement) in policy.statements.iter().enumerate() {
+        let trace = trace_statement(ope, index, statement, input)?;
+        if trace.applicable() && effect != Some(Effect::Deny) {
+            effect = Some(statement.effect.clone());
+        }
+        statements.push(trace);
+    }
+    Ok(PolicyTrace {
+        id: policy.id.clone(),
+        effect,
+        statements,
+    })
+}
+
+fn trace_statement<M: Matcher>(
+    ope: &Ope<M>,
+    index: usize,
+    statement: &Statement,
+    input: &Request,
+) -> Result<StatementTrace> {
+    let mut spans = BTreeMap::new();
+    let mut field =
+        |name: &str, kind: Field, haystack: &[String], needle: &str| -> Result<Option<String>> {
+            Ok(
+                match matched_pattern(ope, statement, kind, haystack, input, needle)? {
+                    Some((pattern, ranges)) => {
+                        if !ranges.is_empty() {
+                            spans.insert(name.to_owned(), ranges);
+                        }
+                        Some(pattern)
+                    }
+                    None => None,
+                },
+            )
+        };
+    let action = field("action", Field::Action, &statement.actions, &input.action)?;
+    let subject = field(
+        "subject",
+        Field::Subject,
+        &statement.subjects,
+        &input.subject,
+    )?;
+    let resource = field(
+        "resource",
+        Field::Resource,
+        &statement.resources,
+        &input.resource,
+    )?;
+    let mut excluded = None;
+    for (kind, haystack, needle) in [
+        (Field::Action, &statement.not_actions, &input.action),
+        (Field::Subject, &statement.not_subjects, &input.subject),
+        (Field::Resource, &statement.not_resources, &input.resource),
+    ] {
+        if haystack.is_empty() {
+            continue;
+        }
+        if let Some((pattern, _)) = matched_pattern(ope, statement, kind, haystack, input, needle)?
+        {
+            excluded = Some(pattern);
+            break;
+        }
+    }
+    let matched = action.is_some() && subject.is_some() && resource.is_some() && excluded.is_none();
+    let mut conditions = Vec::new();
+    if let Some(list) = &statement.conditions {
+        let captured = match matched {
+            true => ope.with_captures(statement, input)?,
+            false => None,
+        };
+        let env_input = captured.as_deref().unwrap_or(input);
+        for (key, value) in list {
+            let condition = match matched {
+                true => Some(ope.conditions.build(value)?),
+                false => None,
+            };
+            let env = condition
+                .as_ref()
+                .and_then(|v| condition::lookup(env_input, key, v.reads_context()));
+            let (evaluated, passed) = match (&condition, env) {
+                (Some(condition), Some(env)) => (true, condition.evaluate(env, env_input)),
+                _ => (false, false),
+            };
+            conditions.push(ConditionTrace {
+                key: key.clone(),
+                condition_type: value.jtype.clone(),
+                evaluated,
+                passed,
+            });
+        }
+    }
+    Ok(StatementTrace {
+        index,
+        effect: statement.effect.clone(),
+        action,
+        subject,
+        resource,
+        spans,
+        excluded,
+        conditions,
+        obligations: statement.obligations.clone(),
+    })
+}
+
+fn matched_pattern<M: Matcher>(
+    ope: &Ope<M>,
+    statement: &Statement,
+    field: Field,
+    haystack: &[String],
+    input: &Request,
+    needle: &str,
+) -> Result<Option<(String, Vec<Range<usize>>)>> {
+    match ope.fields.shortcut(haystack) {
+        Some(true) if haystack.is_empty() => return Ok(Some((String::new(), Vec::new()))),
+        Some(false) => return Ok(None),
+        _ => {}
+    }
+    for h in haystack.iter() {
+        if ope.fields.shortcut(std::slice::from_ref(h)) == Some(true) {
+            return Ok(Some((h.clone(), Vec::new())));
+        }
+        let resolved = ope.resolve(statement, field, std::slice::from_ref(h), &input.context)?;
+        if resolved.is_empty() {
+            continue;
+        }
+        if let Some(ranges) = ope.spans_with(statement, field, &resolved, needle)? {
+            return Ok(Some((h.clone(), ranges)));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,149 @@
+use super::partial::{PartialRequest, Residual};
+use super::Enforcer;
+use crate::matcher::Matcher;
+use crate::statement::Effect;
+use crate::store::PolicyStore;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grant {
+    pub policy: String,
+    pub effect: Effect,
+    pub action: String,
+    pub resource: String,
+    pub pattern: bool,
+    pub conditional: bool,
+}
+
+impl<M: Matcher, S: PolicyStore> Enforcer<M, S> {
+    pub fn permissions_for(&self, subject: &str) -> Result<Vec<Grant>> {
+        let residuals = self.partial_eval(&PartialRequest {
+            subject: Some(subject.to_owned()),
+            ..Default::default()
+        })?;
+        self.grants(residuals, None)
+    }
+
+    pub fn actions_for(&self, subject: &str, resource: &str) -> Result<Vec<Grant>> {
+        let residuals = self.partial_eval(&PartialRequest {
+            subject: Some(subject.to_owned()),
+            resource: Some(resource.to_owned()),
+            ..Default::default()
+        })?;
+        self.grants(residuals, Some(resource))
+    }
+
+    fn grants(&self, residuals: Vec<Residual>, resource: Option<&str>) -> Result<Vec<Grant>> {
+        let mut grants = Vec::new();
+        for residual in residuals {
+            let policy = match self.store.get(&residual.policy) {
+                Ok(policy) => policy,
+                Err(Error::PolicyNotFound(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let Some(statement) = policy.statements.get(residual.index) else {
+                continue;
+            };
+            let delimiter = statement.get_start_delimiter();
+            let resources = match &residual.resources {
+                Some(resources) => resources.clone(),
+                None => vec![resource.unwrap_or_default().to_owned()],
+            };
+            for action in residual.actions.iter().flatten() {
+                for resource in resources.iter() {
+                    let pattern = action.contains(delimiter)
+                        || (residual.resources.is_some() && resource.contains(delimiter));
+                    let grant = Grant {
+                        policy: residual.policy.clone(),
+                        effect: residual.effect.clone(),
+                        action: action.clone(),
+                        resource: resource.clone(),
+                        pattern,
+                        conditional: !residual.conditions.is_empty()
+                            || residual.subjects.is_some()
+                            || residual.not_subjects.is_some(),
+                    };
+                    if !grants.contains(&grant) {
+                        grants.push(grant);
+                    }
+                }
+            }
+        }
+        Ok(grants)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Policy, PolicyBuilder, Regexp};
+
+    #[test]
+    fn permissions() {
+        let policies = vec![
+            PolicyBuilder::new("articles")
+                .allow()
+                .subject("<peter|ken>")
+                .actions(["get", "update"])
+                .resources(["articles:1", "articles:<[0-9]+>"])
+                .condition("clientIP", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+                .build()
+                .unwrap(),
+            PolicyBuilder::new("secret")
+                .deny()
+                .subject("peter")
+                .action("<.*>")
+                .resource("articles:0")
+                .build()
+                .unwrap(),
+        ];
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies);
+
+        let grants = enforcer.permissions_for("peter").unwrap();
+        assert_eq!(grants.len(), 5);
+        assert_eq!(
+            grants[0],
+            Grant {
+                policy: "articles".to_owned(),
+                effect: Effect::Allow,
+                action: "get".to_owned(),
+                resource: "articles:1".to_owned(),
+                pattern: false,
+                conditional: true,
+            }
+        );
+        assert!(grants[1].pattern);
+        assert!(grants[4].pattern && grants[4].effect == Effect::Deny);
+        assert!(enforcer.permissions_for("max").unwrap().is_empty());
+
+        let actions = enforcer.actions_for("ken", "articles:7").unwrap();
+        assert_eq!(
+            actions
+                .iter()
+                .map(|v| v.action.as_str())
+                .collect::<Vec<_>>(),
+            vec!["get", "update"]
+        );
+        assert!(actions
+            .iter()
+            .all(|v| v.resource == "articles:7" && !v.pattern));
+        assert_eq!(
+            enforcer.actions_for("peter", "articles:0").unwrap().len(),
+            3
+        );
+
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut shrunk = Policy::clone(&enforcer.store().get("articles").unwrap());
+        shrunk.statements.clear();
+        enforcer.store().update(shrunk).unwrap();
+        enforcer.store().delete("secret").unwrap();
+        assert!(enforcer.grants(residuals, None).unwrap().is_empty());
+    }
+}
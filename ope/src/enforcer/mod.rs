@@ -0,0 +1,1212 @@
+#[cfg(feature = "async")]
+pub(crate) mod asynchronous;
+pub(crate) mod batch;
+pub(crate) mod cache;
+pub(crate) mod combining;
+pub(crate) mod decision;
+pub(crate) mod grants;
+pub(crate) mod namespace;
+pub(crate) mod options;
+pub(crate) mod partial;
+pub(crate) mod sql;
+pub(crate) mod stats;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::audit::{AuditEvent, AuditSink};
+use crate::condition::ConditionRegistry;
+use crate::instrument::{self, Instant, Timings};
+use crate::matcher::{registry::MatcherRegistry, Matcher};
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::resolver::SubjectResolver;
+use crate::statement::Effect;
+use crate::store::{may_match_with, MemoryStore, PolicyStore};
+use crate::{Error, Ope, Outcome, Result};
+
+use combining::Combiner;
+use decision::Located;
+use options::Deadline;
+use stats::Stats;
+
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncEnforcer;
+pub use cache::DecisionCache;
+pub use combining::Combining;
+pub use decision::{ConditionTrace, Decision, PolicyTrace, StatementTrace};
+pub use grants::Grant;
+pub use namespace::Namespaces;
+pub use options::{EmptyFields, EnforcerOptions};
+pub use partial::{PartialRequest, Residual};
+pub use sql::{Placeholder, SqlFilter, TableMapping};
+pub use stats::{EvaluationStats, PolicyStats};
+
+pub struct Enforcer<M, S = MemoryStore> {
+    ope: Ope<M>,
+    store: S,
+    combining: Combining,
+    resolver: Option<Arc<dyn SubjectResolver + Send + Sync>>,
+    cache: Option<DecisionCache>,
+    audit: Option<Arc<dyn AuditSink + Send + Sync>>,
+    options: EnforcerOptions,
+    shadow: Option<Arc<dyn PolicyStore + Send + Sync>>,
+    stats: Stats,
+    rerank_every: Option<u64>,
+}
+
+impl<M> Enforcer<M> {
+    pub fn new(matcher: M, policies: Vec<Policy>) -> Self {
+        Self::with_store(matcher, MemoryStore::from(policies))
+    }
+
+    pub fn with_conditions(
+        matcher: M,
+        conditions: ConditionRegistry,
+        policies: Vec<Policy>,
+    ) -> Self {
+        Self {
+            ope: Ope::with_conditions(matcher, conditions),
+            store: MemoryStore::from(policies),
+            combining: Combining::default(),
+            resolver: None,
+            cache: None,
+            audit: None,
+            options: EnforcerOptions::default(),
+            shadow: None,
+            stats: Stats::default(),
+            rerank_every: None,
+        }
+    }
+}
+
+impl<M, S> Enforcer<M, S> {
+    pub fn with_store(matcher: M, store: S) -> Self {
+        Self {
+            ope: Ope::new(matcher),
+            store,
+            combining: Combining::default(),
+            resolver: None,
+            cache: None,
+            audit: None,
+            options: EnforcerOptions::default(),
+            shadow: None,
+            stats: Stats::default(),
+            rerank_every: None,
+        }
+    }
+
+    pub fn conditions(&self) -> &ConditionRegistry {
+        self.ope.conditions()
+    }
+
+    pub fn with_matchers(mut self, matchers: MatcherRegistry) -> Self {
+        self.ope.matchers = matchers;
+        self
+    }
+
+    pub fn matchers(&self) -> &MatcherRegistry {
+        self.ope.matchers()
+    }
+
+    pub fn with_combining(mut self, combining: Combining) -> Self {
+        self.combining = combining;
+        self
+    }
+
+    pub fn combining(&self) -> Combining {
+        self.combining
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn with_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: SubjectResolver + Send + Sync + 'static,
+    {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn with_decision_cache(mut self, cache: DecisionCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_audit<A>(mut self, sink: A) -> Self
+    where
+        A: AuditSink + Send + Sync + 'static,
+    {
+        self.audit = Some(Arc::new(sink));
+        self
+    }
+
+    pub fn with_shadow<T>(mut self, store: T) -> Self
+    where
+        T: PolicyStore + Send + Sync + 'static,
+    {
+        self.shadow = Some(Arc::new(store));
+        self
+    }
+
+    pub fn with_options(mut self, options: EnforcerOptions) -> Self {
+        self.ope.fields = options;
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> EnforcerOptions {
+        self.options
+    }
+
+    pub fn invalidate(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    pub fn with_adaptive_ordering(mut self, every: u64) -> Self {
+        self.rerank_every = Some(every.max(1));
+        self.stats.track();
+        self
+    }
+
+    pub fn with_policy_stats(mut self) -> Self {
+        self.stats.track();
+        self
+    }
+
+    pub fn stats(&self) -> EvaluationStats {
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    fn audit(
+        &self,
+        input: &Request,
+        decision: &Result<()>,
+        policies: &[String],
+        latency: Duration,
+        cached: bool,
+        shadow: Option<&Result<()>>,
+    ) {
+        instrument::decision(decision, latency, policies, cached);
+        let evaluations = self.stats.record(decision, policies, cached);
+        if self
+            .rerank_every
+            .is_some_and(|every| evaluations.is_multiple_of(every))
+        {
+            self.stats.rerank();
+        }
+        if let Some(sink) = &self.audit {
+            sink.record(&AuditEvent {
+                timestamp: Utc::now(),
+                request: input,
+                decision,
+                policies,
+                latency,
+                cached,
+                shadow,
+            });
+        }
+    }
+
+    pub(crate) fn prioritize(&self, policies: &mut [Arc<Policy>]) {
+        if self.combining == Combining::FirstApplicable {
+            policies.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.id.cmp(&b.id)));
+            return;
+        }
+        let adaptive = self.rerank_every.is_some()
+            && matches!(
+                self.combining,
+                Combining::DenyOverrides | Combining::AllowOverrides
+            );
+        if !adaptive {
+            return;
+        }
+        let ranking = self.stats.ranking();
+        let hits = |policy: &Policy| ranking.get(&policy.id).copied().unwrap_or_default();
+        policies.sort_by(|a, b| hits(b).cmp(&hits(a)).then_with(|| a.id.cmp(&b.id)));
+    }
+
+    fn expand<'a>(&self, input: &'a Request) -> Result<Vec<Cow<'a, Request>>> {
+        let mut requests = vec![Cow::Borrowed(input)];
+        if let Some(resolver) = &self.resolver {
+            for subject in resolver.resolve(&input.subject)? {
+                if subject != input.subject {
+                    let mut request = input.clone();
+                    request.subject = subject;
+                    requests.push(Cow::Owned(request));
+                }
+            }
+        }
+        Ok(requests)
+    }
+}
+
+impl<M: Matcher, S: PolicyStore> Enforcer<M, S> {
+    pub fn warm(&self) -> Result<()> {
+        for policy in self.store.all()?.iter() {
+            self.ope.warm(&policy.statements)?;
+        }
+        Ok(())
+    }
+
+    pub fn policy_stats(&self) -> Result<BTreeMap<String, PolicyStats>> {
+        let mut stats = self.stats.policies();
+        Ok(self
+            .store
+            .all()?
+            .iter()
+            .map(|policy| {
+                let entry = stats.remove(&policy.id).unwrap_or_default();
+                (policy.id.clone(), entry)
+            })
+            .collect())
+    }
+
+    pub fn is_allowed(&self, input: &Request) -> Result<()> {
+        let span = instrument::evaluation(input);
+        let _entered = span.enter();
+        let started = Instant::now();
+        let (mut policies, mut cached) = (Vec::new(), false);
+        let deadline = self.options.deadline(started);
+        let decision = self.decide(input, deadline, &mut policies, &mut cached);
+        let latency = started.elapsed();
+        let shadow = self.shadow_decision(input);
+        self.audit(
+            input,
+            &decision,
+            &policies,
+            latency,
+            cached,
+            shadow.as_ref(),
+        );
+        self.prune_stats();
+        decision
+    }
+
+    pub(crate) fn prune_stats(&self) {
+        let revision = self.store.revision();
+        if !self.stats.stale(revision) {
+            return;
+        }
+        match self.store.all() {
+            Ok(policies) => self.stats.prune(
+                revision,
+                &policies.iter().map(|policy| policy.id.as_str()).collect(),
+            ),
+            Err(err) => tracing::warn!("prune policy stats failed: {}", err),
+        }
+    }
+
+    fn decide(
+        &self,
+        input: &Request,
+        deadline: Deadline,
+        policies: &mut Vec<String>,
+        cached: &mut bool,
+    ) -> Result<()> {
+        let Some(cache) = &self.cache else {
+            return self.evaluate(input, deadline, policies);
+        };
+        let (key, revision) = (input.canonical()?, self.store.revision());
+        if let Some(decision) = cache.get(&key, revision) {
+            *cached = true;
+            return decision;
+        }
+        let decision = self.evaluate(input, deadline, policies);
+        cache.put(key, revision, &decision);
+        decision
+    }
+
+    fn evaluate(
+        &self,
+        input: &Request,
+        deadline: Deadline,
+        policies: &mut Vec<String>,
+    ) -> Result<()> {
+        self.evaluate_in(&self.store, input, deadline, policies)
+    }
+
+    pub(crate) fn shadow_decision(&self, input: &Request) -> Option<Result<()>> {
+        let shadow = self.shadow.as_deref()?;
+        self.audit.as_ref()?;
+        let deadline = self.options.deadline(Instant::now());
+        Some(self.evaluate_in(shadow, input, deadline, &mut Vec::new()))
+    }
+
+    fn evaluate_in<T: PolicyStore + ?Sized>(
+        &self,
+        store: &T,
+        input: &Request,
+        deadline: Deadline,
+        policies: &mut Vec<String>,
+    ) -> Result<()> {
+        tracing::debug!("input = {:?}, combining = {:?}", input, self.combining);
+        let requests = self.expand(input)?;
+        let mut candidates = candidates(&self.ope.matcher, store, &requests)?;
+        self.prioritize(&mut candidates);
+        let mut located = Located::default();
+        let decision = combine(
+            self.combining,
+            candidates.iter().map(|policy| {
+                deadline.check()?;
+                let span = instrument::policy(&policy.id);
+                let _entered = span.enter();
+                let (mut effect, mut timings) = (None, Timings::default());
+                for request in requests.iter() {
+                    effect = merge(
+                        effect,
+                        self.policy_effect(policy, request, &mut located, &mut timings)?,
+                    );
+                }
+                instrument::policy_effect(&span, effect.as_ref(), &timings);
+                if effect.is_some() {
+                    policies.push(policy.id.clone());
+                }
+                Ok((policy.id.as_str(), effect))
+            }),
+        );
+        located.locate(decision)
+    }
+
+    pub fn explain(&self, input: &Request) -> Decision {
+        let deadline = self.options.deadline(Instant::now());
+        let prepared = self.expand(input).and_then(|requests| {
+            let mut candidates = candidates(&self.ope.matcher, &self.store, &requests)?;
+            self.prioritize(&mut candidates);
+            Ok((requests, candidates))
+        });
+        let (requests, candidates) = match prepared {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                return Decision {
+                    result: Err(err),
+                    policies: Vec::new(),
+                }
+            }
+        };
+        let mut policies = Vec::with_capacity(candidates.len());
+        for policy in candidates.iter() {
+            match deadline
+                .check()
+                .and_then(|_| self.trace_policy(policy, &requests))
+            {
+                Ok(trace) => policies.push(trace),
+                Err(err) => {
+                    return Decision {
+                        result: Err(err),
+                        policies,
+                    }
+                }
+            }
+        }
+        let result = combine(
+            self.combining,
+            policies
+                .iter()
+                .map(|trace| Ok((trace.id.as_str(), trace.effect.clone()))),
+        );
+        let mut located = Located::default();
+        for trace in policies.iter() {
+            located.trace(trace);
+        }
+        Decision {
+            result: located.locate(result),
+            policies,
+        }
+    }
+
+    fn trace_policy(&self, policy: &Policy, requests: &[Cow<'_, Request>]) -> Result<PolicyTrace> {
+        let mut traced: Option<PolicyTrace> = None;
+        for request in requests.iter() {
+            let trace = decision::trace_policy(&self.ope, policy, request)?;
+            let stronger = match (&traced, &trace.effect) {
+                (None, _) => true,
+                (Some(prev), Some(Effect::Deny)) => prev.effect != Some(Effect::Deny),
+                (Some(prev), Some(Effect::Allow)) => prev.effect.is_none(),
+                (Some(_), None) => false,
+            };
+            if stronger {
+                traced = Some(trace);
+            }
+        }
+        traced.ok_or_else(Error::not_matched)
+    }
+
+    fn policy_effect(
+        &self,
+        policy: &Policy,
+        input: &Request,
+        located: &mut Located,
+        timings: &mut Timings,
+    ) -> Result<Option<Effect>> {
+        let mut effect = None;
+        for (index, statement) in policy.statements.iter().enumerate() {
+            match self.ope.statement_outcome(statement, input, timings)? {
+                Outcome::Matched => {}
+                Outcome::Missed => continue,
+                Outcome::Rejected(condition) => {
+                    located.miss(&policy.id, index, condition);
+                    continue;
+                }
+            }
+            if let Effect::Deny = statement.effect {
+                located.deny(&policy.id, index);
+                return Ok(Some(Effect::Deny));
+            }
+            effect = Some(Effect::Allow);
+        }
+        Ok(effect)
+    }
+}
+
+fn candidates<M: Matcher, T: PolicyStore + ?Sized>(
+    matcher: &M,
+    store: &T,
+    requests: &[Cow<'_, Request>],
+) -> Result<Vec<Arc<Policy>>> {
+    if matcher.indexable() {
+        return store.find_request_candidates(requests);
+    }
+    Ok(store
+        .all()?
+        .into_iter()
+        .filter(|policy| {
+            requests.iter().any(|input| {
+                may_match_with(
+                    policy,
+                    &input.subject,
+                    &input.resource,
+                    &input.action,
+                    |delimiter, pattern| matcher.is_literal(delimiter, pattern),
+                )
+            })
+        })
+        .collect())
+}
+
+fn merge(a: Option<Effect>, b: Option<Effect>) -> Option<Effect> {
+    match (a, b) {
+        (Some(Effect::Deny), _) | (_, Some(Effect::Deny)) => Some(Effect::Deny),
+        (Some(Effect::Allow), _) | (_, Some(Effect::Allow)) => Some(Effect::Allow),
+        _ => None,
+    }
+}
+
+fn combine<'a, I>(combining: Combining, effects: I) -> Result<()>
+where
+    I: Iterator<Item = Result<(&'a str, Option<Effect>)>>,
+{
+    let mut combiner = Combiner::new(combining);
+    for item in effects {
+        let (id, effect) = item?;
+        if let Some(result) = combiner.push(id, effect) {
+            return result;
+        }
+    }
+    combiner.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, Glob, HierarchyMatcher, Regexp, RoleGraph, SpiffeMatcher, Statement};
+
+    fn policy(id: &str, effect: Effect, resources: &[&str]) -> Policy {
+        Policy {
+            id: id.to_owned(),
+            description: String::new(),
+            statements: vec![Statement {
+                effect,
+                subjects: vec!["<.*>".to_owned()],
+                actions: vec!["get".to_owned()],
+                resources: resources.iter().map(|v| v.to_string()).collect(),
+                conditions: None,
+                meta: None,
+                obligations: None,
+                not_subjects: Vec::new(),
+                not_actions: Vec::new(),
+                not_resources: Vec::new(),
+                subject_matcher: None,
+                action_matcher: None,
+                resource_matcher: None,
+                compiled: Default::default(),
+            }],
+            meta: None,
+            priority: 0,
+        }
+    }
+
+    fn request(resource: &str) -> Request {
+        Request {
+            resource: resource.to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::new(),
+        }
+    }
+
+    #[test]
+    fn deny_overrides() {
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+                policy("deny-secret", Effect::Deny, &["articles:secret"]),
+            ],
+        );
+        enforcer.warm().unwrap();
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        assert!(matches!(
+            enforcer.is_allowed(&request("articles:secret")),
+            Err(Error::Deny { policy, .. }) if policy == "deny-secret"
+        ));
+        assert!(matches!(
+            enforcer.is_allowed(&request("users:1")),
+            Err(Error::NotMatched { .. })
+        ));
+    }
+
+    #[test]
+    fn structured_errors() {
+        let policies = vec![Policy::builder("guarded")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:1")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:<.*>")
+            .condition("ip", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .deny()
+            .subject("peter")
+            .action("get")
+            .resource("articles:secret")
+            .build()
+            .unwrap()];
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies);
+        let mut input = request("articles:secret");
+        let denied = enforcer.is_allowed(&input).unwrap_err();
+        assert!(matches!(
+            &denied,
+            Error::Deny { policy, statement: Some(2) } if policy == "guarded"
+        ));
+        assert_eq!(
+            denied.to_string(),
+            "The request was denied because a statement denied request.Please proofread the policy guarded"
+        );
+
+        input.resource = "articles:2".to_owned();
+        input.context.insert("ip", "192.168.0.1");
+        let missed = enforcer.is_allowed(&input).unwrap_err();
+        assert!(matches!(
+            &missed,
+            Error::NotMatched {
+                policy: Some(policy),
+                statement: Some(1),
+                condition: Some(condition),
+            } if policy == "guarded" && condition == "ip"
+        ));
+        assert_eq!(
+            missed.to_string(),
+            "The request was denied because no matching statement was found."
+        );
+        assert_eq!(
+            format!("{:?}", enforcer.explain(&input).result.unwrap_err()),
+            format!("{missed:?}")
+        );
+        assert!(matches!(
+            enforcer.is_allowed(&request("users:1")),
+            Err(Error::NotMatched { policy: None, .. })
+        ));
+    }
+
+    #[test]
+    fn priority() {
+        let mut deny = policy("z-deny-secret", Effect::Deny, &["articles:secret"]);
+        deny.priority = 10;
+        let policies = vec![
+            policy("a-allow-all", Effect::Allow, &["articles:<.*>"]),
+            deny,
+        ];
+        let first = Enforcer::new(Regexp::new(16).unwrap(), policies)
+            .with_combining(Combining::FirstApplicable);
+        assert!(matches!(
+            first.is_allowed(&request("articles:secret")),
+            Err(Error::Deny { policy, .. }) if policy == "z-deny-secret"
+        ));
+        assert!(matches!(
+            &first.is_allowed_batch(&[request("articles:secret")])[0],
+            Err(Error::Deny { policy, .. }) if policy == "z-deny-secret"
+        ));
+        assert_eq!(
+            first.explain(&request("articles:secret")).policies[0].id,
+            "z-deny-secret"
+        );
+    }
+
+    #[test]
+    fn prefilter() {
+        let glob = Policy::builder("glob")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:*")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Glob::new(), vec![glob]);
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        assert!(enforcer.explain(&request("articles:1")).is_allowed());
+        assert!(enforcer.is_allowed(&request("users:1")).is_err());
+
+        let insensitive = Regexp::builder().case_insensitive(true).build().unwrap();
+        let enforcer = Enforcer::new(
+            insensitive,
+            vec![policy("upper", Effect::Allow, &["Articles:<[0-9]+>"])],
+        );
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+
+        let mut named = policy("named", Effect::Allow, &["articles:1"]);
+        named.statements[0].subjects = vec!["Peter".to_owned()];
+        let store = MemoryStore::from(vec![named])
+            .with_subject_filter(16, 0.01)
+            .unwrap();
+        let enforcer = Enforcer::with_store(
+            Regexp::builder().case_insensitive(true).build().unwrap(),
+            store,
+        );
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+
+        let mut braces = policy("braces", Effect::Allow, &["articles:{{[0-9]+}}"]);
+        braces.statements[0].subjects = vec!["{{.*}}".to_owned()];
+        let enforcer = Enforcer::new(
+            Regexp::builder().delimiters("{{", "}}").build().unwrap(),
+            vec![braces],
+        );
+        assert!(enforcer.is_allowed(&request("articles:7")).is_ok());
+        assert!(enforcer.is_allowed(&request("articles:x")).is_err());
+
+        let project = Policy::builder("project")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("projects:42")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(HierarchyMatcher::default(), vec![project]);
+        assert!(enforcer
+            .is_allowed(&request("projects:42:datasets:7"))
+            .is_ok());
+        assert!(enforcer.is_allowed(&request("projects:420")).is_err());
+
+        let mut workload = Policy::builder("workload")
+            .allow()
+            .subject("spiffe://example.org/ns/prod")
+            .action("get")
+            .resource("spiffe://example.org/db")
+            .build()
+            .unwrap();
+        workload.statements[0].action_matcher = Some("exact".to_owned());
+        let enforcer = Enforcer::new(SpiffeMatcher::new(), vec![workload]);
+        let mut input = request("spiffe://example.org/db");
+        input.subject = "spiffe://example.org/ns/prod/sa/web".to_owned();
+        assert!(enforcer.is_allowed(&input).is_ok());
+        input.subject = "spiffe://example.org/ns/dev/sa/web".to_owned();
+        assert!(enforcer.is_allowed(&input).is_err());
+    }
+
+    #[test]
+    fn field_semantics() {
+        let mut wildcard = policy("wildcard", Effect::Allow, &["*"]);
+        wildcard.statements[0].subjects = vec!["*".to_owned()];
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![wildcard.clone()]);
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        assert!(enforcer.explain(&request("users:1")).is_allowed());
+        assert!(enforcer.is_allowed_batch(&[request("users:1")])[0].is_ok());
+        let literal = Enforcer::new(Regexp::new(16).unwrap(), vec![wildcard])
+            .with_options(EnforcerOptions::new().wildcard(false));
+        assert!(literal.is_allowed(&request("articles:1")).is_err());
+
+        let mut empty = policy("empty", Effect::Allow, &["articles:<.*>"]);
+        empty.statements[0].actions.clear();
+        let nothing = Enforcer::new(Regexp::new(16).unwrap(), vec![empty.clone()]);
+        assert!(nothing.is_allowed(&request("articles:1")).is_err());
+        let all = Enforcer::new(Regexp::new(16).unwrap(), vec![empty])
+            .with_options(EnforcerOptions::new().empty_fields(EmptyFields::MatchAll));
+        assert!(all.is_allowed(&request("articles:1")).is_ok());
+        assert!(all.is_allowed(&request("users:1")).is_err());
+        assert!(all.explain(&request("articles:1")).is_allowed());
+    }
+
+    #[test]
+    fn exclusions() {
+        let mut buckets = policy("buckets", Effect::Allow, &["buckets:<.*>"]);
+        buckets.statements[0].not_resources = vec!["buckets:audit-<.*>".to_owned()];
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![buckets]);
+        assert!(enforcer.is_allowed(&request("buckets:photos")).is_ok());
+        assert!(matches!(
+            enforcer.is_allowed(&request("buckets:audit-logs")),
+            Err(Error::NotMatched { .. })
+        ));
+        let results =
+            enforcer.is_allowed_batch(&[request("buckets:photos"), request("buckets:audit-logs")]);
+        assert!(results[0].is_ok() && results[1].is_err());
+        let decision = enforcer.explain(&request("buckets:audit-logs"));
+        let trace = &decision.policies[0].statements[0];
+        assert_eq!(trace.excluded.as_deref(), Some("buckets:audit-<.*>"));
+        assert!(!trace.applicable());
+
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                action: Some("get".to_owned()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            residuals[0].not_resources,
+            Some(vec!["buckets:audit-<.*>".to_owned()])
+        );
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                resource: Some("buckets:audit-logs".to_owned()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(residuals.is_empty());
+    }
+
+    #[test]
+    fn timeout() {
+        use std::time::Duration;
+
+        let policies = vec![policy("allow-all", Effect::Allow, &["articles:<.*>"])];
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies.clone())
+            .with_options(EnforcerOptions::new().timeout(Duration::from_secs(60)));
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies)
+            .with_options(EnforcerOptions::new().timeout(Duration::ZERO));
+        assert!(matches!(
+            enforcer.is_allowed(&request("articles:1")),
+            Err(Error::Timeout(_))
+        ));
+        assert!(matches!(
+            enforcer.explain(&request("articles:1")).result,
+            Err(Error::Timeout(_))
+        ));
+        assert!(matches!(
+            enforcer.is_allowed_batch(&[request("articles:1")])[0],
+            Err(Error::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn combining() {
+        let policies = vec![
+            policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+            policy("deny-secret", Effect::Deny, &["articles:secret"]),
+        ];
+        let allow_overrides = Enforcer::new(Regexp::new(16).unwrap(), policies.clone())
+            .with_combining(Combining::AllowOverrides);
+        assert!(allow_overrides
+            .is_allowed(&request("articles:secret"))
+            .is_ok());
+
+        let first = Enforcer::new(Regexp::new(16).unwrap(), policies.clone())
+            .with_combining(Combining::FirstApplicable);
+        assert!(first.is_allowed(&request("articles:secret")).is_ok());
+        let first = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("a-deny-secret", Effect::Deny, &["articles:secret"]),
+                policy("b-allow-all", Effect::Allow, &["articles:<.*>"]),
+            ],
+        )
+        .with_combining(Combining::FirstApplicable);
+        assert!(matches!(
+            first.is_allowed(&request("articles:secret")),
+            Err(Error::Deny { policy, .. }) if policy == "a-deny-secret"
+        ));
+
+        let only_one = Enforcer::new(Regexp::new(16).unwrap(), policies)
+            .with_combining(Combining::OnlyOneApplicable);
+        assert!(only_one.is_allowed(&request("articles:1")).is_ok());
+        assert!(matches!(
+            only_one.is_allowed(&request("articles:secret")),
+            Err(Error::MultipleApplicable(_))
+        ));
+    }
+
+    #[test]
+    fn explain() {
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+                policy("deny-secret", Effect::Deny, &["articles:secret"]),
+            ],
+        );
+        let decision = enforcer.explain(&request("articles:secret"));
+        assert!(
+            matches!(&decision.result, Err(Error::Deny { policy, .. }) if policy == "deny-secret")
+        );
+        assert_eq!(decision.policies.len(), 2);
+        let allow = &decision.policies[0].statements[0];
+        assert_eq!(allow.resource.as_deref(), Some("articles:<.*>"));
+        assert_eq!(allow.subject.as_deref(), Some("<.*>"));
+        assert_eq!(allow.spans["resource"], vec![9..15]);
+        assert!(!allow.spans.contains_key("action"));
+        assert!(allow.applicable());
+
+        let decision = enforcer.explain(&request("users:1"));
+        assert!(matches!(decision.result, Err(Error::NotMatched { .. })));
+        assert!(decision.matched().next().is_none());
+        assert!(decision.policies.is_empty());
+
+        let mut unknown = policy("unknown", Effect::Allow, &["articles:<.*>"]);
+        let mut deny = policy("unknown", Effect::Deny, &["users:<.*>"]).statements[0].clone();
+        deny.conditions = Some(HashMap::from([(
+            "clientIP".to_owned(),
+            serde_json::from_value(json!({"type": "Unknown", "options": {}})).unwrap(),
+        )]));
+        unknown.statements.push(deny);
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![unknown]);
+        let mut input = request("articles:1");
+        input.context.insert("clientIP", "10.1.2.3");
+        let decision = enforcer.explain(&input);
+        assert!(decision.is_allowed());
+        assert!(!decision.policies[0].statements[1].conditions[0].evaluated);
+        assert_eq!(decision.result.is_ok(), enforcer.is_allowed(&input).is_ok());
+    }
+
+    #[test]
+    fn obligations() {
+        let policy = crate::PolicyBuilder::new("articles")
+            .allow()
+            .subject("<.*>")
+            .action("get")
+            .resource("articles:<.*>")
+            .obligation("mask_fields", ["ssn"])
+            .deny()
+            .subject("<.*>")
+            .action("get")
+            .resource("articles:secret")
+            .obligation("require_mfa", true)
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+
+        let decision = enforcer.explain(&request("articles:1"));
+        assert!(decision.is_allowed());
+        assert_eq!(
+            decision.obligations(),
+            BTreeMap::from([("mask_fields".to_owned(), json!(["ssn"]))])
+        );
+        let decision = enforcer.explain(&request("articles:secret"));
+        assert_eq!(
+            decision.obligations(),
+            BTreeMap::from([("require_mfa".to_owned(), json!(true))])
+        );
+        assert!(enforcer
+            .explain(&request("users:1"))
+            .obligations()
+            .is_empty());
+    }
+
+    #[test]
+    fn named_captures() {
+        let policy = crate::PolicyBuilder::new("own-tenant")
+            .allow()
+            .subject("users:<tenant:[a-z]+>:<.*>")
+            .action("get")
+            .resource("tenants:<tenant:[a-z]+>:articles:<id:[0-9]+>")
+            .condition("resource.tenant", "StringEqual", json!({"equals": "acme"}))
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let input = |resource: &str| Request {
+            resource: resource.to_owned(),
+            action: "get".to_owned(),
+            subject: "users:acme:peter".to_owned(),
+            context: Context::new(),
+        };
+        assert!(enforcer
+            .is_allowed(&input("tenants:acme:articles:1"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&input("tenants:other:articles:1"))
+            .is_err());
+        let decision = enforcer.explain(&input("tenants:other:articles:1"));
+        let conditions = &decision.policies[0].statements[0].conditions;
+        assert!(conditions[0].evaluated && !conditions[0].passed);
+    }
+
+    #[test]
+    fn ownership() {
+        let policy = crate::PolicyBuilder::new("own-tenant")
+            .allow()
+            .subject("users:<tenant:[a-z]+>:<.*>")
+            .action("get")
+            .resource("tenants:<tenant:[a-z]+>:articles:<.*>")
+            .condition(
+                "resource.tenant",
+                "EqualsAttribute",
+                json!({"attribute": "subject.tenant"}),
+            )
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let input = |resource: &str| Request {
+            resource: resource.to_owned(),
+            action: "get".to_owned(),
+            subject: "users:acme:peter".to_owned(),
+            context: Context::new(),
+        };
+        assert!(enforcer
+            .is_allowed(&input("tenants:acme:articles:1"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&input("tenants:other:articles:1"))
+            .is_err());
+    }
+
+    #[test]
+    fn placeholders() {
+        let policy = crate::PolicyBuilder::new("tenant-articles")
+            .allow()
+            .subject("<.*>")
+            .action("get")
+            .resources([
+                "tenants:${ctx.tenant}:articles:<.*>",
+                "teams:${ctx.tenant}<[0-9]+>",
+                "${ctx.home}",
+            ])
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let input = |resource: &str, tenant: &str| Request {
+            resource: resource.to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([("tenant", tenant)]),
+        };
+        assert!(enforcer
+            .is_allowed(&input("tenants:acme:articles:1", "acme"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&input("tenants:acme:articles:1", "other"))
+            .is_err());
+        assert!(enforcer
+            .is_allowed(&input("tenants:a<b:articles:1", "a<b"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&input(r"tenants:a\:articles:1", r"a\"))
+            .is_ok());
+        assert!(enforcer.is_allowed(&input(r"teams:a\1", r"a\")).is_ok());
+        assert!(enforcer.is_allowed(&input(r"teams:a\x", r"a\")).is_err());
+        assert!(enforcer
+            .is_allowed(&request("tenants::articles:1"))
+            .is_err());
+
+        let mut home = request("home:peter");
+        home.context.insert("home", "home:peter");
+        let results = enforcer.is_allowed_batch(&[
+            input("tenants:acme:articles:1", "acme"),
+            input("tenants:acme:articles:1", "other"),
+            home,
+        ]);
+        assert!(results[0].is_ok() && results[1].is_err() && results[2].is_ok());
+
+        let policy = crate::PolicyBuilder::new("tenant-globs")
+            .allow()
+            .subject("*")
+            .action("get")
+            .resource("tenants:${ctx.tenant}:*")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Glob::new(), vec![policy]);
+        assert!(enforcer
+            .is_allowed(&input("tenants:acme:secret", "acme"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&input("tenants:other:secret", "*"))
+            .is_err());
+        assert!(enforcer.is_allowed(&input("tenants:*:secret", "*")).is_ok());
+    }
+
+    #[test]
+    fn resolver() {
+        let mut admins = policy("admins", Effect::Allow, &["articles:<.*>"]);
+        admins.statements[0].subjects = vec!["group:admins".to_owned()];
+        let mut banned = policy("banned", Effect::Deny, &["articles:secret"]);
+        banned.statements[0].subjects = vec!["role:banned".to_owned()];
+        let graph = RoleGraph::new();
+        graph.add("peter", "group:admins").unwrap();
+        let enforcer =
+            Enforcer::new(Regexp::new(16).unwrap(), vec![admins, banned]).with_resolver(graph);
+        assert!(enforcer.is_allowed(&request("articles:secret")).is_ok());
+        let mut input = request("articles:1");
+        input.subject = "max".to_owned();
+        assert!(matches!(
+            enforcer.is_allowed(&input),
+            Err(Error::NotMatched { .. })
+        ));
+
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+                policy("deny-secret", Effect::Deny, &["articles:secret"]),
+            ],
+        )
+        .with_resolver(|subject: &str| Ok(vec![format!("{subject}:alias")]));
+        let decision = enforcer.explain(&request("articles:secret"));
+        assert!(
+            matches!(&decision.result, Err(Error::Deny { policy, .. }) if policy == "deny-secret")
+        );
+    }
+
+    #[test]
+    fn decision_cache() {
+        use std::time::Duration;
+
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy("allow-all", Effect::Allow, &["articles:<.*>"])],
+        )
+        .with_decision_cache(DecisionCache::new(16, Duration::from_secs(60)).unwrap());
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+
+        enforcer
+            .store()
+            .create(policy("deny-1", Effect::Deny, &["articles:1"]))
+            .unwrap();
+        assert!(matches!(
+            enforcer.is_allowed(&request("articles:1")),
+            Err(Error::Deny { policy, .. }) if policy == "deny-1"
+        ));
+        enforcer.store().delete("deny-1").unwrap();
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        enforcer.invalidate();
+
+        let expiring = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy("allow-all", Effect::Allow, &["articles:<.*>"])],
+        )
+        .with_decision_cache(DecisionCache::new(16, Duration::ZERO).unwrap());
+        assert!(expiring.is_allowed(&request("articles:1")).is_ok());
+        assert!(expiring.is_allowed(&request("articles:1")).is_ok());
+
+        let cache = DecisionCache::new(16, Duration::from_secs(60)).unwrap();
+        cache.put("articles:1".to_owned(), 1, &Ok(()));
+        assert!(cache.get("articles:1", 2).is_none());
+        assert!(cache.get("articles:1", 1).is_some());
+    }
+
+    #[test]
+    fn audit() {
+        use std::sync::Mutex;
+
+        type Events = Arc<Mutex<Vec<(bool, Vec<String>, bool)>>>;
+
+        #[derive(Default)]
+        struct Recorder(Events);
+
+        impl AuditSink for Recorder {
+            fn record(&self, event: &AuditEvent<'_>) {
+                self.0.lock().unwrap().push((
+                    event.allowed(),
+                    event.policies.to_vec(),
+                    event.cached,
+                ));
+            }
+        }
+
+        let recorder = Recorder::default();
+        let events = recorder.0.clone();
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+                policy("deny-secret", Effect::Deny, &["articles:secret"]),
+            ],
+        )
+        .with_audit(recorder);
+        let _ = enforcer.is_allowed(&request("articles:1"));
+        let _ = enforcer.is_allowed(&request("articles:secret"));
+        let _ = enforcer.is_allowed_batch(&[request("users:1")]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (true, vec!["allow-all".to_owned()], false),
+                (
+                    false,
+                    vec!["allow-all".to_owned(), "deny-secret".to_owned()],
+                    false
+                ),
+                (false, vec![], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn shadow() {
+        use std::sync::Mutex;
+
+        type Events = Arc<Mutex<Vec<(bool, Option<bool>, bool)>>>;
+
+        #[derive(Default)]
+        struct Recorder(Events);
+
+        impl AuditSink for Recorder {
+            fn record(&self, event: &AuditEvent<'_>) {
+                self.0.lock().unwrap().push((
+                    event.allowed(),
+                    event.shadow.map(|v| v.is_ok()),
+                    event.diverged(),
+                ));
+            }
+        }
+
+        let recorder = Recorder::default();
+        let events = recorder.0.clone();
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy("allow-all", Effect::Allow, &["articles:<.*>"])],
+        )
+        .with_shadow(MemoryStore::from(vec![
+            policy("allow-all", Effect::Allow, &["articles:<.*>"]),
+            policy("deny-secret", Effect::Deny, &["articles:secret"]),
+        ]))
+        .with_audit(recorder);
+        assert!(enforcer.is_allowed(&request("articles:1")).is_ok());
+        assert!(enforcer.is_allowed(&request("articles:secret")).is_ok());
+        let _ = enforcer.is_allowed_batch(&[request("articles:secret")]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (true, Some(true), false),
+                (true, Some(false), true),
+                (true, Some(false), true),
+            ]
+        );
+    }
+}
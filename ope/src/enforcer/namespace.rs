@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::{Combining, DecisionCache, Enforcer, EnforcerOptions};
+use crate::matcher::Matcher;
+use crate::req::Request;
+use crate::store::{MemoryStore, PolicyStore};
+use crate::{Error, Result};
+
+type Tenant<M, S> = Arc<Enforcer<Arc<M>, S>>;
+
+pub struct Namespaces<M, S = MemoryStore> {
+    matcher: Arc<M>,
+    combining: Combining,
+    options: EnforcerOptions,
+    cache: Option<(usize, Duration)>,
+    namespaces: RwLock<BTreeMap<String, Tenant<M, S>>>,
+}
+
+impl<M, S> Namespaces<M, S> {
+    pub fn new(matcher: M) -> Self {
+        Self {
+            matcher: Arc::new(matcher),
+            combining: Combining::default(),
+            options: EnforcerOptions::default(),
+            cache: None,
+            namespaces: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn with_combining(mut self, combining: Combining) -> Self {
+        self.combining = combining;
+        self
+    }
+
+    pub fn with_options(mut self, options: EnforcerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_decision_cache(mut self, cache_size: usize, ttl: Duration) -> Self {
+        self.cache = Some((cache_size, ttl));
+        self
+    }
+
+    pub fn insert(&self, namespace: impl Into<String>, store: S) -> Result<()> {
+        let mut enforcer = Enforcer::with_store(self.matcher.clone(), store)
+            .with_combining(self.combining)
+            .with_options(self.options);
+        if let Some((cache_size, ttl)) = self.cache {
+            enforcer = enforcer.with_decision_cache(DecisionCache::new(cache_size, ttl)?);
+        }
+        let mut namespaces = self
+            .namespaces
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        namespaces.insert(namespace.into(), Arc::new(enforcer));
+        Ok(())
+    }
+
+    pub fn remove(&self, namespace: &str) -> Result<()> {
+        let mut namespaces = self
+            .namespaces
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        namespaces
+            .remove(namespace)
+            .map(|_| ())
+            .ok_or_else(|| Error::NamespaceNotFound(namespace.to_owned()))
+    }
+
+    pub fn get(&self, namespace: &str) -> Result<Tenant<M, S>> {
+        let namespaces = self
+            .namespaces
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        namespaces
+            .get(namespace)
+            .cloned()
+            .ok_or_else(|| Error::NamespaceNotFound(namespace.to_owned()))
+    }
+
+    pub fn namespaces(&self) -> Result<Vec<String>> {
+        let namespaces = self
+            .namespaces
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        Ok(namespaces.keys().cloned().collect())
+    }
+}
+
+impl<M: Matcher, S: PolicyStore> Namespaces<M, S> {
+    pub fn is_allowed_in(&self, namespace: &str, input: &Request) -> Result<()> {
+        self.get(namespace)?.is_allowed(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, PolicyBuilder, Regexp};
+
+    #[test]
+    fn isolation() {
+        let namespaces = Namespaces::new(Regexp::new(16).unwrap())
+            .with_decision_cache(16, Duration::from_secs(60));
+        let policy = |subject: &str| {
+            PolicyBuilder::new("readers")
+                .allow()
+                .subject(subject)
+                .action("get")
+                .resource("articles:<.*>")
+                .build()
+                .unwrap()
+        };
+        namespaces
+            .insert("acme", MemoryStore::from(vec![policy("peter")]))
+            .unwrap();
+        namespaces
+            .insert("globex", MemoryStore::from(vec![policy("max")]))
+            .unwrap();
+        let input = |subject: &str| Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: subject.to_owned(),
+            context: Context::new(),
+        };
+        assert!(namespaces.is_allowed_in("acme", &input("peter")).is_ok());
+        assert!(namespaces.is_allowed_in("globex", &input("peter")).is_err());
+        assert!(namespaces.is_allowed_in("globex", &input("max")).is_ok());
+        assert!(namespaces.is_allowed_in("acme", &input("max")).is_err());
+        assert!(matches!(
+            namespaces.is_allowed_in("initech", &input("peter")),
+            Err(Error::NamespaceNotFound(_))
+        ));
+        assert_eq!(namespaces.namespaces().unwrap(), vec!["acme", "globex"]);
+
+        namespaces.remove("acme").unwrap();
+        assert!(namespaces.is_allowed_in("acme", &input("peter")).is_err());
+        assert!(namespaces.is_allowed_in("globex", &input("max")).is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::instrument::Instant;
+use crate::{Error, Result};
+
+pub(crate) const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyFields {
+    #[default]
+    MatchNothing,
+    MatchAll,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnforcerOptions {
+    timeout: Option<Duration>,
+    pub(crate) wildcard: bool,
+    pub(crate) empty_fields: EmptyFields,
+}
+
+impl Default for EnforcerOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            wildcard: true,
+            empty_fields: EmptyFields::default(),
+        }
+    }
+}
+
+impl EnforcerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn wildcard(mut self, wildcard: bool) -> Self {
+        self.wildcard = wildcard;
+        self
+    }
+
+    pub fn empty_fields(mut self, empty_fields: EmptyFields) -> Self {
+        self.empty_fields = empty_fields;
+        self
+    }
+
+    pub(crate) fn shortcut(&self, patterns: &[String]) -> Option<bool> {
+        if patterns.is_empty() {
+            return Some(self.empty_fields == EmptyFields::MatchAll);
+        }
+        if self.wildcard && patterns.iter().any(|p| p == WILDCARD) {
+            return Some(true);
+        }
+        None
+    }
+
+    pub(crate) fn deadline(&self, started: Instant) -> Deadline {
+        Deadline {
+            started,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Deadline {
+    started: Instant,
+    timeout: Option<Duration>,
+}
+
+impl Deadline {
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.timeout {
+            Some(timeout) if self.started.elapsed() >= timeout => Err(Error::Timeout(timeout)),
+            _ => Ok(()),
+        }
+    }
+}
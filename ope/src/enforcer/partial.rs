@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+
+use super::Enforcer;
+use crate::condition::{self, JsonCondition};
+use crate::context::Context;
+use crate::matcher::Matcher;
+use crate::req::Request;
+use crate::statement::{Effect, Field, Statement, PLACEHOLDER};
+use crate::store::PolicyStore;
+use crate::Result;
+
+#[derive(Debug, Default, Clone)]
+pub struct PartialRequest {
+    pub subject: Option<String>,
+    pub action: Option<String>,
+    pub resource: Option<String>,
+    pub context: Context,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Residual {
+    pub policy: String,
+    pub index: usize,
+    pub effect: Effect,
+    pub subjects: Option<Vec<String>>,
+    pub actions: Option<Vec<String>>,
+    pub resources: Option<Vec<String>>,
+    pub not_subjects: Option<Vec<String>>,
+    pub not_actions: Option<Vec<String>>,
+    pub not_resources: Option<Vec<String>>,
+    pub conditions: HashMap<String, JsonCondition>,
+    pub resource_matcher: Option<String>,
+}
+
+impl Residual {
+    pub fn is_resolved(&self) -> bool {
+        self.subjects.is_none()
+            && self.actions.is_none()
+            && self.resources.is_none()
+            && self.not_subjects.is_none()
+            && self.not_actions.is_none()
+            && self.not_resources.is_none()
+            && self.conditions.is_empty()
+    }
+}
+
+enum Known {
+    Matched,
+    Missed,
+    Unresolved(Vec<String>),
+}
+
+impl<M: Matcher, S: PolicyStore> Enforcer<M, S> {
+    pub fn partial_eval(&self, fragment: &PartialRequest) -> Result<Vec<Residual>> {
+        let subjects = match &fragment.subject {
+            Some(subject) => {
+                let mut subjects = vec![subject.clone()];
+                if let Some(resolver) = &self.resolver {
+                    for resolved in resolver.resolve(subject)? {
+                        if !subjects.contains(&resolved) {
+                            subjects.push(resolved);
+                        }
+                    }
+                }
+                Some(subjects)
+            }
+            None => None,
+        };
+        let input = Request {
+            resource: fragment.resource.clone().unwrap_or_default(),
+            action: fragment.action.clone().unwrap_or_default(),
+            subject: fragment.subject.clone().unwrap_or_default(),
+            context: fragment.context.clone(),
+        };
+        let complete =
+            fragment.subject.is_some() && fragment.action.is_some() && fragment.resource.is_some();
+
+        let mut residuals = Vec::new();
+        for policy in self.store.all()?.iter() {
+            for (index, statement) in policy.statements.iter().enumerate() {
+                let Some(conditions) = self.residual_conditions(statement, &input, complete)?
+                else {
+                    continue;
+                };
+                let subject = match &subjects {
+                    Some(subjects) => match self.known(
+                        statement,
+                        Field::Subject,
+                        &statement.subjects,
+                        &input,
+                        subjects,
+                    )? {
+                        Known::Matched => None,
+                        Known::Missed => continue,
+                        Known::Unresolved(patterns) => Some(patterns),
+                    },
+                    None => Some(statement.subjects.clone()),
+                };
+                let action = match &fragment.action {
+                    Some(action) => match self.known(
+                        statement,
+                        Field::Action,
+                        &statement.actions,
+                        &input,
+                        [action],
+                    )? {
+                        Known::Matched => None,
+                        Known::Missed => continue,
+                        Known::Unresolved(patterns) => Some(patterns),
+                    },
+                    None => Some(statement.actions.clone()),
+                };
+                let resource = match &fragment.resource {
+                    Some(resource) => match self.known(
+                        statement,
+                        Field::Resource,
+                        &statement.resources,
+                        &input,
+                        [resource],
+                    )? {
+                        Known::Matched => None,
+                        Known::Missed => continue,
+                        Known::Unresolved(patterns) => Some(patterns),
+                    },
+                    None => Some(statement.resources.clone()),
+                };
+                let mut excluded = false;
+                let mut exclusion =
+                    |field: Field, patterns: &[String], known: Option<&String>| -> Result<_> {
+                        if patterns.is_empty() {
+                            return Ok(None);
+                        }
+                        let Some(value) = known else {
+                            return Ok(Some(patterns.to_vec()));
+                        };
+                        match self.known(statement, field, patterns, &input, [value])? {
+                            Known::Matched => excluded = true,
+                            Known::Missed => {}
+                            Known::Unresolved(patterns) => return Ok(Some(patterns)),
+                        }
+                        Ok(None)
+                    };
+                let not_subjects = exclusion(
+                    Field::Subject,
+                    &statement.not_subjects,
+                    fragment.subject.as_ref(),
+                )?;
+                let not_actions = exclusion(
+                    Field::Action,
+                    &statement.not_actions,
+                    fragment.action.as_ref(),
+                )?;
+                let not_resources = exclusion(
+                    Field::Resource,
+                    &statement.not_resources,
+                    fragment.resource.as_ref(),
+                )?;
+                if excluded {
+                    continue;
+                }
+                residuals.push(Residual {
+                    policy: policy.id.clone(),
+                    index,
+                    effect: statement.effect.clone(),
+                    subjects: subject,
+                    actions: action,
+                    resources: resource,
+                    not_subjects,
+                    not_actions,
+                    not_resources,
+                    conditions,
+                    resource_matcher: statement.resource_matcher.clone(),
+                });
+            }
+        }
+        Ok(residuals)
+    }
+
+    fn known<'a, I>(
+        &self,
+        statement: &Statement,
+        field: Field,
+        haystack: &[String],
+        input: &Request,
+        needles: I,
+    ) -> Result<Known>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        if let Some(matched) = self.options.shortcut(haystack) {
+            return Ok(if matched {
+                Known::Matched
+            } else {
+                Known::Missed
+            });
+        }
+        let resolved = self
+            .ope
+            .resolve(statement, field, haystack, &input.context)?;
+        for needle in needles {
+            if self.ope.matches_with(statement, field, &resolved, needle)? {
+                return Ok(Known::Matched);
+            }
+        }
+        let unresolved: Vec<_> = haystack
+            .iter()
+            .filter(|pattern| {
+                pattern.contains(PLACEHOLDER)
+                    && statement
+                        .resolve(std::slice::from_ref(*pattern), &input.context, |value| {
+                            Some(value.to_owned())
+                        })
+                        .is_empty()
+            })
+            .cloned()
+            .collect();
+        if unresolved.is_empty() {
+            return Ok(Known::Missed);
+        }
+        Ok(Known::Unresolved(unresolved))
+    }
+
+    fn residual_conditions(
+        &self,
+        statement: &Statement,
+        input: &Request,
+        complete: bool,
+    ) -> Result<Option<HashMap<String, JsonCondition>>> {
+        let mut residual = HashMap::new();
+        let Some(conditions) = &statement.conditions else {
+            return Ok(Some(residual));
+        };
+        for (key, value) in conditions.iter() {
+            let condition = self.ope.conditions().build(value)?;
+            let Some(env) = condition::lookup(input, key, condition.reads_context()) else {
+                residual.insert(key.clone(), value.clone());
+                continue;
+            };
+            if !complete && condition.reads_request() {
+                residual.insert(key.clone(), value.clone());
+                continue;
+            }
+            if !condition.evaluate(env, input) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(residual))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{PolicyBuilder, Regexp, RoleGraph};
+
+    #[test]
+    fn partial_eval() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("role:editor")
+            .actions(["get", "update"])
+            .resource("articles:<[0-9]+>")
+            .condition("clientIP", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .allow()
+            .subject("<peter|ken>")
+            .action("get")
+            .resource("drafts:<.*>")
+            .deny()
+            .subject("max")
+            .action("<.*>")
+            .resource("<.*>")
+            .build()
+            .unwrap();
+        let roles = RoleGraph::new();
+        roles.add("peter", "role:editor").unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]).with_resolver(roles);
+
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                action: Some("get".to_owned()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(residuals.len(), 2);
+        assert_eq!(residuals[0].index, 0);
+        assert_eq!(
+            residuals[0].resources,
+            Some(vec!["articles:<[0-9]+>".to_owned()])
+        );
+        assert!(residuals[0].conditions.contains_key("clientIP"));
+        assert_eq!(residuals[1].resources, Some(vec!["drafts:<.*>".to_owned()]));
+        assert!(residuals.iter().all(|v| v.subjects.is_none()));
+
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                action: Some("update".to_owned()),
+                resource: Some("articles:1".to_owned()),
+                context: Context::from([("clientIP", "10.0.0.1")]),
+            })
+            .unwrap();
+        assert_eq!(residuals.len(), 1);
+        assert!(residuals[0].is_resolved());
+        assert!(enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                context: Context::from([("clientIP", "192.168.0.1")]),
+                ..Default::default()
+            })
+            .unwrap()
+            .iter()
+            .all(|v| v.index == 1));
+
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                resource: Some("drafts:1".to_owned()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            residuals.iter().map(|v| v.index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(residuals[1].effect, Effect::Deny);
+
+        let guarded = PolicyBuilder::new("guarded")
+            .deny()
+            .subject("<.*>")
+            .action("<.*>")
+            .resource("drafts:<.*>")
+            .condition("owner", "EqualsAttribute", json!({"attribute": "resource"}))
+            .deny()
+            .subject("${ctx.blocked}")
+            .action("<.*>")
+            .resource("<.*>")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![guarded]);
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                action: Some("get".to_owned()),
+                context: Context::from([("owner", "drafts:1")]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(residuals.len(), 2);
+        assert!(residuals[0].conditions.contains_key("owner"));
+        assert_eq!(
+            residuals[1].subjects,
+            Some(vec!["${ctx.blocked}".to_owned()])
+        );
+        let residuals = enforcer
+            .partial_eval(&PartialRequest {
+                subject: Some("peter".to_owned()),
+                action: Some("get".to_owned()),
+                resource: Some("drafts:2".to_owned()),
+                context: Context::from([("owner", "drafts:1"), ("blocked", "ken")]),
+            })
+            .unwrap();
+        assert!(residuals.is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+
+use ope_core::{segments, split_name, Segment};
+use serde_json::Value;
+
+use super::options::EnforcerOptions;
+use super::partial::{PartialRequest, Residual};
+use super::{Combining, Enforcer};
+use crate::condition::JsonCondition;
+use crate::matcher::Matcher;
+use crate::statement::{Effect, PLACEHOLDER};
+use crate::store::PolicyStore;
+use crate::Result;
+
+const WILDCARD: &str = ".*";
+const LIKE_ESCAPE: char = '!';
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    #[default]
+    Question,
+    Dollar,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableMapping {
+    prefix: String,
+    column: String,
+    columns: HashMap<String, String>,
+    regex_operator: String,
+    placeholder: Placeholder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlFilter {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Bool(bool),
+    Compare {
+        column: String,
+        op: &'static str,
+        value: Value,
+    },
+    Like {
+        column: String,
+        pattern: String,
+    },
+    Regex {
+        column: String,
+        pattern: String,
+    },
+    In {
+        column: String,
+        values: Vec<Value>,
+    },
+    All(Vec<Expr>),
+    Any(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    fn all(items: Vec<Expr>) -> Self {
+        let mut kept = Vec::new();
+        for item in items {
+            match item {
+                Expr::Bool(true) => {}
+                Expr::Bool(false) => return Expr::Bool(false),
+                item => kept.push(item),
+            }
+        }
+        match kept.len() {
+            0 => Expr::Bool(true),
+            1 => kept.remove(0),
+            _ => Expr::All(kept),
+        }
+    }
+
+    fn any(items: Vec<Expr>) -> Self {
+        let mut kept = Vec::new();
+        for item in items {
+            match item {
+                Expr::Bool(false) => {}
+                Expr::Bool(true) => return Expr::Bool(true),
+                item => kept.push(item),
+            }
+        }
+        match kept.len() {
+            0 => Expr::Bool(false),
+            1 => kept.remove(0),
+            _ => Expr::Any(kept),
+        }
+    }
+
+    fn not(item: Expr) -> Self {
+        match item {
+            Expr::Bool(value) => Expr::Bool(!value),
+            item => Expr::Not(Box::new(item)),
+        }
+    }
+}
+
+impl TableMapping {
+    pub fn new(prefix: impl Into<String>, column: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            column: column.into(),
+            columns: HashMap::new(),
+            regex_operator: "~".to_owned(),
+            placeholder: Placeholder::default(),
+        }
+    }
+
+    pub fn with_column(mut self, key: impl Into<String>, column: impl Into<String>) -> Self {
+        self.columns.insert(key.into(), column.into());
+        self
+    }
+
+    pub fn with_regex_operator(mut self, operator: impl Into<String>) -> Self {
+        self.regex_operator = operator.into();
+        self
+    }
+
+    pub fn with_placeholder(mut self, placeholder: Placeholder) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    fn resources(
+        &self,
+        patterns: &[String],
+        options: &EnforcerOptions,
+        regexp: bool,
+        widen: bool,
+    ) -> Result<Expr> {
+        if let Some(matched) = options.shortcut(patterns) {
+            return Ok(Expr::Bool(matched));
+        }
+        if !regexp {
+            return Ok(Expr::Bool(widen));
+        }
+        let mut any = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            any.push(self.resource(pattern)?.unwrap_or(Expr::Bool(widen)));
+        }
+        Ok(Expr::any(any))
+    }
+
+    fn resource(&self, pattern: &str) -> Result<Option<Expr>> {
+        if pattern.contains(PLACEHOLDER) {
+            return Ok(None);
+        }
+        let mut parts = segments(pattern, "<", ">")?;
+        let head = match parts.first() {
+            Some(Segment::Literal(head)) => head.clone(),
+            _ => String::new(),
+        };
+        let Some(rest) = head.strip_prefix(self.prefix.as_str()) else {
+            let literals = usize::from(!head.is_empty());
+            if parts.len() == literals || !self.prefix.starts_with(&head) {
+                return Ok(Some(Expr::Bool(false)));
+            }
+            let covers = parts.len() == literals + 1 && parts.last().is_some_and(wildcard);
+            return Ok(covers.then_some(Expr::Bool(true)));
+        };
+        if !head.is_empty() {
+            parts[0] = Segment::Literal(rest.to_owned());
+        }
+
+        let column = self.column.clone();
+        let mut literal = String::new();
+        let mut templates = Vec::new();
+        for part in parts.iter() {
+            match part {
+                Segment::Literal(raw) if templates.is_empty() => literal.push_str(raw),
+                part => templates.push(part),
+            }
+        }
+        match templates.as_slice() {
+            [] => Ok(Some(Expr::Compare {
+                column,
+                op: "=",
+                value: Value::String(literal),
+            })),
+            [part] if wildcard(part) && literal.is_empty() => Ok(Some(Expr::Bool(true))),
+            [part] if wildcard(part) => {
+                let mut pattern = String::with_capacity(literal.len() + 1);
+                for c in literal.chars() {
+                    if matches!(c, '%' | '_' | LIKE_ESCAPE) {
+                        pattern.push(LIKE_ESCAPE);
+                    }
+                    pattern.push(c);
+                }
+                pattern.push('%');
+                Ok(Some(Expr::Like { column, pattern }))
+            }
+            _ => {
+                let mut regex = String::from("^");
+                for part in parts.iter() {
+                    match part {
+                        Segment::Literal(raw) => regex.push_str(&ope_core::escape(raw)),
+                        Segment::Template(tpl) => {
+                            regex.push_str(&format!("(?:{})", split_name(tpl).1));
+                        }
+                    }
+                }
+                regex.push('$');
+                Ok(Some(Expr::Regex {
+                    column,
+                    pattern: regex,
+                }))
+            }
+        }
+    }
+
+    fn conditions(&self, conditions: &HashMap<String, JsonCondition>, widen: bool) -> Result<Expr> {
+        let mut keys: Vec<_> = conditions.keys().collect();
+        keys.sort();
+        let mut all = Vec::with_capacity(keys.len());
+        for (key, condition) in keys.into_iter().map(|key| (key, &conditions[key])) {
+            all.push(self.condition(key, condition)?.unwrap_or(Expr::Bool(widen)));
+        }
+        Ok(Expr::all(all))
+    }
+
+    fn condition(&self, key: &str, condition: &JsonCondition) -> Result<Option<Expr>> {
+        let Some(column) = self.columns.get(key).cloned() else {
+            return Ok(None);
+        };
+        let options: Value = serde_json::from_str(condition.options.get())?;
+        let expr = match condition.jtype.as_str() {
+            "StringEqual" => options["equals"].as_str().map(|equals| Expr::Compare {
+                column,
+                op: "=",
+                value: Value::String(equals.to_owned()),
+            }),
+            "StringIn" => options["values"]
+                .as_array()
+                .map(|values| match values.len() {
+                    0 => Expr::Bool(false),
+                    _ => Expr::In {
+                        column,
+                        values: values.clone(),
+                    },
+                }),
+            "Boolean" if options["coerce"] != Value::Bool(true) => {
+                options["value"].as_bool().map(|value| Expr::Compare {
+                    column,
+                    op: "=",
+                    value: Value::Bool(value),
+                })
+            }
+            _ => None,
+        };
+        Ok(expr)
+    }
+
+    fn clause(&self, residual: &Residual, options: &EnforcerOptions, regexp: bool) -> Result<Expr> {
+        let widen = residual.effect == Effect::Deny;
+        if residual.subjects.is_some()
+            || residual.actions.is_some()
+            || residual.not_subjects.is_some()
+            || residual.not_actions.is_some()
+        {
+            return Ok(Expr::Bool(widen));
+        }
+        let regexp = regexp && residual.resource_matcher.is_none();
+        let resources = match &residual.resources {
+            Some(patterns) => self.resources(patterns, options, regexp, widen)?,
+            None => Expr::Bool(true),
+        };
+        let excluded = match &residual.not_resources {
+            Some(patterns) => self.resources(patterns, options, regexp, !widen)?,
+            None => Expr::Bool(false),
+        };
+        Ok(Expr::all(vec![
+            resources,
+            Expr::not(excluded),
+            self.conditions(&residual.conditions, widen)?,
+        ]))
+    }
+
+    fn render(&self, expr: &Expr) -> SqlFilter {
+        let mut filter = SqlFilter {
+            sql: String::new(),
+            params: Vec::new(),
+        };
+        self.write(expr, false, &mut filter);
+        filter
+    }
+
+    fn write(&self, expr: &Expr, nested: bool, filter: &mut SqlFilter) {
+        match expr {
+            Expr::Bool(true) => filter.sql.push_str("1 = 1"),
+            Expr::Bool(false) => filter.sql.push_str("1 = 0"),
+            Expr::Compare { column, op, value } => {
+                filter.sql.push_str(&format!("{column} {op} "));
+                self.bind(value.clone(), filter);
+            }
+            Expr::Regex { column, pattern } => {
+                filter
+                    .sql
+                    .push_str(&format!("{column} {} ", self.regex_operator));
+                self.bind(Value::String(pattern.clone()), filter);
+            }
+            Expr::Like { column, pattern } => {
+                filter.sql.push_str(&format!("{column} LIKE "));
+                self.bind(Value::String(pattern.clone()), filter);
+                filter.sql.push_str(&format!(" ESCAPE '{LIKE_ESCAPE}'"));
+            }
+            Expr::In { column, values } => {
+                filter.sql.push_str(&format!("{column} IN ("));
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        filter.sql.push_str(", ");
+                    }
+                    self.bind(value.clone(), filter);
+                }
+                filter.sql.push(')');
+            }
+            Expr::All(items) | Expr::Any(items) => {
+                let join = match expr {
+                    Expr::All(_) => " AND ",
+                    _ => " OR ",
+                };
+                if nested {
+                    filter.sql.push('(');
+                }
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        filter.sql.push_str(join);
+                    }
+                    self.write(item, true, filter);
+                }
+                if nested {
+                    filter.sql.push(')');
+                }
+            }
+            Expr::Not(item) => {
+                filter.sql.push_str("NOT (");
+                self.write(item, false, filter);
+                filter.sql.push(')');
+            }
+        }
+    }
+
+    fn bind(&self, value: Value, filter: &mut SqlFilter) {
+        filter.params.push(value);
+        match self.placeholder {
+            Placeholder::Question => filter.sql.push('?'),
+            Placeholder::Dollar => filter.sql.push_str(&format!("${}", filter.params.len())),
+        }
+    }
+}
+
+fn wildcard(part: &Segment) -> bool {
+    matches!(part, Segment::Template(tpl) if split_name(tpl).1 == WILDCARD)
+}
+
+impl<M: Matcher, S: PolicyStore> Enforcer<M, S> {
+    pub fn to_sql_filter(
+        &self,
+        subject: &str,
+        action: &str,
+        mapping: &TableMapping,
+    ) -> Result<SqlFilter> {
+        let residuals = self.partial_eval(&PartialRequest {
+            subject: Some(subject.to_owned()),
+            action: Some(action.to_owned()),
+            ..Default::default()
+        })?;
+        let regexp = self.ope.matcher.indexable();
+        let (mut allows, mut denies) = (Vec::new(), Vec::new());
+        for residual in residuals.iter() {
+            let clause = mapping.clause(residual, &self.options, regexp)?;
+            match residual.effect {
+                Effect::Deny => denies.push(clause),
+                _ => allows.push(clause),
+            }
+        }
+        let mut filter = Expr::any(allows);
+        if self.combining != Combining::AllowOverrides {
+            filter = Expr::all(vec![filter, Expr::not(Expr::any(denies))]);
+        }
+        Ok(mapping.render(&filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{PolicyBuilder, Regexp};
+
+    #[test]
+    fn to_sql_filter() {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resources(["articles:<[0-9]+>", "articles:draft_<.*>", "tags:<.*>"])
+            .condition("owner", "StringEqual", json!({"equals": "peter"}))
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:public_1")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:archive-<.*>")
+            .condition("clientIP", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .deny()
+            .subject("<.*>")
+            .action("<.*>")
+            .resource("articles:42")
+            .deny()
+            .subject("ken")
+            .action("get")
+            .resource("<.*>")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let mapping = TableMapping::new("articles:", "a.id")
+            .with_column("owner", "a.owner")
+            .with_placeholder(Placeholder::Dollar);
+
+        let filter = enforcer.to_sql_filter("peter", "get", &mapping).unwrap();
+        assert_eq!(
+            filter.sql,
+            "(((a.id ~ $1 OR a.id LIKE $2 ESCAPE '!') AND a.owner = $3) OR a.id = $4) AND NOT (a.id = $5)"
+        );
+        assert_eq!(
+            filter.params,
+            vec![
+                json!("^(?:[0-9]+)$"),
+                json!("draft!_%"),
+                json!("peter"),
+                json!("public_1"),
+                json!("42"),
+            ]
+        );
+        assert_eq!(
+            enforcer.to_sql_filter("ken", "get", &mapping).unwrap().sql,
+            "1 = 0"
+        );
+        assert_eq!(
+            enforcer.to_sql_filter("max", "get", &mapping).unwrap().sql,
+            "1 = 0"
+        );
+
+        let everything = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![PolicyBuilder::new("all")
+                .allow()
+                .subject("max")
+                .action("<.*>")
+                .resource("<.*>")
+                .build()
+                .unwrap()],
+        );
+        let filter = everything.to_sql_filter("max", "get", &mapping).unwrap();
+        assert_eq!(filter.sql, "1 = 1");
+        assert!(filter.params.is_empty());
+
+        let mut globbed = PolicyBuilder::new("globbed")
+            .allow()
+            .subject("ken")
+            .action("get")
+            .resource("articles:<[0-9]+>")
+            .deny()
+            .subject("ken")
+            .action("get")
+            .resource("articles:1*")
+            .build()
+            .unwrap();
+        globbed.statements[1].resource_matcher = Some("glob".to_owned());
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![globbed.clone()]);
+        assert_eq!(
+            enforcer.to_sql_filter("ken", "get", &mapping).unwrap().sql,
+            "1 = 0"
+        );
+        globbed.statements[1].resource_matcher = None;
+        let enforcer = Enforcer::new(crate::Glob::new(), vec![globbed]);
+        assert_eq!(
+            enforcer.to_sql_filter("ken", "get", &mapping).unwrap().sql,
+            "1 = 0"
+        );
+    }
+}
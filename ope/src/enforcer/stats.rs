@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use arc_swap::{ArcSwap, Guard};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyStats {
+    pub hits: u64,
+    pub denies: u64,
+    pub last_matched: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvaluationStats {
+    pub evaluations: u64,
+    pub allowed: u64,
+    pub denied: u64,
+    pub not_matched: u64,
+    pub errors: u64,
+    pub cached: u64,
+}
+
+const SHARDS: usize = 16;
+
+pub(crate) struct Stats {
+    evaluations: AtomicU64,
+    allowed: AtomicU64,
+    denied: AtomicU64,
+    not_matched: AtomicU64,
+    errors: AtomicU64,
+    cached: AtomicU64,
+    tracking: bool,
+    revision: AtomicU64,
+    pruned: AtomicBool,
+    state: RandomState,
+    policies: Vec<Mutex<HashMap<String, PolicyStats>>>,
+    ranking: ArcSwap<HashMap<String, u64>>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            evaluations: AtomicU64::default(),
+            allowed: AtomicU64::default(),
+            denied: AtomicU64::default(),
+            not_matched: AtomicU64::default(),
+            errors: AtomicU64::default(),
+            cached: AtomicU64::default(),
+            tracking: false,
+            revision: AtomicU64::default(),
+            pruned: AtomicBool::default(),
+            state: RandomState::new(),
+            policies: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            ranking: ArcSwap::default(),
+        }
+    }
+}
+
+impl Stats {
+    pub(crate) fn track(&mut self) {
+        self.tracking = true;
+    }
+
+    fn shard(&self, id: &str) -> &Mutex<HashMap<String, PolicyStats>> {
+        &self.policies[self.state.hash_one(id) as usize % SHARDS]
+    }
+
+    pub(crate) fn record(&self, decision: &Result<()>, policies: &[String], cached: bool) -> u64 {
+        let evaluations = self.evaluations.fetch_add(1, Ordering::Relaxed) + 1;
+        let counter = match decision {
+            Ok(()) => &self.allowed,
+            Err(Error::Deny { .. }) | Err(Error::MultipleApplicable(_)) => &self.denied,
+            Err(Error::NotMatched { .. }) => &self.not_matched,
+            Err(_) => &self.errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if cached {
+            self.cached.fetch_add(1, Ordering::Relaxed);
+        }
+        if !self.tracking || policies.is_empty() {
+            return evaluations;
+        }
+        let denying = match decision {
+            Err(Error::Deny { policy, .. }) => Some(policy.as_str()),
+            _ => None,
+        };
+        let now = Utc::now();
+        for id in policies.iter() {
+            let mut stats = self.shard(id).lock();
+            let entry = match stats.get_mut(id.as_str()) {
+                Some(entry) => entry,
+                None => stats.entry(id.clone()).or_default(),
+            };
+            entry.hits += 1;
+            if denying == Some(id.as_str()) {
+                entry.denies += 1;
+            }
+            entry.last_matched = Some(now);
+        }
+        evaluations
+    }
+
+    pub(crate) fn rerank(&self) {
+        let mut ranking = HashMap::new();
+        for shard in self.policies.iter() {
+            ranking.extend(
+                shard
+                    .lock()
+                    .iter()
+                    .map(|(id, stats)| (id.clone(), stats.hits)),
+            );
+        }
+        self.ranking.store(Arc::new(ranking));
+    }
+
+    pub(crate) fn stale(&self, revision: u64) -> bool {
+        self.tracking
+            && (!self.pruned.load(Ordering::Acquire)
+                || self.revision.load(Ordering::Acquire) != revision)
+    }
+
+    pub(crate) fn prune(&self, revision: u64, live: &HashSet<&str>) {
+        for shard in self.policies.iter() {
+            shard.lock().retain(|id, _| live.contains(id.as_str()));
+        }
+        self.revision.store(revision, Ordering::Release);
+        self.pruned.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn ranking(&self) -> Guard<Arc<HashMap<String, u64>>> {
+        self.ranking.load()
+    }
+
+    pub(crate) fn policies(&self) -> HashMap<String, PolicyStats> {
+        let mut policies = HashMap::new();
+        for shard in self.policies.iter() {
+            policies.extend(
+                shard
+                    .lock()
+                    .iter()
+                    .map(|(id, stats)| (id.clone(), stats.clone())),
+            );
+        }
+        policies
+    }
+
+    pub(crate) fn snapshot(&self) -> EvaluationStats {
+        EvaluationStats {
+            evaluations: self.evaluations.load(Ordering::Relaxed),
+            allowed: self.allowed.load(Ordering::Relaxed),
+            denied: self.denied.load(Ordering::Relaxed),
+            not_matched: self.not_matched.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            cached: self.cached.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.evaluations,
+            &self.allowed,
+            &self.denied,
+            &self.not_matched,
+            &self.errors,
+            &self.cached,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        for shard in self.policies.iter() {
+            shard.lock().clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enforcer::Enforcer;
+    use crate::matcher::reg::Regexp;
+    use crate::policy::Policy;
+    use crate::req::Request;
+
+    fn policy(id: &str, effect: &str, resource: &str) -> Policy {
+        serde_json::from_str(&format!(
+            r#"{{"id": "{id}", "statements": [{{"effect": "{effect}", "subjects": ["peter"], "actions": ["get"], "resources": ["{resource}"]}}]}}"#
+        ))
+        .unwrap()
+    }
+
+    fn request(resource: &str) -> Request {
+        Request {
+            resource: resource.to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Default::default(),
+        }
+    }
+
+    #[test]
+    fn policy_stats() {
+        use crate::store::PolicyStore;
+
+        let untracked = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![policy("allow-all", "Allow", "articles:<.*>")],
+        );
+        let _ = untracked.is_allowed(&request("articles:1"));
+        assert_eq!(untracked.stats().allowed, 1);
+        assert_eq!(untracked.policy_stats().unwrap()["allow-all"].hits, 0);
+
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![
+                policy("allow-all", "Allow", "articles:<.*>"),
+                policy("deny-secret", "Deny", "articles:secret"),
+                policy("dead", "Allow", "users:<.*>"),
+            ],
+        )
+        .with_policy_stats();
+        let _ = enforcer.is_allowed(&request("articles:1"));
+        let _ = enforcer.is_allowed(&request("articles:secret"));
+        let _ = enforcer.is_allowed(&request("comments:1"));
+
+        let stats = enforcer.policy_stats().unwrap();
+        assert_eq!(stats["allow-all"].hits, 2);
+        assert_eq!(stats["allow-all"].denies, 0);
+        assert_eq!(stats["deny-secret"].hits, 1);
+        assert_eq!(stats["deny-secret"].denies, 1);
+        assert!(stats["deny-secret"].last_matched.is_some());
+        assert_eq!(stats["dead"], PolicyStats::default());
+        assert_eq!(
+            enforcer.stats(),
+            EvaluationStats {
+                evaluations: 3,
+                allowed: 1,
+                denied: 1,
+                not_matched: 1,
+                ..Default::default()
+            }
+        );
+
+        enforcer.store().delete("deny-secret").unwrap();
+        let _ = enforcer.is_allowed(&request("articles:1"));
+        assert!(!enforcer.stats.policies().contains_key("deny-secret"));
+        assert_eq!(enforcer.stats.policies()["allow-all"].hits, 3);
+
+        enforcer.reset_stats();
+        assert_eq!(enforcer.stats(), EvaluationStats::default());
+        assert_eq!(enforcer.policy_stats().unwrap()["allow-all"].hits, 0);
+    }
+
+    #[test]
+    fn adaptive_ordering() {
+        use crate::enforcer::Combining;
+
+        let mut urgent = policy("urgent", "Deny", "<users>:<.*>");
+        urgent.priority = 1;
+        let policies = vec![
+            urgent,
+            policy("a", "Allow", "<users>:<.*>"),
+            policy("z", "Allow", "<articles>:<.*>"),
+        ];
+        let order = |enforcer: &Enforcer<Regexp>| {
+            enforcer
+                .explain(&request("articles:1"))
+                .policies
+                .iter()
+                .map(|trace| trace.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first = Enforcer::new(Regexp::new(16).unwrap(), policies.clone())
+            .with_combining(Combining::FirstApplicable)
+            .with_adaptive_ordering(1);
+        assert!(first.is_allowed(&request("articles:1")).is_ok());
+        assert!(first.is_allowed(&request("articles:2")).is_ok());
+        assert_eq!(order(&first), vec!["urgent", "a", "z"]);
+        assert!(first.is_allowed(&request("users:1")).is_err());
+
+        let deny = Enforcer::new(Regexp::new(16).unwrap(), policies)
+            .with_combining(Combining::DenyOverrides)
+            .with_adaptive_ordering(2);
+        assert_eq!(order(&deny), vec!["a", "urgent", "z"]);
+        assert!(deny.is_allowed(&request("articles:1")).is_ok());
+        assert_eq!(order(&deny), vec!["a", "urgent", "z"]);
+        assert!(deny.is_allowed(&request("articles:2")).is_ok());
+        assert_eq!(order(&deny), vec!["z", "a", "urgent"]);
+        assert!(deny.is_allowed(&request("users:1")).is_err());
+    }
+}
@@ -3,23 +3,162 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum Error {
     #[error(
-        "The request was denied because a statement denied request.Please proofread the policy {0}"
+        "The request was denied because a statement denied request.{}",
+        proofread(.policy, .statement)
     )]
-    Deny(String),
+    Deny {
+        policy: String,
+        statement: Option<usize>,
+    },
     #[error("The request was denied because no matching statement was found.")]
-    NotMatched,
+    NotMatched {
+        policy: Option<String>,
+        statement: Option<usize>,
+        condition: Option<String>,
+    },
+    #[error("The request was denied because more than one policy is applicable: {0:?}")]
+    MultipleApplicable(Vec<String>),
     #[error("invalid cache size {0}")]
     InvalidCacheSize(usize),
     #[error("lock error: {0}")]
     LockError(String),
+    #[cfg(feature = "regex")]
     #[error(transparent)]
     CompileRegexError(#[from] regex::Error),
+    #[cfg(any(feature = "regex-lite", feature = "fancy-regex"))]
+    #[error("{0}")]
+    RegexBackendError(String),
     #[error("Unbalanced braces in {0}")]
     UnbalancedBraces(String),
+    #[error("invalid delimiters {0} {1}")]
+    InvalidDelimiters(String, String),
+    #[error("pattern {0} is too complex")]
+    PatternTooComplex(String),
+    #[error("invalid expression {0}")]
+    InvalidExpression(String),
+    #[error("invalid glob pattern {0}")]
+    InvalidGlob(String),
+    #[error("invalid cidr {0}")]
+    InvalidCidr(String),
+    #[error("invalid spiffe id {0}")]
+    InvalidSpiffeId(String),
     #[error("{0}")]
     NotIndex(String),
     #[error(transparent)]
     SerdeError(#[from] serde_json::Error),
+    #[cfg(feature = "yaml")]
+    #[error(transparent)]
+    YamlError(#[from] serde_yaml::Error),
+    #[cfg(feature = "toml")]
+    #[error(transparent)]
+    TomlError(#[from] toml::de::Error),
+    #[cfg(feature = "geoip")]
+    #[error(transparent)]
+    GeoIpError(#[from] maxminddb::MaxMindDBError),
+    #[cfg(feature = "jwt")]
+    #[error(transparent)]
+    JwtError(#[from] jsonwebtoken::errors::Error),
+    #[cfg(feature = "jwt")]
+    #[error("claim {0} not found")]
+    ClaimNotFound(String),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ValidationError(#[from] validator::ValidationErrors),
+    #[cfg(feature = "sqlx")]
+    #[error(transparent)]
+    SqlError(#[from] sqlx::Error),
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    RedisError(#[from] redis::RedisError),
+    #[cfg(feature = "watch")]
+    #[error(transparent)]
+    WatchError(#[from] notify::Error),
+    #[error("invalid policy: {0:?}")]
+    InvalidPolicy(Vec<crate::policy::Diagnostic>),
+    #[error("unsupported IAM policy element: {0}")]
+    UnsupportedIam(String),
+    #[error("unsupported Rego construct: {0}")]
+    UnsupportedRego(String),
+    #[error("unsupported Casbin construct: {0}")]
+    UnsupportedCasbin(String),
+    #[cfg(feature = "cedar")]
+    #[error("unsupported Cedar construct: {0}")]
+    UnsupportedCedar(String),
+    #[cfg(feature = "proto")]
+    #[error("invalid protobuf message: {0}")]
+    InvalidProto(String),
+    #[cfg(feature = "proto")]
+    #[error("remote evaluation failed: {0}")]
+    RemoteError(String),
+    #[cfg(feature = "bundle")]
+    #[error("invalid policy bundle: {0}")]
+    InvalidBundle(String),
+    #[cfg(feature = "bundle")]
+    #[error("policy bundle signature verification failed")]
+    InvalidSignature,
     #[error("Could not find condition type {0}")]
     NotFoundConditionType(String),
+    #[error("Could not find matcher {0}")]
+    NotFoundMatcher(String),
+    #[error("policy store error: {0}")]
+    StoreError(String),
+    #[error("namespace {0} not found")]
+    NamespaceNotFound(String),
+    #[error("policy {0} not found")]
+    PolicyNotFound(String),
+    #[error("policy {0} version {1} not found")]
+    PolicyVersionNotFound(String, u64),
+    #[error("policy {0} already exists")]
+    PolicyAlreadyExists(String),
+    #[error("policy template {0} not found")]
+    TemplateNotFound(String),
+    #[error("policy template {template} requires parameter {parameter}")]
+    MissingParameter { template: String, parameter: String },
+    #[error("policy template {template} has no parameter {parameter}")]
+    UnknownParameter { template: String, parameter: String },
+    #[error("policy template {template} got an invalid value for {parameter}")]
+    InvalidParameter { template: String, parameter: String },
+    #[error("evaluation exceeded timeout {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("context key {0} not found")]
+    ContextKeyNotFound(String),
+    #[error("context key {key} is not a valid {expected}")]
+    ContextTypeMismatch { key: String, expected: String },
+}
+
+impl From<ope_core::Error> for Error {
+    fn from(err: ope_core::Error) -> Self {
+        match err {
+            ope_core::Error::UnbalancedBraces(tpl) => Error::UnbalancedBraces(tpl),
+            ope_core::Error::InvalidRegex(pattern, err) => {
+                Error::InvalidExpression(format!("{pattern}: {err}"))
+            }
+        }
+    }
+}
+
+fn proofread(policy: &str, statement: &Option<usize>) -> String {
+    match (policy, statement) {
+        ("", Some(index)) => format!("Please proofread statement {index}"),
+        ("", None) => String::new(),
+        (policy, _) => format!("Please proofread the policy {policy}"),
+    }
+}
+
+impl Error {
+    pub(crate) fn deny(policy: impl Into<String>) -> Self {
+        Self::Deny {
+            policy: policy.into(),
+            statement: None,
+        }
+    }
+
+    pub(crate) fn not_matched() -> Self {
+        Self::NotMatched {
+            policy: None,
+            statement: None,
+            condition: None,
+        }
+    }
 }
@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+use crate::{Enforcer, Error, Policy, PolicyStore, Regexp, Request};
+
+pub const OPE_OK: c_int = 0;
+pub const OPE_DENY: c_int = 1;
+pub const OPE_NOT_MATCHED: c_int = 2;
+pub const OPE_MULTIPLE_APPLICABLE: c_int = 3;
+pub const OPE_ERR_INVALID_ARGUMENT: c_int = -1;
+pub const OPE_ERR_PARSE: c_int = -2;
+pub const OPE_ERR_STORE: c_int = -3;
+pub const OPE_ERR_PATTERN: c_int = -4;
+pub const OPE_ERR_CONDITION: c_int = -5;
+pub const OPE_ERR_INTERNAL: c_int = -99;
+
+pub struct OpeEnforcer {
+    inner: Enforcer<Regexp>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn code(err: &Error) -> c_int {
+    match err {
+        Error::Deny { .. } => OPE_DENY,
+        Error::NotMatched { .. } => OPE_NOT_MATCHED,
+        Error::MultipleApplicable(_) => OPE_MULTIPLE_APPLICABLE,
+        Error::SerdeError(_)
+        | Error::ValidationError(_)
+        | Error::InvalidPolicy(_)
+        | Error::MissingParameter { .. }
+        | Error::UnknownParameter { .. }
+        | Error::InvalidParameter { .. } => OPE_ERR_PARSE,
+        Error::StoreError(_)
+        | Error::PolicyNotFound(_)
+        | Error::PolicyAlreadyExists(_)
+        | Error::TemplateNotFound(_) => OPE_ERR_STORE,
+        #[cfg(feature = "regex")]
+        Error::CompileRegexError(_) => OPE_ERR_PATTERN,
+        Error::UnbalancedBraces(_)
+        | Error::PatternTooComplex(_)
+        | Error::InvalidGlob(_)
+        | Error::InvalidCidr(_)
+        | Error::InvalidSpiffeId(_)
+        | Error::NotIndex(_) => OPE_ERR_PATTERN,
+        #[cfg(any(feature = "regex-lite", feature = "fancy-regex"))]
+        Error::RegexBackendError(_) => OPE_ERR_PATTERN,
+        Error::NotFoundConditionType(_)
+        | Error::ContextKeyNotFound(_)
+        | Error::ContextTypeMismatch { .. } => OPE_ERR_CONDITION,
+        _ => OPE_ERR_INTERNAL,
+    }
+}
+
+fn report(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => {
+            clear_error();
+            OPE_OK
+        }
+        Err(err) => {
+            set_error(&err.to_string());
+            code(&err)
+        }
+    }
+}
+
+fn set_error(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).ok();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+unsafe fn read_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        set_error("null pointer argument");
+        return None;
+    }
+    match CStr::from_ptr(value).to_str() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            set_error(&err.to_string());
+            None
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ope_enforcer_new() -> *mut OpeEnforcer {
+    match Regexp::new(256) {
+        Ok(matcher) => Box::into_raw(Box::new(OpeEnforcer {
+            inner: Enforcer::new(matcher, Vec::new()),
+        })),
+        Err(err) => {
+            set_error(&err.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `enforcer` must be null or a pointer returned by `ope_enforcer_new` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ope_enforcer_free(enforcer: *mut OpeEnforcer) {
+    if !enforcer.is_null() {
+        drop(Box::from_raw(enforcer));
+    }
+}
+
+/// # Safety
+///
+/// `enforcer` must come from `ope_enforcer_new` and `policies` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ope_load_policies_json(
+    enforcer: *mut OpeEnforcer,
+    policies: *const c_char,
+) -> c_int {
+    let Some(enforcer) = enforcer.as_ref() else {
+        set_error("null enforcer");
+        return OPE_ERR_INVALID_ARGUMENT;
+    };
+    let Some(policies) = read_str(policies) else {
+        return OPE_ERR_INVALID_ARGUMENT;
+    };
+    report((|| {
+        let policies: Vec<Policy> = serde_json::from_str(policies)?;
+        enforcer.inner.store().replace(policies)
+    })())
+}
+
+/// # Safety
+///
+/// `enforcer` must come from `ope_enforcer_new` and `request` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ope_is_allowed(
+    enforcer: *const OpeEnforcer,
+    request: *const c_char,
+) -> c_int {
+    let Some(enforcer) = enforcer.as_ref() else {
+        set_error("null enforcer");
+        return OPE_ERR_INVALID_ARGUMENT;
+    };
+    let Some(request) = read_str(request) else {
+        return OPE_ERR_INVALID_ARGUMENT;
+    };
+    report(
+        serde_json::from_str::<Request>(request)
+            .map_err(Error::from)
+            .and_then(|request| enforcer.inner.is_allowed(&request)),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn ope_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match last.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi() {
+        let policies = CString::new(
+            r#"[{"id": "readers", "statements": [{"effect": "Allow", "subjects": ["peter"],
+                "actions": ["get"], "resources": ["articles:<[0-9]+>"], "conditions": null,
+                "meta": null}], "meta": null}]"#,
+        )
+        .unwrap();
+        let request = |resource: &str| {
+            CString::new(format!(
+                r#"{{"subject": "peter", "action": "get", "resource": "{resource}", "context": {{}}}}"#
+            ))
+            .unwrap()
+        };
+        unsafe {
+            let enforcer = ope_enforcer_new();
+            assert!(!enforcer.is_null());
+            assert_eq!(ope_load_policies_json(enforcer, policies.as_ptr()), OPE_OK);
+            assert_eq!(
+                ope_is_allowed(enforcer, request("articles:1").as_ptr()),
+                OPE_OK
+            );
+            assert!(ope_last_error().is_null());
+            let duplicated =
+                CString::new(r#"[{"id": "a", "statements": []}, {"id": "a", "statements": []}]"#)
+                    .unwrap();
+            assert_eq!(
+                ope_load_policies_json(enforcer, duplicated.as_ptr()),
+                OPE_ERR_STORE
+            );
+            assert_eq!(
+                ope_is_allowed(enforcer, request("articles:1").as_ptr()),
+                OPE_OK
+            );
+            assert_eq!(
+                ope_is_allowed(enforcer, request("articles:x").as_ptr()),
+                OPE_NOT_MATCHED
+            );
+            assert!(!ope_last_error().is_null());
+            let invalid = CString::new("{").unwrap();
+            assert_eq!(ope_is_allowed(enforcer, invalid.as_ptr()), OPE_ERR_PARSE);
+            assert_eq!(
+                ope_is_allowed(enforcer, ptr::null()),
+                OPE_ERR_INVALID_ARGUMENT
+            );
+            ope_enforcer_free(enforcer);
+        }
+    }
+}
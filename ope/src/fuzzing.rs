@@ -0,0 +1,14 @@
+use crate::matcher::reg;
+use crate::Result;
+
+pub fn build_regex(tpl: &str, delimiter_start: char, delimiter_end: char) -> Result<String> {
+    reg::build_regex(tpl, delimiter_start, delimiter_end)
+}
+
+pub fn delimiter_indices(
+    s: &str,
+    delimiter_start: char,
+    delimiter_end: char,
+) -> Result<Vec<usize>> {
+    reg::delimiter_indices(s, delimiter_start, delimiter_end)
+}
@@ -0,0 +1,154 @@
+#![cfg_attr(
+    not(all(feature = "metrics", feature = "tracing")),
+    allow(unused_variables)
+)]
+
+use std::time::Duration;
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub(crate) use std::time::Instant;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub(crate) use web_time::Instant;
+
+use tracing::Span;
+
+use crate::req::Request;
+use crate::statement::Effect;
+use crate::Result;
+
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+fn effect(decision: &Result<()>) -> &'static str {
+    match decision {
+        Ok(()) => "allow",
+        Err(crate::Error::Deny { .. }) => "deny",
+        Err(crate::Error::NotMatched { .. }) => "not_matched",
+        Err(crate::Error::MultipleApplicable(_)) => "multiple_applicable",
+        Err(_) => "error",
+    }
+}
+
+pub(crate) fn decision(
+    decision: &Result<()>,
+    latency: Duration,
+    policies: &[String],
+    cached: bool,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("ope_decisions_total", "effect" => effect(decision)).increment(1);
+        metrics::histogram!("ope_evaluation_seconds").record(latency.as_secs_f64());
+    }
+    #[cfg(feature = "tracing")]
+    {
+        let span = Span::current();
+        span.record("decision", effect(decision));
+        span.record("policies", policies.join(",").as_str());
+        span.record("cached", cached);
+        span.record("latency_us", latency.as_micros() as u64);
+    }
+}
+
+pub(crate) fn evaluation(input: &Request) -> Span {
+    #[cfg(feature = "tracing")]
+    return tracing::info_span!(
+        "ope.evaluate",
+        subject = %input.subject,
+        action = %input.action,
+        resource = %input.resource,
+        decision = tracing::field::Empty,
+        policies = tracing::field::Empty,
+        cached = tracing::field::Empty,
+        latency_us = tracing::field::Empty,
+    );
+    #[cfg(not(feature = "tracing"))]
+    Span::none()
+}
+
+pub(crate) fn policy(id: &str) -> Span {
+    #[cfg(feature = "tracing")]
+    return tracing::info_span!(
+        "ope.policy",
+        policy.id = %id,
+        effect = tracing::field::Empty,
+        matcher_us = tracing::field::Empty,
+        conditions_us = tracing::field::Empty,
+    );
+    #[cfg(not(feature = "tracing"))]
+    Span::none()
+}
+
+pub(crate) fn policy_effect(span: &Span, effect: Option<&Effect>, timings: &Timings) {
+    #[cfg(feature = "tracing")]
+    {
+        span.record(
+            "effect",
+            match effect {
+                Some(Effect::Allow) => "allow",
+                Some(Effect::Deny) => "deny",
+                None => "not_applicable",
+            },
+        );
+        span.record("matcher_us", timings.matcher.as_micros() as u64);
+        span.record("conditions_us", timings.conditions.as_micros() as u64);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Timings {
+    #[cfg(feature = "tracing")]
+    matcher: Duration,
+    #[cfg(feature = "tracing")]
+    conditions: Duration,
+}
+
+impl Timings {
+    pub(crate) fn matcher<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        {
+            let started = Instant::now();
+            let output = f();
+            self.matcher += started.elapsed();
+            output
+        }
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    pub(crate) fn conditions<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        {
+            let started = Instant::now();
+            let output = f();
+            self.conditions += started.elapsed();
+            output
+        }
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn matched_since(&mut self, started: Instant) {
+        #[cfg(feature = "tracing")]
+        {
+            self.matcher += started.elapsed();
+        }
+    }
+}
+
+pub(crate) fn cache_lookup(hit: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::trace!(hit, "ope.decision_cache");
+    #[cfg(feature = "metrics")]
+    {
+        if hit {
+            metrics::counter!("ope_decision_cache_hits_total").increment(1);
+        } else {
+            metrics::counter!("ope_decision_cache_misses_total").increment(1);
+        }
+    }
+}
+
+pub(crate) fn regex_compiled() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("ope_regex_compiles_total").increment(1);
+}
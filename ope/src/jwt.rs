@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+
+use crate::context::Context;
+use crate::req::Request;
+use crate::{Error, Result};
+
+pub struct JwtExtractor {
+    key: DecodingKey,
+    validation: Validation,
+    subject_claim: String,
+    subject_prefix: String,
+    claims: Vec<(String, String)>,
+}
+
+impl JwtExtractor {
+    pub fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+        Self {
+            key,
+            validation: Validation::new(algorithm),
+            subject_claim: "sub".to_owned(),
+            subject_prefix: String::new(),
+            claims: Vec::new(),
+        }
+    }
+
+    pub fn with_validation(mut self, validation: Validation) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn with_audience(mut self, audience: &[impl ToString]) -> Self {
+        self.validation.set_audience(audience);
+        self
+    }
+
+    pub fn with_issuer(mut self, issuer: &[impl ToString]) -> Self {
+        self.validation.set_issuer(issuer);
+        self
+    }
+
+    pub fn with_subject_claim(mut self, claim: impl Into<String>) -> Self {
+        self.subject_claim = claim.into();
+        self
+    }
+
+    pub fn with_subject_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.subject_prefix = prefix.into();
+        self
+    }
+
+    pub fn with_claim(mut self, claim: impl Into<String>, key: impl Into<String>) -> Self {
+        self.claims.push((claim.into(), key.into()));
+        self
+    }
+
+    pub fn claims(&self, token: &str) -> Result<Context> {
+        let data = decode::<HashMap<String, Value>>(token, &self.key, &self.validation)?;
+        Ok(Context::from(data.claims))
+    }
+
+    pub fn request(
+        &self,
+        token: &str,
+        resource: impl Into<String>,
+        action: impl Into<String>,
+    ) -> Result<Request> {
+        let claims = self.claims(token)?;
+        let subject = match claims.lookup(&self.subject_claim) {
+            Some(Value::String(v)) => v.clone(),
+            Some(Value::Number(v)) => v.to_string(),
+            _ => return Err(Error::ClaimNotFound(self.subject_claim.clone())),
+        };
+        let mut context = Context::new();
+        for (claim, key) in self.claims.iter() {
+            if let Some(value) = claims.lookup(claim) {
+                context.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(Request {
+            resource: resource.into(),
+            action: action.into(),
+            subject: format!("{}{subject}", self.subject_prefix),
+            context,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Enforcer, Policy, Regexp};
+
+    #[test]
+    fn request() {
+        let secret = b"secret";
+        let token = |claims: Value| {
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(secret),
+            )
+            .unwrap()
+        };
+        let extractor = JwtExtractor::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+            .with_issuer(&["https://issuer.example"])
+            .with_subject_prefix("users:")
+            .with_claim("/realm/roles", "roles")
+            .with_claim("tenant", "tenant");
+        let valid = token(json!({
+            "sub": "peter",
+            "iss": "https://issuer.example",
+            "exp": 4102444800u64,
+            "tenant": "acme",
+            "realm": {"roles": ["admin"]},
+        }));
+        let req = extractor.request(&valid, "articles:1", "get").unwrap();
+        assert_eq!(req.subject, "users:peter");
+        assert_eq!(req.context.get("roles"), Some(&json!(["admin"])));
+        assert_eq!(req.context.get("tenant"), Some(&json!("acme")));
+
+        let enforcer = Enforcer::new(
+            Regexp::new(16).unwrap(),
+            vec![Policy::builder("1")
+                .allow()
+                .subject("users:<.*>")
+                .action("get")
+                .resource("articles:<.*>")
+                .condition("tenant", "StringEqual", json!({"equals": "acme"}))
+                .build()
+                .unwrap()],
+        );
+        assert!(enforcer.is_allowed(&req).is_ok());
+
+        let expired = token(json!({"sub": "peter", "iss": "https://issuer.example", "exp": 1}));
+        assert!(matches!(
+            extractor.request(&expired, "articles:1", "get"),
+            Err(Error::JwtError(_))
+        ));
+        let anonymous = token(json!({"iss": "https://issuer.example", "exp": 4102444800u64}));
+        assert!(matches!(
+            extractor.request(&anonymous, "articles:1", "get"),
+            Err(Error::ClaimNotFound(_))
+        ));
+    }
+}
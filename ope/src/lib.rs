@@ -1,83 +1,449 @@
+mod audit;
 mod condition;
+mod context;
+mod convert;
+mod enforcer;
 mod err;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+mod instrument;
+#[cfg(feature = "jwt")]
+mod jwt;
 mod matcher;
+mod policy;
+#[cfg(feature = "proto")]
+pub mod proto;
 mod req;
+mod resolver;
 mod statement;
+mod store;
+mod testing;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-pub use condition::JsonCondition;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use instrument::Timings;
+use matcher::registry::DynMatcher;
+use statement::Field;
+
+pub use audit::{AuditEvent, AuditSink, JsonLinesSink, TracingSink};
+#[cfg(feature = "cel")]
+pub use condition::CelCondition;
+#[cfg(feature = "geoip")]
+pub use condition::MaxMindResolver;
+pub use condition::{Condition, ConditionRegistry, GeoIpCondition, GeoIpResolver, JsonCondition};
+pub use context::Context;
+#[cfg(feature = "cedar")]
+pub use convert::cedar::CedarPolicySet;
+pub use convert::{
+    casbin::{CasbinImport, CasbinModel},
+    iam::IamPolicy,
+    rego::RegoPolicy,
+};
+pub use enforcer::{
+    Combining, ConditionTrace, Decision, DecisionCache, EmptyFields, Enforcer, EnforcerOptions,
+    EvaluationStats, Grant, Namespaces, PartialRequest, Placeholder, PolicyStats, PolicyTrace,
+    Residual, SqlFilter, StatementTrace, TableMapping,
+};
 pub use err::Error;
-pub use matcher::{reg::Regexp, Matcher};
-pub use req::Request;
+#[cfg(feature = "jwt")]
+pub use jwt::JwtExtractor;
+pub use matcher::{
+    bitmap::ActionBitmap,
+    cache::CacheStats,
+    cidr::CidrMatcher,
+    exact::Exact,
+    glob::Glob,
+    hierarchy::HierarchyMatcher,
+    reg::{Regexp, RegexpBuilder, Validation},
+    registry::MatcherRegistry,
+    spiffe::{SpiffeId, SpiffeMatcher},
+    CompiledPatterns, Matcher,
+};
+pub use policy::{
+    diff, impact, optimize, Diagnostic, Format, Impact, LintCode, Observation, Policy,
+    PolicyBuilder, PolicyDiff, PolicyGenerator, PolicyTemplate, Severity, TemplateInstance,
+    TemplateRegistry,
+};
+#[cfg(feature = "bundle")]
+pub use policy::{Bundle, BundleLoader};
+pub use req::{Request, RequestBuilder};
+pub use resolver::{RoleGraph, SubjectResolver};
 pub use statement::{Effect, Statement};
+#[cfg(feature = "watch")]
+pub use store::FileWatcher;
+#[cfg(feature = "sqlx")]
+pub use store::SqlStore;
+pub use store::{FileStore, MemoryStore, MemoryTransaction, PolicyChange, PolicyStore};
+#[cfg(feature = "redis")]
+pub use store::{PolicyUpdate, RedisDecisionCache, RedisListener, RedisStore, RedisSubscriber};
+#[cfg(feature = "remote")]
+pub use store::{RemoteStore, RemoteSync};
+pub use testing::{CaseOutcome, Expect, Fixture, FixtureCase};
+#[cfg(feature = "wasm")]
+pub use wasm::WasmEnforcer;
+#[cfg(feature = "async")]
+pub use {enforcer::AsyncEnforcer, matcher::asynchronous::AsyncMatcher, store::AsyncPolicyStore};
 
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
+pub(crate) enum Outcome {
+    Matched,
+    Missed,
+    Rejected(String),
+}
+
 pub struct Ope<M> {
     matcher: M,
+    conditions: ConditionRegistry,
+    matchers: MatcherRegistry,
+    fields: EnforcerOptions,
 }
 
 impl<M> Ope<M> {
     pub fn new(matcher: M) -> Self {
-        Self { matcher }
+        Self::with_conditions(matcher, ConditionRegistry::new())
+    }
+
+    pub fn with_conditions(matcher: M, conditions: ConditionRegistry) -> Self {
+        Self {
+            matcher,
+            conditions,
+            matchers: MatcherRegistry::new(),
+            fields: EnforcerOptions::default(),
+        }
+    }
+
+    pub fn conditions(&self) -> &ConditionRegistry {
+        &self.conditions
+    }
+
+    pub fn matchers(&self) -> &MatcherRegistry {
+        &self.matchers
+    }
+
+    pub(crate) fn field_matcher(
+        &self,
+        statement: &Statement,
+        field: Field,
+    ) -> Result<Option<Arc<dyn DynMatcher>>> {
+        statement
+            .matcher(field)
+            .map(|name| self.matchers.get(name))
+            .transpose()
+    }
+
+    fn evaluate_conditions(&self, statement: &Statement, input: &Request) -> Result<bool> {
+        if let Some(conditions) = &statement.conditions {
+            for (key, value) in conditions {
+                let condition = self.conditions.build(value)?;
+                let Some(env) = condition::lookup(input, key, condition.reads_context()) else {
+                    return Ok(false);
+                };
+                if !condition.evaluate(env, input) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn rejected_condition(
+        &self,
+        statement: &Statement,
+        input: &Request,
+    ) -> Result<Option<String>> {
+        let mut rejected: Option<&String> = None;
+        for (key, value) in statement.conditions.iter().flatten() {
+            let condition = self.conditions.build(value)?;
+            let passed = match condition::lookup(input, key, condition.reads_context()) {
+                Some(env) => condition.evaluate(env, input),
+                None => false,
+            };
+            if !passed && rejected.is_none_or(|v| key < v) {
+                rejected = Some(key);
+            }
+        }
+        Ok(rejected.cloned())
     }
 }
 
 impl<M: Matcher> Ope<M> {
-    pub fn is_allow(&self, list: &[Statement], input: &Request) -> Result<()> {
-        tracing::debug!("input = {:?}, list = {:?}", input, list);
-        let mut allowed = false;
+    pub fn warm(&self, list: &[Statement]) -> Result<()> {
         for statement in list.iter() {
-            if !self.matcher.matches(
-                statement.get_start_delimiter(),
-                statement.get_end_delimiter(),
-                statement.actions.clone(),
-                &input.action,
-            )? {
-                continue;
-            }
-            if !self.matcher.matches(
-                statement.get_start_delimiter(),
-                statement.get_end_delimiter(),
-                statement.subjects.clone(),
-                &input.subject,
-            )? {
-                continue;
-            }
-            if !self.matcher.matches(
+            let (start, end) = (
                 statement.get_start_delimiter(),
                 statement.get_end_delimiter(),
-                statement.resources.clone(),
-                &input.resource,
-            )? {
-                continue;
+            );
+            for (field, patterns) in [
+                (Field::Subject, &statement.subjects),
+                (Field::Action, &statement.actions),
+                (Field::Resource, &statement.resources),
+            ] {
+                match self.field_matcher(statement, field)? {
+                    Some(matcher) => matcher.warm(start, end, patterns)?,
+                    None => {
+                        statement
+                            .compiled(patterns, |patterns| self.matcher.compile(start, patterns));
+                        self.matcher.warm(start, end, patterns)?
+                    }
+                }
             }
-            if !evaluate_conditions(statement, input)? {
-                continue;
+        }
+        Ok(())
+    }
+
+    pub fn is_allow(&self, list: &[Statement], input: &Request) -> Result<()> {
+        tracing::debug!("input = {:?}, list = {:?}", input, list);
+        let (mut allowed, mut rejected) = (false, None);
+        for (index, statement) in list.iter().enumerate() {
+            match self.statement_outcome(statement, input, &mut Timings::default())? {
+                Outcome::Matched => {}
+                Outcome::Missed => continue,
+                Outcome::Rejected(condition) => {
+                    rejected.get_or_insert((index, condition));
+                    continue;
+                }
             }
             if let Effect::Deny = statement.effect {
-                return Err(Error::Deny(format!("{statement:?}")));
+                return Err(Error::Deny {
+                    policy: String::new(),
+                    statement: Some(index),
+                });
             }
             allowed = true;
         }
         if !allowed {
-            return Err(Error::NotMatched);
+            return Err(match rejected {
+                Some((index, condition)) => Error::NotMatched {
+                    policy: None,
+                    statement: Some(index),
+                    condition: Some(condition),
+                },
+                None => Error::not_matched(),
+            });
         }
         Ok(())
     }
-}
 
-fn evaluate_conditions(statement: &Statement, input: &Request) -> Result<bool> {
-    if let Some(conditions) = &statement.conditions {
-        for (key, value) in conditions {
-            if let Some(env) = input.context.get(key) {
-                let condition = value.into()?;
-                if !condition.evaluate(env.clone(), input) {
-                    return Ok(false);
-                }
+    pub(crate) fn statement_outcome(
+        &self,
+        statement: &Statement,
+        input: &Request,
+        timings: &mut Timings,
+    ) -> Result<Outcome> {
+        if statement.conditions.as_ref().is_none_or(|c| c.is_empty()) {
+            return Ok(
+                match timings.matcher(|| self.statement_matches(statement, input))? {
+                    true => Outcome::Matched,
+                    false => Outcome::Missed,
+                },
+            );
+        }
+        let captured = timings.matcher(|| -> Result<_> {
+            Ok(match self.with_captures(statement, input)? {
+                Some(input) if !self.excluded(statement, &input)? => Some(input),
+                _ => None,
+            })
+        })?;
+        let Some(input) = captured else {
+            return Ok(Outcome::Missed);
+        };
+        Ok(
+            match timings.conditions(|| self.rejected_condition(statement, &input))? {
+                Some(condition) => Outcome::Rejected(condition),
+                None => Outcome::Matched,
+            },
+        )
+    }
+
+    pub(crate) fn statement_matches(&self, statement: &Statement, input: &Request) -> Result<bool> {
+        if statement.conditions.as_ref().is_some_and(|c| !c.is_empty()) {
+            return match self.with_captures(statement, input)? {
+                Some(input) => Ok(!self.excluded(statement, &input)?
+                    && self.evaluate_conditions(statement, &input)?),
+                None => Ok(false),
+            };
+        }
+        Ok(self.field_matches(
+            statement,
+            Field::Action,
+            &statement.actions,
+            input,
+            &input.action,
+        )? && self.field_matches(
+            statement,
+            Field::Subject,
+            &statement.subjects,
+            input,
+            &input.subject,
+        )? && self.field_matches(
+            statement,
+            Field::Resource,
+            &statement.resources,
+            input,
+            &input.resource,
+        )? && !self.excluded(statement, input)?)
+    }
+
+    pub(crate) fn excluded(&self, statement: &Statement, input: &Request) -> Result<bool> {
+        let fields = [
+            (Field::Action, &statement.not_actions, &input.action),
+            (Field::Subject, &statement.not_subjects, &input.subject),
+            (Field::Resource, &statement.not_resources, &input.resource),
+        ];
+        for (field, patterns, needle) in fields {
+            if !patterns.is_empty()
+                && self.field_matches(statement, field, patterns, input, needle)?
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn field_matches(
+        &self,
+        statement: &Statement,
+        field: Field,
+        patterns: &[String],
+        input: &Request,
+        needle: &str,
+    ) -> Result<bool> {
+        if let Some(matched) = self.fields.shortcut(patterns) {
+            return Ok(matched);
+        }
+        self.matches_with(
+            statement,
+            field,
+            &self.resolve(statement, field, patterns, &input.context)?,
+            needle,
+        )
+    }
+
+    pub(crate) fn resolve<'a>(
+        &self,
+        statement: &Statement,
+        field: Field,
+        patterns: &'a [String],
+        context: &Context,
+    ) -> Result<Cow<'a, [String]>> {
+        let (start, end) = (
+            statement.get_start_delimiter(),
+            statement.get_end_delimiter(),
+        );
+        Ok(match self.field_matcher(statement, field)? {
+            Some(matcher) => statement.resolve(patterns, context, |value| {
+                matcher.escape_literal(start, end, value)
+            }),
+            None => statement.resolve(patterns, context, |value| {
+                self.matcher.escape_literal(start, end, value)
+            }),
+        })
+    }
+
+    pub(crate) fn matches_with(
+        &self,
+        statement: &Statement,
+        field: Field,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<bool> {
+        let (start, end) = (
+            statement.get_start_delimiter(),
+            statement.get_end_delimiter(),
+        );
+        if let Some(matcher) = self.field_matcher(statement, field)? {
+            return matcher.matches(start, end, haystack, needle);
+        }
+        let compiled =
+            statement.compiled(haystack, |patterns| self.matcher.compile(start, patterns));
+        if let Some(matched) = compiled.and_then(|compiled| {
+            self.matcher
+                .matches_compiled(compiled, start, end, haystack, needle)
+        }) {
+            return matched;
+        }
+        self.matcher.matches(start, end, haystack, needle)
+    }
+
+    pub(crate) fn spans_with(
+        &self,
+        statement: &Statement,
+        field: Field,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        let (start, end) = (
+            statement.get_start_delimiter(),
+            statement.get_end_delimiter(),
+        );
+        match self.field_matcher(statement, field)? {
+            Some(matcher) => matcher.spans(start, end, haystack, needle),
+            None => self.matcher.spans(start, end, haystack, needle),
+        }
+    }
+
+    pub(crate) fn with_captures<'a>(
+        &self,
+        statement: &Statement,
+        input: &'a Request,
+    ) -> Result<Option<Cow<'a, Request>>> {
+        let fields = [
+            (Field::Action, "action", &statement.actions, &input.action),
+            (
+                Field::Subject,
+                "subject",
+                &statement.subjects,
+                &input.subject,
+            ),
+            (
+                Field::Resource,
+                "resource",
+                &statement.resources,
+                &input.resource,
+            ),
+        ];
+        let (start, end) = (
+            statement.get_start_delimiter(),
+            statement.get_end_delimiter(),
+        );
+        let mut captured = Vec::new();
+        for (field, name, haystack, needle) in fields {
+            match self.fields.shortcut(haystack) {
+                Some(true) => continue,
+                Some(false) => return Ok(None),
+                None => {}
             }
+            let haystack = self.resolve(statement, field, haystack, &input.context)?;
+            let captures = match self.field_matcher(statement, field)? {
+                Some(matcher) => matcher.named_captures(start, end, &haystack, needle)?,
+                None => self.matcher.named_captures(start, end, &haystack, needle)?,
+            };
+            match captures {
+                Some(captures) => captured.extend(
+                    captures
+                        .into_iter()
+                        .map(|(key, value)| (format!("{name}.{key}"), value)),
+                ),
+                None => return Ok(None),
+            }
+        }
+        if captured.is_empty() {
+            return Ok(Some(Cow::Borrowed(input)));
         }
+        let mut input = input.clone();
+        for (key, value) in captured {
+            input.context.insert(key, value);
+        }
+        Ok(Some(Cow::Owned(input)))
     }
-    Ok(true)
 }
 
 #[cfg(test)]
@@ -143,7 +509,11 @@ mod tests {
                     "enable".to_owned(),
                     JsonCondition {
                         jtype: "Boolean".to_owned(),
-                        options: serde_json::value::to_raw_value(&Boolean { value: true }).unwrap(),
+                        options: serde_json::value::to_raw_value(&Boolean {
+                            value: true,
+                            coerce: false,
+                        })
+                        .unwrap(),
                     },
                 ),
                 (
@@ -181,51 +551,59 @@ mod tests {
                 ),
             ])),
             meta: None,
+            obligations: None,
+            not_subjects: Vec::new(),
+            not_actions: Vec::new(),
+            not_resources: Vec::new(),
+            subject_matcher: None,
+            action_matcher: None,
+            resource_matcher: None,
+            compiled: Default::default(),
         }];
 
         let p = super::Ope::new(Regexp::new(256).unwrap());
-        p.is_allow(
-            &sts,
-            &Request {
-                resource: "myrn:some.domain.com:resource:123".to_owned(),
-                action: "delete".to_owned(),
-                subject: "peter".to_owned(),
-                context: HashMap::from([
-                    (
-                        "clientIP".to_owned(),
-                        serde_json::value::to_raw_value("192.168.1.67").unwrap(),
-                    ),
-                    (
-                        "year".to_owned(),
-                        serde_json::value::to_raw_value("2023").unwrap(),
-                    ),
-                    (
-                        "password".to_owned(),
-                        serde_json::value::to_raw_value("a12345678901234567").unwrap(),
-                    ),
-                    (
-                        "enable".to_owned(),
-                        serde_json::value::to_raw_value(&true).unwrap(),
-                    ),
-                    (
-                        "count".to_owned(),
-                        serde_json::value::to_raw_value(&6.0).unwrap(),
-                    ),
-                    (
-                        "login".to_owned(),
-                        serde_json::value::to_raw_value("15/01/2023 12:50").unwrap(),
-                    ),
-                    (
-                        "resource".to_owned(),
-                        serde_json::value::to_raw_value(&HashMap::from([
-                            ("value".to_owned(), "123".to_owned()),
-                            ("delimiter".to_owned(), "".to_owned()),
-                        ]))
-                        .unwrap(),
-                    ),
-                ]),
-            },
-        )
-        .unwrap();
+        let input = Request {
+            resource: "myrn:some.domain.com:resource:123".to_owned(),
+            action: "delete".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from(HashMap::from([
+                (
+                    "clientIP".to_owned(),
+                    serde_json::to_value("192.168.1.67").unwrap(),
+                ),
+                ("year".to_owned(), serde_json::to_value("2023").unwrap()),
+                (
+                    "password".to_owned(),
+                    serde_json::to_value("a12345678901234567").unwrap(),
+                ),
+                ("enable".to_owned(), serde_json::to_value(true).unwrap()),
+                ("count".to_owned(), serde_json::to_value(6.0).unwrap()),
+                (
+                    "login".to_owned(),
+                    serde_json::to_value("15/01/2023 12:50").unwrap(),
+                ),
+                (
+                    "resource".to_owned(),
+                    serde_json::to_value(HashMap::from([
+                        ("value".to_owned(), "123".to_owned()),
+                        ("delimiter".to_owned(), "".to_owned()),
+                    ]))
+                    .unwrap(),
+                ),
+            ])),
+        };
+        p.is_allow(&sts, &input).unwrap();
+
+        let mut denied = sts.clone();
+        denied[0].effect = Effect::Deny;
+        let err = p.is_allow(&denied, &input).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Deny {
+                statement: Some(0),
+                ..
+            }
+        ));
+        assert!(err.to_string().ends_with("Please proofread statement 0"));
     }
 }
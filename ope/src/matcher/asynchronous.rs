@@ -0,0 +1,72 @@
+use std::future::{self, Future};
+
+use super::{escape_template, Matcher};
+use crate::Result;
+
+pub trait AsyncMatcher {
+    fn matches(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        let mut buf = [0; 8];
+        let (start, end) = buf.split_at_mut(4);
+        Some(escape_template(
+            delimiter_start.encode_utf8(start),
+            delimiter_end.encode_utf8(end),
+            value,
+        ))
+    }
+
+    fn is_literal(&self, _delimiter_start: char, _pattern: &str) -> bool {
+        false
+    }
+
+    fn indexable(&self) -> bool {
+        false
+    }
+}
+
+impl<M: Matcher + Sync> AsyncMatcher for M {
+    fn matches(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> impl Future<Output = Result<bool>> + Send {
+        future::ready(Matcher::matches(
+            self,
+            delimiter_start,
+            delimiter_end,
+            haystack,
+            needle,
+        ))
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        Matcher::escape_literal(self, delimiter_start, delimiter_end, value)
+    }
+
+    fn is_literal(&self, delimiter_start: char, pattern: &str) -> bool {
+        Matcher::is_literal(self, delimiter_start, pattern)
+    }
+
+    fn indexable(&self) -> bool {
+        Matcher::indexable(self)
+    }
+}
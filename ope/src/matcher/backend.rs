@@ -0,0 +1,258 @@
+use std::ops::Range;
+
+#[cfg(not(any(feature = "regex", feature = "regex-lite", feature = "fancy-regex")))]
+compile_error!("enable one of the regex, regex-lite or fancy-regex features");
+
+#[cfg(feature = "fancy-regex")]
+use fancy_regex as engine;
+#[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+use regex as engine;
+#[cfg(all(feature = "regex-lite", not(feature = "fancy-regex")))]
+use regex_lite as engine;
+
+use super::reg::Validation;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Limits {
+    pub(crate) validation: Validation,
+    pub(crate) size_limit: Option<usize>,
+    pub(crate) dfa_size_limit: Option<usize>,
+    pub(crate) max_pattern_len: Option<usize>,
+}
+
+impl Limits {
+    pub(crate) fn check(&self, tpl: &str) -> Result<()> {
+        match self.max_pattern_len {
+            Some(max) if tpl.len() > max => Err(Error::PatternTooComplex(tpl.to_owned())),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+    pub(crate) fn regex(&self, tpl: &str, pattern: &str, case_insensitive: bool) -> Result<Regex> {
+        let mut builder = engine::RegexBuilder::new(pattern);
+        builder.case_insensitive(case_insensitive);
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        if let Some(dfa_size_limit) = self.dfa_size_limit {
+            builder.dfa_size_limit(dfa_size_limit);
+        }
+        builder
+            .build()
+            .map(Regex)
+            .map_err(|err| compile_error(tpl, err))
+    }
+
+    #[cfg(all(feature = "regex-lite", not(feature = "fancy-regex")))]
+    pub(crate) fn regex(&self, tpl: &str, pattern: &str, case_insensitive: bool) -> Result<Regex> {
+        let mut builder = engine::RegexBuilder::new(pattern);
+        builder.case_insensitive(case_insensitive);
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        match builder.build() {
+            Ok(regex) => Ok(Regex(regex)),
+            Err(_)
+                if self.size_limit.is_some()
+                    && engine::RegexBuilder::new(pattern)
+                        .case_insensitive(case_insensitive)
+                        .build()
+                        .is_ok() =>
+            {
+                Err(Error::PatternTooComplex(tpl.to_owned()))
+            }
+            Err(err) => Err(Error::RegexBackendError(err.to_string())),
+        }
+    }
+
+    #[cfg(feature = "fancy-regex")]
+    pub(crate) fn regex(&self, tpl: &str, pattern: &str, case_insensitive: bool) -> Result<Regex> {
+        let mut builder = engine::RegexBuilder::new(pattern);
+        builder.case_insensitive(case_insensitive);
+        if let Some(size_limit) = self.size_limit {
+            builder.delegate_size_limit(size_limit);
+        }
+        if let Some(dfa_size_limit) = self.dfa_size_limit {
+            builder.delegate_dfa_size_limit(dfa_size_limit);
+        }
+        builder
+            .build()
+            .map(Regex)
+            .map_err(|err| compile_error(tpl, err))
+    }
+
+    #[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+    pub(crate) fn regex_set(
+        &self,
+        templates: &[String],
+        patterns: Vec<String>,
+        case_insensitive: bool,
+    ) -> Result<RegexSet> {
+        let mut builder = engine::RegexSetBuilder::new(patterns);
+        builder.case_insensitive(case_insensitive);
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        if let Some(dfa_size_limit) = self.dfa_size_limit {
+            builder.dfa_size_limit(dfa_size_limit);
+        }
+        builder
+            .build()
+            .map(RegexSet)
+            .map_err(|err| compile_error(&templates.join(","), err))
+    }
+
+    #[cfg(any(feature = "regex-lite", feature = "fancy-regex"))]
+    pub(crate) fn regex_set(
+        &self,
+        templates: &[String],
+        patterns: Vec<String>,
+        case_insensitive: bool,
+    ) -> Result<RegexSet> {
+        templates
+            .iter()
+            .zip(patterns.iter())
+            .map(|(tpl, pattern)| self.regex(tpl, pattern, case_insensitive))
+            .collect::<Result<_>>()
+            .map(RegexSet)
+    }
+}
+
+#[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+fn compile_error(tpl: &str, err: engine::Error) -> Error {
+    match err {
+        engine::Error::CompiledTooBig(_) => Error::PatternTooComplex(tpl.to_owned()),
+        err => Error::CompileRegexError(err),
+    }
+}
+
+#[cfg(feature = "fancy-regex")]
+fn compile_error(tpl: &str, err: engine::Error) -> Error {
+    match err {
+        engine::Error::CompileError(engine::CompileError::InnerError(inner))
+            if inner.size_limit().is_some() =>
+        {
+            Error::PatternTooComplex(tpl.to_owned())
+        }
+        err => Error::RegexBackendError(err.to_string()),
+    }
+}
+
+#[cfg(not(feature = "fancy-regex"))]
+pub(crate) use engine::escape;
+
+#[cfg(feature = "fancy-regex")]
+pub(crate) fn escape(text: &str) -> String {
+    engine::escape(text).into_owned()
+}
+
+#[derive(Clone)]
+pub(crate) struct Regex(engine::Regex);
+
+impl Regex {
+    pub(crate) fn new(pattern: &str) -> Result<Self> {
+        Limits::default().regex(pattern, pattern, false)
+    }
+
+    pub(crate) fn captures_len(&self) -> usize {
+        self.0.captures_len()
+    }
+
+    pub(crate) fn group(&self, name: &str) -> Option<usize> {
+        self.0.capture_names().position(|group| group == Some(name))
+    }
+
+    #[cfg(not(feature = "fancy-regex"))]
+    pub(crate) fn is_match(&self, needle: &str) -> Result<bool> {
+        Ok(self.0.is_match(needle))
+    }
+
+    #[cfg(not(feature = "fancy-regex"))]
+    pub(crate) fn captures(&self, needle: &str) -> Result<Option<Vec<Option<Range<usize>>>>> {
+        Ok(self
+            .0
+            .captures(needle)
+            .map(|caps| caps.iter().map(|v| v.map(|v| v.range())).collect()))
+    }
+
+    #[cfg(feature = "fancy-regex")]
+    pub(crate) fn is_match(&self, needle: &str) -> Result<bool> {
+        self.0
+            .is_match(needle)
+            .map_err(|err| Error::RegexBackendError(err.to_string()))
+    }
+
+    #[cfg(feature = "fancy-regex")]
+    pub(crate) fn captures(&self, needle: &str) -> Result<Option<Vec<Option<Range<usize>>>>> {
+        self.0
+            .captures(needle)
+            .map(|caps| caps.map(|caps| caps.iter().map(|v| v.map(|v| v.range())).collect()))
+            .map_err(|err| Error::RegexBackendError(err.to_string()))
+    }
+}
+
+#[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+pub(crate) struct RegexSet(engine::RegexSet);
+
+#[cfg(not(any(feature = "regex-lite", feature = "fancy-regex")))]
+impl RegexSet {
+    pub(crate) fn is_match(&self, needle: &str) -> Result<bool> {
+        Ok(self.0.is_match(needle))
+    }
+}
+
+#[cfg(any(feature = "regex-lite", feature = "fancy-regex"))]
+pub(crate) struct RegexSet(Vec<Regex>);
+
+#[cfg(any(feature = "regex-lite", feature = "fancy-regex"))]
+impl RegexSet {
+    pub(crate) fn is_match(&self, needle: &str) -> Result<bool> {
+        for regex in self.0.iter() {
+            if regex.is_match(needle)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend() {
+        let limits = Limits::default();
+        let regex = limits
+            .regex("<id:[0-9]+>", "^(?P<id>[0-9]+)-([a-z])$", true)
+            .unwrap();
+        assert_eq!(regex.captures_len(), 3);
+        assert_eq!(regex.group("id"), Some(1));
+        assert!(regex.is_match("42-A").unwrap());
+        assert_eq!(
+            regex.captures("42-a").unwrap(),
+            Some(vec![Some(0..4), Some(0..2), Some(3..4)])
+        );
+        assert_eq!(regex.captures("a-42").unwrap(), None);
+
+        let set = limits
+            .regex_set(
+                &["<a>".to_owned(), "<b>".to_owned()],
+                vec!["^(a)$".to_owned(), "^(b)$".to_owned()],
+                false,
+            )
+            .unwrap();
+        assert!(set.is_match("b").unwrap());
+        assert!(!set.is_match("c").unwrap());
+        assert!(limits.regex("<(>", "^(($", false).is_err());
+
+        #[cfg(feature = "fancy-regex")]
+        assert!(limits
+            .regex("<(a)\\1>", r"^((a)\2)$", false)
+            .unwrap()
+            .is_match("aa")
+            .unwrap());
+    }
+}
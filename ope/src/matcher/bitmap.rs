@@ -0,0 +1,316 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::cache::{ShardedCache, Source};
+use super::{next_id, CompiledPatterns, Matcher};
+use crate::Result;
+
+struct Bitmap {
+    words: Box<[u64]>,
+}
+
+impl Bitmap {
+    fn contains(&self, id: usize) -> bool {
+        self.words[id / 64] & (1 << (id % 64)) != 0
+    }
+}
+
+type Compiled = (Source, Option<Arc<Bitmap>>);
+
+struct Precompiled {
+    owner: u64,
+    bitmap: Option<Bitmap>,
+    inner: Option<CompiledPatterns>,
+}
+
+pub struct ActionBitmap<M> {
+    id: u64,
+    inner: M,
+    ids: HashMap<String, usize>,
+    state: RandomState,
+    cache: ShardedCache<u64, Arc<Compiled>>,
+}
+
+impl<M> ActionBitmap<M> {
+    pub fn new<I, T>(cache_size: usize, vocabulary: I, inner: M) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut ids = HashMap::new();
+        for action in vocabulary {
+            let next = ids.len();
+            ids.entry(action.into()).or_insert(next);
+        }
+        Ok(Self {
+            id: next_id(),
+            inner,
+            ids,
+            state: RandomState::new(),
+            cache: ShardedCache::new(cache_size, 16)?,
+        })
+    }
+
+    pub fn vocabulary(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn bitmap(&self, delimiter_start: char, haystack: &[impl AsRef<str>]) -> Option<Arc<Bitmap>> {
+        let mut hasher = self.state.build_hasher();
+        delimiter_start.hash(&mut hasher);
+        haystack.len().hash(&mut hasher);
+        for h in haystack.iter() {
+            h.as_ref().hash(&mut hasher);
+        }
+        let key = hasher.finish();
+        if let Some(entry) = self.cache.get(&key) {
+            if entry.0.is(delimiter_start, haystack) {
+                return entry.1.clone();
+            }
+        }
+        let bitmap = self.build(delimiter_start, haystack).map(Arc::new);
+        self.cache.put(
+            key,
+            Arc::new((Source::new(delimiter_start, haystack), bitmap.clone())),
+        );
+        bitmap
+    }
+
+    fn build(&self, delimiter_start: char, haystack: &[impl AsRef<str>]) -> Option<Bitmap> {
+        let mut words = vec![0u64; self.ids.len().div_ceil(64)].into_boxed_slice();
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if h.contains(delimiter_start) {
+                return None;
+            }
+            let id = *self.ids.get(h)?;
+            words[id / 64] |= 1 << (id % 64);
+        }
+        Some(Bitmap { words })
+    }
+
+    fn test(&self, bitmap: &Bitmap, needle: &str) -> bool {
+        self.ids.get(needle).is_some_and(|id| bitmap.contains(*id))
+    }
+}
+
+impl<M: Matcher> Matcher for ActionBitmap<M> {
+    fn matches(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        match self.bitmap(delimiter_start, haystack) {
+            Some(bitmap) => Ok(self.test(&bitmap, needle)),
+            None => self
+                .inner
+                .matches(delimiter_start, delimiter_end, haystack, needle),
+        }
+    }
+
+    fn compile(
+        &self,
+        delimiter_start: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Option<CompiledPatterns> {
+        let bitmap = self.build(delimiter_start, patterns);
+        let inner = match bitmap {
+            Some(_) => None,
+            None => self.inner.compile(delimiter_start, patterns),
+        };
+        Some(Arc::new(Precompiled {
+            owner: self.id,
+            bitmap,
+            inner,
+        }))
+    }
+
+    fn matches_compiled(
+        &self,
+        compiled: &(dyn Any + Send + Sync),
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Option<Result<bool>> {
+        let compiled = compiled
+            .downcast_ref::<Precompiled>()
+            .filter(|compiled| compiled.owner == self.id)?;
+        if let Some(bitmap) = &compiled.bitmap {
+            return Some(Ok(self.test(bitmap, needle)));
+        }
+        let matched = compiled.inner.as_deref().and_then(|inner| {
+            self.inner
+                .matches_compiled(inner, delimiter_start, delimiter_end, haystack, needle)
+        });
+        Some(matched.unwrap_or_else(|| {
+            self.inner
+                .matches(delimiter_start, delimiter_end, haystack, needle)
+        }))
+    }
+
+    fn captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<String>>> {
+        match self.bitmap(delimiter_start, haystack) {
+            Some(bitmap) => Ok(self.test(&bitmap, needle).then(Vec::new)),
+            None => self
+                .inner
+                .captures(delimiter_start, delimiter_end, haystack, needle),
+        }
+    }
+
+    fn named_captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        match self.bitmap(delimiter_start, haystack) {
+            Some(bitmap) => Ok(self.test(&bitmap, needle).then(Vec::new)),
+            None => self
+                .inner
+                .named_captures(delimiter_start, delimiter_end, haystack, needle),
+        }
+    }
+
+    fn spans(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        match self.bitmap(delimiter_start, haystack) {
+            Some(bitmap) => Ok(self.test(&bitmap, needle).then(Vec::new)),
+            None => self
+                .inner
+                .spans(delimiter_start, delimiter_end, haystack, needle),
+        }
+    }
+
+    fn warm(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Result<()> {
+        if self.bitmap(delimiter_start, patterns).is_some() {
+            return Ok(());
+        }
+        self.inner.warm(delimiter_start, delimiter_end, patterns)
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        self.inner
+            .escape_literal(delimiter_start, delimiter_end, value)
+    }
+
+    fn is_literal(&self, delimiter_start: char, pattern: &str) -> bool {
+        self.inner.is_literal(delimiter_start, pattern)
+    }
+
+    fn indexable(&self) -> bool {
+        self.inner.indexable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Enforcer, Policy, Regexp, Request};
+
+    #[test]
+    fn action_bitmap() {
+        let verbs: Vec<String> = (0..70).map(|i| format!("verb{i}")).collect();
+        let m = ActionBitmap::new(16, verbs.iter().cloned(), Regexp::new(16).unwrap()).unwrap();
+        assert_eq!(m.vocabulary(), 70);
+        let haystack = ["verb1", "verb65"];
+        for _ in 0..2 {
+            assert!(m.matches('<', '>', &haystack, "verb65").unwrap());
+            assert!(!m.matches('<', '>', &haystack, "verb2").unwrap());
+            assert!(!m.matches('<', '>', &haystack, "unknown").unwrap());
+        }
+        assert!(m.bitmap('<', &haystack).is_some());
+        assert!(m.bitmap('<', &["verb1", "<verb[0-9]>"]).is_none());
+        assert!(m.bitmap('<', &["verb1", "other"]).is_none());
+        assert!(m
+            .matches('<', '>', &["verb1", "<verb[0-9]>"], "verb7")
+            .unwrap());
+        assert!(m.matches('<', '>', &["other"], "other").unwrap());
+
+        let compiled = m.compile('<', &haystack).unwrap();
+        let matches = |needle| {
+            m.matches_compiled(&*compiled, '<', '>', &haystack, needle)
+                .map(Result::unwrap)
+        };
+        assert_eq!(matches("verb65"), Some(true));
+        assert_eq!(matches("verb2"), Some(false));
+        let templated = ["verb1", "<verb[0-9]>"];
+        let compiled = m.compile('<', &templated).unwrap();
+        assert_eq!(
+            m.matches_compiled(&*compiled, '<', '>', &templated, "verb7")
+                .map(Result::unwrap),
+            Some(true)
+        );
+
+        let m = Arc::new(m);
+        let ope = crate::Ope::new(m.clone());
+        let statements = Policy::builder("verbs")
+            .allow()
+            .subject("peter")
+            .actions(["verb1", "verb65"])
+            .resource("articles")
+            .build()
+            .unwrap()
+            .statements;
+        let stats = m.cache.stats();
+        for action in ["verb65", "verb2"] {
+            let input = Request {
+                resource: "articles".to_owned(),
+                action: action.to_owned(),
+                subject: "peter".to_owned(),
+                context: Context::new(),
+            };
+            assert_eq!(
+                ope.is_allow(&statements, &input).is_ok(),
+                action == "verb65"
+            );
+        }
+        assert_eq!(m.cache.stats(), stats);
+        drop(ope);
+        let m = Arc::into_inner(m).unwrap();
+
+        let policy = Policy::builder("articles")
+            .allow()
+            .subject("peter")
+            .action("verb3")
+            .action("verb64")
+            .resource("articles:<.*>")
+            .build()
+            .unwrap();
+        let enforcer = Enforcer::new(m, vec![policy]);
+        let request = |action: &str| Request {
+            resource: "articles:1".to_owned(),
+            action: action.to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::new(),
+        };
+        assert!(enforcer.is_allowed(&request("verb64")).is_ok());
+        assert!(enforcer.is_allowed(&request("verb4")).is_err());
+    }
+}
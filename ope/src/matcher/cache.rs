@@ -0,0 +1,182 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use lru::LruCache;
+use parking_lot::Mutex;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub size: usize,
+}
+
+impl std::ops::Add for CacheStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            hits: self.hits + other.hits,
+            misses: self.misses + other.misses,
+            evictions: self.evictions + other.evictions,
+            size: self.size + other.size,
+        }
+    }
+}
+
+pub(crate) struct Source {
+    delimiter_start: char,
+    patterns: Box<[String]>,
+}
+
+impl Source {
+    pub(crate) fn new(delimiter_start: char, haystack: &[impl AsRef<str>]) -> Self {
+        Self {
+            delimiter_start,
+            patterns: haystack.iter().map(|h| h.as_ref().to_owned()).collect(),
+        }
+    }
+
+    pub(crate) fn is(&self, delimiter_start: char, haystack: &[impl AsRef<str>]) -> bool {
+        self.delimiter_start == delimiter_start
+            && self.patterns.len() == haystack.len()
+            && self
+                .patterns
+                .iter()
+                .zip(haystack.iter())
+                .all(|(p, h)| p == h.as_ref())
+    }
+}
+
+pub(crate) struct ShardedCache<K, V> {
+    state: RandomState,
+    shards: Vec<Mutex<LruCache<K, V>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl<K: Hash + Eq, V: Clone> ShardedCache<K, V> {
+    pub(crate) fn new(cache_size: usize, shards: usize) -> Result<Self> {
+        let capacity = NonZeroUsize::new(cache_size).ok_or(Error::InvalidCacheSize(cache_size))?;
+        let shards = shards.clamp(1, capacity.get());
+        let per_shard = NonZeroUsize::new(capacity.get().div_ceil(shards))
+            .ok_or(Error::InvalidCacheSize(cache_size))?;
+        Ok(Self {
+            state: RandomState::new(),
+            shards: (0..shards)
+                .map(|_| Mutex::new(LruCache::new(per_shard)))
+                .collect(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    fn shard<Q>(&self, key: &Q) -> &Mutex<LruCache<K, V>>
+    where
+        Q: Hash + ?Sized,
+    {
+        let idx = self.state.hash_one(key) as usize % self.shards.len();
+        &self.shards[idx]
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = self.shard(key).lock();
+        let value = shard.get(key).cloned();
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+
+    pub(crate) fn remove<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = self.shard(key).lock();
+        shard.pop(key).is_some()
+    }
+
+    pub(crate) fn remove_if(&self, predicate: impl Fn(&V) -> bool) -> usize
+    where
+        K: Clone,
+    {
+        let mut removed = 0;
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock();
+            let keys: Vec<K> = shard
+                .iter()
+                .filter(|(_, value)| predicate(value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in keys.iter() {
+                shard.pop(key);
+            }
+            removed += keys.len();
+        }
+        removed
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let mut size = 0;
+        for shard in self.shards.iter() {
+            size += shard.lock().len();
+        }
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            size,
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().clear();
+        }
+    }
+
+    pub(crate) fn put(&self, key: K, value: V) {
+        let mut shard = self.shard(&key).lock();
+        if let Some((evicted, _)) = shard.push(key, value) {
+            if !shard.contains(&evicted) {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn poison() {
+        let cache = Arc::new(ShardedCache::new(4, 1).unwrap());
+        cache.put("a".to_owned(), 1);
+        let shared = cache.clone();
+        assert!(
+            thread::spawn(move || shared.remove_if(|_| panic!("predicate")))
+                .join()
+                .is_err()
+        );
+        assert_eq!(cache.get("a"), Some(1));
+        cache.put("b".to_owned(), 2);
+        assert_eq!(cache.stats().size, 2);
+    }
+}
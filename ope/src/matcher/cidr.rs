@@ -0,0 +1,69 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use cidr_utils::cidr::IpCidr;
+
+use super::Matcher;
+use crate::{Error, Result};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CidrMatcher;
+
+impl CidrMatcher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Matcher for CidrMatcher {
+    fn matches(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        let ip = match needle.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => return Ok(false),
+        };
+        for h in haystack.iter().map(AsRef::as_ref) {
+            let cidr = IpCidr::from_str(h).map_err(|_| Error::InvalidCidr(h.to_owned()))?;
+            if cidr.contains(&ip) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn escape_literal(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        Some(value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cidr() {
+        let m = CidrMatcher;
+        let haystack = vec![
+            "10.0.0.0/8".to_owned(),
+            "192.168.1.7".to_owned(),
+            "2001:db8::/32".to_owned(),
+        ];
+        assert!(m.matches('<', '>', &haystack, "10.1.2.3").unwrap());
+        assert!(m.matches('<', '>', &haystack, "192.168.1.7").unwrap());
+        assert!(!m.matches('<', '>', &haystack, "192.168.1.8").unwrap());
+        assert!(m.matches('<', '>', &haystack, "2001:db8::1").unwrap());
+        assert!(!m.matches('<', '>', &haystack, "2001:db9::1").unwrap());
+        assert!(!m.matches('<', '>', &haystack, "not-an-ip").unwrap());
+        assert!(m.matches('<', '>', &["10.0.0.0/33"], "10.0.0.1").is_err());
+    }
+}
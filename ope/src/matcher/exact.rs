@@ -0,0 +1,284 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::cache::{ShardedCache, Source};
+use super::{next_id, CompiledPatterns, Matcher};
+use crate::Result;
+
+struct Index {
+    owner: u64,
+    source: Source,
+    literals: HashSet<String>,
+    templates: Vec<String>,
+}
+
+pub struct Exact<M> {
+    id: u64,
+    inner: M,
+    state: RandomState,
+    cache: ShardedCache<u64, Arc<Index>>,
+}
+
+impl<M> Exact<M> {
+    pub fn new(cache_size: usize, inner: M) -> Result<Self> {
+        Ok(Self {
+            id: next_id(),
+            inner,
+            state: RandomState::new(),
+            cache: ShardedCache::new(cache_size, 16)?,
+        })
+    }
+
+    fn key(&self, delimiter_start: char, haystack: &[impl AsRef<str>]) -> u64 {
+        let mut hasher = self.state.build_hasher();
+        delimiter_start.hash(&mut hasher);
+        haystack.len().hash(&mut hasher);
+        for h in haystack.iter() {
+            h.as_ref().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn index(&self, delimiter_start: char, haystack: &[impl AsRef<str>]) -> Index {
+        let (templates, literals) = partition(delimiter_start, haystack);
+        Index {
+            owner: self.id,
+            source: Source::new(delimiter_start, haystack),
+            literals: literals.into_iter().map(str::to_owned).collect(),
+            templates: templates.into_iter().map(str::to_owned).collect(),
+        }
+    }
+
+    fn lookup(
+        &self,
+        index: &Index,
+        delimiter_start: char,
+        delimiter_end: char,
+        needle: &str,
+    ) -> Result<bool>
+    where
+        M: Matcher,
+    {
+        if index.literals.contains(needle) {
+            return Ok(true);
+        }
+        if index.templates.is_empty() {
+            return Ok(false);
+        }
+        self.inner
+            .matches(delimiter_start, delimiter_end, &index.templates, needle)
+    }
+}
+
+fn partition(delimiter_start: char, haystack: &[impl AsRef<str>]) -> (Vec<&str>, Vec<&str>) {
+    haystack
+        .iter()
+        .map(AsRef::as_ref)
+        .partition(|h| h.contains(delimiter_start))
+}
+
+impl<M: Matcher> Matcher for Exact<M> {
+    fn matches(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        let key = self.key(delimiter_start, haystack);
+        let index = match self.cache.get(&key) {
+            Some(index) if index.source.is(delimiter_start, haystack) => index,
+            _ => {
+                let index = Arc::new(self.index(delimiter_start, haystack));
+                self.cache.put(key, index.clone());
+                index
+            }
+        };
+        self.lookup(&index, delimiter_start, delimiter_end, needle)
+    }
+
+    fn compile(
+        &self,
+        delimiter_start: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Option<CompiledPatterns> {
+        Some(Arc::new(self.index(delimiter_start, patterns)))
+    }
+
+    fn matches_compiled(
+        &self,
+        compiled: &(dyn Any + Send + Sync),
+        delimiter_start: char,
+        delimiter_end: char,
+        _haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Option<Result<bool>> {
+        let index = compiled
+            .downcast_ref::<Index>()
+            .filter(|index| index.owner == self.id)?;
+        Some(self.lookup(index, delimiter_start, delimiter_end, needle))
+    }
+
+    fn matches_which(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<usize>> {
+        for (index, h) in haystack.iter().map(AsRef::as_ref).enumerate() {
+            let matched = match h.contains(delimiter_start) {
+                true => self
+                    .inner
+                    .matches(delimiter_start, delimiter_end, &[h], needle)?,
+                false => h == needle,
+            };
+            if matched {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<String>>> {
+        let (templates, literals) = partition(delimiter_start, haystack);
+        if self.matches(delimiter_start, delimiter_end, &literals, needle)? {
+            return Ok(Some(Vec::new()));
+        }
+        if templates.is_empty() {
+            return Ok(None);
+        }
+        self.inner
+            .captures(delimiter_start, delimiter_end, &templates, needle)
+    }
+
+    fn named_captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        let (templates, literals) = partition(delimiter_start, haystack);
+        if self.matches(delimiter_start, delimiter_end, &literals, needle)? {
+            return Ok(Some(Vec::new()));
+        }
+        if templates.is_empty() {
+            return Ok(None);
+        }
+        self.inner
+            .named_captures(delimiter_start, delimiter_end, &templates, needle)
+    }
+
+    fn spans(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        let (templates, literals) = partition(delimiter_start, haystack);
+        if self.matches(delimiter_start, delimiter_end, &literals, needle)? {
+            return Ok(Some(Vec::new()));
+        }
+        if templates.is_empty() {
+            return Ok(None);
+        }
+        self.inner
+            .spans(delimiter_start, delimiter_end, &templates, needle)
+    }
+
+    fn warm(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Result<()> {
+        self.inner.warm(delimiter_start, delimiter_end, patterns)
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        self.inner
+            .escape_literal(delimiter_start, delimiter_end, value)
+    }
+
+    fn is_literal(&self, delimiter_start: char, pattern: &str) -> bool {
+        !pattern.contains(delimiter_start)
+    }
+
+    fn indexable(&self) -> bool {
+        self.inner.indexable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Regexp;
+
+    #[test]
+    fn exact() {
+        let m = Exact::new(16, Regexp::new(16).unwrap()).unwrap();
+        let haystack: Vec<String> = (0..1000).map(|i| format!("user:{i}")).collect();
+        for _ in 0..2 {
+            assert!(m.matches('<', '>', &haystack, "user:999").unwrap());
+            assert!(!m.matches('<', '>', &haystack, "user:1000").unwrap());
+        }
+        let haystack = vec!["peter".to_owned(), "<zac|ken>".to_owned()];
+        assert!(m.matches('<', '>', &haystack, "peter").unwrap());
+        assert!(m.matches('<', '>', &haystack, "ken").unwrap());
+        assert!(!m.matches('<', '>', &haystack, "max").unwrap());
+
+        let key = m.key('<', &haystack);
+        m.cache
+            .put(m.key('<', &["max"]), m.cache.get(&key).unwrap());
+        assert!(m.matches('<', '>', &["max"], "max").unwrap());
+        assert!(!m.matches('<', '>', &["max"], "peter").unwrap());
+
+        let compiled = m.compile('<', &haystack).unwrap();
+        let matches = |m: &Exact<Regexp>, needle| {
+            m.matches_compiled(&*compiled, '<', '>', &haystack, needle)
+                .map(Result::unwrap)
+        };
+        assert_eq!(matches(&m, "peter"), Some(true));
+        assert_eq!(matches(&m, "zac"), Some(true));
+        assert_eq!(matches(&m, "max"), Some(false));
+        let other = Exact::new(16, Regexp::new(16).unwrap()).unwrap();
+        assert_eq!(matches(&other, "peter"), None);
+
+        let ope = crate::Ope::new(m);
+        let mut statements = crate::Policy::builder("users")
+            .allow()
+            .subjects(["peter", "<zac|ken>"])
+            .action("get")
+            .resource("users")
+            .build()
+            .unwrap()
+            .statements;
+        let request = |subject: &str| crate::Request {
+            resource: "users".to_owned(),
+            action: "get".to_owned(),
+            subject: subject.to_owned(),
+            context: Default::default(),
+        };
+        ope.warm(&statements).unwrap();
+        assert!(ope.is_allow(&statements, &request("ken")).is_ok());
+        assert!(ope.is_allow(&statements, &request("max")).is_err());
+        statements[0].subjects = vec!["max".to_owned()];
+        assert!(ope.is_allow(&statements, &request("max")).is_ok());
+    }
+}
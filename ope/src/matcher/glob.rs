@@ -0,0 +1,205 @@
+use super::Matcher;
+use crate::{Error, Result};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Glob;
+
+impl Glob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Matcher for Glob {
+    fn matches(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(['*', '?', '[', '\\']) {
+                if h.eq(needle) {
+                    return Ok(true);
+                }
+                continue;
+            }
+            let tokens = compile(h)?;
+            if glob_match(&tokens, needle) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn escape_literal(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '*' | '?' | '[' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        Some(escaped)
+    }
+
+    fn is_literal(&self, _delimiter_start: char, pattern: &str) -> bool {
+        !pattern.contains(['*', '?', '[', '\\'])
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(v) => *v == c,
+            Token::Any => true,
+            Token::Star => false,
+            Token::Class { negated, ranges } => {
+                let found = ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi);
+                found != *negated
+            }
+        }
+    }
+}
+
+fn compile(pattern: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if tokens.last() != Some(&Token::Star) {
+                    tokens.push(Token::Star);
+                }
+            }
+            '?' => tokens.push(Token::Any),
+            '\\' => match chars.next() {
+                Some(v) => tokens.push(Token::Char(v)),
+                None => return Err(Error::InvalidGlob(pattern.to_owned())),
+            },
+            '[' => {
+                let negated = matches!(chars.peek(), Some('!') | Some('^'));
+                if negated {
+                    chars.next();
+                }
+                let mut ranges = Vec::new();
+                let mut closed = false;
+                while let Some(lo) = chars.next() {
+                    if lo == ']' && !ranges.is_empty() {
+                        closed = true;
+                        break;
+                    }
+                    let lo = if lo == '\\' {
+                        chars
+                            .next()
+                            .ok_or_else(|| Error::InvalidGlob(pattern.to_owned()))?
+                    } else {
+                        lo
+                    };
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some('-') {
+                        if let Some(hi) = lookahead.next().filter(|v| *v != ']') {
+                            if hi < lo {
+                                return Err(Error::InvalidGlob(pattern.to_owned()));
+                            }
+                            chars = lookahead;
+                            ranges.push((lo, hi));
+                            continue;
+                        }
+                    }
+                    ranges.push((lo, lo));
+                }
+                if !closed {
+                    return Err(Error::InvalidGlob(pattern.to_owned()));
+                }
+                tokens.push(Token::Class { negated, ranges });
+            }
+            v => tokens.push(Token::Char(v)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn glob_match(tokens: &[Token], needle: &str) -> bool {
+    let input: Vec<char> = needle.chars().collect();
+    let (mut t, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < input.len() {
+        match tokens.get(t) {
+            Some(Token::Star) => {
+                backtrack = Some((t, n));
+                t += 1;
+                continue;
+            }
+            Some(token) if token.matches(input[n]) => {
+                t += 1;
+                n += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star, start)) => {
+                t = star + 1;
+                n = start + 1;
+                backtrack = Some((star, start + 1));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|v| *v == Token::Star)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, needle: &str) -> bool {
+        Glob.matches('<', '>', &[pattern], needle).unwrap()
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(glob("urn:service:*:bucket/*", "urn:service:s3:bucket/a/b"));
+        assert!(!glob("urn:service:*:bucket/*", "urn:service:s3:object/a"));
+        assert!(glob("file-?.txt", "file-1.txt"));
+        assert!(!glob("file-?.txt", "file-12.txt"));
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXXbYYc"));
+        assert!(!glob("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(glob("disk[0-9]", "disk7"));
+        assert!(!glob("disk[0-9]", "diskx"));
+        assert!(glob("disk[!0-9]", "diskx"));
+        assert!(glob("[a-cx]yz", "xyz"));
+        assert!(glob("[]]", "]"));
+        assert!(glob("\\*", "*"));
+        assert!(!glob("\\*", "a"));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Glob.matches('<', '>', &["disk[0-9"], "disk1").is_err());
+        assert!(Glob.matches('<', '>', &["[z-a]".to_owned()], "b").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use super::Matcher;
+use crate::Result;
+
+const ANY: &str = "*";
+
+#[derive(Debug, Clone)]
+pub struct HierarchyMatcher {
+    separator: String,
+    max_depth: Option<usize>,
+}
+
+impl Default for HierarchyMatcher {
+    fn default() -> Self {
+        Self::new(":")
+    }
+}
+
+impl HierarchyMatcher {
+    pub fn new(separator: impl Into<String>) -> Self {
+        Self {
+            separator: separator.into(),
+            max_depth: None,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    fn implies(&self, pattern: &str, needle: &str) -> bool {
+        let mut granted = pattern.split(self.separator.as_str());
+        let mut requested = needle.split(self.separator.as_str());
+        loop {
+            match (granted.next(), requested.next()) {
+                (Some(g), Some(r)) if g == ANY || g == r => {}
+                (Some(_), _) => return false,
+                (None, None) => return true,
+                (None, Some(_)) => {
+                    let below = requested.count() + 1;
+                    return match self.max_depth {
+                        Some(max_depth) => below <= max_depth,
+                        None => true,
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl Matcher for HierarchyMatcher {
+    fn matches(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        Ok(haystack.iter().any(|h| self.implies(h.as_ref(), needle)))
+    }
+
+    fn escape_literal(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        (value != ANY && !value.contains(self.separator.as_str())).then(|| value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn implies(m: &HierarchyMatcher, pattern: &str, needle: &str) -> bool {
+        m.matches('<', '>', &[pattern], needle).unwrap()
+    }
+
+    #[test]
+    fn hierarchy() {
+        let m = HierarchyMatcher::default();
+        assert!(implies(&m, "projects:42", "projects:42"));
+        assert!(implies(&m, "projects:42", "projects:42:datasets:7"));
+        assert!(!implies(&m, "projects:42", "projects:420"));
+        assert!(!implies(&m, "projects:42:datasets", "projects:42"));
+        assert!(implies(&m, "projects:*:datasets", "projects:1:datasets:2"));
+        assert!(!implies(&m, "projects:*:datasets", "projects:1:models:2"));
+
+        let m = HierarchyMatcher::new("/").with_max_depth(1);
+        assert!(implies(&m, "/org/eng", "/org/eng/repo"));
+        assert!(!implies(&m, "/org/eng", "/org/eng/repo/issues"));
+        assert!(!implies(&m, "/org/eng", "/org/ops/repo"));
+    }
+}
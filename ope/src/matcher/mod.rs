@@ -1,4 +1,20 @@
+#[cfg(feature = "async")]
+pub(crate) mod asynchronous;
+pub(crate) mod backend;
+pub(crate) mod bitmap;
+pub(crate) mod cache;
+pub(crate) mod cidr;
+pub(crate) mod exact;
+pub(crate) mod glob;
+pub(crate) mod hierarchy;
 pub(crate) mod reg;
+pub(crate) mod registry;
+pub(crate) mod spiffe;
+
+use std::any::Any;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use crate::Result;
 
@@ -7,7 +23,237 @@ pub trait Matcher {
         &self,
         delimiter_start: char,
         delimiter_end: char,
-        haystack: Vec<String>,
+        haystack: &[impl AsRef<str>],
         needle: &str,
     ) -> Result<bool>;
+
+    fn matches_which(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<usize>> {
+        for (index, h) in haystack.iter().enumerate() {
+            if self.matches(
+                delimiter_start,
+                delimiter_end,
+                std::slice::from_ref(h),
+                needle,
+            )? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<String>>> {
+        Ok(self
+            .matches(delimiter_start, delimiter_end, haystack, needle)?
+            .then(Vec::new))
+    }
+
+    fn named_captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        Ok(self
+            .matches(delimiter_start, delimiter_end, haystack, needle)?
+            .then(Vec::new))
+    }
+
+    fn spans(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        Ok(self
+            .matches(delimiter_start, delimiter_end, haystack, needle)?
+            .then(Vec::new))
+    }
+
+    fn warm(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        _patterns: &[impl AsRef<str>],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn compile(
+        &self,
+        _delimiter_start: char,
+        _patterns: &[impl AsRef<str>],
+    ) -> Option<CompiledPatterns> {
+        None
+    }
+
+    fn matches_compiled(
+        &self,
+        _compiled: &(dyn Any + Send + Sync),
+        _delimiter_start: char,
+        _delimiter_end: char,
+        _haystack: &[impl AsRef<str>],
+        _needle: &str,
+    ) -> Option<Result<bool>> {
+        None
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        let mut buf = [0; 8];
+        let (start, end) = buf.split_at_mut(4);
+        Some(escape_template(
+            delimiter_start.encode_utf8(start),
+            delimiter_end.encode_utf8(end),
+            value,
+        ))
+    }
+
+    fn is_literal(&self, _delimiter_start: char, _pattern: &str) -> bool {
+        false
+    }
+
+    fn indexable(&self) -> bool {
+        false
+    }
+}
+
+pub type CompiledPatterns = Arc<dyn Any + Send + Sync>;
+
+pub(crate) fn next_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+pub(crate) fn escape_template(delimiter_start: &str, delimiter_end: &str, value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(c) = rest.chars().next() {
+        if let Some(delimiter) = [delimiter_start, delimiter_end, "\\"]
+            .into_iter()
+            .find(|d| rest.starts_with(*d))
+        {
+            escaped.push('\\');
+            escaped.push_str(delimiter);
+            rest = &rest[delimiter.len()..];
+            continue;
+        }
+        escaped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    escaped
+}
+
+impl<M: Matcher + ?Sized> Matcher for Arc<M> {
+    fn matches(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        (**self).matches(delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn matches_which(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<usize>> {
+        (**self).matches_which(delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<String>>> {
+        (**self).captures(delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn named_captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        (**self).named_captures(delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn spans(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        (**self).spans(delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn warm(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Result<()> {
+        (**self).warm(delimiter_start, delimiter_end, patterns)
+    }
+
+    fn compile(
+        &self,
+        delimiter_start: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Option<CompiledPatterns> {
+        (**self).compile(delimiter_start, patterns)
+    }
+
+    fn matches_compiled(
+        &self,
+        compiled: &(dyn Any + Send + Sync),
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Option<Result<bool>> {
+        (**self).matches_compiled(compiled, delimiter_start, delimiter_end, haystack, needle)
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        (**self).escape_literal(delimiter_start, delimiter_end, value)
+    }
+
+    fn is_literal(&self, delimiter_start: char, pattern: &str) -> bool {
+        (**self).is_literal(delimiter_start, pattern)
+    }
+
+    fn indexable(&self) -> bool {
+        (**self).indexable()
+    }
 }
@@ -1,22 +1,279 @@
-use std::num::NonZeroUsize;
-use std::{cmp::Ordering, sync::Mutex};
+use std::hash::{BuildHasher, RandomState};
+use std::ops::Range;
+use std::sync::Arc;
 
-use lru::LruCache;
-use regex::Regex;
+use ope_core::{segments, split_name, Segment};
 
-use super::Matcher;
+use super::backend::{Limits, Regex, RegexSet};
+use super::cache::{CacheStats, ShardedCache};
+use super::{escape_template, Matcher};
+use crate::instrument;
 use crate::{Error, Result};
 
 pub struct Regexp {
-    lru: Mutex<LruCache<String, Regex>>,
+    cache: ShardedCache<Arc<str>, Arc<Template>>,
+    sets: Option<ShardedCache<u64, Arc<HaystackSet>>>,
+    state: RandomState,
+    case_insensitive: bool,
+    delimiters: Option<(String, String)>,
+    limits: Limits,
+}
+
+struct HaystackSet {
+    delimiters: (String, String),
+    templates: Vec<String>,
+    set: RegexSet,
+}
+
+struct Template {
+    regex: Regex,
+    groups: Vec<usize>,
+    names: Vec<String>,
 }
 
 impl Regexp {
     pub fn new(cache_size: usize) -> Result<Self> {
-        Ok(Self {
-            lru: Mutex::new(lru::LruCache::new(
-                NonZeroUsize::new(cache_size).ok_or(Error::InvalidCacheSize(cache_size))?,
-            )),
+        RegexpBuilder::new().cache_size(cache_size).build()
+    }
+
+    pub fn builder() -> RegexpBuilder {
+        RegexpBuilder::new()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let stats = self.cache.stats();
+        match &self.sets {
+            Some(sets) => stats + sets.stats(),
+            None => stats,
+        }
+    }
+
+    pub fn clear(&self) {
+        self.cache.clear();
+        if let Some(sets) = &self.sets {
+            sets.clear();
+        }
+    }
+
+    pub fn evict(&self, pattern: &str) -> bool {
+        let mut evicted = self.cache.remove(pattern);
+        if let Some(sets) = &self.sets {
+            evicted |= sets.remove_if(|set| set.templates.iter().any(|tpl| tpl == pattern)) > 0;
+        }
+        evicted
+    }
+
+    fn literal_eq(&self, pattern: &str, needle: &str) -> bool {
+        if self.case_insensitive {
+            return pattern.to_lowercase() == needle.to_lowercase();
+        }
+        pattern.eq(needle)
+    }
+
+    fn delimiters<'a>(
+        &'a self,
+        delimiter_start: char,
+        delimiter_end: char,
+        buf: &'a mut [u8; 8],
+    ) -> (&'a str, &'a str) {
+        if let Some((start, end)) = &self.delimiters {
+            return (start, end);
+        }
+        let (start, end) = buf.split_at_mut(4);
+        (
+            delimiter_start.encode_utf8(start),
+            delimiter_end.encode_utf8(end),
+        )
+    }
+
+    fn has_prefix(&self, tpl: &str, delimiter_start: &str, needle: &str) -> bool {
+        let prefix = tpl.split(delimiter_start).next().unwrap_or_default();
+        let prefix = match prefix.find('\\') {
+            Some(escape) => &prefix[..escape],
+            None => prefix,
+        };
+        if self.case_insensitive {
+            return needle.to_lowercase().starts_with(&prefix.to_lowercase());
+        }
+        needle.starts_with(prefix)
+    }
+
+    fn template(
+        &self,
+        tpl: &str,
+        delimiter_start: &str,
+        delimiter_end: &str,
+    ) -> Result<Arc<Template>> {
+        if let Some(template) = self.cache.get(tpl) {
+            return Ok(template);
+        }
+        let (pattern, groups, names) =
+            build_template(tpl, delimiter_start, delimiter_end, &self.limits)?;
+        let template = Arc::new(Template {
+            regex: self.compile(tpl, &pattern)?,
+            groups,
+            names,
+        });
+        self.cache.put(Arc::from(tpl), template.clone());
+        Ok(template)
+    }
+
+    fn set_matches(
+        &self,
+        sets: &ShardedCache<u64, Arc<HaystackSet>>,
+        delimiter_start: &str,
+        delimiter_end: &str,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        let (mut templates, mut candidate) = (Vec::new(), false);
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(delimiter_start) {
+                if self.literal_eq(h, needle) {
+                    return Ok(true);
+                }
+                continue;
+            }
+            candidate |= self.has_prefix(h, delimiter_start, needle);
+            templates.push(h);
+        }
+        if !candidate {
+            return Ok(false);
+        }
+        let key = self
+            .state
+            .hash_one((delimiter_start, delimiter_end, &templates));
+        if let Some(set) = sets.get(&key) {
+            if set.delimiters.0 == delimiter_start
+                && set.delimiters.1 == delimiter_end
+                && set
+                    .templates
+                    .iter()
+                    .map(String::as_str)
+                    .eq(templates.iter().copied())
+            {
+                return set.set.is_match(needle);
+            }
+        }
+        let patterns = templates
+            .iter()
+            .map(|tpl| {
+                build_template(tpl, delimiter_start, delimiter_end, &self.limits).map(|(p, ..)| p)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        instrument::regex_compiled();
+        let templates: Vec<String> = templates.iter().map(|tpl| tpl.to_string()).collect();
+        let set = self
+            .limits
+            .regex_set(&templates, patterns, self.case_insensitive)?;
+        let matched = set.is_match(needle)?;
+        sets.put(
+            key,
+            Arc::new(HaystackSet {
+                delimiters: (delimiter_start.to_owned(), delimiter_end.to_owned()),
+                templates,
+                set,
+            }),
+        );
+        Ok(matched)
+    }
+
+    fn compile(&self, tpl: &str, pattern: &str) -> Result<Regex> {
+        instrument::regex_compiled();
+        self.limits.regex(tpl, pattern, self.case_insensitive)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegexpBuilder {
+    cache_size: usize,
+    shards: usize,
+    case_insensitive: bool,
+    regex_set: bool,
+    delimiters: Option<(String, String)>,
+    limits: Limits,
+}
+
+impl Default for RegexpBuilder {
+    fn default() -> Self {
+        Self {
+            cache_size: 256,
+            shards: 16,
+            case_insensitive: false,
+            regex_set: false,
+            delimiters: None,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl RegexpBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cache_size(mut self, cache_size: usize) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn regex_set(mut self, regex_set: bool) -> Self {
+        self.regex_set = regex_set;
+        self
+    }
+
+    pub fn delimiters(mut self, start: impl Into<String>, end: impl Into<String>) -> Self {
+        self.delimiters = Some((start.into(), end.into()));
+        self
+    }
+
+    pub fn size_limit(mut self, size_limit: usize) -> Self {
+        self.limits.size_limit = Some(size_limit);
+        self
+    }
+
+    pub fn dfa_size_limit(mut self, dfa_size_limit: usize) -> Self {
+        self.limits.dfa_size_limit = Some(dfa_size_limit);
+        self
+    }
+
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.limits.validation = validation;
+        self
+    }
+
+    pub fn max_pattern_len(mut self, max_pattern_len: usize) -> Self {
+        self.limits.max_pattern_len = Some(max_pattern_len);
+        self
+    }
+
+    pub fn build(self) -> Result<Regexp> {
+        if let Some((start, end)) = &self.delimiters {
+            if start.is_empty() || end.is_empty() || start == end || start.contains('\\') {
+                return Err(Error::InvalidDelimiters(start.clone(), end.clone()));
+            }
+        }
+        let sets = match self.regex_set {
+            true => Some(ShardedCache::new(self.cache_size, self.shards)?),
+            false => None,
+        };
+        Ok(Regexp {
+            cache: ShardedCache::new(self.cache_size, self.shards)?,
+            sets,
+            state: RandomState::new(),
+            case_insensitive: self.case_insensitive,
+            delimiters: self.delimiters,
+            limits: self.limits,
         })
     }
 }
@@ -26,128 +283,373 @@ impl Matcher for Regexp {
         &self,
         delimiter_start: char,
         delimiter_end: char,
-        haystack: Vec<String>,
+        haystack: &[impl AsRef<str>],
         needle: &str,
     ) -> Result<bool> {
-        for h in haystack.iter() {
-            if !h.contains(delimiter_start) {
-                if h.eq(needle) {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        if let Some(sets) = &self.sets {
+            return self.set_matches(sets, start, end, haystack, needle);
+        }
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(start) {
+                if self.literal_eq(h, needle) {
                     return Ok(true);
                 }
                 continue;
             }
-            {
-                let mut rlru = self
-                    .lru
-                    .lock()
-                    .map_err(|err| Error::LockError(format!("{err}")))?;
-                if let Some(reg) = rlru.get(h) {
-                    if reg.is_match(needle) {
-                        return Ok(true);
-                    }
-                    continue;
-                }
-            };
-
-            let pattern = build_regex(h, delimiter_start, delimiter_end)?;
-            let reg = Regex::new(pattern.as_str()).map_err(Error::CompileRegexError)?;
-            {
-                let mut wlru = self
-                    .lru
-                    .lock()
-                    .map_err(|err| Error::LockError(format!("{err}")))?;
-                wlru.put(h.to_owned(), reg.clone());
-            };
-
-            if reg.is_match(needle) {
+            if !self.has_prefix(h, start, needle) {
+                continue;
+            }
+            if self.template(h, start, end)?.regex.is_match(needle)? {
                 return Ok(true);
             }
         }
         Ok(false)
     }
-}
 
-fn delimiter_indices(s: &str, delimiter_start: char, delimiter_end: char) -> Result<Vec<usize>> {
-    let (mut level, mut idx) = (0, 0);
-    let mut idxs: Vec<usize> = Vec::new();
-    for (i, value) in s.chars().enumerate() {
-        if value == delimiter_start {
-            level += 1;
-            if level == 1 {
-                idx = i;
+    fn matches_which(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<usize>> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        for (index, h) in haystack.iter().map(AsRef::as_ref).enumerate() {
+            if !h.contains(start) {
+                if self.literal_eq(h, needle) {
+                    return Ok(Some(index));
+                }
+                continue;
             }
-        } else if value == delimiter_end {
-            level -= 1;
-            match level.cmp(&0) {
-                Ordering::Less => {
-                    return Err(Error::UnbalancedBraces(s.to_owned()));
+            if !self.has_prefix(h, start, needle) {
+                continue;
+            }
+            if self.template(h, start, end)?.regex.is_match(needle)? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<String>>> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(start) {
+                if self.literal_eq(h, needle) {
+                    return Ok(Some(Vec::new()));
                 }
-                Ordering::Equal => {
-                    idxs.push(idx);
-                    idxs.push(i + 1);
+                continue;
+            }
+            if !self.has_prefix(h, start, needle) {
+                continue;
+            }
+            let template = self.template(h, start, end)?;
+            if let Some(caps) = template.regex.captures(needle)? {
+                return Ok(Some(
+                    template
+                        .groups
+                        .iter()
+                        .map(|i| {
+                            caps.get(*i)
+                                .cloned()
+                                .flatten()
+                                .map(|v| needle[v].to_owned())
+                                .unwrap_or_default()
+                        })
+                        .collect(),
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    fn named_captures(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(start) {
+                if self.literal_eq(h, needle) {
+                    return Ok(Some(Vec::new()));
+                }
+                continue;
+            }
+            if !self.has_prefix(h, start, needle) {
+                continue;
+            }
+            let template = self.template(h, start, end)?;
+            if template.names.is_empty() {
+                if template.regex.is_match(needle)? {
+                    return Ok(Some(Vec::new()));
+                }
+                continue;
+            }
+            if let Some(caps) = template.regex.captures(needle)? {
+                return Ok(Some(
+                    template
+                        .names
+                        .iter()
+                        .filter_map(|name| {
+                            let range = caps.get(template.regex.group(name)?)?.clone()?;
+                            Some((name.clone(), needle[range].to_owned()))
+                        })
+                        .collect(),
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    fn spans(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        for h in haystack.iter().map(AsRef::as_ref) {
+            if !h.contains(start) {
+                if self.literal_eq(h, needle) {
+                    return Ok(Some(Vec::new()));
                 }
-                Ordering::Greater => {}
+                continue;
+            }
+            if !self.has_prefix(h, start, needle) {
+                continue;
+            }
+            let template = self.template(h, start, end)?;
+            if let Some(caps) = template.regex.captures(needle)? {
+                return Ok(Some(
+                    template
+                        .groups
+                        .iter()
+                        .filter_map(|i| caps.get(*i).cloned().flatten())
+                        .collect(),
+                ));
             }
         }
+        Ok(None)
     }
-    if level != 0 {
-        return Err(Error::UnbalancedBraces(s.to_owned()));
+
+    fn warm(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        patterns: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        for p in patterns.iter().map(AsRef::as_ref) {
+            if p.contains(start) {
+                self.template(p, start, end)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn escape_literal(
+        &self,
+        delimiter_start: char,
+        delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        let mut buf = [0; 8];
+        let (start, end) = self.delimiters(delimiter_start, delimiter_end, &mut buf);
+        Some(escape_template(start, end, value))
+    }
+
+    fn is_literal(&self, delimiter_start: char, pattern: &str) -> bool {
+        let mut buf = [0; 8];
+        let (start, _) = self.delimiters(delimiter_start, delimiter_start, &mut buf);
+        !self.case_insensitive && !pattern.contains(start)
+    }
+
+    fn indexable(&self) -> bool {
+        !self.case_insensitive && self.delimiters.is_none()
     }
-    Ok(idxs)
 }
 
-fn build_regex(tpl: &str, delimiter_start: char, delimiter_end: char) -> Result<String> {
-    let idx = delimiter_indices(tpl, delimiter_start, delimiter_end)?;
-    let mut buffer = String::new();
-    buffer.push('^');
-    let (mut i, mut end) = (0, 0);
-    loop {
-        if i >= idx.len() {
-            break;
-        }
-        let temp_id = match idx.get(i) {
-            Some(v) => v.to_owned(),
-            None => return Err(Error::NotIndex(format!("not index {i} in {idx:?}"))),
-        };
-        let raw = match tpl.get(end..temp_id) {
-            Some(v) => v,
-            None => {
-                return Err(Error::NotIndex(format!(
-                    "not index {end} to {temp_id} in {tpl:?}"
-                )));
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Validation {
+    Trusted,
+    #[default]
+    Compile,
+    Strict,
+}
+
+#[derive(Debug, Default)]
+struct Shape {
+    captures: usize,
+    nested_quantifier: bool,
+}
+
+fn shape(patt: &str) -> Shape {
+    let chars: Vec<char> = patt.chars().collect();
+    let (mut shape, mut groups, mut i) = (Shape::default(), vec![false], 0);
+    let (mut wildcard, mut closed) = (false, false);
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        let (atom, group) = match c {
+            '\\' => {
+                let class = matches!(
+                    chars.get(i),
+                    Some('w' | 'W' | 's' | 'S' | 'd' | 'D' | 'p' | 'P')
+                );
+                i += 1;
+                if class && chars.get(i) == Some(&'{') {
+                    while i < chars.len() && chars[i] != '}' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                (class, false)
             }
+            '.' => (true, false),
+            '[' => {
+                let mut depth = 1;
+                if chars.get(i) == Some(&'^') {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&']') {
+                    i += 1;
+                }
+                while i < chars.len() && depth > 0 {
+                    match chars[i] {
+                        '\\' => i += 1,
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                (true, false)
+            }
+            '(' => {
+                let capturing = match (chars.get(i), chars.get(i + 1), chars.get(i + 2)) {
+                    (Some('?'), Some('P'), Some('<')) => true,
+                    (Some('?'), Some('<'), Some(next)) => !matches!(next, '=' | '!'),
+                    (Some('?'), ..) => false,
+                    _ => true,
+                };
+                if capturing {
+                    shape.captures += 1;
+                }
+                groups.push(false);
+                (false, false)
+            }
+            ')' if groups.len() > 1 => {
+                let inner = groups.pop().unwrap_or_default();
+                if let Some(parent) = groups.last_mut() {
+                    *parent |= inner;
+                }
+                (false, inner)
+            }
+            '*' | '+' | '{' => {
+                let unbounded = match c {
+                    '{' => {
+                        let close = chars[i..].iter().position(|c| *c == '}');
+                        let bound = close.is_some_and(|close| chars[i + close - 1] == ',');
+                        i += close.map(|close| close + 1).unwrap_or_default();
+                        bound
+                    }
+                    _ => true,
+                };
+                if unbounded && wildcard {
+                    if let Some(current) = groups.last_mut() {
+                        *current = true;
+                    }
+                }
+                if unbounded && closed {
+                    shape.nested_quantifier = true;
+                }
+                (false, false)
+            }
+            _ => (false, false),
         };
+        (wildcard, closed) = (atom, group);
+    }
+    shape
+}
 
-        end = match idx.get(i + 1) {
-            Some(v) => v.to_owned(),
-            None => return Err(Error::NotIndex(format!("not index {} in {:?}", i + 1, idx))),
-        };
-        let patt = match tpl.get(temp_id + 1..end - 1) {
-            Some(v) => v,
-            None => {
-                return Err(Error::NotIndex(format!(
-                    "not index {} to {} in {:?}",
-                    temp_id + 1,
-                    end - 1,
-                    tpl
-                )))
+pub(crate) fn delimiter_indices(
+    s: &str,
+    delimiter_start: char,
+    delimiter_end: char,
+) -> Result<Vec<usize>> {
+    Ok(ope_core::delimiter_indices(
+        s,
+        delimiter_start,
+        delimiter_end,
+    )?)
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub(crate) fn build_regex(tpl: &str, delimiter_start: char, delimiter_end: char) -> Result<String> {
+    let (start, end) = (delimiter_start.to_string(), delimiter_end.to_string());
+    build_template(tpl, &start, &end, &Limits::default()).map(|(pattern, ..)| pattern)
+}
+
+fn build_template(
+    tpl: &str,
+    delimiter_start: &str,
+    delimiter_end: &str,
+    limits: &Limits,
+) -> Result<(String, Vec<usize>, Vec<String>)> {
+    limits.check(tpl)?;
+    let (mut groups, mut names) = (Vec::new(), Vec::new());
+    let mut group = 1;
+    let mut buffer = String::new();
+    buffer.push('^');
+    for segment in segments(tpl, delimiter_start, delimiter_end)? {
+        match segment {
+            Segment::Literal(raw) => buffer.push_str(&ope_core::escape(&raw)),
+            Segment::Template(patt) => {
+                let (name, patt) = split_name(&patt);
+                match name {
+                    Some(name) => {
+                        buffer.push_str(format!("(?P<{name}>{patt})").as_str());
+                        names.push(name.to_owned());
+                    }
+                    None => buffer.push_str(format!("({patt})").as_str()),
+                }
+                groups.push(group);
+                group += match limits.validation {
+                    Validation::Trusted => 1 + shape(patt).captures,
+                    Validation::Compile => limits
+                        .regex(tpl, format!("^{patt}$").as_str(), false)?
+                        .captures_len(),
+                    Validation::Strict => {
+                        if shape(patt).nested_quantifier {
+                            return Err(Error::PatternTooComplex(tpl.to_owned()));
+                        }
+                        limits
+                            .regex(tpl, format!("^{patt}$").as_str(), false)?
+                            .captures_len()
+                    }
+                };
             }
-        };
-        buffer.push_str(format!("{}({})", regex::escape(raw), patt).as_str());
-        Regex::new(format!("^{patt}$").as_str()).map_err(Error::CompileRegexError)?;
-        i += 2;
-    }
-    let raw = match tpl.get(end..) {
-        Some(v) => v,
-        None => {
-            return Err(Error::NotIndex(format!(
-                "not index {end} to end in {tpl:?}"
-            )))
-        }
-    };
-    buffer.push_str(regex::escape(raw).as_str());
+        }
+    }
     buffer.push('$');
-    Ok(buffer)
+    Ok((buffer, groups, names))
 }
 
 #[cfg(test)]
@@ -166,4 +668,435 @@ mod tests {
             "^(create|delete)$".to_owned()
         )
     }
+
+    #[test]
+    fn case_insensitive() {
+        let reg = Regexp::builder().case_insensitive(true).build().unwrap();
+        let haystack = vec!["Peter".to_owned(), "user:<zac|ken>".to_owned()];
+        assert!(reg.matches('<', '>', &haystack, "PETER").unwrap());
+        assert!(reg.matches('<', '>', &haystack, "User:KEN").unwrap());
+
+        let reg = Regexp::new(8).unwrap();
+        assert!(!reg.matches('<', '>', &haystack, "PETER").unwrap());
+        assert!(!reg.matches('<', '>', &haystack, "User:KEN").unwrap());
+    }
+
+    #[test]
+    fn prefix() {
+        let reg = Regexp::new(8).unwrap();
+        let haystack = vec!["articles:<[0-9]+>".to_owned(), "<.*>:files".to_owned()];
+        assert!(!reg.matches('<', '>', &haystack, "users:1").unwrap());
+        assert!(reg.cache.get("articles:<[0-9]+>").is_none());
+        assert!(reg.matches('<', '>', &haystack, "articles:1").unwrap());
+        assert!(reg.matches('<', '>', &haystack, "users:files").unwrap());
+
+        let reg = Regexp::builder().case_insensitive(true).build().unwrap();
+        assert!(reg
+            .matches('<', '>', &["Articles:<.*>"], "ARTICLES:1")
+            .unwrap());
+    }
+
+    #[test]
+    fn escapes_and_delimiters() {
+        assert_eq!(
+            build_regex(r"a\<b\><[0-9]+>", '<', '>').unwrap(),
+            r"^a<b>([0-9]+)$"
+        );
+        assert_eq!(build_regex(r"<[\<]+>", '<', '>').unwrap(), "^([<]+)$");
+        assert!(build_regex(r"a\<", '<', '>').is_ok());
+        assert!(build_regex("a>", '<', '>').is_err());
+        assert_eq!(
+            build_regex(r"a\\<[0-9]+>", '<', '>').unwrap(),
+            r"^a\\([0-9]+)$"
+        );
+        assert_eq!(build_regex(r"<a\\>", '<', '>').unwrap(), r"^(a\\)$");
+        assert_eq!(
+            delimiter_indices(r"a\<b<c>\>", '<', '>').unwrap(),
+            vec![4, 7]
+        );
+
+        let reg = Regexp::new(8).unwrap();
+        let haystack = vec![r"tag:\<<[a-z]+>\>".to_owned()];
+        assert!(reg.matches('<', '>', &haystack, "tag:<abc>").unwrap());
+        assert!(!reg.matches('<', '>', &haystack, "tag:abc").unwrap());
+
+        let reg = Regexp::builder().delimiters("{{", "}}").build().unwrap();
+        let haystack = vec!["articles:{{[0-9]+}}:<draft>".to_owned()];
+        assert!(reg
+            .matches('<', '>', &haystack, "articles:1:<draft>")
+            .unwrap());
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "articles:42:<draft>")
+                .unwrap(),
+            Some(vec!["42".to_owned()])
+        );
+        assert!(!reg
+            .matches('<', '>', &haystack, "articles:x:<draft>")
+            .unwrap());
+        assert!(Regexp::builder().delimiters("%", "%").build().is_err());
+    }
+
+    #[test]
+    fn non_ascii() {
+        let pattern = "资源:文章:<[0-9]+>:ü<.*>";
+        let idxs = delimiter_indices(pattern, '<', '>').unwrap();
+        assert_eq!(idxs, vec![14, 22, 25, 29]);
+        assert_eq!(&pattern[idxs[0]..idxs[1]], "<[0-9]+>");
+        assert_eq!(&pattern[idxs[2]..idxs[3]], "<.*>");
+        assert_eq!(
+            delimiter_indices(r"é\<<ß>", '《', '》').unwrap(),
+            Vec::<usize>::new()
+        );
+        assert_eq!(delimiter_indices("《a》", '《', '》').unwrap(), vec![0, 7]);
+
+        let reg = Regexp::new(8).unwrap();
+        let haystack = [pattern];
+        assert!(reg
+            .matches('<', '>', &haystack, "资源:文章:42:über")
+            .unwrap());
+        assert!(!reg
+            .matches('<', '>', &haystack, "资源:文章:x:über")
+            .unwrap());
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "资源:文章:7:ü").unwrap(),
+            Some(vec!["7".to_owned(), String::new()])
+        );
+    }
+
+    #[test]
+    fn named_captures() {
+        assert_eq!(
+            build_regex("articles:<id:[0-9]+>:<.*>", '<', '>').unwrap(),
+            "^articles:(?P<id>[0-9]+):(.*)$"
+        );
+        let reg = Regexp::new(8).unwrap();
+        let haystack = vec![
+            "peter".to_owned(),
+            "tenants:<tenant:[a-z]+>:articles:<id:[0-9]+>".to_owned(),
+        ];
+        assert_eq!(
+            reg.named_captures('<', '>', &haystack, "tenants:acme:articles:42")
+                .unwrap(),
+            Some(vec![
+                ("tenant".to_owned(), "acme".to_owned()),
+                ("id".to_owned(), "42".to_owned())
+            ])
+        );
+        assert_eq!(
+            reg.named_captures('<', '>', &haystack, "peter").unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "tenants:acme:articles:42")
+                .unwrap(),
+            Some(vec!["acme".to_owned(), "42".to_owned()])
+        );
+        assert_eq!(
+            reg.named_captures('<', '>', &haystack, "tenants:1:articles:42")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn regex_set() {
+        let reg = Regexp::builder().regex_set(true).build().unwrap();
+        let haystack = vec![
+            "peter".to_owned(),
+            "articles:<[0-9]+>".to_owned(),
+            "articles:<[a-z]+>:draft".to_owned(),
+        ];
+        for (needle, expected) in [
+            ("peter", true),
+            ("articles:1", true),
+            ("articles:abc:draft", true),
+            ("articles:abc", false),
+            ("users:1", false),
+        ] {
+            assert_eq!(
+                reg.matches('<', '>', &haystack, needle).unwrap(),
+                expected,
+                "{needle}"
+            );
+        }
+        assert!(reg.matches('<', '>', &["<(>"], "x").is_err());
+    }
+
+    #[test]
+    fn matches_which() {
+        use crate::matcher::{exact::Exact, glob::Glob};
+
+        let haystack = ["peter", "articles:<[0-9]+>", "articles:<.*>", "articles:42"];
+        let reg = Regexp::builder().regex_set(true).build().unwrap();
+        assert_eq!(
+            reg.matches_which('<', '>', &haystack, "articles:42")
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            reg.matches_which('<', '>', &haystack, "articles:x")
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(reg.matches_which('<', '>', &haystack, "max").unwrap(), None);
+
+        let exact = Exact::new(8, Regexp::new(8).unwrap()).unwrap();
+        assert_eq!(
+            exact
+                .matches_which('<', '>', &haystack, "articles:42")
+                .unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            exact.matches_which('<', '>', &haystack, "peter").unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            Glob.matches_which('<', '>', &["a", "b*", "*"], "bc")
+                .unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let haystack = ["tenants:<(?P<x>[a-z])(?:-[a-z])*>:<id:(a|b)?[0-9]+>:<[\\(]+>"];
+        let needle = "tenants:a-b:a42:((";
+        let expected = Regexp::new(8)
+            .unwrap()
+            .captures('<', '>', &haystack, needle)
+            .unwrap();
+        assert_eq!(
+            expected,
+            Some(vec!["a-b".to_owned(), "a42".to_owned(), "((".to_owned()])
+        );
+        for validation in [Validation::Trusted, Validation::Strict] {
+            let reg = Regexp::builder().validation(validation).build().unwrap();
+            assert_eq!(reg.captures('<', '>', &haystack, needle).unwrap(), expected);
+        }
+
+        let trusted = Regexp::builder()
+            .validation(Validation::Trusted)
+            .build()
+            .unwrap();
+        assert!(trusted.matches('<', '>', &["<(>"], "x").is_err());
+
+        let strict = Regexp::builder()
+            .validation(Validation::Strict)
+            .build()
+            .unwrap();
+        for tpl in ["<(.*)*>", r"<(a|\w+)+>", "<((x.+)y)+>", "<(?:[a-z]*){2,}>"] {
+            assert!(
+                matches!(
+                    strict.matches('<', '>', &[tpl], "x"),
+                    Err(Error::PatternTooComplex(_))
+                ),
+                "{tpl}"
+            );
+            assert!(trusted.matches('<', '>', &[tpl], "x").is_ok(), "{tpl}");
+        }
+        assert!(strict
+            .matches('<', '>', &["<(.*)?>:<([0-9]+)>:<(ab)+>"], "x:1:abab")
+            .unwrap());
+    }
+
+    #[test]
+    fn limits() {
+        let reg = Regexp::builder()
+            .size_limit(1 << 12)
+            .max_pattern_len(32)
+            .build()
+            .unwrap();
+        assert!(reg
+            .matches('<', '>', &["articles:<[0-9]+>".to_owned()], "articles:1")
+            .unwrap());
+        assert!(matches!(
+            reg.matches('<', '>', &[r"<\w{100}>"], "x"),
+            Err(Error::PatternTooComplex(_))
+        ));
+        assert!(matches!(
+            reg.matches('<', '>', &[format!("<{}>", "a".repeat(40))], "x"),
+            Err(Error::PatternTooComplex(_))
+        ));
+
+        let reg = Regexp::builder()
+            .regex_set(true)
+            .size_limit(1 << 12)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            reg.matches('<', '>', &[r"<\w{100}>"], "x"),
+            Err(Error::PatternTooComplex(_))
+        ));
+    }
+
+    #[test]
+    fn spans() {
+        let reg = Regexp::new(8).unwrap();
+        let haystack = ["peter", "tenants:<[a-z]+>:articles:<(a|b)?[0-9]+>"];
+        assert_eq!(
+            reg.spans('<', '>', &haystack, "tenants:äcme:articles:42")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            reg.spans('<', '>', &haystack, "tenants:acme:articles:42")
+                .unwrap(),
+            Some(vec![8..12, 22..24])
+        );
+        assert_eq!(
+            reg.spans('<', '>', &haystack, "peter").unwrap(),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let reg = Regexp::builder().cache_size(1).build().unwrap();
+        assert!(reg.matches('<', '>', &["a:<.*>"], "a:1").unwrap());
+        assert!(reg.matches('<', '>', &["a:<.*>"], "a:2").unwrap());
+        assert!(reg.matches('<', '>', &["b:<.*>"], "b:1").unwrap());
+        assert_eq!(
+            reg.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                evictions: 1,
+                size: 1,
+            }
+        );
+        assert!(reg.evict("b:<.*>"));
+        assert!(!reg.evict("b:<.*>"));
+        assert_eq!(reg.stats().size, 0);
+        assert!(reg.matches('<', '>', &["a:<.*>"], "a:1").unwrap());
+        reg.clear();
+        assert_eq!(reg.stats().size, 0);
+
+        let reg = Regexp::builder().regex_set(true).build().unwrap();
+        assert!(reg.matches('<', '>', &["a:<.*>", "b:<.*>"], "a:1").unwrap());
+        assert_eq!(reg.stats().size, 1);
+        assert!(!reg.evict("c:<.*>"));
+        assert!(reg.evict("b:<.*>"));
+        assert_eq!(reg.stats().size, 0);
+    }
+
+    #[test]
+    fn warm() {
+        let reg = Regexp::new(8).unwrap();
+        reg.warm('<', '>', &["literal", "user:<\\d+>"]).unwrap();
+        assert!(reg.cache.get("user:<\\d+>").is_some());
+        assert!(reg.cache.get("literal").is_none());
+        assert!(reg.warm('<', '>', &["<(>"]).is_err());
+    }
+
+    #[test]
+    fn captures() {
+        let reg = Regexp::new(8).unwrap();
+        let haystack = vec![
+            "resources:<[0-9]+>:files".to_owned(),
+            "users:<(a|b)c>:<.*>".to_owned(),
+        ];
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "resources:42:files")
+                .unwrap(),
+            Some(vec!["42".to_owned()])
+        );
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "users:bc:x:y").unwrap(),
+            Some(vec!["bc".to_owned(), "x:y".to_owned()])
+        );
+        assert_eq!(
+            reg.captures('<', '>', &haystack, "resources:x:files")
+                .unwrap(),
+            None
+        );
+    }
+
+    mod equivalence {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        const TEMPLATES: [&str; 5] = ["[0-9]+", ".*", "a|b", "[a-c]{1,2}", "c?"];
+
+        #[derive(Debug, Clone)]
+        enum Part {
+            Literal(String),
+            Template(usize),
+        }
+
+        fn parts() -> impl Strategy<Value = Vec<Part>> {
+            prop::collection::vec(
+                prop_oneof![
+                    "[a-c0-9:.]{1,3}".prop_map(Part::Literal),
+                    (0..TEMPLATES.len()).prop_map(Part::Template),
+                ],
+                1..5,
+            )
+        }
+
+        fn template(parts: &[Part]) -> String {
+            parts
+                .iter()
+                .map(|part| match part {
+                    Part::Literal(raw) => raw.clone(),
+                    Part::Template(i) => format!("<{}>", TEMPLATES[*i]),
+                })
+                .collect()
+        }
+
+        fn oracle(parts: &[Part]) -> regex::Regex {
+            let body: String = parts
+                .iter()
+                .map(|part| match part {
+                    Part::Literal(raw) => regex::escape(raw),
+                    Part::Template(i) => format!("(?:{})", TEMPLATES[*i]),
+                })
+                .collect();
+            regex::Regex::new(&format!("^{body}$")).unwrap()
+        }
+
+        fn needle(parts: &[Part]) -> impl Strategy<Value = String> {
+            let pieces: Vec<_> = parts
+                .iter()
+                .map(|part| match part {
+                    Part::Literal(raw) => prop_oneof![
+                        4 => Just(raw.clone()),
+                        1 => "[a-c0-9:.]{0,2}",
+                    ]
+                    .boxed(),
+                    Part::Template(_) => "[a-c0-9:.]{0,3}".boxed(),
+                })
+                .collect();
+            pieces.prop_map(|pieces| pieces.concat())
+        }
+
+        fn case() -> impl Strategy<Value = (Vec<Vec<Part>>, String)> {
+            prop::collection::vec(parts(), 1..4).prop_flat_map(|haystack| {
+                let needle = needle(&haystack[0]);
+                (Just(haystack), needle)
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn agrees_with_oracle((haystack, needle) in case()) {
+                let templates: Vec<_> = haystack.iter().map(|parts| template(parts)).collect();
+                let expected = haystack.iter().any(|parts| oracle(parts).is_match(&needle));
+
+                let warm = Regexp::new(64).unwrap();
+                prop_assert_eq!(warm.matches('<', '>', &templates, &needle).unwrap(), expected);
+                prop_assert_eq!(warm.matches('<', '>', &templates, &needle).unwrap(), expected);
+
+                let cold = Regexp::new(1).unwrap();
+                for _ in 0..2 {
+                    prop_assert_eq!(cold.matches('<', '>', &templates, &needle).unwrap(), expected);
+                }
+
+                let set = Regexp::builder().regex_set(true).build().unwrap();
+                prop_assert_eq!(set.matches('<', '>', &templates, &needle).unwrap(), expected);
+                prop_assert_eq!(set.matches('<', '>', &templates, &needle).unwrap(), expected);
+            }
+        }
+    }
 }
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+
+use super::{
+    cidr::CidrMatcher, glob::Glob, hierarchy::HierarchyMatcher, reg::Regexp, spiffe::SpiffeMatcher,
+    Matcher,
+};
+use crate::{Error, Result};
+
+pub(crate) trait DynMatcher: Send + Sync {
+    fn matches(&self, start: char, end: char, haystack: &[String], needle: &str) -> Result<bool>;
+
+    fn named_captures(
+        &self,
+        start: char,
+        end: char,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>>;
+
+    fn spans(
+        &self,
+        start: char,
+        end: char,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>>;
+
+    fn warm(&self, start: char, end: char, patterns: &[String]) -> Result<()>;
+
+    fn escape_literal(&self, start: char, end: char, value: &str) -> Option<String>;
+}
+
+impl<M: Matcher + Send + Sync> DynMatcher for M {
+    fn matches(&self, start: char, end: char, haystack: &[String], needle: &str) -> Result<bool> {
+        Matcher::matches(self, start, end, haystack, needle)
+    }
+
+    fn named_captures(
+        &self,
+        start: char,
+        end: char,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        Matcher::named_captures(self, start, end, haystack, needle)
+    }
+
+    fn spans(
+        &self,
+        start: char,
+        end: char,
+        haystack: &[String],
+        needle: &str,
+    ) -> Result<Option<Vec<Range<usize>>>> {
+        Matcher::spans(self, start, end, haystack, needle)
+    }
+
+    fn warm(&self, start: char, end: char, patterns: &[String]) -> Result<()> {
+        Matcher::warm(self, start, end, patterns)
+    }
+
+    fn escape_literal(&self, start: char, end: char, value: &str) -> Option<String> {
+        Matcher::escape_literal(self, start, end, value)
+    }
+}
+
+struct Literal;
+
+impl Matcher for Literal {
+    fn matches(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        Ok(haystack.iter().any(|h| h.as_ref() == needle))
+    }
+
+    fn escape_literal(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        Some(value.to_owned())
+    }
+}
+
+pub struct MatcherRegistry {
+    matchers: RwLock<HashMap<String, Arc<dyn DynMatcher>>>,
+}
+
+impl Default for MatcherRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MatcherRegistry {
+    pub fn new() -> Self {
+        let mut matchers: HashMap<String, Arc<dyn DynMatcher>> = HashMap::new();
+        if let Ok(regexp) = Regexp::new(256) {
+            matchers.insert("regexp".to_owned(), Arc::new(regexp));
+        }
+        matchers.insert("exact".to_owned(), Arc::new(Literal));
+        matchers.insert("glob".to_owned(), Arc::new(Glob::new()));
+        matchers.insert("cidr".to_owned(), Arc::new(CidrMatcher::new()));
+        matchers.insert(
+            "hierarchy".to_owned(),
+            Arc::new(HierarchyMatcher::default()),
+        );
+        matchers.insert("spiffe".to_owned(), Arc::new(SpiffeMatcher::new()));
+        Self {
+            matchers: RwLock::new(matchers),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            matchers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn register<M>(&self, name: &str, matcher: M) -> Result<()>
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        let mut matchers = self
+            .matchers
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        matchers.insert(name.to_owned(), Arc::new(matcher));
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> Result<bool> {
+        let matchers = self
+            .matchers
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        Ok(matchers.contains_key(name))
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<Arc<dyn DynMatcher>> {
+        let matchers = self
+            .matchers
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        matchers
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::NotFoundMatcher(name.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Context, Enforcer, Error, MatcherRegistry, Policy, Regexp, Request};
+
+    #[test]
+    fn per_field_matchers() {
+        let policy: Policy = serde_json::from_value(serde_json::json!({
+            "id": "files",
+            "description": "",
+            "statements": [{
+                "effect": "Allow",
+                "subjects": ["<alice|bob>"],
+                "actions": ["<read|list>"],
+                "resources": ["files/**/*.txt"],
+                "subject_matcher": "exact",
+                "resource_matcher": "glob",
+                "conditions": null,
+                "meta": null
+            }]
+        }))
+        .unwrap();
+        let statement = &policy.statements[0];
+        assert_eq!(statement.resource_matcher.as_deref(), Some("glob"));
+        let roundtrip: Policy =
+            serde_json::from_str(&serde_json::to_string(&policy).unwrap()).unwrap();
+        assert_eq!(roundtrip.statements, policy.statements);
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy.clone()]);
+        let request = |subject: &str, resource: &str| Request {
+            resource: resource.to_owned(),
+            action: "read".to_owned(),
+            subject: subject.to_owned(),
+            context: Context::new(),
+        };
+        assert!(enforcer
+            .is_allowed(&request("<alice|bob>", "files/a/b/notes.txt"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&request("alice", "files/a/b/notes.txt"))
+            .is_err());
+        assert!(enforcer
+            .is_allowed(&request("<alice|bob>", "files/a/notes.md"))
+            .is_err());
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![policy])
+            .with_matchers(MatcherRegistry::empty());
+        assert!(matches!(
+            enforcer.is_allowed(&request("<alice|bob>", "files/a/b/notes.txt")),
+            Err(Error::NotFoundMatcher(_))
+        ));
+    }
+}
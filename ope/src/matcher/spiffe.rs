@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use super::Matcher;
+use crate::{Error, Result};
+
+const SCHEME: &str = "spiffe://";
+const ANY: &str = "*";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpiffeId {
+    trust_domain: String,
+    path: Vec<String>,
+}
+
+impl SpiffeId {
+    pub fn trust_domain(&self) -> &str {
+        &self.trust_domain
+    }
+
+    pub fn path(&self) -> impl Iterator<Item = &str> {
+        self.path.iter().map(String::as_str)
+    }
+
+    fn parse(id: &str, wildcard: bool) -> Option<Self> {
+        let scheme = id.get(..SCHEME.len())?;
+        if !scheme.eq_ignore_ascii_case(SCHEME) {
+            return None;
+        }
+        let rest = &id[SCHEME.len()..];
+        let (trust_domain, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        let trust_domain = trust_domain.to_ascii_lowercase();
+        if trust_domain.is_empty()
+            || !trust_domain
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c))
+        {
+            return None;
+        }
+        let path = match path {
+            Some(path) => path
+                .split('/')
+                .map(|segment| {
+                    let valid = !segment.is_empty()
+                        && segment != "."
+                        && segment != ".."
+                        && (segment
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "-._".contains(c))
+                            || (wildcard && segment == ANY));
+                    valid.then(|| segment.to_owned())
+                })
+                .collect::<Option<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Some(Self { trust_domain, path })
+    }
+}
+
+impl FromStr for SpiffeId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self> {
+        Self::parse(id, false).ok_or_else(|| Error::InvalidSpiffeId(id.to_owned()))
+    }
+}
+
+impl std::fmt::Display for SpiffeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{SCHEME}{}", self.trust_domain)?;
+        for segment in self.path.iter() {
+            write!(f, "/{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpiffeMatcher {
+    exact: bool,
+}
+
+impl SpiffeMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    fn implies(&self, pattern: &SpiffeId, id: &SpiffeId) -> bool {
+        if pattern.trust_domain != id.trust_domain {
+            return false;
+        }
+        if pattern.path.is_empty() {
+            return true;
+        }
+        if pattern.path.len() > id.path.len() || (self.exact && pattern.path.len() != id.path.len())
+        {
+            return false;
+        }
+        pattern
+            .path
+            .iter()
+            .zip(id.path.iter())
+            .all(|(granted, requested)| granted == ANY || granted == requested)
+    }
+}
+
+impl Matcher for SpiffeMatcher {
+    fn matches(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        haystack: &[impl AsRef<str>],
+        needle: &str,
+    ) -> Result<bool> {
+        let Some(id) = SpiffeId::parse(needle, false) else {
+            return Ok(false);
+        };
+        for h in haystack.iter().map(AsRef::as_ref) {
+            let pattern =
+                SpiffeId::parse(h, true).ok_or_else(|| Error::InvalidSpiffeId(h.to_owned()))?;
+            if self.implies(&pattern, &id) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn escape_literal(
+        &self,
+        _delimiter_start: char,
+        _delimiter_end: char,
+        value: &str,
+    ) -> Option<String> {
+        (value != ANY && !value.contains('/')).then(|| value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn implies(m: &SpiffeMatcher, pattern: &str, needle: &str) -> bool {
+        m.matches('<', '>', &[pattern], needle).unwrap()
+    }
+
+    #[test]
+    fn spiffe() {
+        let id: SpiffeId = "SPIFFE://Example.org/ns/prod/sa/web".parse().unwrap();
+        assert_eq!(id.trust_domain(), "example.org");
+        assert_eq!(id.path().collect::<Vec<_>>(), ["ns", "prod", "sa", "web"]);
+        assert_eq!(id.to_string(), "spiffe://example.org/ns/prod/sa/web");
+        for invalid in [
+            "https://example.org/web",
+            "spiffe://",
+            "spiffe://example.org/",
+            "spiffe://example.org//web",
+            "spiffe://example.org/ns/../web",
+            "spiffe://user@example.org/web",
+            "spiffe://example.org:8443/web",
+            "spiffe://example.org/web?x=1",
+        ] {
+            assert!(invalid.parse::<SpiffeId>().is_err(), "{invalid}");
+        }
+
+        let m = SpiffeMatcher::new();
+        let web = "spiffe://example.org/ns/prod/sa/web";
+        assert!(implies(&m, "spiffe://example.org", web));
+        assert!(implies(&m, "spiffe://EXAMPLE.org/ns/prod", web));
+        assert!(implies(&m, "spiffe://example.org/ns/*/sa/web", web));
+        assert!(!implies(&m, "spiffe://example.org/ns/pro", web));
+        assert!(!implies(&m, "spiffe://example.org.evil", web));
+        assert!(!implies(&m, "spiffe://evil.org/ns/prod", web));
+        assert!(!implies(&m, web, "spiffe://example.org/ns/prod"));
+        assert!(!implies(&m, "spiffe://example.org", "not-an-id"));
+        assert!(m
+            .matches('<', '>', &["spiffe://example.org/a//b"], web)
+            .is_err());
+
+        let m = SpiffeMatcher::new().exact(true);
+        assert!(implies(&m, web, web));
+        assert!(implies(&m, "spiffe://example.org", web));
+        assert!(!implies(&m, "spiffe://example.org/ns/prod", web));
+    }
+}
@@ -0,0 +1,254 @@
+use serde::Serialize;
+use serde_json::value::{to_raw_value, RawValue};
+use validator::Validate;
+
+use super::{Policy, Severity};
+use crate::condition::JsonCondition;
+use crate::statement::{Effect, Statement};
+use crate::{Error, Result};
+
+pub struct PolicyBuilder {
+    id: String,
+    description: String,
+    statements: Vec<Statement>,
+    meta: Option<Box<RawValue>>,
+    priority: i32,
+    error: Option<Error>,
+}
+
+impl PolicyBuilder {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            statements: Vec::new(),
+            meta: None,
+            priority: 0,
+            error: None,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn meta(mut self, meta: impl Serialize) -> Self {
+        match to_raw_value(&meta) {
+            Ok(meta) => self.meta = Some(meta),
+            Err(err) => self.fail(err.into()),
+        }
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn allow(self) -> Self {
+        self.statement(Effect::Allow)
+    }
+
+    pub fn deny(self) -> Self {
+        self.statement(Effect::Deny)
+    }
+
+    pub fn statement(mut self, effect: Effect) -> Self {
+        self.push(effect);
+        self
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.current().subjects.push(subject.into());
+        self
+    }
+
+    pub fn subjects<I: IntoIterator<Item = S>, S: Into<String>>(mut self, subjects: I) -> Self {
+        let current = self.current();
+        current
+            .subjects
+            .extend(subjects.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.current().actions.push(action.into());
+        self
+    }
+
+    pub fn actions<I: IntoIterator<Item = S>, S: Into<String>>(mut self, actions: I) -> Self {
+        let current = self.current();
+        current.actions.extend(actions.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.current().resources.push(resource.into());
+        self
+    }
+
+    pub fn resources<I: IntoIterator<Item = S>, S: Into<String>>(mut self, resources: I) -> Self {
+        let current = self.current();
+        current
+            .resources
+            .extend(resources.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn not_subject(mut self, subject: impl Into<String>) -> Self {
+        self.current().not_subjects.push(subject.into());
+        self
+    }
+
+    pub fn not_action(mut self, action: impl Into<String>) -> Self {
+        self.current().not_actions.push(action.into());
+        self
+    }
+
+    pub fn not_resource(mut self, resource: impl Into<String>) -> Self {
+        self.current().not_resources.push(resource.into());
+        self
+    }
+
+    pub fn condition(
+        mut self,
+        key: impl Into<String>,
+        jtype: impl Into<String>,
+        options: impl Serialize,
+    ) -> Self {
+        match to_raw_value(&options) {
+            Ok(options) => {
+                let condition = JsonCondition {
+                    jtype: jtype.into(),
+                    options,
+                };
+                self.current()
+                    .conditions
+                    .get_or_insert_with(Default::default)
+                    .insert(key.into(), condition);
+            }
+            Err(err) => self.fail(err.into()),
+        }
+        self
+    }
+
+    pub fn obligation(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.current()
+                    .obligations
+                    .get_or_insert_with(Default::default)
+                    .insert(key.into(), value);
+            }
+            Err(err) => self.fail(err.into()),
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<Policy> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let policy = Policy {
+            id: self.id,
+            description: self.description,
+            statements: self.statements,
+            meta: self.meta,
+            priority: self.priority,
+        };
+        let errors: Vec<_> = policy
+            .lint()
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::InvalidPolicy(errors));
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn push(&mut self, effect: Effect) {
+        self.statements.push(Statement {
+            effect,
+            subjects: Vec::new(),
+            actions: Vec::new(),
+            resources: Vec::new(),
+            conditions: None,
+            meta: None,
+            obligations: None,
+            not_subjects: Vec::new(),
+            not_actions: Vec::new(),
+            not_resources: Vec::new(),
+            subject_matcher: None,
+            action_matcher: None,
+            resource_matcher: None,
+            compiled: Default::default(),
+        });
+    }
+
+    fn current(&mut self) -> &mut Statement {
+        if self.statements.is_empty() {
+            self.push(Effect::Allow);
+        }
+        let last = self.statements.len() - 1;
+        &mut self.statements[last]
+    }
+
+    fn fail(&mut self, err: Error) {
+        self.error.get_or_insert(err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::LintCode;
+
+    #[test]
+    fn build() {
+        let policy = PolicyBuilder::new("articles")
+            .description("article editors")
+            .allow()
+            .subjects(["<zac|ken>", "peter"])
+            .actions(["<create|delete>"])
+            .resource("articles:<[0-9]+>")
+            .condition("clientIP", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .deny()
+            .subject("<.*>")
+            .action("delete")
+            .resource("articles:0")
+            .build()
+            .unwrap();
+        assert_eq!(policy.statements.len(), 2);
+        assert_eq!(policy.statements[0].subjects, vec!["<zac|ken>", "peter"]);
+        assert_eq!(policy.statements[1].effect, Effect::Deny);
+        let conditions = policy.statements[0].conditions.as_ref().unwrap();
+        assert!(JsonCondition::into(&conditions["clientIP"]).is_ok());
+
+        assert!(matches!(
+            PolicyBuilder::new("")
+                .allow()
+                .subject("a")
+                .action("a")
+                .resource("a")
+                .build(),
+            Err(Error::ValidationError(_))
+        ));
+        match PolicyBuilder::new("broken")
+            .subject("<(>")
+            .action("get")
+            .build()
+        {
+            Err(Error::InvalidPolicy(diagnostics)) => {
+                let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+                assert!(codes.contains(&LintCode::InvalidRegex));
+                assert!(codes.contains(&LintCode::EmptyField));
+            }
+            _ => panic!("expected lint errors"),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use super::{Format, Policy};
+use crate::{Error, Result};
+
+const MAGIC: &[u8; 4] = b"OPE1";
+const TRAILER: usize = 8 + 64 + MAGIC.len();
+const MAX_UNPACKED: u64 = 64 << 20;
+
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    #[serde(default)]
+    version: u64,
+    #[serde(borrow)]
+    policies: &'a RawValue,
+    signature: String,
+}
+
+#[derive(Debug)]
+pub struct Bundle {
+    pub version: u64,
+    pub policies: Vec<Policy>,
+}
+
+pub struct BundleLoader;
+
+impl BundleLoader {
+    pub fn load_verified(bytes: &[u8], public_key: &[u8; 32], min_version: u64) -> Result<Bundle> {
+        let key = VerifyingKey::from_bytes(public_key)
+            .map_err(|_| Error::InvalidBundle("invalid ed25519 public key".to_owned()))?;
+        let bundle = match bytes.strip_suffix(MAGIC) {
+            Some(framed) if framed.len() >= TRAILER - MAGIC.len() => {
+                let (archive, trailer) = framed.split_at(framed.len() + MAGIC.len() - TRAILER);
+                let (version, signature) = trailer.split_at(8);
+                let version = u64::from_be_bytes(version.try_into().map_err(invalid)?);
+                let signature =
+                    Signature::from_slice(signature).map_err(|_| Error::InvalidSignature)?;
+                check(version, min_version)?;
+                verify(&key, version, archive, &signature)?;
+                Bundle {
+                    version,
+                    policies: load_entries(unpack(archive)?)?,
+                }
+            }
+            _ => {
+                let envelope: Envelope = serde_json::from_slice(bytes)?;
+                check(envelope.version, min_version)?;
+                verify(
+                    &key,
+                    envelope.version,
+                    envelope.policies.get().as_bytes(),
+                    &decode(&envelope.signature)?,
+                )?;
+                Bundle {
+                    version: envelope.version,
+                    policies: Policy::parse_all(envelope.policies.get(), Format::Json)?,
+                }
+            }
+        };
+        Ok(bundle)
+    }
+}
+
+fn invalid(err: impl std::fmt::Display) -> Error {
+    Error::InvalidBundle(err.to_string())
+}
+
+fn check(version: u64, min_version: u64) -> Result<()> {
+    if version < min_version {
+        return Err(Error::InvalidBundle(format!(
+            "bundle version {version} is older than {min_version}"
+        )));
+    }
+    Ok(())
+}
+
+fn unpack(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut budget = MAX_UNPACKED;
+    if bytes.starts_with(b"PK\x03\x04") {
+        return unzip(bytes, &mut budget);
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let content = read(flate2::read::GzDecoder::new(bytes), &mut budget)?;
+        return untar(&content, &mut budget);
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return untar(bytes, &mut budget);
+    }
+    Err(Error::InvalidBundle("unknown archive format".to_owned()))
+}
+
+fn read(reader: impl Read, budget: &mut u64) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader
+        .take(*budget + 1)
+        .read_to_end(&mut content)
+        .map_err(invalid)?;
+    *budget = budget.checked_sub(content.len() as u64).ok_or_else(|| {
+        Error::InvalidBundle(format!("bundle unpacks to more than {MAX_UNPACKED} bytes"))
+    })?;
+    Ok(content)
+}
+
+fn insert(entries: &mut BTreeMap<String, Vec<u8>>, name: &str, content: Vec<u8>) -> Result<()> {
+    let name = name.trim_start_matches("./").to_owned();
+    if entries.insert(name.clone(), content).is_some() {
+        return Err(Error::InvalidBundle(format!("duplicate entry {name}")));
+    }
+    Ok(())
+}
+
+fn untar(bytes: &[u8], budget: &mut u64) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    let mut archive = tar::Archive::new(bytes);
+    for entry in archive.entries().map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .map_err(invalid)?
+            .to_string_lossy()
+            .into_owned();
+        insert(&mut entries, &name, read(entry, budget)?)?;
+    }
+    Ok(entries)
+}
+
+fn unzip(bytes: &[u8], budget: &mut u64) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+    for index in 0..archive.len() {
+        let file = archive.by_index(index).map_err(invalid)?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name().to_owned();
+        insert(&mut entries, &name, read(file, budget)?)?;
+    }
+    Ok(entries)
+}
+
+fn load_entries(entries: BTreeMap<String, Vec<u8>>) -> Result<Vec<Policy>> {
+    let mut policies = Vec::new();
+    for (name, content) in entries.iter() {
+        let Some(format) = Format::from_path(Path::new(name)) else {
+            continue;
+        };
+        let content = std::str::from_utf8(content).map_err(invalid)?;
+        let parsed = Policy::parse_all(content, format).map_err(|err| match err {
+            Error::ValidationError(_) => err,
+            _ => Error::InvalidBundle(format!("parse {name} failed: {err}")),
+        })?;
+        for policy in parsed {
+            if policies.iter().any(|v: &Policy| v.id == policy.id) {
+                return Err(Error::PolicyAlreadyExists(policy.id));
+            }
+            policies.push(policy);
+        }
+    }
+    Ok(policies)
+}
+
+fn decode(signature: &str) -> Result<Signature> {
+    let signature = signature.trim();
+    if signature.len() != 128 || !signature.is_ascii() {
+        return Err(Error::InvalidSignature);
+    }
+    let mut bytes = [0u8; 64];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&signature[index * 2..index * 2 + 2], 16)
+            .map_err(|_| Error::InvalidSignature)?;
+    }
+    Ok(Signature::from_bytes(&bytes))
+}
+
+fn verify(key: &VerifyingKey, version: u64, payload: &[u8], signature: &Signature) -> Result<()> {
+    let mut message = Vec::with_capacity(8 + payload.len());
+    message.extend_from_slice(&version.to_be_bytes());
+    message.extend_from_slice(payload);
+    key.verify_strict(&message, signature)
+        .map_err(|_| Error::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    fn document(id: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "statements": [{{"effect": "Allow", "subjects": ["peter"], "actions": ["get"], "resources": ["articles:<.*>"]}}]}}"#
+        )
+    }
+
+    fn sign(signing: &SigningKey, version: u64, payload: &[u8]) -> Signature {
+        let mut message = version.to_be_bytes().to_vec();
+        message.extend_from_slice(payload);
+        signing.sign(&message)
+    }
+
+    fn frame(signing: &SigningKey, version: u64, archive: &[u8]) -> Vec<u8> {
+        let mut framed = archive.to_vec();
+        framed.extend_from_slice(&version.to_be_bytes());
+        framed.extend_from_slice(&sign(signing, version, archive).to_bytes());
+        framed.extend_from_slice(MAGIC);
+        framed
+    }
+
+    #[test]
+    fn load_verified() {
+        let signing = SigningKey::from_bytes(&[7; 32]);
+        let public = signing.verifying_key().to_bytes();
+        let other = SigningKey::from_bytes(&[9; 32]).verifying_key().to_bytes();
+
+        let policies = format!("[{}, {}]", document("a"), document("b"));
+        let signature: String = sign(&signing, 3, policies.as_bytes())
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let json =
+            format!(r#"{{"version": 3, "policies": {policies}, "signature": "{signature}"}}"#);
+        let loaded = BundleLoader::load_verified(json.as_bytes(), &public, 3).unwrap();
+        assert_eq!((loaded.version, loaded.policies.len()), (3, 2));
+        assert!(matches!(
+            BundleLoader::load_verified(json.as_bytes(), &other, 0),
+            Err(Error::InvalidSignature)
+        ));
+        for tampered in [
+            json.replace("peter", "admin"),
+            json.replace("\"version\": 3", "\"version\": 4"),
+        ] {
+            assert!(matches!(
+                BundleLoader::load_verified(tampered.as_bytes(), &public, 0),
+                Err(Error::InvalidSignature)
+            ));
+        }
+        assert!(matches!(
+            BundleLoader::load_verified(json.as_bytes(), &public, 4),
+            Err(Error::InvalidBundle(_))
+        ));
+
+        let entries = BTreeMap::from([
+            ("a.json".to_owned(), document("a").into_bytes()),
+            ("b.json".to_owned(), document("b").into_bytes()),
+            ("README".to_owned(), b"ignored".to_vec()),
+        ]);
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in entries.iter() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_slice())
+                .unwrap();
+        }
+        let archive = builder.into_inner().unwrap();
+        let loaded =
+            BundleLoader::load_verified(&frame(&signing, 1, &archive), &public, 0).unwrap();
+        assert_eq!(loaded.policies[1].id, "b");
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&archive).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(
+            BundleLoader::load_verified(&frame(&signing, 1, &gzip), &public, 1)
+                .unwrap()
+                .policies
+                .len(),
+            2
+        );
+        let mut spliced = frame(&signing, 1, &gzip);
+        spliced[0..gzip.len()].copy_from_slice(&frame(&signing, 1, &archive)[..gzip.len()]);
+        assert!(matches!(
+            BundleLoader::load_verified(&spliced, &public, 0),
+            Err(Error::InvalidSignature)
+        ));
+
+        let mut reader = Cursor::new(vec![0u8; 16]);
+        let mut budget = 8;
+        assert!(read(&mut reader, &mut budget).is_err());
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries.iter() {
+            writer
+                .start_file(name.as_str(), zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        let zipped = writer.finish().unwrap().into_inner();
+        assert!(BundleLoader::load_verified(&zipped, &public, 0).is_err());
+        assert_eq!(
+            BundleLoader::load_verified(&frame(&signing, 2, &zipped), &public, 0)
+                .unwrap()
+                .version,
+            2
+        );
+        let mut tampered = frame(&signing, 2, &zipped);
+        tampered[40] ^= 1;
+        assert!(matches!(
+            BundleLoader::load_verified(&tampered, &public, 0),
+            Err(Error::InvalidSignature)
+        ));
+    }
+}
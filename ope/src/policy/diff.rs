@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::Policy;
+use crate::enforcer::Enforcer;
+use crate::matcher::Matcher;
+use crate::req::Request;
+
+#[derive(Debug, Clone, Default)]
+pub struct PolicyDiff {
+    pub added: Vec<Policy>,
+    pub removed: Vec<Policy>,
+    pub changed: Vec<(Policy, Policy)>,
+    before: Vec<Policy>,
+    after: Vec<Policy>,
+}
+
+impl PolicyDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Impact {
+    pub index: usize,
+    pub request: Request,
+    pub was_allowed: bool,
+    pub now_allowed: bool,
+}
+
+pub fn diff(old: &[Policy], new: &[Policy]) -> PolicyDiff {
+    let before: BTreeMap<_, _> = old
+        .iter()
+        .map(|policy| (policy.id.as_str(), policy))
+        .collect();
+    let after: BTreeMap<_, _> = new
+        .iter()
+        .map(|policy| (policy.id.as_str(), policy))
+        .collect();
+    let mut diff = PolicyDiff {
+        before: old.to_vec(),
+        after: new.to_vec(),
+        ..Default::default()
+    };
+    for (id, policy) in before.iter() {
+        match after.get(id) {
+            Some(updated) if policy != updated => diff
+                .changed
+                .push((Policy::clone(policy), Policy::clone(updated))),
+            Some(_) => {}
+            None => diff.removed.push(Policy::clone(policy)),
+        }
+    }
+    for (id, policy) in after.iter() {
+        if !before.contains_key(id) {
+            diff.added.push(Policy::clone(policy));
+        }
+    }
+    diff
+}
+
+pub fn impact<M: Matcher>(diff: &PolicyDiff, matcher: M, requests: &[Request]) -> Vec<Impact> {
+    let matcher = Arc::new(matcher);
+    let before = Enforcer::new(matcher.clone(), diff.before.clone());
+    let after = Enforcer::new(matcher, diff.after.clone());
+    let was = before.is_allowed_batch(requests);
+    let now = after.is_allowed_batch(requests);
+    requests
+        .iter()
+        .zip(was.iter().zip(now.iter()))
+        .enumerate()
+        .filter(|(_, (_, (was, now)))| was.is_ok() != now.is_ok())
+        .map(|(index, (request, (was, now)))| Impact {
+            index,
+            request: request.clone(),
+            was_allowed: was.is_ok(),
+            now_allowed: now.is_ok(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, Regexp};
+
+    fn policy(id: &str, resource: &str) -> Policy {
+        Policy::builder(id)
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource(resource)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn diff_and_impact() {
+        let old = vec![policy("a", "articles:<.*>"), policy("b", "files:1")];
+        let new = vec![policy("a", "articles:<[0-9]+>"), policy("c", "users:1")];
+        let changes = diff(&old, &new);
+        assert_eq!(changes.added[0].id, "c");
+        assert_eq!(changes.removed[0].id, "b");
+        assert_eq!(changes.changed[0].0.id, "a");
+        assert!(diff(&old, &old).is_empty());
+
+        let requests: Vec<_> = ["articles:1", "articles:x", "files:1", "users:1"]
+            .iter()
+            .map(|resource| Request {
+                resource: resource.to_string(),
+                action: "get".to_owned(),
+                subject: "peter".to_owned(),
+                context: Context::new(),
+            })
+            .collect();
+        let flips: Vec<_> = impact(&changes, Regexp::new(16).unwrap(), &requests)
+            .into_iter()
+            .map(|v| (v.request.resource, v.was_allowed, v.now_allowed))
+            .collect();
+        assert_eq!(
+            flips,
+            vec![
+                ("articles:x".to_owned(), true, false),
+                ("files:1".to_owned(), true, false),
+                ("users:1".to_owned(), false, true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use crate::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|v| v.to_str())? {
+            "json" => Some(Format::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(self, content: &str) -> Result<String> {
+        match self {
+            Format::Json => Ok(content.to_owned()),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                let value: serde_json::Value = serde_yaml::from_str(content)?;
+                Ok(serde_json::to_string(&value)?)
+            }
+            #[cfg(feature = "toml")]
+            Format::Toml => {
+                let value: serde_json::Value = toml::from_str(content)?;
+                Ok(serde_json::to_string(&value)?)
+            }
+        }
+    }
+}
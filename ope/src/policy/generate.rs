@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use super::Policy;
+use crate::audit::{AuditEvent, AuditSink};
+use crate::Result;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub struct Observation {
+    pub subject: String,
+    pub action: String,
+    pub resource: String,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    observation: Observation,
+    allowed: bool,
+}
+
+#[derive(Default)]
+pub struct PolicyGenerator {
+    observed: Mutex<BTreeSet<Observation>>,
+    include_denied: bool,
+}
+
+impl PolicyGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include_denied(mut self, include_denied: bool) -> Self {
+        self.include_denied = include_denied;
+        self
+    }
+
+    pub fn observe(
+        &self,
+        subject: impl Into<String>,
+        action: impl Into<String>,
+        resource: impl Into<String>,
+    ) {
+        self.observed.lock().insert(Observation {
+            subject: subject.into(),
+            action: action.into(),
+            resource: resource.into(),
+        });
+    }
+
+    pub fn read_json_lines(&self, reader: impl BufRead) -> Result<usize> {
+        let mut count = 0;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(&line)?;
+            if entry.allowed || self.include_denied {
+                self.observed.lock().insert(entry.observation);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn observations(&self) -> Vec<Observation> {
+        self.observed.lock().iter().cloned().collect()
+    }
+
+    pub fn generate(&self, prefix: &str) -> Result<Vec<Policy>> {
+        let mut subjects: BTreeMap<&str, BTreeMap<&str, BTreeSet<&str>>> = BTreeMap::new();
+        let observed = self.observed.lock();
+        for observation in observed.iter() {
+            subjects
+                .entry(&observation.subject)
+                .or_default()
+                .entry(&observation.resource)
+                .or_default()
+                .insert(&observation.action);
+        }
+
+        let mut groups: BTreeMap<BTreeMap<BTreeSet<&str>, BTreeSet<&str>>, Vec<&str>> =
+            BTreeMap::new();
+        for (subject, resources) in subjects {
+            let mut statements: BTreeMap<BTreeSet<&str>, BTreeSet<&str>> = BTreeMap::new();
+            for (resource, actions) in resources {
+                statements.entry(actions).or_default().insert(resource);
+            }
+            groups.entry(statements).or_default().push(subject);
+        }
+
+        let mut policies = Vec::new();
+        for (index, (statements, subjects)) in groups.into_iter().enumerate() {
+            let mut builder = Policy::builder(format!("{prefix}{}", index + 1)).description(
+                format!("generated from observed traffic of {}", subjects.join(", ")),
+            );
+            for (actions, resources) in statements {
+                builder = builder
+                    .allow()
+                    .subjects(subjects.iter().map(|v| literal(v)))
+                    .actions(actions.into_iter().map(literal))
+                    .resources(resources.into_iter().map(literal));
+            }
+            policies.push(builder.build()?);
+        }
+        Ok(policies)
+    }
+}
+
+impl AuditSink for PolicyGenerator {
+    fn record(&self, event: &AuditEvent<'_>) {
+        if event.allowed() || self.include_denied {
+            self.observe(
+                event.request.subject.as_str(),
+                event.request.action.as_str(),
+                event.request.resource.as_str(),
+            );
+        }
+    }
+}
+
+fn literal(value: &str) -> String {
+    value.replace('<', "\\<").replace('>', "\\>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Enforcer, PolicyStore, Regexp, Request};
+
+    #[test]
+    fn generate() {
+        let generator = PolicyGenerator::new();
+        let lines = [
+            r#"{"subject": "peter", "action": "get", "resource": "articles:1", "allowed": true, "policies": ["all"]}"#,
+            r#"{"subject": "peter", "action": "update", "resource": "articles:1", "allowed": true}"#,
+            r#"{"subject": "peter", "action": "get", "resource": "articles:2", "allowed": true}"#,
+            r#"{"subject": "ken", "action": "get", "resource": "articles:2", "allowed": true}"#,
+            r#"{"subject": "ken", "action": "delete", "resource": "articles:2", "allowed": false}"#,
+            "",
+        ];
+        assert_eq!(
+            generator
+                .read_json_lines(lines.join("\n").as_bytes())
+                .unwrap(),
+            4
+        );
+        generator.observe("max", "get", "articles:2");
+        generator.observe("max", "get", "tags:<b>");
+
+        let policies = generator.generate("least-privilege-").unwrap();
+        assert_eq!(policies.len(), 3);
+        let enforcer = Enforcer::new(Regexp::new(64).unwrap(), policies);
+        for observation in generator.observations() {
+            let request = Request::new(
+                observation.subject,
+                observation.action,
+                observation.resource,
+            );
+            assert!(enforcer.is_allowed(&request).is_ok(), "{request:?}");
+        }
+        for (subject, action, resource) in [
+            ("ken", "delete", "articles:2"),
+            ("ken", "get", "articles:1"),
+            ("peter", "update", "articles:2"),
+            ("max", "get", "tags:b"),
+        ] {
+            assert!(enforcer
+                .is_allowed(&Request::new(subject, action, resource))
+                .is_err());
+        }
+        let policy = enforcer.store().get("least-privilege-1").unwrap();
+        assert_eq!(policy.statements[0].subjects, vec!["ken"]);
+    }
+}
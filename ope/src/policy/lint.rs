@@ -0,0 +1,334 @@
+use validator::ValidationError;
+
+use super::Policy;
+use crate::condition::{registry::default_registry, ConditionRegistry};
+use crate::enforcer::options::WILDCARD;
+use crate::matcher::backend::Limits;
+use crate::matcher::reg::delimiter_indices;
+use crate::statement::{Effect, Statement};
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintCode {
+    EmptyField,
+    UnbalancedDelimiters,
+    InvalidRegex,
+    UnknownCondition,
+    InvalidConditionOptions,
+    DuplicateStatement,
+    UnreachableStatement,
+    AmbiguousWildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: LintCode,
+    pub path: String,
+    pub offset: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(code: LintCode, path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            path,
+            offset: None,
+            message,
+        }
+    }
+
+    fn warning(code: LintCode, path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, path, message)
+        }
+    }
+}
+
+pub(crate) fn unambiguous(policy: &Policy) -> Result<(), ValidationError> {
+    let ambiguous = policy.statements.iter().any(|statement| {
+        [
+            &statement.subjects,
+            &statement.actions,
+            &statement.resources,
+        ]
+        .into_iter()
+        .any(|patterns| patterns.len() > 1 && patterns.iter().any(|p| p == WILDCARD))
+    });
+    match ambiguous {
+        true => Err(ValidationError::new("ambiguous_wildcard")
+            .with_message(format!("{WILDCARD} mixed with other patterns").into())),
+        false => Ok(()),
+    }
+}
+
+pub(crate) fn compilable(policy: &Policy) -> Result<(), ValidationError> {
+    for condition in policy
+        .statements
+        .iter()
+        .filter_map(|statement| statement.conditions.as_ref())
+        .flat_map(|conditions| conditions.values())
+    {
+        match default_registry().build(condition) {
+            Ok(_) | Err(Error::NotFoundConditionType(_)) => {}
+            Err(err) => {
+                return Err(ValidationError::new("invalid_condition")
+                    .with_message(format!("{} condition: {err}", condition.jtype).into()))
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Policy {
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        self.lint_with(default_registry())
+    }
+
+    pub fn lint_with(&self, registry: &ConditionRegistry) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (i, statement) in self.statements.iter().enumerate() {
+            let path = format!("statements[{i}]");
+            let fields = [
+                ("subjects", &statement.subjects),
+                ("actions", &statement.actions),
+                ("resources", &statement.resources),
+            ];
+            for (name, patterns) in fields {
+                if patterns.is_empty() {
+                    diagnostics.push(Diagnostic::error(
+                        LintCode::EmptyField,
+                        format!("{path}.{name}"),
+                        format!("{name} is empty, the statement never applies"),
+                    ));
+                }
+                if patterns.len() > 1 && patterns.iter().any(|p| p == WILDCARD) {
+                    diagnostics.push(Diagnostic::error(
+                        LintCode::AmbiguousWildcard,
+                        format!("{path}.{name}"),
+                        format!("{name} mixes the {WILDCARD} wildcard with other patterns"),
+                    ));
+                }
+                for (j, pattern) in patterns.iter().enumerate() {
+                    lint_pattern(
+                        statement,
+                        pattern,
+                        format!("{path}.{name}[{j}]"),
+                        &mut diagnostics,
+                    );
+                }
+            }
+            let exclusions = [
+                ("not_subjects", &statement.not_subjects),
+                ("not_actions", &statement.not_actions),
+                ("not_resources", &statement.not_resources),
+            ];
+            for (name, patterns) in exclusions {
+                for (j, pattern) in patterns.iter().enumerate() {
+                    lint_pattern(
+                        statement,
+                        pattern,
+                        format!("{path}.{name}[{j}]"),
+                        &mut diagnostics,
+                    );
+                }
+            }
+            for (key, condition) in statement.conditions.iter().flatten() {
+                let path = format!("{path}.conditions.{key}");
+                match registry.build(condition) {
+                    Ok(_) => {}
+                    Err(Error::NotFoundConditionType(jtype)) => {
+                        diagnostics.push(Diagnostic::error(
+                            LintCode::UnknownCondition,
+                            path,
+                            format!("unknown condition type {jtype}"),
+                        ))
+                    }
+                    Err(err) => diagnostics.push(Diagnostic::error(
+                        LintCode::InvalidConditionOptions,
+                        path,
+                        format!("invalid {} options: {}", condition.jtype, err),
+                    )),
+                }
+            }
+            for (k, earlier) in self.statements[..i].iter().enumerate() {
+                if earlier == statement {
+                    diagnostics.push(Diagnostic::warning(
+                        LintCode::DuplicateStatement,
+                        path.clone(),
+                        format!("duplicate of statements[{k}]"),
+                    ));
+                    break;
+                }
+                if shadows(earlier, statement) {
+                    diagnostics.push(Diagnostic::warning(
+                        LintCode::UnreachableStatement,
+                        path.clone(),
+                        format!("always overridden by the unconditional deny in statements[{k}]"),
+                    ));
+                    break;
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+fn lint_pattern(
+    statement: &Statement,
+    pattern: &str,
+    path: String,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let (start, end) = (
+        statement.get_start_delimiter(),
+        statement.get_end_delimiter(),
+    );
+    if !pattern.contains(start) && !pattern.contains(end) {
+        return;
+    }
+    let idxs = match delimiter_indices(pattern, start, end) {
+        Ok(idxs) => idxs,
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(
+                LintCode::UnbalancedDelimiters,
+                path,
+                err.to_string(),
+            ));
+            return;
+        }
+    };
+    for pair in idxs.chunks(2) {
+        let inner = &pattern[pair[0] + start.len_utf8()..pair[1] - end.len_utf8()];
+        if let Err(err) = Limits::default().regex(inner, &format!("^{inner}$"), false) {
+            diagnostics.push(Diagnostic {
+                offset: Some(pair[0]),
+                ..Diagnostic::error(
+                    LintCode::InvalidRegex,
+                    path.clone(),
+                    format!("invalid regex {inner:?}: {err}"),
+                )
+            });
+        }
+    }
+}
+
+fn shadows(deny: &Statement, statement: &Statement) -> bool {
+    if deny.effect != Effect::Deny || statement.effect != Effect::Allow {
+        return false;
+    }
+    if deny.conditions.as_ref().is_some_and(|c| !c.is_empty()) || deny.has_exclusions() {
+        return false;
+    }
+    let covers = |deny: &[String], field: &[String]| {
+        deny.iter().any(|p| p == "<.*>") || field.iter().all(|v| deny.contains(v))
+    };
+    covers(&deny.subjects, &statement.subjects)
+        && covers(&deny.actions, &statement.actions)
+        && covers(&deny.resources, &statement.resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint() {
+        let raw = r#"{
+            "id": "lint",
+            "statements": [{
+                "effect": "Deny",
+                "subjects": ["<.*>"],
+                "actions": ["delete"],
+                "resources": ["articles:<[0-9]+>"],
+                "conditions": null,
+                "meta": null
+            }, {
+                "effect": "Allow",
+                "subjects": ["peter"],
+                "actions": ["delete"],
+                "resources": ["articles:<[0-9]+>"],
+                "conditions": null,
+                "meta": null
+            }, {
+                "effect": "Allow",
+                "subjects": [],
+                "actions": ["<get", "<(>"],
+                "resources": ["files"],
+                "conditions": {
+                    "ip": {"type": "Nope", "options": {}},
+                    "owner": {"type": "StringEqual", "options": {"equal": 1}}
+                },
+                "meta": null
+            }],
+            "meta": null
+        }"#;
+        let policy: Policy = serde_json::from_str(raw).unwrap();
+        let mut diagnostics: Vec<(LintCode, String)> = policy
+            .lint()
+            .into_iter()
+            .map(|d| (d.code, d.path))
+            .collect();
+        diagnostics.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            diagnostics,
+            vec![
+                (LintCode::UnreachableStatement, "statements[1]".to_owned()),
+                (
+                    LintCode::UnbalancedDelimiters,
+                    "statements[2].actions[0]".to_owned()
+                ),
+                (
+                    LintCode::InvalidRegex,
+                    "statements[2].actions[1]".to_owned()
+                ),
+                (
+                    LintCode::UnknownCondition,
+                    "statements[2].conditions.ip".to_owned()
+                ),
+                (
+                    LintCode::InvalidConditionOptions,
+                    "statements[2].conditions.owner".to_owned()
+                ),
+                (LintCode::EmptyField, "statements[2].subjects".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wildcard() {
+        let policy = Policy::builder("wildcard")
+            .subject("*")
+            .subject("peter")
+            .action("*")
+            .resource("articles:*");
+        match policy.build() {
+            Err(Error::InvalidPolicy(diagnostics)) => {
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].code, LintCode::AmbiguousWildcard);
+                assert_eq!(diagnostics[0].path, "statements[0].subjects");
+            }
+            _ => panic!("expected an ambiguous wildcard"),
+        }
+
+        let raw = r#"{"id": "wildcard", "statements": [{"effect": "Allow",
+            "subjects": ["*", "peter"], "actions": ["*"], "resources": ["articles:*"]}]}"#;
+        assert!(matches!(
+            Policy::parse(raw, crate::Format::Json),
+            Err(Error::ValidationError(_))
+        ));
+        let store = crate::MemoryStore::new();
+        assert!(
+            crate::PolicyStore::load_from_reader(&store, raw.replace('\n', "").as_bytes()).is_err()
+        );
+    }
+}
@@ -0,0 +1,259 @@
+mod builder;
+#[cfg(feature = "bundle")]
+mod bundle;
+mod diff;
+mod format;
+mod generate;
+mod lint;
+mod optimize;
+mod template;
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use validator::Validate;
+
+use crate::statement::Statement;
+use crate::Result;
+
+pub use builder::PolicyBuilder;
+#[cfg(feature = "bundle")]
+pub use bundle::{Bundle, BundleLoader};
+pub use diff::{diff, impact, Impact, PolicyDiff};
+pub use format::Format;
+pub use generate::{Observation, PolicyGenerator};
+pub use lint::{Diagnostic, LintCode, Severity};
+pub use optimize::optimize;
+pub use template::{PolicyTemplate, TemplateInstance, TemplateRegistry};
+
+#[derive(Deserialize)]
+struct PolicySet {
+    #[serde(default)]
+    policies: Vec<Policy>,
+    #[serde(default)]
+    templates: Vec<PolicyTemplate>,
+    #[serde(default)]
+    instances: Vec<TemplateInstance>,
+}
+
+impl PolicySet {
+    fn into_policies(self) -> Result<Vec<Policy>> {
+        let registry = TemplateRegistry::new();
+        for template in self.templates {
+            registry.register(template)?;
+        }
+        let mut policies = self.policies;
+        policies.extend(registry.instantiate_all(&self.instances)?);
+        Ok(policies)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[validate(schema(function = "lint::unambiguous"))]
+#[validate(schema(function = "lint::compilable"))]
+pub struct Policy {
+    #[validate(length(min = 1))]
+    pub id: String,
+    #[serde(default)]
+    pub description: String,
+    pub statements: Vec<Statement>,
+    #[cfg_attr(feature = "schema", schemars(with = "Option<serde_json::Value>"))]
+    pub meta: Option<Box<RawValue>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+}
+
+fn is_zero(priority: &i32) -> bool {
+    *priority == 0
+}
+
+impl Policy {
+    pub fn builder(id: impl Into<String>) -> PolicyBuilder {
+        PolicyBuilder::new(id)
+    }
+
+    #[cfg(feature = "schema")]
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(Policy).to_value()
+    }
+
+    pub fn parse(content: &str, format: Format) -> Result<Self> {
+        let policy: Policy = serde_json::from_str(&format.to_json(content)?)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn parse_all(content: &str, format: Format) -> Result<Vec<Self>> {
+        let content = format.to_json(content)?;
+        let policies = match serde_json::from_str(&content)? {
+            serde_json::Value::Array(_) => serde_json::from_str(&content)?,
+            serde_json::Value::Object(document)
+                if (document.contains_key("policies") || document.contains_key("templates"))
+                    && !document.contains_key("id") =>
+            {
+                serde_json::from_str::<PolicySet>(&content)?.into_policies()?
+            }
+            _ => vec![serde_json::from_str(&content)?],
+        };
+        for policy in policies.iter() {
+            policy.validate()?;
+        }
+        Ok(policies)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        Self::parse(content, Format::Json)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        Self::parse(content, Format::Yaml)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(content: &str) -> Result<Self> {
+        Self::parse(content, Format::Toml)
+    }
+}
+
+impl PartialEq for Policy {
+    fn eq(&self, other: &Self) -> bool {
+        if self.id == other.id
+            && self.description == other.description
+            && self.priority == other.priority
+            && self.statements == other.statements
+        {
+            return match (&self.meta, &other.meta) {
+                (Some(meta1), Some(meta2)) => meta1.get() == meta2.get(),
+                (None, None) => true,
+                _ => false,
+            };
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let raw = r#"{
+            "id": "articles",
+            "description": "article editors",
+            "statements": [{
+                "effect": "Allow",
+                "subjects": ["<zac|ken>"],
+                "actions": ["<create|delete>"],
+                "resources": ["articles:<[0-9]+>"],
+                "conditions": {
+                    "clientIP": {"type": "CIDR", "options": {"cidr": ["10.0.0.0/8"]}}
+                },
+                "meta": null
+            }],
+            "meta": {"owner": "cms"}
+        }"#;
+        let policy: Policy = serde_json::from_str(raw).unwrap();
+        assert_eq!(policy.id, "articles");
+        assert_eq!(policy.statements.len(), 1);
+        assert!(policy.validate().is_ok());
+
+        let encoded = serde_json::to_string(&policy).unwrap();
+        let decoded: Policy = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(policy, decoded);
+        let mut reprioritized = decoded.clone();
+        reprioritized.priority = 10;
+        assert_ne!(policy, reprioritized);
+        assert!(Policy::from_json(&encoded).is_ok());
+        assert_eq!(Policy::parse_all(&encoded, Format::Json).unwrap().len(), 1);
+        assert_eq!(
+            Policy::parse_all(
+                &format!(r#"{{"policies": [{encoded}, {encoded}]}}"#),
+                Format::Json
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+        assert!(matches!(
+            Policy::from_json(r#"{"id": "", "statements": [], "meta": null}"#),
+            Err(crate::Error::ValidationError(_))
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let raw = r#"
+id: articles
+statements:
+  - effect: Allow
+    subjects: ["<zac|ken>"]
+    actions: ["<create|delete>"]
+    resources: ["articles:<[0-9]+>"]
+    conditions:
+      clientIP:
+        type: CIDR
+        options:
+          cidr: ["10.0.0.0/8"]
+meta:
+  owner: cms
+"#;
+        let policy = Policy::from_yaml(raw).unwrap();
+        assert_eq!(policy.statements[0].subjects, vec!["<zac|ken>"]);
+        assert_eq!(policy.meta.unwrap().get(), r#"{"owner":"cms"}"#);
+        assert!(matches!(
+            Policy::from_yaml("id: ''\nstatements: []"),
+            Err(crate::Error::ValidationError(_))
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        let raw = r#"
+id = "articles"
+
+[[statements]]
+effect = "Allow"
+subjects = ["<zac|ken>"]
+actions = ["<create|delete>"]
+resources = ["articles:<[0-9]+>"]
+
+[statements.conditions.clientIP]
+type = "CIDR"
+options = { cidr = ["10.0.0.0/8"] }
+"#;
+        let policy = Policy::from_toml(raw).unwrap();
+        assert_eq!(policy.statements[0].resources, vec!["articles:<[0-9]+>"]);
+        assert!(policy.statements[0].conditions.is_some());
+        assert!(policy.meta.is_none());
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn json_schema() {
+        let schema = Policy::json_schema();
+        assert_eq!(schema["title"], "Policy");
+        assert_eq!(schema["required"], serde_json::json!(["id", "statements"]));
+        assert_eq!(schema["properties"]["id"]["minLength"], 1);
+        let statement = &schema["$defs"]["Statement"];
+        for field in [
+            "subjects",
+            "not_resources",
+            "conditions",
+            "resource_matcher",
+        ] {
+            assert!(statement["properties"].get(field).is_some(), "{field}");
+        }
+        assert_eq!(
+            schema["$defs"]["Effect"]["enum"],
+            serde_json::json!(["Allow", "Deny"])
+        );
+        assert_eq!(
+            schema["$defs"]["JsonCondition"]["required"],
+            serde_json::json!(["type", "options"])
+        );
+    }
+}
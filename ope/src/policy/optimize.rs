@@ -0,0 +1,229 @@
+use super::Policy;
+use crate::enforcer::options::WILDCARD;
+use crate::statement::{Effect, Statement};
+
+const CAPTURES: [&str; 3] = ["action.", "subject.", "resource."];
+
+pub fn optimize(policies: &[Policy]) -> Vec<Policy> {
+    policies
+        .iter()
+        .map(|policy| {
+            let mut policy = policy.clone();
+            policy.statements = statements(std::mem::take(&mut policy.statements));
+            policy
+        })
+        .collect()
+}
+
+fn statements(list: Vec<Statement>) -> Vec<Statement> {
+    let mut list: Vec<_> = list.into_iter().map(normalize).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut i = 0;
+        while i < list.len() {
+            let mut j = i + 1;
+            while j < list.len() {
+                match merge(&list[i], &list[j]) {
+                    Some(statement) => {
+                        list[i] = statement;
+                        list.remove(j);
+                        changed = true;
+                    }
+                    None => j += 1,
+                }
+            }
+            i += 1;
+        }
+    }
+    list.sort_by(|a, b| {
+        (
+            matches!(a.effect, Effect::Deny),
+            &a.subjects,
+            &a.actions,
+            &a.resources,
+        )
+            .cmp(&(
+                matches!(b.effect, Effect::Deny),
+                &b.subjects,
+                &b.actions,
+                &b.resources,
+            ))
+    });
+    list
+}
+
+fn pinned(statement: &Statement) -> bool {
+    statement
+        .conditions
+        .iter()
+        .flatten()
+        .any(|(key, _)| CAPTURES.iter().any(|prefix| key.starts_with(prefix)))
+}
+
+fn normalize(mut statement: Statement) -> Statement {
+    let sort = !pinned(&statement);
+    for patterns in [
+        &mut statement.subjects,
+        &mut statement.actions,
+        &mut statement.resources,
+        &mut statement.not_subjects,
+        &mut statement.not_actions,
+        &mut statement.not_resources,
+    ] {
+        let mut seen = Vec::with_capacity(patterns.len());
+        patterns.retain(|v| match seen.contains(v) {
+            true => false,
+            false => {
+                seen.push(v.clone());
+                true
+            }
+        });
+        if sort {
+            patterns.sort();
+        }
+    }
+    statement
+}
+
+fn subset(a: &[String], b: &[String]) -> bool {
+    a.is_empty() == b.is_empty() && a.iter().all(|v| b.contains(v))
+}
+
+fn same(a: &[String], b: &[String]) -> bool {
+    subset(a, b) && subset(b, a)
+}
+
+fn merge(a: &Statement, b: &Statement) -> Option<Statement> {
+    if a == b {
+        return Some(a.clone());
+    }
+    let compatible = a.effect == b.effect
+        && a.conditions == b.conditions
+        && a.obligations == b.obligations
+        && a.meta.as_ref().map(|v| v.get()) == b.meta.as_ref().map(|v| v.get())
+        && same(&a.not_subjects, &b.not_subjects)
+        && same(&a.not_actions, &b.not_actions)
+        && same(&a.not_resources, &b.not_resources)
+        && a.subject_matcher == b.subject_matcher
+        && a.action_matcher == b.action_matcher
+        && a.resource_matcher == b.resource_matcher
+        && !pinned(a)
+        && !pinned(b);
+    if !compatible {
+        return None;
+    }
+    let fields = [
+        (&a.subjects, &b.subjects),
+        (&a.actions, &b.actions),
+        (&a.resources, &b.resources),
+    ];
+    if fields.iter().all(|(x, y)| subset(y, x)) {
+        return Some(a.clone());
+    }
+    if fields.iter().all(|(x, y)| subset(x, y)) {
+        return Some(b.clone());
+    }
+    let differing: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, (x, y))| !same(x, y))
+        .map(|(i, _)| i)
+        .collect();
+    let [field] = differing.as_slice() else {
+        return None;
+    };
+    let (x, y) = fields[*field];
+    if x.is_empty() || y.is_empty() || x.iter().chain(y).any(|p| p == WILDCARD) {
+        return None;
+    }
+    let mut merged = a.clone();
+    let target = match field {
+        0 => &mut merged.subjects,
+        1 => &mut merged.actions,
+        _ => &mut merged.resources,
+    };
+    target.extend(y.iter().cloned());
+    Some(normalize(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, Enforcer, Regexp, Request};
+
+    #[test]
+    fn optimize_policies() {
+        let policy = Policy::builder("generated")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:2")
+            .resource("articles:2")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:1")
+            .allow()
+            .subject("max")
+            .action("get")
+            .resource("articles:1")
+            .resource("articles:2")
+            .allow()
+            .subject("max")
+            .action("get")
+            .resource("articles:1")
+            .deny()
+            .subject("max")
+            .action("get")
+            .resource("articles:2")
+            .condition("ip", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .build()
+            .unwrap();
+        let optimized = optimize(std::slice::from_ref(&policy));
+        let statements = &optimized[0].statements;
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].subjects, vec!["max", "peter"]);
+        assert_eq!(statements[0].resources, vec!["articles:1", "articles:2"]);
+        assert_eq!(optimize(&optimized)[0].statements, *statements);
+
+        let mut distinct = Policy::builder("distinct")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("*")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:1")
+            .allow()
+            .subject("peter")
+            .action("get")
+            .resource("articles:<.*>")
+            .build()
+            .unwrap();
+        distinct.statements[2].resource_matcher = Some("glob".to_owned());
+        assert_eq!(optimize(&[distinct])[0].statements.len(), 3);
+
+        let before = Enforcer::new(Regexp::new(16).unwrap(), vec![policy]);
+        let after = Enforcer::new(Regexp::new(16).unwrap(), optimized);
+        for subject in ["peter", "max", "alice"] {
+            for resource in ["articles:1", "articles:2", "articles:3"] {
+                for ip in ["10.1.1.1", "192.168.0.1"] {
+                    let request = Request {
+                        resource: resource.to_owned(),
+                        action: "get".to_owned(),
+                        subject: subject.to_owned(),
+                        context: Context::from([("ip", ip)]),
+                    };
+                    assert_eq!(
+                        before.is_allowed(&request).is_ok(),
+                        after.is_allowed(&request).is_ok()
+                    );
+                }
+            }
+        }
+    }
+}
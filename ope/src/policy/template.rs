@@ -0,0 +1,427 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use validator::Validate;
+
+use super::Policy;
+use crate::condition::JsonCondition;
+use crate::enforcer::options::WILDCARD;
+use crate::matcher::backend;
+use crate::statement::{Statement, PLACEHOLDER};
+use crate::{Error, Result};
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PolicyTemplate {
+    pub id: String,
+    #[serde(default)]
+    pub parameters: Vec<String>,
+    pub policy: Policy,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TemplateInstance {
+    pub template: String,
+    #[serde(default)]
+    pub values: BTreeMap<String, String>,
+}
+
+impl PolicyTemplate {
+    pub fn new<I, T>(id: impl Into<String>, parameters: I, policy: Policy) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let template = Self {
+            id: id.into(),
+            parameters: parameters.into_iter().map(Into::into).collect(),
+            policy,
+        };
+        template.check()?;
+        Ok(template)
+    }
+
+    pub fn check(&self) -> Result<()> {
+        self.render(|name| {
+            if self.parameters.iter().any(|p| p == name) {
+                return Ok(String::new());
+            }
+            Err(self.unknown(name))
+        })?;
+        Ok(())
+    }
+
+    fn unknown(&self, parameter: &str) -> Error {
+        Error::UnknownParameter {
+            template: self.id.clone(),
+            parameter: parameter.to_owned(),
+        }
+    }
+
+    pub fn instantiate(&self, values: &BTreeMap<String, String>) -> Result<Policy> {
+        if let Some(parameter) = self.parameters.iter().find(|p| !values.contains_key(*p)) {
+            return Err(Error::MissingParameter {
+                template: self.id.clone(),
+                parameter: parameter.clone(),
+            });
+        }
+        if let Some(parameter) = values.keys().find(|v| !self.parameters.contains(v)) {
+            return Err(self.unknown(parameter));
+        }
+        if let Some((parameter, _)) = values.iter().find(|(_, v)| v.contains(PLACEHOLDER)) {
+            return Err(Error::InvalidParameter {
+                template: self.id.clone(),
+                parameter: parameter.clone(),
+            });
+        }
+        let policy =
+            self.render(|name| values.get(name).cloned().ok_or_else(|| self.unknown(name)))?;
+        for (rendered, statement) in policy.statements.iter().zip(self.policy.statements.iter()) {
+            let fields = [
+                (&rendered.subjects, &statement.subjects),
+                (&rendered.actions, &statement.actions),
+                (&rendered.resources, &statement.resources),
+            ];
+            for (rendered, patterns) in fields {
+                for (pattern, original) in rendered.iter().zip(patterns.iter()) {
+                    if pattern == WILDCARD && original != WILDCARD {
+                        let mut parameter = None;
+                        render(original, |name, _| {
+                            parameter.get_or_insert_with(|| name.to_owned());
+                            Ok(String::new())
+                        })?;
+                        return Err(Error::InvalidParameter {
+                            template: self.id.clone(),
+                            parameter: parameter.unwrap_or_default(),
+                        });
+                    }
+                }
+            }
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn render(&self, lookup: impl Fn(&str) -> Result<String>) -> Result<Policy> {
+        let plain = |text: &str| render(text, |name, _| lookup(name));
+        let patterns = |patterns: &[String], statement: &Statement| -> Result<Vec<String>> {
+            let (start, end) = (
+                statement.get_start_delimiter(),
+                statement.get_end_delimiter(),
+            );
+            patterns
+                .iter()
+                .map(|pattern| {
+                    render(pattern, |name, offset| {
+                        let value = lookup(name)?;
+                        let nested = depth(&pattern[..offset], start, end) > 0;
+                        let value = match nested {
+                            true => backend::escape(&value),
+                            false => value,
+                        };
+                        let mut escaped = String::new();
+                        for c in value.chars() {
+                            if c == start || c == end || (c == '\\' && !nested) {
+                                escaped.push('\\');
+                            }
+                            escaped.push(c);
+                        }
+                        Ok(escaped)
+                    })
+                })
+                .collect()
+        };
+        let raw = |raw: &RawValue| -> Result<Box<RawValue>> {
+            let rendered = render(raw.get(), |name, _| {
+                let quoted = serde_json::to_string(&lookup(name)?)?;
+                Ok(quoted[1..quoted.len() - 1].to_owned())
+            })?;
+            Ok(RawValue::from_string(rendered)?)
+        };
+
+        let mut statements = Vec::with_capacity(self.policy.statements.len());
+        for statement in self.policy.statements.iter() {
+            let conditions = match &statement.conditions {
+                Some(conditions) => {
+                    let mut rendered = HashMap::with_capacity(conditions.len());
+                    for (key, condition) in conditions.iter() {
+                        rendered.insert(
+                            plain(key)?,
+                            JsonCondition {
+                                jtype: condition.jtype.clone(),
+                                options: raw(&condition.options)?,
+                            },
+                        );
+                    }
+                    Some(rendered)
+                }
+                None => None,
+            };
+            let obligations = match &statement.obligations {
+                Some(obligations) => {
+                    let mut rendered = BTreeMap::new();
+                    for (key, value) in obligations.iter() {
+                        rendered.insert(plain(key)?, json(value, &plain)?);
+                    }
+                    Some(rendered)
+                }
+                None => None,
+            };
+            statements.push(Statement {
+                effect: statement.effect.clone(),
+                subjects: patterns(&statement.subjects, statement)?,
+                actions: patterns(&statement.actions, statement)?,
+                resources: patterns(&statement.resources, statement)?,
+                not_subjects: patterns(&statement.not_subjects, statement)?,
+                not_actions: patterns(&statement.not_actions, statement)?,
+                not_resources: patterns(&statement.not_resources, statement)?,
+                conditions,
+                meta: statement.meta.as_deref().map(raw).transpose()?,
+                obligations,
+                subject_matcher: statement.subject_matcher.clone(),
+                action_matcher: statement.action_matcher.clone(),
+                resource_matcher: statement.resource_matcher.clone(),
+                compiled: Default::default(),
+            });
+        }
+
+        Ok(Policy {
+            id: plain(&self.policy.id)?,
+            description: plain(&self.policy.description)?,
+            statements,
+            meta: self.policy.meta.as_deref().map(raw).transpose()?,
+            priority: self.policy.priority,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct TemplateRegistry {
+    templates: RwLock<HashMap<String, Arc<PolicyTemplate>>>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, template: PolicyTemplate) -> Result<()> {
+        template.check()?;
+        self.templates
+            .write()
+            .insert(template.id.clone(), Arc::new(template));
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<PolicyTemplate>> {
+        self.templates.read().get(id).cloned()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.templates.read().contains_key(id)
+    }
+
+    pub fn instantiate(&self, id: &str, values: &BTreeMap<String, String>) -> Result<Policy> {
+        self.get(id)
+            .ok_or_else(|| Error::TemplateNotFound(id.to_owned()))?
+            .instantiate(values)
+    }
+
+    pub fn instantiate_all(&self, instances: &[TemplateInstance]) -> Result<Vec<Policy>> {
+        instances
+            .iter()
+            .map(|instance| self.instantiate(&instance.template, &instance.values))
+            .collect()
+    }
+}
+
+fn render(text: &str, mut value: impl FnMut(&str, usize) -> Result<String>) -> Result<String> {
+    let (mut rendered, mut rest) = (String::with_capacity(text.len()), text);
+    while let Some(open) = rest.find(OPEN) {
+        let after = &rest[open + OPEN.len()..];
+        let Some(close) = after.find(CLOSE) else {
+            break;
+        };
+        let name = after[..close].trim();
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            rendered.push_str(&rest[..open + OPEN.len()]);
+            rest = after;
+            continue;
+        }
+        rendered.push_str(&rest[..open]);
+        rendered.push_str(&value(name, text.len() - rest.len() + open)?);
+        rest = &after[close + CLOSE.len()..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn depth(text: &str, start: char, end: char) -> usize {
+    let (mut depth, mut escaped) = (0usize, false);
+    for c in text.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == start => depth += 1,
+            c if c == end => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+fn json(value: &Value, plain: &impl Fn(&str) -> Result<String>) -> Result<Value> {
+    Ok(match value {
+        Value::String(text) => Value::String(plain(text)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| json(item, plain))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => {
+            let mut rendered = serde_json::Map::with_capacity(map.len());
+            for (key, item) in map.iter() {
+                rendered.insert(plain(key)?, json(item, plain)?);
+            }
+            Value::Object(rendered)
+        }
+        value => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Enforcer, Format, Regexp, Request};
+
+    #[test]
+    fn template() {
+        let set = json!({
+            "templates": [{
+                "id": "project-admin",
+                "parameters": ["project", "team"],
+                "policy": {
+                    "id": "project-admin-{{project}}",
+                    "description": "admins of {{ project }}",
+                    "statements": [{
+                        "effect": "Allow",
+                        "subjects": ["group:{{team}}"],
+                        "actions": ["<.*>"],
+                        "resources": ["projects:{{project}}:<.*>"],
+                        "conditions": {
+                            "project": {"type": "StringEqual", "options": {"equals": "{{project}}"}}
+                        },
+                        "obligations": {"audit": ["{{team}}"]},
+                        "meta": null
+                    }],
+                    "meta": null
+                }
+            }],
+            "instances": [
+                {"template": "project-admin", "values": {"project": "apollo", "team": "core"}},
+                {"template": "project-admin", "values": {"project": "<gemini>", "team": "web"}}
+            ],
+            "policies": []
+        });
+        let policies = Policy::parse_all(&set.to_string(), Format::Json).unwrap();
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies[0].id, "project-admin-apollo");
+        assert_eq!(policies[0].description, "admins of apollo");
+        assert_eq!(
+            policies[1].statements[0].resources,
+            vec![r"projects:\<gemini\>:<.*>"]
+        );
+        assert_eq!(
+            policies[1].statements[0].obligations.as_ref().unwrap()["audit"],
+            json!(["web"])
+        );
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), policies);
+        let request = |subject: &str, resource: &str, project: &str| {
+            Request::builder()
+                .subject(subject)
+                .action("delete")
+                .resource(resource)
+                .context("project", project)
+                .build()
+        };
+        assert!(enforcer
+            .is_allowed(&request("group:core", "projects:apollo:1", "apollo"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&request("group:web", "projects:<gemini>:1", "<gemini>"))
+            .is_ok());
+        assert!(enforcer
+            .is_allowed(&request("group:web", "projects:apollo:1", "apollo"))
+            .is_err());
+
+        let registry = TemplateRegistry::new();
+        let template: PolicyTemplate = serde_json::from_value(set["templates"][0].clone()).unwrap();
+        registry.register(template.clone()).unwrap();
+        let backslash = template
+            .instantiate(&BTreeMap::from([
+                ("project".to_owned(), r"a\".to_owned()),
+                ("team".to_owned(), "ops".to_owned()),
+            ]))
+            .unwrap();
+        assert_eq!(
+            backslash.statements[0].resources,
+            vec![r"projects:a\\:<.*>"]
+        );
+        assert!(matches!(
+            registry.instantiate("project-admin", &BTreeMap::from([("project".to_owned(), "x".to_owned())])),
+            Err(Error::MissingParameter { parameter, .. }) if parameter == "team"
+        ));
+        assert!(matches!(
+            registry.instantiate("missing", &BTreeMap::new()),
+            Err(Error::TemplateNotFound(_))
+        ));
+        let values = |project: &str, team: &str| {
+            BTreeMap::from([
+                ("project".to_owned(), project.to_owned()),
+                ("team".to_owned(), team.to_owned()),
+            ])
+        };
+        let mut regional = template.clone();
+        regional.policy.statements[0].resources =
+            vec!["projects:<{{project}}|<a{{2}}>>".to_owned()];
+        regional.policy.statements[0].subjects = vec!["{{team}}".to_owned()];
+        regional.check().unwrap();
+        assert_eq!(
+            regional
+                .instantiate(&values("a.b", "core"))
+                .unwrap()
+                .statements[0]
+                .resources,
+            vec![r"projects:<a\.b|<a{{2}}>>"]
+        );
+        for (project, team, parameter) in
+            [("a", "*", "team"), ("${ctx.project}", "core", "project")]
+        {
+            assert!(matches!(
+                regional.instantiate(&values(project, team)),
+                Err(Error::InvalidParameter { parameter: p, .. }) if p == parameter
+            ));
+        }
+
+        let mut undeclared = template;
+        undeclared.parameters.pop();
+        assert!(matches!(
+            registry.register(undeclared),
+            Err(Error::UnknownParameter { parameter, .. }) if parameter == "team"
+        ));
+    }
+}
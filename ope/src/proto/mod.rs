@@ -0,0 +1,426 @@
+#[allow(clippy::all)]
+mod v1 {
+    include!("ope.v1.rs");
+}
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::value::RawValue;
+use serde_json::Value;
+use validator::Validate;
+
+pub use prost::Message;
+pub use v1::*;
+
+use crate::condition::JsonCondition;
+use crate::{Error, Result};
+
+impl From<&crate::Policy> for Policy {
+    fn from(policy: &crate::Policy) -> Self {
+        Self {
+            id: policy.id.clone(),
+            description: policy.description.clone(),
+            statements: policy.statements.iter().map(Into::into).collect(),
+            meta: policy.meta.as_ref().map(|v| v.get().to_owned()),
+            priority: policy.priority,
+        }
+    }
+}
+
+impl TryFrom<Policy> for crate::Policy {
+    type Error = Error;
+
+    fn try_from(policy: Policy) -> Result<Self> {
+        let policy = Self {
+            id: policy.id,
+            description: policy.description,
+            statements: policy
+                .statements
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+            meta: policy.meta.map(raw).transpose()?,
+            priority: policy.priority,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+impl From<&crate::Statement> for Statement {
+    fn from(statement: &crate::Statement) -> Self {
+        Self {
+            effect: effect(&statement.effect),
+            subjects: statement.subjects.clone(),
+            actions: statement.actions.clone(),
+            resources: statement.resources.clone(),
+            not_subjects: statement.not_subjects.clone(),
+            not_actions: statement.not_actions.clone(),
+            not_resources: statement.not_resources.clone(),
+            conditions: statement
+                .conditions
+                .iter()
+                .flatten()
+                .map(|(key, condition)| (key.clone(), Condition::from(condition)))
+                .collect(),
+            meta: statement.meta.as_ref().map(|v| v.get().to_owned()),
+            obligations: encode_values(statement.obligations.iter().flatten()),
+            subject_matcher: statement.subject_matcher.clone(),
+            action_matcher: statement.action_matcher.clone(),
+            resource_matcher: statement.resource_matcher.clone(),
+        }
+    }
+}
+
+impl TryFrom<Statement> for crate::Statement {
+    type Error = Error;
+
+    fn try_from(statement: Statement) -> Result<Self> {
+        let conditions = statement
+            .conditions
+            .into_iter()
+            .map(|(key, condition)| Ok((key, condition.try_into()?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let obligations = decode_values(statement.obligations)?;
+        Ok(Self {
+            effect: native_effect(statement.effect)?,
+            subjects: statement.subjects,
+            actions: statement.actions,
+            resources: statement.resources,
+            not_subjects: statement.not_subjects,
+            not_actions: statement.not_actions,
+            not_resources: statement.not_resources,
+            conditions: (!conditions.is_empty()).then_some(conditions),
+            meta: statement.meta.map(raw).transpose()?,
+            obligations: (!obligations.is_empty()).then_some(obligations),
+            subject_matcher: statement.subject_matcher,
+            action_matcher: statement.action_matcher,
+            resource_matcher: statement.resource_matcher,
+            compiled: Default::default(),
+        })
+    }
+}
+
+impl From<&JsonCondition> for Condition {
+    fn from(condition: &JsonCondition) -> Self {
+        Self {
+            r#type: condition.jtype.clone(),
+            options: condition.options.get().to_owned(),
+        }
+    }
+}
+
+impl TryFrom<Condition> for JsonCondition {
+    type Error = Error;
+
+    fn try_from(condition: Condition) -> Result<Self> {
+        Ok(Self {
+            jtype: condition.r#type,
+            options: raw(condition.options)?,
+        })
+    }
+}
+
+impl From<&crate::Request> for Request {
+    fn from(request: &crate::Request) -> Self {
+        Self {
+            resource: request.resource.clone(),
+            action: request.action.clone(),
+            subject: request.subject.clone(),
+            context: encode_values(request.context.iter()),
+        }
+    }
+}
+
+impl TryFrom<Request> for crate::Request {
+    type Error = Error;
+
+    fn try_from(request: Request) -> Result<Self> {
+        Ok(Self {
+            resource: request.resource,
+            action: request.action,
+            subject: request.subject,
+            context: decode_values(request.context)?.into_iter().collect(),
+        })
+    }
+}
+
+impl From<&crate::Decision> for Decision {
+    fn from(decision: &crate::Decision) -> Self {
+        let mut message = Self {
+            policies: decision.policies.iter().map(Into::into).collect(),
+            ..Default::default()
+        };
+        let outcome = match &decision.result {
+            Ok(()) => Outcome::Allow,
+            Err(Error::Deny { policy, statement }) => {
+                message.policy = Some(policy.clone());
+                message.statement = statement.map(|v| v as u64);
+                Outcome::Deny
+            }
+            Err(Error::NotMatched {
+                policy,
+                statement,
+                condition,
+            }) => {
+                message.policy = policy.clone();
+                message.statement = statement.map(|v| v as u64);
+                message.condition = condition.clone();
+                Outcome::NotMatched
+            }
+            Err(Error::MultipleApplicable(ids)) => {
+                message.applicable = ids.clone();
+                Outcome::MultipleApplicable
+            }
+            Err(err) => {
+                message.message = err.to_string();
+                Outcome::Error
+            }
+        };
+        message.set_outcome(outcome);
+        message
+    }
+}
+
+impl TryFrom<Decision> for crate::Decision {
+    type Error = Error;
+
+    fn try_from(decision: Decision) -> Result<Self> {
+        let result = match decision.outcome() {
+            Outcome::Allow => Ok(()),
+            Outcome::Deny => Err(Error::Deny {
+                policy: decision.policy.unwrap_or_default(),
+                statement: decision.statement.map(|v| v as usize),
+            }),
+            Outcome::NotMatched => Err(Error::NotMatched {
+                policy: decision.policy,
+                statement: decision.statement.map(|v| v as usize),
+                condition: decision.condition,
+            }),
+            Outcome::MultipleApplicable => Err(Error::MultipleApplicable(decision.applicable)),
+            Outcome::Error => Err(Error::RemoteError(decision.message)),
+            Outcome::Unspecified => return Err(invalid("decision outcome is unspecified")),
+        };
+        Ok(Self {
+            result,
+            policies: decision
+                .policies
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<&crate::PolicyTrace> for PolicyTrace {
+    fn from(trace: &crate::PolicyTrace) -> Self {
+        Self {
+            id: trace.id.clone(),
+            effect: trace.effect.as_ref().map(effect).unwrap_or_default(),
+            statements: trace.statements.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<PolicyTrace> for crate::PolicyTrace {
+    type Error = Error;
+
+    fn try_from(trace: PolicyTrace) -> Result<Self> {
+        let effect = match trace.effect() {
+            Effect::Unspecified => None,
+            _ => Some(native_effect(trace.effect)?),
+        };
+        Ok(Self {
+            id: trace.id,
+            effect,
+            statements: trace
+                .statements
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<&crate::StatementTrace> for StatementTrace {
+    fn from(trace: &crate::StatementTrace) -> Self {
+        Self {
+            index: trace.index as u64,
+            effect: effect(&trace.effect),
+            action: trace.action.clone(),
+            subject: trace.subject.clone(),
+            resource: trace.resource.clone(),
+            spans: trace
+                .spans
+                .iter()
+                .map(|(field, ranges)| {
+                    let ranges = ranges
+                        .iter()
+                        .map(|range| Span {
+                            start: range.start as u64,
+                            end: range.end as u64,
+                        })
+                        .collect();
+                    (field.clone(), Spans { ranges })
+                })
+                .collect(),
+            excluded: trace.excluded.clone(),
+            conditions: trace.conditions.iter().map(Into::into).collect(),
+            obligations: encode_values(trace.obligations.iter().flatten()),
+        }
+    }
+}
+
+impl TryFrom<StatementTrace> for crate::StatementTrace {
+    type Error = Error;
+
+    fn try_from(trace: StatementTrace) -> Result<Self> {
+        let obligations = decode_values(trace.obligations)?;
+        Ok(Self {
+            index: trace.index as usize,
+            effect: native_effect(trace.effect)?,
+            action: trace.action,
+            subject: trace.subject,
+            resource: trace.resource,
+            spans: trace
+                .spans
+                .into_iter()
+                .map(|(field, spans)| {
+                    let ranges = spans
+                        .ranges
+                        .into_iter()
+                        .map(|span| span.start as usize..span.end as usize)
+                        .collect();
+                    (field, ranges)
+                })
+                .collect(),
+            excluded: trace.excluded,
+            conditions: trace.conditions.into_iter().map(Into::into).collect(),
+            obligations: (!obligations.is_empty()).then_some(obligations),
+        })
+    }
+}
+
+impl From<&crate::ConditionTrace> for ConditionTrace {
+    fn from(trace: &crate::ConditionTrace) -> Self {
+        Self {
+            key: trace.key.clone(),
+            condition_type: trace.condition_type.clone(),
+            evaluated: trace.evaluated,
+            passed: trace.passed,
+        }
+    }
+}
+
+impl From<ConditionTrace> for crate::ConditionTrace {
+    fn from(trace: ConditionTrace) -> Self {
+        Self {
+            key: trace.key,
+            condition_type: trace.condition_type,
+            evaluated: trace.evaluated,
+            passed: trace.passed,
+        }
+    }
+}
+
+fn effect(effect: &crate::Effect) -> i32 {
+    match effect {
+        crate::Effect::Allow => Effect::Allow as i32,
+        crate::Effect::Deny => Effect::Deny as i32,
+    }
+}
+
+fn native_effect(value: i32) -> Result<crate::Effect> {
+    match Effect::try_from(value) {
+        Ok(Effect::Allow) => Ok(crate::Effect::Allow),
+        Ok(Effect::Deny) => Ok(crate::Effect::Deny),
+        _ => Err(invalid(format!("effect {value}"))),
+    }
+}
+
+fn raw(value: String) -> Result<Box<RawValue>> {
+    Ok(RawValue::from_string(value)?)
+}
+
+fn encode_values<'a>(
+    values: impl Iterator<Item = (&'a String, &'a Value)>,
+) -> HashMap<String, String> {
+    values
+        .map(|(key, value)| (key.clone(), value.to_string()))
+        .collect()
+}
+
+fn decode_values(values: HashMap<String, String>) -> Result<BTreeMap<String, Value>> {
+    values
+        .into_iter()
+        .map(|(key, value)| Ok((key, serde_json::from_str(&value)?)))
+        .collect()
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidProto(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Context, Enforcer, Regexp};
+
+    #[test]
+    fn roundtrip() {
+        let policy = crate::Policy::builder("articles")
+            .allow()
+            .subject("<peter|max>")
+            .action("get")
+            .resource("articles:<[0-9]+>")
+            .condition("ip", "CIDR", json!({"cidr": ["10.0.0.0/8"]}))
+            .obligation("log", json!({"level": "info"}))
+            .deny()
+            .subject("max")
+            .action("get")
+            .resource("articles:2")
+            .build()
+            .unwrap();
+        let bytes = Policy::from(&policy).encode_to_vec();
+        let decoded = crate::Policy::try_from(Policy::decode(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(decoded.statements, policy.statements);
+        assert_eq!(decoded.id, policy.id);
+
+        let request = crate::Request {
+            resource: "articles:2".to_owned(),
+            action: "get".to_owned(),
+            subject: "max".to_owned(),
+            context: Context::from([("ip", json!("10.0.0.1")), ("mfa", json!(true))]),
+        };
+        let bytes = Request::from(&request).encode_to_vec();
+        let decoded = crate::Request::try_from(Request::decode(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(decoded.context.get("mfa"), Some(&json!(true)));
+
+        let enforcer = Enforcer::new(Regexp::new(16).unwrap(), vec![decoded_policy(&policy)]);
+        let decision = enforcer.explain(&decoded);
+        let message = Decision::from(&decision);
+        assert_eq!(message.outcome(), Outcome::Deny);
+        let decoded = crate::Decision::try_from(
+            Decision::decode(message.encode_to_vec().as_slice()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(decoded.policies, decision.policies);
+        assert!(matches!(
+            decoded.result,
+            Err(Error::Deny { policy, statement: Some(1) }) if policy == "articles"
+        ));
+
+        let mut statement = Statement::from(&policy.statements[0]);
+        statement.effect = Effect::Unspecified as i32;
+        assert!(matches!(
+            crate::Statement::try_from(statement),
+            Err(Error::InvalidProto(_))
+        ));
+    }
+
+    fn decoded_policy(policy: &crate::Policy) -> crate::Policy {
+        crate::Policy::try_from(Policy::from(policy)).unwrap()
+    }
+}
@@ -0,0 +1,216 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Condition {
+    #[prost(string, tag = "1")]
+    pub r#type: ::prost::alloc::string::String,
+    /// JSON encoded condition options.
+    #[prost(string, tag = "2")]
+    pub options: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Statement {
+    #[prost(enumeration = "Effect", tag = "1")]
+    pub effect: i32,
+    #[prost(string, repeated, tag = "2")]
+    pub subjects: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "3")]
+    pub actions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "4")]
+    pub resources: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "5")]
+    pub not_subjects: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "6")]
+    pub not_actions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "7")]
+    pub not_resources: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(map = "string, message", tag = "8")]
+    pub conditions: ::std::collections::HashMap<::prost::alloc::string::String, Condition>,
+    /// JSON encoded statement meta.
+    #[prost(string, optional, tag = "9")]
+    pub meta: ::core::option::Option<::prost::alloc::string::String>,
+    /// JSON encoded obligation values.
+    #[prost(map = "string, string", tag = "10")]
+    pub obligations: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(string, optional, tag = "11")]
+    pub subject_matcher: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "12")]
+    pub action_matcher: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "13")]
+    pub resource_matcher: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Policy {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub description: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub statements: ::prost::alloc::vec::Vec<Statement>,
+    /// JSON encoded policy meta.
+    #[prost(string, optional, tag = "4")]
+    pub meta: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, tag = "5")]
+    pub priority: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Request {
+    #[prost(string, tag = "1")]
+    pub resource: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub action: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub subject: ::prost::alloc::string::String,
+    /// JSON encoded context values.
+    #[prost(map = "string, string", tag = "4")]
+    pub context: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Span {
+    #[prost(uint64, tag = "1")]
+    pub start: u64,
+    #[prost(uint64, tag = "2")]
+    pub end: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Spans {
+    #[prost(message, repeated, tag = "1")]
+    pub ranges: ::prost::alloc::vec::Vec<Span>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConditionTrace {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub condition_type: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub evaluated: bool,
+    #[prost(bool, tag = "4")]
+    pub passed: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatementTrace {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(enumeration = "Effect", tag = "2")]
+    pub effect: i32,
+    #[prost(string, optional, tag = "3")]
+    pub action: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub subject: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub resource: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(map = "string, message", tag = "6")]
+    pub spans: ::std::collections::HashMap<::prost::alloc::string::String, Spans>,
+    #[prost(string, optional, tag = "7")]
+    pub excluded: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "8")]
+    pub conditions: ::prost::alloc::vec::Vec<ConditionTrace>,
+    /// JSON encoded obligation values.
+    #[prost(map = "string, string", tag = "9")]
+    pub obligations: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PolicyTrace {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(enumeration = "Effect", tag = "2")]
+    pub effect: i32,
+    #[prost(message, repeated, tag = "3")]
+    pub statements: ::prost::alloc::vec::Vec<StatementTrace>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Decision {
+    #[prost(enumeration = "Outcome", tag = "1")]
+    pub outcome: i32,
+    /// Denying policy, or the policy of the closest miss.
+    #[prost(string, optional, tag = "2")]
+    pub policy: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(uint64, optional, tag = "3")]
+    pub statement: ::core::option::Option<u64>,
+    #[prost(string, optional, tag = "4")]
+    pub condition: ::core::option::Option<::prost::alloc::string::String>,
+    /// Policies reported by OUTCOME_MULTIPLE_APPLICABLE.
+    #[prost(string, repeated, tag = "5")]
+    pub applicable: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Error text reported by OUTCOME_ERROR.
+    #[prost(string, tag = "6")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "7")]
+    pub policies: ::prost::alloc::vec::Vec<PolicyTrace>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Effect {
+    Unspecified = 0,
+    Allow = 1,
+    Deny = 2,
+}
+impl Effect {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "EFFECT_UNSPECIFIED",
+            Self::Allow => "EFFECT_ALLOW",
+            Self::Deny => "EFFECT_DENY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EFFECT_UNSPECIFIED" => Some(Self::Unspecified),
+            "EFFECT_ALLOW" => Some(Self::Allow),
+            "EFFECT_DENY" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Outcome {
+    Unspecified = 0,
+    Allow = 1,
+    Deny = 2,
+    NotMatched = 3,
+    MultipleApplicable = 4,
+    Error = 5,
+}
+impl Outcome {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "OUTCOME_UNSPECIFIED",
+            Self::Allow => "OUTCOME_ALLOW",
+            Self::Deny => "OUTCOME_DENY",
+            Self::NotMatched => "OUTCOME_NOT_MATCHED",
+            Self::MultipleApplicable => "OUTCOME_MULTIPLE_APPLICABLE",
+            Self::Error => "OUTCOME_ERROR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "OUTCOME_UNSPECIFIED" => Some(Self::Unspecified),
+            "OUTCOME_ALLOW" => Some(Self::Allow),
+            "OUTCOME_DENY" => Some(Self::Deny),
+            "OUTCOME_NOT_MATCHED" => Some(Self::NotMatched),
+            "OUTCOME_MULTIPLE_APPLICABLE" => Some(Self::MultipleApplicable),
+            "OUTCOME_ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
@@ -1,13 +1,125 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::Deserialize;
-use serde_json::value::RawValue;
+use serde_json::Value;
 use validator::Validate;
 
+use crate::context::Context;
+use crate::Result;
+
 #[derive(Debug, Deserialize, Validate, Clone)]
 pub struct Request {
     pub resource: String,
     pub action: String,
     pub subject: String,
-    pub context: HashMap<String, Box<RawValue>>,
+    pub context: Context,
+}
+
+impl Request {
+    pub fn new(
+        subject: impl Into<String>,
+        action: impl Into<String>,
+        resource: impl Into<String>,
+    ) -> Self {
+        Self {
+            resource: resource.into(),
+            action: action.into(),
+            subject: subject.into(),
+            context: Context::new(),
+        }
+    }
+
+    pub fn builder() -> RequestBuilder {
+        RequestBuilder::default()
+    }
+
+    pub(crate) fn canonical(&self) -> Result<String> {
+        let context: BTreeMap<_, _> = self.context.iter().collect();
+        Ok(serde_json::to_string(&(
+            &self.subject,
+            &self.action,
+            &self.resource,
+            context,
+        ))?)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RequestBuilder {
+    subject: String,
+    action: String,
+    resource: String,
+    context: Context,
+}
+
+impl RequestBuilder {
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.action = action.into();
+        self
+    }
+
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = resource.into();
+        self
+    }
+
+    pub fn context(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.context.insert(key, value);
+        self
+    }
+
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn build(self) -> Request {
+        Request {
+            resource: self.resource,
+            action: self.action,
+            subject: self.subject,
+            context: self.context,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let request = Request::builder()
+            .subject("peter")
+            .action("get")
+            .resource("articles:1")
+            .context("clientIP", "192.168.1.67")
+            .context("count", 6)
+            .build();
+        assert_eq!(request.subject, "peter");
+        assert_eq!(request.action, "get");
+        assert_eq!(request.resource, "articles:1");
+        assert_eq!(request.context.get_i64("count").unwrap(), 6);
+        assert_eq!(
+            request.canonical().unwrap(),
+            Request {
+                context: Context::from([
+                    ("count", Value::from(6)),
+                    ("clientIP", Value::from("192.168.1.67")),
+                ]),
+                ..Request::new("peter", "get", "articles:1")
+            }
+            .canonical()
+            .unwrap()
+        );
+        assert_ne!(
+            Request::new("a:b", "c", "d").canonical().unwrap(),
+            Request::new("a", "b:c", "d").canonical().unwrap()
+        );
+    }
 }
@@ -0,0 +1,96 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+
+use crate::{Error, Result};
+
+pub trait SubjectResolver {
+    fn resolve(&self, subject: &str) -> Result<Vec<String>>;
+}
+
+impl<F> SubjectResolver for F
+where
+    F: Fn(&str) -> Result<Vec<String>>,
+{
+    fn resolve(&self, subject: &str) -> Result<Vec<String>> {
+        self(subject)
+    }
+}
+
+#[derive(Default)]
+pub struct RoleGraph {
+    parents: RwLock<HashMap<String, Vec<String>>>,
+}
+
+impl RoleGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, member: impl Into<String>, parent: impl Into<String>) -> Result<()> {
+        let mut parents = self
+            .parents
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        let entry = parents.entry(member.into()).or_default();
+        let parent = parent.into();
+        if !entry.contains(&parent) {
+            entry.push(parent);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, member: &str, parent: &str) -> Result<bool> {
+        let mut parents = self
+            .parents
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        let Some(entry) = parents.get_mut(member) else {
+            return Ok(false);
+        };
+        let len = entry.len();
+        entry.retain(|v| v != parent);
+        Ok(entry.len() != len)
+    }
+}
+
+impl SubjectResolver for RoleGraph {
+    fn resolve(&self, subject: &str) -> Result<Vec<String>> {
+        let parents = self
+            .parents
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        let mut seen = HashSet::from([subject]);
+        let mut queue = VecDeque::from([subject]);
+        let mut resolved = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            for parent in parents.get(current).into_iter().flatten() {
+                if seen.insert(parent.as_str()) {
+                    resolved.push(parent.clone());
+                    queue.push_back(parent.as_str());
+                }
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_graph() {
+        let graph = RoleGraph::new();
+        graph.add("user:alice", "group:admins").unwrap();
+        graph.add("group:admins", "role:editor").unwrap();
+        graph.add("role:editor", "role:viewer").unwrap();
+        graph.add("role:viewer", "group:admins").unwrap();
+        assert_eq!(
+            graph.resolve("user:alice").unwrap(),
+            vec!["group:admins", "role:editor", "role:viewer"]
+        );
+        assert!(graph.resolve("user:bob").unwrap().is_empty());
+        assert!(graph.remove("group:admins", "role:editor").unwrap());
+        assert_eq!(graph.resolve("user:alice").unwrap(), vec!["group:admins"]);
+    }
+}
@@ -1,19 +1,74 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
-use serde_json::value::RawValue;
+use serde_json::{value::RawValue, Value};
 use validator::Validate;
 
 use crate::condition::JsonCondition;
+use crate::context::Context;
+use crate::matcher::CompiledPatterns;
+
+pub(crate) const PLACEHOLDER: &str = "${ctx.";
 
 #[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Statement {
     pub effect: Effect,
     pub subjects: Vec<String>,
     pub actions: Vec<String>,
     pub resources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_subjects: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_actions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_resources: Vec<String>,
     pub conditions: Option<HashMap<String, JsonCondition>>,
+    #[cfg_attr(feature = "schema", schemars(with = "Option<Value>"))]
     pub meta: Option<Box<RawValue>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obligations: Option<BTreeMap<String, Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_matcher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_matcher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_matcher: Option<String>,
+    #[serde(skip)]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    pub compiled: Compiled,
+}
+
+struct Slot {
+    patterns: (usize, usize),
+    compiled: Option<CompiledPatterns>,
+}
+
+#[derive(Default)]
+pub struct Compiled {
+    slots: [OnceLock<Slot>; 6],
+}
+
+impl Clone for Compiled {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for Compiled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Compiled")
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Field {
+    Action,
+    Subject,
+    Resource,
 }
 
 impl Statement {
@@ -24,6 +79,95 @@ impl Statement {
     pub fn get_end_delimiter(&self) -> char {
         '>'
     }
+
+    pub(crate) fn matcher(&self, field: Field) -> Option<&str> {
+        match field {
+            Field::Action => self.action_matcher.as_deref(),
+            Field::Subject => self.subject_matcher.as_deref(),
+            Field::Resource => self.resource_matcher.as_deref(),
+        }
+    }
+
+    pub(crate) fn compiled(
+        &self,
+        haystack: &[String],
+        compile: impl FnOnce(&[String]) -> Option<CompiledPatterns>,
+    ) -> Option<&(dyn Any + Send + Sync)> {
+        let patterns = (haystack.as_ptr() as usize, haystack.len());
+        let index = [
+            &self.subjects,
+            &self.actions,
+            &self.resources,
+            &self.not_subjects,
+            &self.not_actions,
+            &self.not_resources,
+        ]
+        .iter()
+        .position(|field| (field.as_ptr() as usize, field.len()) == patterns)?;
+        let slot = self.compiled.slots[index].get_or_init(|| Slot {
+            patterns,
+            compiled: compile(haystack),
+        });
+        match slot.patterns == patterns {
+            true => slot.compiled.as_deref(),
+            false => None,
+        }
+    }
+
+    pub(crate) fn has_exclusions(&self) -> bool {
+        !(self.not_subjects.is_empty()
+            && self.not_actions.is_empty()
+            && self.not_resources.is_empty())
+    }
+
+    pub(crate) fn has_placeholders(&self) -> bool {
+        [&self.subjects, &self.actions, &self.resources]
+            .iter()
+            .any(|patterns| patterns.iter().any(|p| p.contains(PLACEHOLDER)))
+    }
+
+    pub(crate) fn resolve<'a>(
+        &self,
+        patterns: &'a [String],
+        context: &Context,
+        escape: impl Fn(&str) -> Option<String>,
+    ) -> Cow<'a, [String]> {
+        if !patterns.iter().any(|pattern| pattern.contains(PLACEHOLDER)) {
+            return Cow::Borrowed(patterns);
+        }
+        Cow::Owned(
+            patterns
+                .iter()
+                .filter_map(|pattern| substitute(pattern, context, &escape))
+                .collect(),
+        )
+    }
+}
+
+fn substitute(
+    pattern: &str,
+    context: &Context,
+    escape: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    if !pattern.contains(PLACEHOLDER) {
+        return Some(pattern.to_owned());
+    }
+    let (mut resolved, mut rest) = (String::with_capacity(pattern.len()), pattern);
+    while let Some(i) = rest.find(PLACEHOLDER) {
+        resolved.push_str(&rest[..i]);
+        let after = &rest[i + PLACEHOLDER.len()..];
+        let close = after.find('}')?;
+        let value = match context.lookup(&after[..close])? {
+            Value::String(v) => escape(v)?,
+            Value::Number(v) => escape(&v.to_string())?,
+            Value::Bool(v) => escape(&v.to_string())?,
+            _ => return None,
+        };
+        resolved.push_str(&value);
+        rest = &after[close + 1..];
+    }
+    resolved.push_str(rest);
+    Some(resolved)
 }
 
 impl PartialEq for Statement {
@@ -32,17 +176,27 @@ impl PartialEq for Statement {
             && self.subjects == other.subjects
             && self.actions == other.actions
             && self.resources == other.resources
+            && self.not_subjects == other.not_subjects
+            && self.not_actions == other.not_actions
+            && self.not_resources == other.not_resources
             && self.conditions == other.conditions
+            && self.obligations == other.obligations
+            && self.subject_matcher == other.subject_matcher
+            && self.action_matcher == other.action_matcher
+            && self.resource_matcher == other.resource_matcher
         {
-            if let (Some(meta1), Some(meta2)) = (&self.meta, &other.meta) {
-                return meta1.get() == meta2.get();
-            }
+            return match (&self.meta, &other.meta) {
+                (Some(meta1), Some(meta2)) => meta1.get() == meta2.get(),
+                (None, None) => true,
+                _ => false,
+            };
         }
         false
     }
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Effect {
     Allow,
     Deny,
@@ -0,0 +1,58 @@
+use std::future::{self, Future};
+use std::sync::Arc;
+
+use super::PolicyStore;
+use crate::policy::Policy;
+use crate::Result;
+
+pub trait AsyncPolicyStore {
+    fn create(&self, policy: Policy) -> impl Future<Output = Result<()>> + Send;
+
+    fn update(&self, policy: Policy) -> impl Future<Output = Result<()>> + Send;
+
+    fn get(&self, id: &str) -> impl Future<Output = Result<Arc<Policy>>> + Send;
+
+    fn delete(&self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn all(&self) -> impl Future<Output = Result<Vec<Arc<Policy>>>> + Send;
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> impl Future<Output = Result<Vec<Arc<Policy>>>> + Send;
+}
+
+impl<S: PolicyStore + Sync> AsyncPolicyStore for S {
+    fn create(&self, policy: Policy) -> impl Future<Output = Result<()>> + Send {
+        future::ready(PolicyStore::create(self, policy))
+    }
+
+    fn update(&self, policy: Policy) -> impl Future<Output = Result<()>> + Send {
+        future::ready(PolicyStore::update(self, policy))
+    }
+
+    fn get(&self, id: &str) -> impl Future<Output = Result<Arc<Policy>>> + Send {
+        future::ready(PolicyStore::get(self, id))
+    }
+
+    fn delete(&self, id: &str) -> impl Future<Output = Result<()>> + Send {
+        future::ready(PolicyStore::delete(self, id))
+    }
+
+    fn all(&self) -> impl Future<Output = Result<Vec<Arc<Policy>>>> + Send {
+        future::ready(PolicyStore::all(self))
+    }
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> impl Future<Output = Result<Vec<Arc<Policy>>>> + Send {
+        future::ready(PolicyStore::find_candidates(
+            self, subject, resource, action,
+        ))
+    }
+}
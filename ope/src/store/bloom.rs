@@ -0,0 +1,115 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
+
+use super::literal;
+use crate::policy::Policy;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SubjectFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+    open: BTreeSet<String>,
+}
+
+impl SubjectFilter {
+    pub(crate) fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let rate = false_positive_rate.clamp(1e-9, 0.5);
+        let bits = (-(capacity.max(1) as f64) * rate.ln() / std::f64::consts::LN_2.powi(2))
+            .ceil()
+            .max(64.0) as usize;
+        let hashes = ((bits as f64 / capacity.max(1) as f64) * std::f64::consts::LN_2)
+            .round()
+            .max(1.0) as u32;
+        Self {
+            bits: vec![0; bits.div_ceil(64)],
+            hashes,
+            open: BTreeSet::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, policy: &Policy) {
+        for statement in policy.statements.iter() {
+            let delimiter = statement.get_start_delimiter();
+            if statement.subjects.is_empty() || statement.subject_matcher.is_some() {
+                self.open.insert(policy.id.clone());
+            }
+            for subject in statement.subjects.iter() {
+                if literal(subject, delimiter) {
+                    self.insert_subject(subject);
+                } else {
+                    self.open.insert(policy.id.clone());
+                }
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, policy: &Policy) {
+        self.open.remove(&policy.id);
+    }
+
+    pub(crate) fn contains(&self, subject: &str) -> bool {
+        self.positions(subject)
+            .all(|(word, bit)| self.bits[word] & (1 << bit) != 0)
+    }
+
+    pub(crate) fn open(&self) -> &BTreeSet<String> {
+        &self.open
+    }
+
+    fn insert_subject(&mut self, subject: &str) {
+        let positions: Vec<_> = self.positions(subject).collect();
+        for (word, bit) in positions {
+            self.bits[word] |= 1 << bit;
+        }
+    }
+
+    fn positions(&self, subject: &str) -> impl Iterator<Item = (usize, u64)> {
+        let mut hasher = DefaultHasher::new();
+        subject.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let len = self.bits.len() as u64 * 64;
+        (0..u64::from(self.hashes)).map(move |i| {
+            let position = h1.wrapping_add(i.wrapping_mul(h2)) % len;
+            ((position / 64) as usize, position % 64)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subjects() {
+        let mut filter = SubjectFilter::new(100, 0.01);
+        let exact = Policy::builder("exact")
+            .allow()
+            .subject("peter")
+            .subject("max")
+            .action("get")
+            .resource("articles:1")
+            .build()
+            .unwrap();
+        let open = Policy::builder("open")
+            .allow()
+            .subject("users:<.*>")
+            .action("get")
+            .resource("articles:1")
+            .build()
+            .unwrap();
+        filter.insert(&exact);
+        filter.insert(&open);
+        assert!(filter.contains("peter"));
+        assert!(filter.contains("max"));
+        let probes = (0..1000)
+            .filter(|i| filter.contains(&format!("anonymous-{i}")))
+            .count();
+        assert!(probes < 50);
+        assert_eq!(filter.open().iter().collect::<Vec<_>>(), vec!["open"]);
+
+        filter.remove(&open);
+        assert!(filter.open().is_empty());
+    }
+}
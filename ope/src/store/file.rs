@@ -0,0 +1,286 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use super::{may_match, union, PolicyStore};
+use crate::policy::{Format, Policy};
+use crate::req::Request;
+use crate::{Error, Result};
+
+pub(crate) type Snapshot = BTreeMap<String, Arc<Policy>>;
+type Policies = Arc<ArcSwap<Snapshot>>;
+
+pub struct FileStore {
+    dir: PathBuf,
+    policies: Policies,
+    revision: Arc<AtomicU64>,
+}
+
+impl FileStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let policies = Arc::new(ArcSwap::from_pointee(load(&dir)?));
+        Ok(Self {
+            dir,
+            policies,
+            revision: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        reload(&self.dir, &self.policies, &self.revision)
+    }
+
+    #[cfg(feature = "watch")]
+    pub fn watch(&self) -> Result<FileWatcher> {
+        use notify::{RecursiveMode, Watcher};
+
+        let (dir, policies, revision) = (
+            self.dir.clone(),
+            self.policies.clone(),
+            self.revision.clone(),
+        );
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if event.kind.is_access() => {}
+                Ok(_) => {
+                    if let Err(err) = reload(&dir, &policies, &revision) {
+                        tracing::warn!("keep previous policies, reload failed: {}", err);
+                    }
+                }
+                Err(err) => tracing::warn!("watch {} failed: {}", dir.display(), err),
+            })?;
+        watcher.watch(&self.dir, RecursiveMode::NonRecursive)?;
+        Ok(FileWatcher { _watcher: watcher })
+    }
+
+    fn read_only(&self) -> Error {
+        Error::StoreError(format!("file store {} is read-only", self.dir.display()))
+    }
+}
+
+#[cfg(feature = "watch")]
+pub struct FileWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+fn reload(dir: &Path, policies: &Policies, revision: &AtomicU64) -> Result<()> {
+    let loaded = load(dir)?;
+    let len = loaded.len();
+    policies.store(Arc::new(loaded));
+    revision.fetch_add(1, Ordering::Release);
+    tracing::info!("loaded {} policies from {}", len, dir.display());
+    Ok(())
+}
+
+fn load(dir: &Path) -> Result<Snapshot> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(format) = Format::from_path(&path) {
+            paths.push((path, format));
+        }
+    }
+    paths.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut policies = BTreeMap::new();
+    for (path, format) in paths.iter() {
+        let content = fs::read_to_string(path)?;
+        let parsed = Policy::parse_all(&content, *format).map_err(|err| match err {
+            Error::ValidationError(_) => err,
+            _ => Error::StoreError(format!("parse {} failed: {}", path.display(), err)),
+        })?;
+        for policy in parsed {
+            if policies.contains_key(&policy.id) {
+                return Err(Error::PolicyAlreadyExists(policy.id));
+            }
+            policies.insert(policy.id.clone(), Arc::new(policy));
+        }
+    }
+    Ok(policies)
+}
+
+pub(crate) fn candidates(
+    snapshot: &Snapshot,
+    subject: &str,
+    resource: &str,
+    action: &str,
+) -> Vec<Arc<Policy>> {
+    snapshot
+        .values()
+        .filter(|policy| may_match(policy, subject, resource, action))
+        .cloned()
+        .collect()
+}
+
+impl PolicyStore for FileStore {
+    fn create(&self, _policy: Policy) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn update(&self, _policy: Policy) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Policy>> {
+        self.policies
+            .load()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))
+    }
+
+    fn delete(&self, _id: &str) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn all(&self) -> Result<Vec<Arc<Policy>>> {
+        Ok(self.policies.load().values().cloned().collect())
+    }
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> Result<Vec<Arc<Policy>>> {
+        Ok(candidates(&self.policies.load(), subject, resource, action))
+    }
+
+    fn find_request_candidates(&self, requests: &[Cow<'_, Request>]) -> Result<Vec<Arc<Policy>>> {
+        let snapshot = self.policies.load();
+        Ok(union(
+            requests
+                .iter()
+                .map(|input| candidates(&snapshot, &input.subject, &input.resource, &input.action))
+                .collect(),
+        ))
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(id: &str, subject: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "statements": [{{"effect": "Allow", "subjects": ["{subject}"], "actions": ["get"], "resources": ["articles:<.*>"], "conditions": null, "meta": null}}], "meta": null}}"#
+        )
+    }
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ope-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn load_and_reload() {
+        let dir = tempdir("file-store");
+        fs::write(dir.join("a.json"), document("a", "peter")).unwrap();
+        fs::write(
+            dir.join("b.json"),
+            format!("[{}, {}]", document("b", "max"), document("c", "ken")),
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let store = FileStore::open(&dir).unwrap();
+        assert_eq!(store.all().unwrap().len(), 3);
+        assert_eq!(
+            store.find_candidates("max", "articles:1", "get").unwrap()[0].id,
+            "b"
+        );
+        assert!(store
+            .create(Policy::clone(&store.get("a").unwrap()))
+            .is_err());
+
+        fs::write(dir.join("a.json"), document("a", "zac")).unwrap();
+        store.reload().unwrap();
+        assert_eq!(store.get("a").unwrap().statements[0].subjects, vec!["zac"]);
+
+        fs::write(dir.join("d.json"), document("a", "dup")).unwrap();
+        assert!(matches!(store.reload(), Err(Error::PolicyAlreadyExists(_))));
+        assert_eq!(store.get("a").unwrap().statements[0].subjects, vec!["zac"]);
+        fs::write(dir.join("d.json"), "{").unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.all().unwrap().len(), 3);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn snapshot() {
+        let dir = tempdir("file-snapshot");
+        fs::write(
+            dir.join("a.json"),
+            format!("[{}, {}]", document("a", "peter"), document("b", "max")),
+        )
+        .unwrap();
+        let store = FileStore::open(&dir).unwrap();
+        let request = |subject: &str| {
+            Cow::Owned(Request {
+                resource: "articles:1".to_owned(),
+                action: "get".to_owned(),
+                subject: subject.to_owned(),
+                context: Default::default(),
+            })
+        };
+        let requests = [request("peter"), request("max")];
+        let ids = |policies: Vec<Arc<Policy>>| {
+            policies
+                .iter()
+                .map(|policy| policy.id.clone())
+                .collect::<Vec<_>>()
+        };
+        let all = store.all().unwrap();
+        assert_eq!(
+            ids(store.find_request_candidates(&requests).unwrap()),
+            vec!["a", "b"]
+        );
+
+        fs::write(dir.join("a.json"), document("a", "max")).unwrap();
+        store.reload().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(
+            ids(store.find_request_candidates(&requests).unwrap()),
+            vec!["a"]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn watch() {
+        use std::time::{Duration, Instant};
+
+        let dir = tempdir("file-watch");
+        fs::write(dir.join("a.json"), document("a", "peter")).unwrap();
+        let store = FileStore::open(&dir).unwrap();
+        let _watcher = store.watch().unwrap();
+        fs::write(dir.join("b.json"), document("b", "max")).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while store.get("b").is_err() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(store.get("b").is_ok());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
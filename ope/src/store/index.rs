@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+
+use crate::enforcer::options::WILDCARD;
+use crate::policy::Policy;
+use crate::statement::{Field, Statement, PLACEHOLDER};
+
+#[derive(Debug, Default, Clone)]
+struct Node {
+    edges: Vec<(String, Node)>,
+    ids: BTreeSet<String>,
+}
+
+impl Node {
+    fn insert(&mut self, key: &str, id: &str) {
+        if key.is_empty() {
+            self.ids.insert(id.to_owned());
+            return;
+        }
+        let Some(i) = self
+            .edges
+            .iter()
+            .position(|(label, _)| common_prefix(label, key) > 0)
+        else {
+            let mut child = Node::default();
+            child.ids.insert(id.to_owned());
+            self.edges.push((key.to_owned(), child));
+            return;
+        };
+        let shared = common_prefix(&self.edges[i].0, key);
+        if shared < self.edges[i].0.len() {
+            let (label, child) = std::mem::take(&mut self.edges[i]);
+            let mut split = Node::default();
+            split.edges.push((label[shared..].to_owned(), child));
+            self.edges[i] = (label[..shared].to_owned(), split);
+        }
+        self.edges[i].1.insert(&key[shared..], id);
+    }
+
+    fn remove(&mut self, key: &str, id: &str) {
+        if key.is_empty() {
+            self.ids.remove(id);
+            return;
+        }
+        let Some(i) = self
+            .edges
+            .iter()
+            .position(|(label, _)| key.starts_with(label.as_str()))
+        else {
+            return;
+        };
+        let (label, child) = &mut self.edges[i];
+        child.remove(&key[label.len()..], id);
+        if child.ids.is_empty() && child.edges.len() <= 1 {
+            let (label, child) = self.edges.swap_remove(i);
+            if let Some((rest, grandchild)) = child.edges.into_iter().next() {
+                self.edges.push((label + &rest, grandchild));
+            }
+        }
+    }
+
+    fn collect(&self, needle: &str, ids: &mut BTreeSet<String>) {
+        ids.extend(self.ids.iter().cloned());
+        for (label, child) in self.edges.iter() {
+            if let Some(rest) = needle.strip_prefix(label.as_str()) {
+                child.collect(rest, ids);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PolicyIndex {
+    resources: Node,
+    actions: Node,
+}
+
+impl PolicyIndex {
+    pub(crate) fn insert(&mut self, policy: &Policy) {
+        for key in prefixes(policy, Field::Resource, |statement| &statement.resources) {
+            self.resources.insert(&key, &policy.id);
+        }
+        for key in prefixes(policy, Field::Action, |statement| &statement.actions) {
+            self.actions.insert(&key, &policy.id);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, policy: &Policy) {
+        for key in prefixes(policy, Field::Resource, |statement| &statement.resources) {
+            self.resources.remove(&key, &policy.id);
+        }
+        for key in prefixes(policy, Field::Action, |statement| &statement.actions) {
+            self.actions.remove(&key, &policy.id);
+        }
+    }
+
+    pub(crate) fn candidates(&self, resource: &str, action: &str) -> BTreeSet<String> {
+        let mut resources = BTreeSet::new();
+        self.resources.collect(resource, &mut resources);
+        if resources.is_empty() {
+            return resources;
+        }
+        let mut actions = BTreeSet::new();
+        self.actions.collect(action, &mut actions);
+        resources.intersection(&actions).cloned().collect()
+    }
+}
+
+fn prefixes(
+    policy: &Policy,
+    kind: Field,
+    field: fn(&Statement) -> &Vec<String>,
+) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+    for statement in policy.statements.iter() {
+        let patterns = field(statement);
+        if patterns.is_empty() || statement.matcher(kind).is_some() {
+            keys.insert(String::new());
+        }
+        let delimiter = statement.get_start_delimiter();
+        for pattern in patterns.iter() {
+            if pattern == WILDCARD {
+                keys.insert(String::new());
+                continue;
+            }
+            let end = [
+                pattern.find(delimiter),
+                pattern.find('\\'),
+                pattern.find(PLACEHOLDER),
+            ]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(pattern.len());
+            keys.insert(pattern[..end].to_owned());
+        }
+    }
+    keys
+}
+
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(id: &str, resource: &str, action: &str) -> Policy {
+        Policy::builder(id)
+            .allow()
+            .subject("peter")
+            .action(action)
+            .resource(resource)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn candidates() {
+        let policies = [
+            policy("a", "articles:<.*>", "get"),
+            policy("b", "articles:1", "<get|put>"),
+            policy("c", "articles:10", "get"),
+            policy("d", "files:${ctx.owner}", "get"),
+            policy("e", "*", "delete"),
+            policy("f", "ärticles:<.*>", "get"),
+        ];
+        let mut index = PolicyIndex::default();
+        for policy in policies.iter() {
+            index.insert(policy);
+        }
+        let ids = |index: &PolicyIndex, resource: &str, action: &str| {
+            index
+                .candidates(resource, action)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&index, "articles:1", "get"), vec!["a", "b"]);
+        assert_eq!(ids(&index, "articles:10", "get"), vec!["a", "b", "c"]);
+        assert_eq!(ids(&index, "articles:1", "put"), vec!["b"]);
+        assert_eq!(ids(&index, "files:peter", "get"), vec!["d"]);
+        assert_eq!(ids(&index, "users:1", "delete"), vec!["e"]);
+        assert_eq!(ids(&index, "ärticles:1", "get"), vec!["f"]);
+
+        index.remove(&policies[1]);
+        assert_eq!(ids(&index, "articles:10", "get"), vec!["a", "c"]);
+        index.remove(&policies[0]);
+        assert_eq!(ids(&index, "articles:10", "get"), vec!["c"]);
+        assert!(ids(&index, "articles:1", "get").is_empty());
+    }
+}
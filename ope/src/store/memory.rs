@@ -0,0 +1,580 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use arc_swap::ArcSwap;
+use chrono::Utc;
+use validator::Validate;
+
+use super::bloom::SubjectFilter;
+use super::index::PolicyIndex;
+use super::{may_match, union, PolicyChange, PolicyStore};
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::{Error, Result};
+
+#[derive(Default, Clone)]
+struct State {
+    policies: BTreeMap<String, Arc<Policy>>,
+    index: PolicyIndex,
+    subjects: Option<SubjectFilter>,
+}
+
+impl State {
+    fn reindex(&mut self, old: Option<&Policy>, new: Option<&Policy>) {
+        if let Some(policy) = old {
+            self.index.remove(policy);
+        }
+        if let Some(policy) = new {
+            self.index.insert(policy);
+        }
+        if let Some(filter) = self.subjects.as_mut() {
+            if let Some(policy) = old {
+                filter.remove(policy);
+            }
+            if let Some(policy) = new {
+                filter.insert(policy);
+            }
+        }
+    }
+
+    fn candidates(&self, subject: &str, resource: &str, action: &str) -> Vec<Arc<Policy>> {
+        let unknown = match self.subjects.as_ref() {
+            Some(filter) if !filter.contains(subject) => Some(filter.open()),
+            _ => None,
+        };
+        if unknown.is_some_and(|open| open.is_empty()) {
+            return Vec::new();
+        }
+        self.index
+            .candidates(resource, action)
+            .iter()
+            .filter(|id| match unknown {
+                Some(open) => open.contains(*id),
+                None => true,
+            })
+            .filter_map(|id| self.policies.get(id))
+            .filter(|policy| may_match(policy, subject, resource, action))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    state: ArcSwap<State>,
+    writer: Mutex<()>,
+    history: RwLock<BTreeMap<String, Vec<PolicyChange>>>,
+    history_limit: Option<usize>,
+    revision: AtomicU64,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = Some(limit);
+        self
+    }
+
+    pub fn transaction<T>(
+        &self,
+        change: impl FnOnce(&mut MemoryTransaction<'_>) -> Result<T>,
+    ) -> Result<T> {
+        let _writer = self
+            .writer
+            .lock()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        let mut tx = MemoryTransaction {
+            store: self,
+            state: State::clone(&self.state.load()),
+            versions: HashMap::new(),
+            changes: Vec::new(),
+        };
+        let value = change(&mut tx)?;
+        let mut history = self
+            .history
+            .write()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        for (id, change) in tx.changes {
+            let changes = history.entry(id).or_default();
+            changes.push(change);
+            if let Some(limit) = self.history_limit {
+                let excess = changes.len().saturating_sub(limit.max(1));
+                changes.drain(..excess);
+            }
+        }
+        self.state.store(Arc::new(tx.state));
+        self.revision.fetch_add(1, Ordering::Release);
+        Ok(value)
+    }
+
+    pub fn with_subject_filter(self, capacity: usize, false_positive_rate: f64) -> Result<Self> {
+        let mut state = State::clone(&self.state.load());
+        let mut filter = SubjectFilter::new(capacity, false_positive_rate);
+        for policy in state.policies.values() {
+            filter.insert(policy);
+        }
+        state.subjects = Some(filter);
+        self.state.store(Arc::new(state));
+        Ok(self)
+    }
+
+    fn version(&self, id: &str, version: u64) -> Result<PolicyChange> {
+        let history = self
+            .history
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        history
+            .get(id)
+            .and_then(|changes| changes.iter().find(|change| change.version == version))
+            .cloned()
+            .ok_or_else(|| Error::PolicyVersionNotFound(id.to_owned(), version))
+    }
+}
+
+pub struct MemoryTransaction<'a> {
+    store: &'a MemoryStore,
+    state: State,
+    versions: HashMap<String, u64>,
+    changes: Vec<(String, PolicyChange)>,
+}
+
+impl MemoryTransaction<'_> {
+    fn record(&mut self, id: &str, policy: Option<Arc<Policy>>, author: &str) -> Result<u64> {
+        let last = match self.versions.get(id) {
+            Some(version) => *version,
+            None => self
+                .store
+                .history
+                .read()
+                .map_err(|err| Error::LockError(format!("{err}")))?
+                .get(id)
+                .and_then(|changes| changes.last())
+                .map(|change| change.version)
+                .unwrap_or_default(),
+        };
+        let version = last + 1;
+        self.versions.insert(id.to_owned(), version);
+        self.changes.push((
+            id.to_owned(),
+            PolicyChange {
+                version,
+                author: author.to_owned(),
+                timestamp: Utc::now(),
+                policy,
+            },
+        ));
+        Ok(version)
+    }
+
+    pub fn create(&mut self, policy: Policy) -> Result<()> {
+        if self.state.policies.contains_key(&policy.id) {
+            return Err(Error::PolicyAlreadyExists(policy.id));
+        }
+        self.put_version(policy, "").map(drop)
+    }
+
+    pub fn update(&mut self, policy: Policy) -> Result<()> {
+        if !self.state.policies.contains_key(&policy.id) {
+            return Err(Error::PolicyNotFound(policy.id));
+        }
+        self.put_version(policy, "").map(drop)
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<()> {
+        let policy = self
+            .state
+            .policies
+            .remove(id)
+            .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))?;
+        self.record(id, None, "")?;
+        self.state.reindex(Some(&policy), None);
+        Ok(())
+    }
+
+    pub fn put_version(&mut self, policy: Policy, author: &str) -> Result<u64> {
+        let policy = Arc::new(policy);
+        let version = self.record(&policy.id, Some(policy.clone()), author)?;
+        let old = self
+            .state
+            .policies
+            .insert(policy.id.clone(), policy.clone());
+        self.state.reindex(old.as_deref(), Some(&policy));
+        Ok(version)
+    }
+}
+
+impl From<Vec<Policy>> for MemoryStore {
+    fn from(value: Vec<Policy>) -> Self {
+        let policies: BTreeMap<_, _> = value
+            .into_iter()
+            .map(|policy| (policy.id.clone(), Arc::new(policy)))
+            .collect();
+        let now = Utc::now();
+        let history = policies
+            .iter()
+            .map(|(id, policy)| {
+                let change = PolicyChange {
+                    version: 1,
+                    author: String::new(),
+                    timestamp: now,
+                    policy: Some(policy.clone()),
+                };
+                (id.clone(), vec![change])
+            })
+            .collect();
+        let mut index = PolicyIndex::default();
+        for policy in policies.values() {
+            index.insert(policy);
+        }
+        Self {
+            state: ArcSwap::from_pointee(State {
+                policies,
+                index,
+                subjects: None,
+            }),
+            writer: Mutex::default(),
+            history: RwLock::new(history),
+            history_limit: None,
+            revision: AtomicU64::new(0),
+        }
+    }
+}
+
+impl PolicyStore for MemoryStore {
+    fn create(&self, policy: Policy) -> Result<()> {
+        self.transaction(|tx| tx.create(policy))
+    }
+
+    fn update(&self, policy: Policy) -> Result<()> {
+        self.transaction(|tx| tx.update(policy))
+    }
+
+    fn create_batch(&self, policies: Vec<Policy>) -> Result<()> {
+        self.transaction(|tx| {
+            for policy in policies {
+                tx.create(policy)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Policy>> {
+        self.state
+            .load()
+            .policies
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.transaction(|tx| tx.delete(id))
+    }
+
+    fn all(&self) -> Result<Vec<Arc<Policy>>> {
+        Ok(self.state.load().policies.values().cloned().collect())
+    }
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> Result<Vec<Arc<Policy>>> {
+        Ok(self.state.load().candidates(subject, resource, action))
+    }
+
+    fn find_request_candidates(&self, requests: &[Cow<'_, Request>]) -> Result<Vec<Arc<Policy>>> {
+        let state = self.state.load();
+        Ok(union(
+            requests
+                .iter()
+                .map(|input| state.candidates(&input.subject, &input.resource, &input.action))
+                .collect(),
+        ))
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+
+    fn replace(&self, policies: Vec<Policy>) -> Result<()> {
+        let mut replaced = BTreeMap::new();
+        for policy in policies {
+            policy.validate()?;
+            if replaced.contains_key(&policy.id) {
+                return Err(Error::PolicyAlreadyExists(policy.id));
+            }
+            replaced.insert(policy.id.clone(), Arc::new(policy));
+        }
+        self.transaction(|tx| {
+            for (id, policy) in tx.state.policies.clone().iter() {
+                if !replaced.contains_key(id) {
+                    tx.record(id, None, "")?;
+                    tx.state.reindex(Some(policy), None);
+                }
+            }
+            for (id, policy) in replaced.iter() {
+                tx.record(id, Some(policy.clone()), "")?;
+                let old = tx.state.policies.get(id).cloned();
+                tx.state.reindex(old.as_deref(), Some(policy));
+            }
+            tx.state.policies = replaced;
+            Ok(())
+        })
+    }
+
+    fn put_version(&self, policy: Policy, author: &str) -> Result<u64> {
+        self.transaction(|tx| tx.put_version(policy, author))
+    }
+
+    fn get_version(&self, id: &str, version: u64) -> Result<Arc<Policy>> {
+        self.version(id, version)?
+            .policy
+            .ok_or_else(|| Error::PolicyVersionNotFound(id.to_owned(), version))
+    }
+
+    fn history(&self, id: &str) -> Result<Vec<PolicyChange>> {
+        let history = self
+            .history
+            .read()
+            .map_err(|err| Error::LockError(format!("{err}")))?;
+        history
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))
+    }
+
+    fn rollback(&self, id: &str, version: u64, author: &str) -> Result<u64> {
+        let policy = self.get_version(id, version)?;
+        self.put_version(Policy::clone(&policy), author)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Effect, Statement};
+
+    fn policy(id: &str, subjects: &[&str]) -> Policy {
+        Policy {
+            id: id.to_owned(),
+            description: String::new(),
+            statements: vec![Statement {
+                effect: Effect::Allow,
+                subjects: subjects.iter().map(|v| v.to_string()).collect(),
+                actions: vec!["get".to_owned()],
+                resources: vec!["articles:<.*>".to_owned()],
+                conditions: None,
+                meta: None,
+                obligations: None,
+                not_subjects: Vec::new(),
+                not_actions: Vec::new(),
+                not_resources: Vec::new(),
+                subject_matcher: None,
+                action_matcher: None,
+                resource_matcher: None,
+                compiled: Default::default(),
+            }],
+            meta: None,
+            priority: 0,
+        }
+    }
+
+    #[test]
+    fn load_from_reader() {
+        let lines = (0..3)
+            .map(|i| serde_json::to_string(&policy(&format!("p{i}"), &["peter"])).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let store = MemoryStore::new();
+        assert_eq!(store.load_from_reader(lines.as_bytes()).unwrap(), 3);
+        assert_eq!(store.get("p2").unwrap().id, "p2");
+
+        let invalid = format!("{}\n{{\"id\": \"\", \"statements\": []}}\n", lines);
+        let store = MemoryStore::new();
+        assert!(matches!(
+            store.load_from_reader(invalid.as_bytes()),
+            Err(Error::ValidationError(_))
+        ));
+        assert!(store.all().unwrap().is_empty());
+
+        let lines = (0..5000)
+            .map(|i| serde_json::to_string(&policy(&format!("p{i}"), &["peter"])).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let store = MemoryStore::new();
+        let started = std::time::Instant::now();
+        assert_eq!(store.load_from_reader(lines.as_bytes()).unwrap(), 5000);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(store.revision(), 5);
+    }
+
+    #[test]
+    fn crud() {
+        let store = MemoryStore::new();
+        store.create(policy("a", &["peter"])).unwrap();
+        store.create(policy("b", &["<zac|ken>"])).unwrap();
+        assert!(matches!(
+            store.create(policy("a", &["max"])),
+            Err(Error::PolicyAlreadyExists(_))
+        ));
+        assert_eq!(store.all().unwrap().len(), 2);
+
+        let ids = |v: Vec<Arc<Policy>>| v.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store.find_candidates("peter", "articles:1", "get").unwrap()),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            ids(store.find_candidates("max", "articles:1", "get").unwrap()),
+            vec!["b"]
+        );
+        assert!(store
+            .find_candidates("peter", "articles:1", "delete")
+            .unwrap()
+            .is_empty());
+
+        let revision = store.revision();
+        store.update(policy("a", &["max"])).unwrap();
+        assert_eq!(store.revision(), revision + 1);
+        assert_eq!(store.get("a").unwrap().statements[0].subjects, vec!["max"]);
+        store.delete("a").unwrap();
+        assert!(matches!(store.get("a"), Err(Error::PolicyNotFound(_))));
+        assert!(matches!(store.delete("a"), Err(Error::PolicyNotFound(_))));
+    }
+
+    #[test]
+    fn transaction() {
+        let store = MemoryStore::new();
+        let revision = store.revision();
+        store
+            .transaction(|tx| {
+                for i in 0..3 {
+                    tx.create(policy(&format!("p{i}"), &["peter"]))?;
+                }
+                tx.update(policy("p0", &["max"]))?;
+                tx.delete("p2")
+            })
+            .unwrap();
+        assert_eq!(store.revision(), revision + 1);
+        assert_eq!(store.all().unwrap().len(), 2);
+        assert_eq!(store.history("p0").unwrap().len(), 2);
+        assert_eq!(
+            store.find_candidates("max", "articles:1", "get").unwrap()[0].id,
+            "p0"
+        );
+
+        assert!(matches!(
+            store.transaction(|tx| {
+                tx.create(policy("p3", &["peter"]))?;
+                tx.create(policy("p1", &["peter"]))
+            }),
+            Err(Error::PolicyAlreadyExists(_))
+        ));
+        assert_eq!(store.revision(), revision + 1);
+        assert!(store.get("p3").is_err());
+        assert!(store.history("p3").is_err());
+    }
+
+    #[test]
+    fn versions() {
+        let store = MemoryStore::from(vec![policy("a", &["peter"])]);
+        assert_eq!(
+            store.put_version(policy("a", &["max"]), "alice").unwrap(),
+            2
+        );
+        assert_eq!(store.put_version(policy("b", &["ken"]), "bob").unwrap(), 1);
+        assert_eq!(store.get("a").unwrap().statements[0].subjects, vec!["max"]);
+        assert_eq!(
+            store.get_version("a", 1).unwrap().statements[0].subjects,
+            vec!["peter"]
+        );
+
+        assert_eq!(store.rollback("a", 1, "carol").unwrap(), 3);
+        assert_eq!(
+            store.get("a").unwrap().statements[0].subjects,
+            vec!["peter"]
+        );
+        let authors: Vec<_> = store
+            .history("a")
+            .unwrap()
+            .iter()
+            .map(|change| (change.version, change.author.clone()))
+            .collect();
+        assert_eq!(
+            authors,
+            vec![
+                (1, String::new()),
+                (2, "alice".to_owned()),
+                (3, "carol".to_owned())
+            ]
+        );
+
+        store.delete("b").unwrap();
+        assert!(store.history("b").unwrap()[1].policy.is_none());
+        assert!(matches!(
+            store.get_version("b", 2),
+            Err(Error::PolicyVersionNotFound(_, 2))
+        ));
+        assert_eq!(store.rollback("b", 1, "bob").unwrap(), 3);
+        assert!(store.get("b").is_ok());
+        assert!(matches!(
+            store.get_version("a", 9),
+            Err(Error::PolicyVersionNotFound(_, 9))
+        ));
+
+        let store = MemoryStore::from(vec![policy("a", &["peter"])]).with_history_limit(2);
+        store.put_version(policy("a", &["max"]), "alice").unwrap();
+        assert_eq!(store.put_version(policy("a", &["ken"]), "bob").unwrap(), 3);
+        let versions: Vec<_> = store
+            .history("a")
+            .unwrap()
+            .iter()
+            .map(|change| change.version)
+            .collect();
+        assert_eq!(versions, vec![2, 3]);
+        assert!(matches!(
+            store.get_version("a", 1),
+            Err(Error::PolicyVersionNotFound(_, 1))
+        ));
+    }
+
+    #[test]
+    fn subject_filter() {
+        let store = MemoryStore::from(vec![policy("a", &["peter"])])
+            .with_subject_filter(16, 0.01)
+            .unwrap();
+        let ids = |v: Vec<Arc<Policy>>| v.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store.find_candidates("peter", "articles:1", "get").unwrap()),
+            vec!["a"]
+        );
+        assert!(store
+            .find_candidates("anonymous", "articles:1", "get")
+            .unwrap()
+            .is_empty());
+
+        store.create(policy("b", &["<.*>"])).unwrap();
+        assert_eq!(
+            ids(store
+                .find_candidates("anonymous", "articles:1", "get")
+                .unwrap()),
+            vec!["b"]
+        );
+        store.update(policy("b", &["max"])).unwrap();
+        assert!(store
+            .find_candidates("anonymous", "articles:1", "get")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            ids(store.find_candidates("max", "articles:1", "get").unwrap()),
+            vec!["b"]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+#[cfg(feature = "async")]
+pub(crate) mod asynchronous;
+pub(crate) mod bloom;
+pub(crate) mod file;
+pub(crate) mod index;
+pub(crate) mod memory;
+#[cfg(feature = "redis")]
+pub(crate) mod redis;
+#[cfg(feature = "remote")]
+pub(crate) mod remote;
+#[cfg(feature = "sqlx")]
+pub(crate) mod sql;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::enforcer::options::WILDCARD;
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::statement::{Field, PLACEHOLDER};
+use crate::{Error, Result};
+
+#[cfg(feature = "redis")]
+pub use self::redis::{
+    PolicyUpdate, RedisDecisionCache, RedisListener, RedisStore, RedisSubscriber,
+};
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncPolicyStore;
+pub use file::FileStore;
+#[cfg(feature = "watch")]
+pub use file::FileWatcher;
+pub use memory::{MemoryStore, MemoryTransaction};
+#[cfg(feature = "remote")]
+pub use remote::{RemoteStore, RemoteSync};
+#[cfg(feature = "sqlx")]
+pub use sql::SqlStore;
+
+const LOAD_CHUNK: usize = 1024;
+
+pub trait PolicyStore {
+    fn create(&self, policy: Policy) -> Result<()>;
+
+    fn update(&self, policy: Policy) -> Result<()>;
+
+    fn get(&self, id: &str) -> Result<Arc<Policy>>;
+
+    fn delete(&self, id: &str) -> Result<()>;
+
+    fn all(&self) -> Result<Vec<Arc<Policy>>>;
+
+    fn load_from_reader<R: BufRead>(&self, mut reader: R) -> Result<usize>
+    where
+        Self: Sized,
+    {
+        let (mut line, mut loaded, mut chunk) = (String::new(), 0, Vec::new());
+        while reader.read_line(&mut line)? > 0 {
+            if !line.trim().is_empty() {
+                let policy: Policy = serde_json::from_str(&line)?;
+                policy.validate()?;
+                chunk.push(policy);
+            }
+            line.clear();
+            if chunk.len() == LOAD_CHUNK {
+                loaded += chunk.len();
+                self.create_batch(std::mem::take(&mut chunk))?;
+            }
+        }
+        if !chunk.is_empty() {
+            loaded += chunk.len();
+            self.create_batch(chunk)?;
+        }
+        Ok(loaded)
+    }
+
+    fn create_batch(&self, policies: Vec<Policy>) -> Result<()> {
+        for policy in policies {
+            self.create(policy)?;
+        }
+        Ok(())
+    }
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> Result<Vec<Arc<Policy>>>;
+
+    fn find_request_candidates(&self, requests: &[Cow<'_, Request>]) -> Result<Vec<Arc<Policy>>> {
+        let lists = requests
+            .iter()
+            .map(|input| self.find_candidates(&input.subject, &input.resource, &input.action))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(union(lists))
+    }
+
+    fn revision(&self) -> u64 {
+        0
+    }
+
+    fn replace(&self, _policies: Vec<Policy>) -> Result<()> {
+        Err(Error::StoreError(
+            "policy store does not support replacing all policies".to_owned(),
+        ))
+    }
+
+    fn put_version(&self, _policy: Policy, _author: &str) -> Result<u64> {
+        Err(unversioned())
+    }
+
+    fn get_version(&self, _id: &str, _version: u64) -> Result<Arc<Policy>> {
+        Err(unversioned())
+    }
+
+    fn history(&self, _id: &str) -> Result<Vec<PolicyChange>> {
+        Err(unversioned())
+    }
+
+    fn rollback(&self, _id: &str, _version: u64, _author: &str) -> Result<u64> {
+        Err(unversioned())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyChange {
+    pub version: u64,
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub policy: Option<Arc<Policy>>,
+}
+
+fn unversioned() -> Error {
+    Error::StoreError("policy store does not support versioning".to_owned())
+}
+
+pub(crate) fn union(mut lists: Vec<Vec<Arc<Policy>>>) -> Vec<Arc<Policy>> {
+    if lists.len() == 1 {
+        return lists.remove(0);
+    }
+    let mut candidates = BTreeMap::new();
+    for policy in lists.into_iter().flatten() {
+        candidates.entry(policy.id.clone()).or_insert(policy);
+    }
+    candidates.into_values().collect()
+}
+
+pub(crate) fn literal(pattern: &str, delimiter: char) -> bool {
+    substituted(pattern) && !pattern.contains(delimiter)
+}
+
+pub(crate) fn may_match(policy: &Policy, subject: &str, resource: &str, action: &str) -> bool {
+    may_match_with(policy, subject, resource, action, |delimiter, pattern| {
+        !pattern.contains(delimiter)
+    })
+}
+
+pub(crate) fn may_match_with(
+    policy: &Policy,
+    subject: &str,
+    resource: &str,
+    action: &str,
+    is_literal: impl Fn(char, &str) -> bool,
+) -> bool {
+    policy.statements.iter().any(|statement| {
+        let delimiter = statement.get_start_delimiter();
+        let field = |kind: Field, haystack: &[String], needle: &str| {
+            haystack.is_empty()
+                || statement.matcher(kind).is_some()
+                || haystack
+                    .iter()
+                    .any(|h| !(substituted(h) && is_literal(delimiter, h)) || h == needle)
+        };
+        field(Field::Subject, &statement.subjects, subject)
+            && field(Field::Resource, &statement.resources, resource)
+            && field(Field::Action, &statement.actions, action)
+    })
+}
+
+fn substituted(pattern: &str) -> bool {
+    !pattern.contains(PLACEHOLDER) && pattern != WILDCARD
+}
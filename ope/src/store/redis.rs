@@ -0,0 +1,432 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use redis::{aio::ConnectionManager, AsyncCommands, Client, Script};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{may_match, AsyncPolicyStore, PolicyStore};
+use crate::enforcer::AsyncEnforcer;
+use crate::policy::Policy;
+use crate::req::Request;
+use crate::{Error, Result};
+
+const UPDATE: &str = "if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 1 then \
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[2]) return 1 end return 0";
+
+async fn connect(url: &str) -> Result<ConnectionManager> {
+    let client = Client::open(url)?;
+    Ok(ConnectionManager::new(client).await?)
+}
+
+pub struct RedisStore {
+    conn: ConnectionManager,
+    key: String,
+    channel: Option<String>,
+}
+
+impl RedisStore {
+    pub async fn connect(url: &str, prefix: &str) -> Result<Self> {
+        Ok(Self {
+            conn: connect(url).await?,
+            key: format!("{prefix}:policies"),
+            channel: None,
+        })
+    }
+
+    pub fn with_channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel = Some(channel.into());
+        self
+    }
+
+    pub async fn publish(&self, update: &PolicyUpdate) -> Result<()> {
+        if let Some(channel) = &self.channel {
+            let _: i64 = self
+                .conn
+                .clone()
+                .publish(channel, serde_json::to_string(update)?)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn changed(&self, id: &str) -> Result<()> {
+        self.publish(&PolicyUpdate {
+            ids: vec![id.to_owned()],
+        })
+        .await
+    }
+
+    async fn documents(&self) -> Result<BTreeMap<String, String>> {
+        let documents: HashMap<String, String> = self.conn.clone().hgetall(&self.key).await?;
+        Ok(documents.into_iter().collect())
+    }
+}
+
+fn decode(document: &str) -> Result<Arc<Policy>> {
+    Ok(Arc::new(serde_json::from_str(document)?))
+}
+
+impl AsyncPolicyStore for RedisStore {
+    async fn create(&self, policy: Policy) -> Result<()> {
+        let document = serde_json::to_string(&policy)?;
+        let created: bool = self
+            .conn
+            .clone()
+            .hset_nx(&self.key, &policy.id, document)
+            .await?;
+        if !created {
+            return Err(Error::PolicyAlreadyExists(policy.id));
+        }
+        self.changed(&policy.id).await
+    }
+
+    async fn update(&self, policy: Policy) -> Result<()> {
+        let document = serde_json::to_string(&policy)?;
+        let updated: i64 = Script::new(UPDATE)
+            .key(&self.key)
+            .arg(&policy.id)
+            .arg(document)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        if updated == 0 {
+            return Err(Error::PolicyNotFound(policy.id));
+        }
+        self.changed(&policy.id).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Arc<Policy>> {
+        let document: Option<String> = self.conn.clone().hget(&self.key, id).await?;
+        decode(&document.ok_or_else(|| Error::PolicyNotFound(id.to_owned()))?)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let deleted: i64 = self.conn.clone().hdel(&self.key, id).await?;
+        if deleted == 0 {
+            return Err(Error::PolicyNotFound(id.to_owned()));
+        }
+        self.changed(id).await
+    }
+
+    async fn all(&self) -> Result<Vec<Arc<Policy>>> {
+        self.documents()
+            .await?
+            .values()
+            .map(|document| decode(document))
+            .collect()
+    }
+
+    async fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> Result<Vec<Arc<Policy>>> {
+        let mut candidates = Vec::new();
+        for document in self.documents().await?.values() {
+            let policy = decode(document)?;
+            if may_match(&policy, subject, resource, action) {
+                candidates.push(policy);
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyUpdate {
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
+
+impl PolicyUpdate {
+    pub async fn reload<S, T>(&self, source: &S, target: &T) -> Result<()>
+    where
+        S: AsyncPolicyStore + Sync,
+        T: PolicyStore,
+    {
+        if self.ids.is_empty() {
+            let policies = source.all().await?;
+            return target.replace(policies.iter().map(|v| Policy::clone(v)).collect());
+        }
+        for id in self.ids.iter() {
+            match source.get(id).await {
+                Ok(policy) => upsert(target, Policy::clone(&policy))?,
+                Err(Error::PolicyNotFound(_)) => match target.delete(id) {
+                    Ok(()) | Err(Error::PolicyNotFound(_)) => {}
+                    Err(err) => return Err(err),
+                },
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn upsert<T: PolicyStore>(target: &T, policy: Policy) -> Result<()> {
+    match target.update(policy.clone()) {
+        Err(Error::PolicyNotFound(_)) => target.create(policy),
+        result => result,
+    }
+}
+
+pub struct RedisSubscriber {
+    client: Client,
+    channel: String,
+}
+
+impl RedisSubscriber {
+    pub fn open(url: &str, channel: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            client: Client::open(url)?,
+            channel: channel.into(),
+        })
+    }
+
+    pub fn listen(self, handler: impl Fn(PolicyUpdate) + Send + 'static) -> Result<RedisListener> {
+        let conn = self.client.get_connection()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            let mut conn = Some(conn);
+            while !stopped.load(Ordering::Acquire) {
+                let connected = match conn.take() {
+                    Some(current) => Ok(current),
+                    None => self.client.get_connection(),
+                };
+                let result = connected.and_then(|mut current| {
+                    let mut pubsub = current.as_pubsub();
+                    pubsub.subscribe(&self.channel)?;
+                    // Updates published while unsubscribed are lost, resync everything.
+                    handler(PolicyUpdate::default());
+                    pubsub.set_read_timeout(Some(Duration::from_millis(200)))?;
+                    while !stopped.load(Ordering::Acquire) {
+                        match pubsub.get_message() {
+                            Ok(message) => match message
+                                .get_payload::<String>()
+                                .map_err(Error::from)
+                                .and_then(|v| Ok(serde_json::from_str(&v)?))
+                            {
+                                Ok(update) => handler(update),
+                                Err(err) => tracing::warn!("ignore policy update: {}", err),
+                            },
+                            Err(err) if err.is_timeout() => {}
+                            Err(err) => return Err(err),
+                        }
+                    }
+                    Ok(())
+                });
+                if let Err(err) = result {
+                    tracing::warn!("subscription {} lost: {}", self.channel, err);
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        });
+        Ok(RedisListener { stop })
+    }
+}
+
+pub struct RedisListener {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for RedisListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+pub struct RedisDecisionCache {
+    conn: ConnectionManager,
+    prefix: String,
+    ttl: Duration,
+}
+
+impl RedisDecisionCache {
+    pub async fn connect(url: &str, prefix: &str, ttl: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: connect(url).await?,
+            prefix: prefix.to_owned(),
+            ttl,
+        })
+    }
+
+    pub async fn get(&self, input: &Request) -> Result<Option<Result<()>>> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(decision_key(&self.prefix, input)?)
+            .await?;
+        Ok(value.and_then(|v| decode_decision(&v)))
+    }
+
+    pub async fn put(&self, input: &Request, decision: &Result<()>) -> Result<()> {
+        let Some(value) = encode_decision(decision) else {
+            return Ok(());
+        };
+        let _: () = self
+            .conn
+            .clone()
+            .set_ex(
+                decision_key(&self.prefix, input)?,
+                value,
+                self.ttl.as_secs().max(1),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_allowed<E: AsyncEnforcer + Sync>(
+        &self,
+        enforcer: &E,
+        input: &Request,
+    ) -> Result<()> {
+        if let Some(decision) = self.get(input).await? {
+            return decision;
+        }
+        let decision = enforcer.is_allowed(input).await;
+        self.put(input, &decision).await?;
+        decision
+    }
+}
+
+fn decision_key(prefix: &str, input: &Request) -> Result<String> {
+    let digest = Sha256::digest(input.canonical()?);
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!("{prefix}:decision:{hex}"))
+}
+
+fn encode_decision(decision: &Result<()>) -> Option<String> {
+    match decision {
+        Ok(()) => Some("allow".to_owned()),
+        Err(Error::Deny { policy, statement }) => serde_json::to_string(&(policy, statement))
+            .ok()
+            .map(|v| format!("deny:{v}")),
+        Err(Error::NotMatched {
+            policy: None,
+            statement: None,
+            condition: None,
+        }) => Some("not_matched".to_owned()),
+        Err(Error::NotMatched {
+            policy,
+            statement,
+            condition,
+        }) => serde_json::to_string(&(policy, statement, condition))
+            .ok()
+            .map(|v| format!("not_matched:{v}")),
+        Err(_) => None,
+    }
+}
+
+fn decode_decision(value: &str) -> Option<Result<()>> {
+    match value {
+        "allow" => Some(Ok(())),
+        "not_matched" => Some(Err(Error::not_matched())),
+        _ => {
+            if let Some(miss) = value.strip_prefix("not_matched:") {
+                let (policy, statement, condition) = serde_json::from_str(miss).ok()?;
+                return Some(Err(Error::NotMatched {
+                    policy,
+                    statement,
+                    condition,
+                }));
+            }
+            let (policy, statement) = serde_json::from_str(value.strip_prefix("deny:")?).ok()?;
+            Some(Err(Error::Deny { policy, statement }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+
+    #[test]
+    fn decision() {
+        let mut input = Request {
+            resource: "articles:1".to_owned(),
+            action: "get".to_owned(),
+            subject: "peter".to_owned(),
+            context: Context::from([("a", 1), ("b", 2), ("c", 3)]),
+        };
+        let key = decision_key("ope", &input).unwrap();
+        assert_eq!(
+            key,
+            "ope:decision:8febc382c1185c74c627f97bd9df18b47e0773905a5baed2fdbb2e395d67f8bd"
+        );
+        assert_eq!(
+            key,
+            decision_key("ope", &input.clone()).unwrap(),
+            "context order must not change the key"
+        );
+        input.context.insert("c", 4);
+        assert_ne!(key, decision_key("ope", &input).unwrap());
+
+        for decision in [
+            Ok(()),
+            Err(Error::deny("p")),
+            Err(Error::Deny {
+                policy: "p".to_owned(),
+                statement: Some(1),
+            }),
+            Err(Error::not_matched()),
+            Err(Error::NotMatched {
+                policy: Some("p".to_owned()),
+                statement: Some(0),
+                condition: Some("ip".to_owned()),
+            }),
+        ] {
+            let encoded = encode_decision(&decision).unwrap();
+            assert_eq!(
+                format!("{:?}", decode_decision(&encoded).unwrap()),
+                format!("{decision:?}")
+            );
+        }
+        assert!(encode_decision(&Err(Error::MultipleApplicable(vec![]))).is_none());
+        assert!(decode_decision("garbage").is_none());
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        use crate::store::MemoryStore;
+
+        let document = |id: &str, subject: &str| -> Policy {
+            serde_json::from_str(&format!(
+                r#"{{"id": "{id}", "statements": [{{"effect": "Allow", "subjects": ["{subject}"], "actions": ["get"], "resources": ["articles:<.*>"]}}]}}"#
+            ))
+            .unwrap()
+        };
+        let source = MemoryStore::from(vec![document("a", "peter"), document("b", "max")]);
+        let target = MemoryStore::from(vec![document("a", "zac"), document("c", "ken")]);
+
+        let update: PolicyUpdate = serde_json::from_str(r#"{"ids": ["a", "c"]}"#).unwrap();
+        update.reload(&source, &target).await.unwrap();
+        assert_eq!(
+            PolicyStore::get(&target, "a").unwrap().statements[0].subjects,
+            vec!["peter"]
+        );
+        assert!(PolicyStore::get(&target, "c").is_err());
+        assert!(PolicyStore::get(&target, "b").is_err());
+
+        PolicyUpdate::default()
+            .reload(&source, &target)
+            .await
+            .unwrap();
+        let ids = PolicyStore::all(&target)
+            .unwrap()
+            .iter()
+            .map(|policy| policy.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        assert!(
+            PolicyStore::replace(&target, vec![document("c", "ken"), document("c", "zac")])
+                .is_err()
+        );
+        assert_eq!(PolicyStore::all(&target).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,397 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use parking_lot::{Condvar, Mutex};
+
+use super::file::{candidates, Snapshot};
+use super::{union, PolicyStore};
+use crate::policy::{BundleLoader, Format, Policy};
+use crate::req::Request;
+use crate::{Error, Result};
+
+const MAX_BODY: u64 = 64 << 20;
+
+type OnSuccess = Arc<dyn Fn(usize) + Send + Sync>;
+type OnFailure = Arc<dyn Fn(&Error) + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    policies: ArcSwap<Snapshot>,
+    revision: AtomicU64,
+    version: AtomicU64,
+    etag: Mutex<Option<String>>,
+}
+
+#[derive(Clone)]
+pub struct RemoteStore {
+    url: String,
+    agent: ureq::Agent,
+    public_key: Option<[u8; 32]>,
+    unsigned: bool,
+    interval: Duration,
+    max_backoff: Duration,
+    long_poll: Option<Duration>,
+    on_success: Option<OnSuccess>,
+    on_failure: Option<OnFailure>,
+    state: Arc<State>,
+}
+
+impl RemoteStore {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+            public_key: None,
+            unsigned: false,
+            interval: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(300),
+            long_poll: None,
+            on_success: None,
+            on_failure: None,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn with_public_key(mut self, public_key: [u8; 32]) -> Self {
+        self.public_key = Some(public_key);
+        self
+    }
+
+    pub fn with_unsigned(mut self) -> Self {
+        self.unsigned = true;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn with_long_poll(mut self, wait: Duration) -> Self {
+        self.agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(30))
+            .timeout_read(wait + Duration::from_secs(30))
+            .build();
+        self.long_poll = Some(wait);
+        self
+    }
+
+    pub fn on_success(mut self, callback: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        self.on_success = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn on_failure(mut self, callback: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.on_failure = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn etag(&self) -> Option<String> {
+        self.state.etag.lock().clone()
+    }
+
+    pub fn sync(&self) -> Result<bool> {
+        match self.fetch() {
+            Ok(Some(len)) => {
+                if let Some(callback) = &self.on_success {
+                    callback(len);
+                }
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(err) => {
+                if let Some(callback) = &self.on_failure {
+                    callback(&err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    pub fn spawn(&self) -> RemoteSync {
+        let store = self.clone();
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let signal = stop.clone();
+        thread::spawn(move || {
+            let mut failures = 0u32;
+            loop {
+                let delay = match store.sync() {
+                    Ok(_) => {
+                        failures = 0;
+                        match store.long_poll {
+                            Some(_) => Duration::ZERO,
+                            None => store.interval,
+                        }
+                    }
+                    Err(err) => {
+                        failures = failures.saturating_add(1);
+                        tracing::warn!(
+                            "keep previous policies, sync {} failed: {}",
+                            store.url,
+                            err
+                        );
+                        store.backoff(failures)
+                    }
+                };
+                let (stopped, condvar) = &*signal;
+                let mut stopped = stopped.lock();
+                if !*stopped {
+                    condvar.wait_for(&mut stopped, delay);
+                }
+                if *stopped {
+                    break;
+                }
+            }
+        });
+        RemoteSync { stop }
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        self.interval
+            .saturating_mul(2u32.saturating_pow(failures.min(16)))
+            .min(self.max_backoff)
+    }
+
+    fn fetch(&self) -> Result<Option<usize>> {
+        if self.public_key.is_none() && !self.unsigned {
+            return Err(Error::StoreError(format!(
+                "remote store {} has no public key, call with_unsigned to accept unsigned bundles",
+                self.url
+            )));
+        }
+        let mut request = self.agent.get(&self.url);
+        if let Some(etag) = self.etag() {
+            request = request.set("If-None-Match", &etag);
+        }
+        if let Some(wait) = self.long_poll {
+            request = request.set("Prefer", &format!("wait={}", wait.as_secs()));
+        }
+        let response = request
+            .call()
+            .map_err(|err| Error::StoreError(format!("fetch {} failed: {}", self.url, err)))?;
+        if response.status() == 304 {
+            return Ok(None);
+        }
+        let etag = response.header("ETag").map(str::to_owned);
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_BODY + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_BODY {
+            return Err(Error::StoreError(format!(
+                "bundle from {} exceeds {MAX_BODY} bytes",
+                self.url
+            )));
+        }
+
+        let (version, parsed) = match &self.public_key {
+            Some(key) => {
+                let bundle = BundleLoader::load_verified(
+                    &body,
+                    key,
+                    self.state.version.load(Ordering::Acquire),
+                )?;
+                (Some(bundle.version), bundle.policies)
+            }
+            None => (
+                None,
+                Policy::parse_all(
+                    std::str::from_utf8(&body)
+                        .map_err(|err| Error::StoreError(format!("invalid bundle body: {err}")))?,
+                    Format::Json,
+                )?,
+            ),
+        };
+        let mut policies = BTreeMap::new();
+        for policy in parsed {
+            if policies.contains_key(&policy.id) {
+                return Err(Error::PolicyAlreadyExists(policy.id));
+            }
+            policies.insert(policy.id.clone(), Arc::new(policy));
+        }
+        let len = policies.len();
+        self.state.policies.store(Arc::new(policies));
+        if let Some(version) = version {
+            self.state.version.store(version, Ordering::Release);
+        }
+        self.state.revision.fetch_add(1, Ordering::Release);
+        *self.state.etag.lock() = etag;
+        tracing::info!("loaded {} policies from {}", len, self.url);
+        Ok(Some(len))
+    }
+
+    fn read_only(&self) -> Error {
+        Error::StoreError(format!("remote store {} is read-only", self.url))
+    }
+}
+
+pub struct RemoteSync {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Drop for RemoteSync {
+    fn drop(&mut self) {
+        let (stopped, condvar) = &*self.stop;
+        *stopped.lock() = true;
+        condvar.notify_all();
+    }
+}
+
+impl PolicyStore for RemoteStore {
+    fn create(&self, _policy: Policy) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn update(&self, _policy: Policy) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Policy>> {
+        self.state
+            .policies
+            .load()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))
+    }
+
+    fn delete(&self, _id: &str) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn all(&self) -> Result<Vec<Arc<Policy>>> {
+        Ok(self.state.policies.load().values().cloned().collect())
+    }
+
+    fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        action: &str,
+    ) -> Result<Vec<Arc<Policy>>> {
+        Ok(candidates(
+            &self.state.policies.load(),
+            subject,
+            resource,
+            action,
+        ))
+    }
+
+    fn find_request_candidates(&self, requests: &[Cow<'_, Request>]) -> Result<Vec<Arc<Policy>>> {
+        let snapshot = self.state.policies.load();
+        Ok(union(
+            requests
+                .iter()
+                .map(|input| candidates(&snapshot, &input.subject, &input.resource, &input.action))
+                .collect(),
+        ))
+    }
+
+    fn revision(&self) -> u64 {
+        self.state.revision.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn document(id: &str) -> String {
+        format!(
+            r#"{{"id": "{id}", "statements": [{{"effect": "Allow", "subjects": ["peter"], "actions": ["get"], "resources": ["articles:<.*>"]}}]}}"#
+        )
+    }
+
+    #[test]
+    fn sync() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bundle", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut seen = Vec::new();
+            for (index, stream) in listener.incoming().take(4).enumerate() {
+                let mut stream = stream.unwrap();
+                let mut etag = None;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("if-none-match:") {
+                        etag = Some(value.trim().to_owned());
+                    }
+                    line.clear();
+                }
+                seen.push(etag.clone());
+                let response = match index {
+                    0 => {
+                        let body = format!("[{}, {}]", document("a"), document("b"));
+                        format!("HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len())
+                    }
+                    1 => "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_owned(),
+                    2 => "HTTP/1.1 200 OK\r\nContent-Length: 1\r\nConnection: close\r\n\r\n{"
+                        .to_owned(),
+                    _ => "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_owned(),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            seen
+        });
+
+        assert!(RemoteStore::new(url.clone()).sync().is_err());
+        let (loaded, failed) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let store = RemoteStore::new(url)
+            .with_unsigned()
+            .on_success({
+                let loaded = loaded.clone();
+                move |len| loaded.store(len, Ordering::SeqCst)
+            })
+            .on_failure({
+                let failed = failed.clone();
+                move |_| {
+                    failed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        assert!(store.sync().unwrap());
+        assert_eq!(store.all().unwrap().len(), 2);
+        assert_eq!(store.etag().as_deref(), Some("\"v1\""));
+        assert_eq!(loaded.load(Ordering::SeqCst), 2);
+        assert!(!store.sync().unwrap());
+        assert!(store.sync().is_err());
+        assert!(store.sync().is_err());
+        assert_eq!(failed.load(Ordering::SeqCst), 2);
+        assert_eq!(store.all().unwrap().len(), 2);
+        assert_eq!(store.revision(), 1);
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                None,
+                Some("\"v1\"".to_owned()),
+                Some("\"v1\"".to_owned()),
+                Some("\"v1\"".to_owned())
+            ]
+        );
+        assert_eq!(store.backoff(1), Duration::from_secs(60));
+        assert_eq!(store.backoff(10), Duration::from_secs(300));
+    }
+}
@@ -0,0 +1,382 @@
+use std::sync::Arc;
+
+use sqlx::any::{install_default_drivers, AnyPoolOptions};
+use sqlx::{Any, AnyPool, Row, Transaction};
+
+use super::AsyncPolicyStore;
+use crate::enforcer::options::WILDCARD;
+use crate::policy::Policy;
+use crate::statement::{Field, PLACEHOLDER};
+use crate::{Error, Result};
+
+const MIGRATIONS: &[(i64, &[&str])] = &[(
+    1,
+    &[
+        "CREATE TABLE IF NOT EXISTS ope_policy (id VARCHAR(255) NOT NULL PRIMARY KEY, document TEXT NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS ope_policy_subject (policy_id VARCHAR(255) NOT NULL, template TEXT NOT NULL, prefix TEXT NOT NULL, has_template INTEGER NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS ope_policy_resource (policy_id VARCHAR(255) NOT NULL, template TEXT NOT NULL, prefix TEXT NOT NULL, has_template INTEGER NOT NULL)",
+    ],
+)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    fn from_url(url: &str) -> Result<Self> {
+        if url.starts_with("postgres") {
+            return Ok(Dialect::Postgres);
+        }
+        if url.starts_with("mysql") || url.starts_with("mariadb") {
+            return Ok(Dialect::MySql);
+        }
+        if url.starts_with("sqlite") {
+            return Ok(Dialect::Sqlite);
+        }
+        Err(Error::StoreError(format!("unsupported database url {url}")))
+    }
+
+    fn param(self, i: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${i}"),
+            Dialect::MySql | Dialect::Sqlite => "?".to_owned(),
+        }
+    }
+
+    fn prefix_like(self, column: &str) -> String {
+        match self {
+            Dialect::MySql => format!("CONCAT({column}, '%')"),
+            Dialect::Postgres | Dialect::Sqlite => format!("{column} || '%'"),
+        }
+    }
+}
+
+pub struct SqlStore {
+    pool: AnyPool,
+    dialect: Dialect,
+}
+
+impl SqlStore {
+    pub async fn connect(url: &str) -> Result<Self> {
+        Self::connect_with(AnyPoolOptions::new(), url).await
+    }
+
+    pub async fn connect_with(options: AnyPoolOptions, url: &str) -> Result<Self> {
+        install_default_drivers();
+        let dialect = Dialect::from_url(url)?;
+        let pool = options.connect(url).await?;
+        Ok(Self { pool, dialect })
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ope_migration (version BIGINT NOT NULL PRIMARY KEY)",
+        )
+        .execute(&self.pool)
+        .await?;
+        let applied: Vec<i64> = sqlx::query("SELECT version FROM ope_migration")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<i64, _>(0))
+            .collect::<Result<_, _>>()?;
+        for (version, statements) in MIGRATIONS.iter() {
+            if applied.contains(version) {
+                continue;
+            }
+            let mut tx = self.pool.begin().await?;
+            for statement in statements.iter() {
+                sqlx::query(statement).execute(&mut *tx).await?;
+            }
+            sqlx::query(&format!(
+                "INSERT INTO ope_migration (version) VALUES ({})",
+                self.dialect.param(1)
+            ))
+            .bind(*version)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            tracing::info!("applied policy store migration {}", version);
+        }
+        Ok(())
+    }
+
+    async fn exists(&self, tx: &mut Transaction<'_, Any>, id: &str) -> Result<bool> {
+        let row = sqlx::query(&format!(
+            "SELECT COUNT(*) FROM ope_policy WHERE id = {}",
+            self.dialect.param(1)
+        ))
+        .bind(id.to_owned())
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(row.try_get::<i64, _>(0)? > 0)
+    }
+
+    async fn write_fields(&self, tx: &mut Transaction<'_, Any>, policy: &Policy) -> Result<()> {
+        for table in ["ope_policy_subject", "ope_policy_resource"] {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE policy_id = {}",
+                self.dialect.param(1)
+            ))
+            .bind(policy.id.clone())
+            .execute(&mut **tx)
+            .await?;
+        }
+        for statement in policy.statements.iter() {
+            let delimiter = statement.get_start_delimiter();
+            let fields = [
+                ("ope_policy_subject", Field::Subject, &statement.subjects),
+                ("ope_policy_resource", Field::Resource, &statement.resources),
+            ];
+            for (table, field, templates) in fields {
+                let custom = statement.matcher(field).is_some();
+                for template in templates.iter() {
+                    let has_template = custom
+                        || template.contains(delimiter)
+                        || template.contains(PLACEHOLDER)
+                        || template == WILDCARD;
+                    let prefix = match custom || template == WILDCARD {
+                        true => "",
+                        false => template
+                            .split(delimiter)
+                            .next()
+                            .and_then(|v| v.split(PLACEHOLDER).next())
+                            .unwrap_or_default(),
+                    };
+                    sqlx::query(&format!(
+                        "INSERT INTO {table} (policy_id, template, prefix, has_template) VALUES ({}, {}, {}, {})",
+                        self.dialect.param(1),
+                        self.dialect.param(2),
+                        self.dialect.param(3),
+                        self.dialect.param(4),
+                    ))
+                    .bind(policy.id.clone())
+                    .bind(template.clone())
+                    .bind(prefix.to_owned())
+                    .bind(i32::from(has_template))
+                    .execute(&mut **tx)
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn field_filter(&self, table: &str, first: usize) -> String {
+        format!(
+            "(NOT EXISTS (SELECT 1 FROM {table} e WHERE e.policy_id = p.id) OR EXISTS (SELECT 1 FROM {table} f WHERE f.policy_id = p.id AND ((f.has_template = 0 AND f.template = {}) OR (f.has_template = 1 AND {} LIKE {}))))",
+            self.dialect.param(first),
+            self.dialect.param(first + 1),
+            self.dialect.prefix_like("f.prefix"),
+        )
+    }
+}
+
+fn decode(document: &str) -> Result<Arc<Policy>> {
+    Ok(Arc::new(serde_json::from_str(document)?))
+}
+
+impl AsyncPolicyStore for SqlStore {
+    async fn create(&self, policy: Policy) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if self.exists(&mut tx, &policy.id).await? {
+            return Err(Error::PolicyAlreadyExists(policy.id));
+        }
+        sqlx::query(&format!(
+            "INSERT INTO ope_policy (id, document) VALUES ({}, {})",
+            self.dialect.param(1),
+            self.dialect.param(2)
+        ))
+        .bind(policy.id.clone())
+        .bind(serde_json::to_string(&policy)?)
+        .execute(&mut *tx)
+        .await?;
+        self.write_fields(&mut tx, &policy).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update(&self, policy: Policy) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if !self.exists(&mut tx, &policy.id).await? {
+            return Err(Error::PolicyNotFound(policy.id));
+        }
+        sqlx::query(&format!(
+            "UPDATE ope_policy SET document = {} WHERE id = {}",
+            self.dialect.param(1),
+            self.dialect.param(2)
+        ))
+        .bind(serde_json::to_string(&policy)?)
+        .bind(policy.id.clone())
+        .execute(&mut *tx)
+        .await?;
+        self.write_fields(&mut tx, &policy).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Arc<Policy>> {
+        let row = sqlx::query(&format!(
+            "SELECT document FROM ope_policy WHERE id = {}",
+            self.dialect.param(1)
+        ))
+        .bind(id.to_owned())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| Error::PolicyNotFound(id.to_owned()))?;
+        decode(&row.try_get::<String, _>(0)?)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for table in ["ope_policy_subject", "ope_policy_resource"] {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE policy_id = {}",
+                self.dialect.param(1)
+            ))
+            .bind(id.to_owned())
+            .execute(&mut *tx)
+            .await?;
+        }
+        let result = sqlx::query(&format!(
+            "DELETE FROM ope_policy WHERE id = {}",
+            self.dialect.param(1)
+        ))
+        .bind(id.to_owned())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::PolicyNotFound(id.to_owned()));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn all(&self) -> Result<Vec<Arc<Policy>>> {
+        sqlx::query("SELECT document FROM ope_policy ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| decode(&row.try_get::<String, _>(0)?))
+            .collect()
+    }
+
+    async fn find_candidates(
+        &self,
+        subject: &str,
+        resource: &str,
+        _action: &str,
+    ) -> Result<Vec<Arc<Policy>>> {
+        let sql = format!(
+            "SELECT p.document FROM ope_policy p WHERE {} AND {} ORDER BY p.id",
+            self.field_filter("ope_policy_subject", 1),
+            self.field_filter("ope_policy_resource", 3),
+        );
+        sqlx::query(&sql)
+            .bind(subject.to_owned())
+            .bind(subject.to_owned())
+            .bind(resource.to_owned())
+            .bind(resource.to_owned())
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| decode(&row.try_get::<String, _>(0)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Effect, Statement};
+
+    fn policy(id: &str, subjects: &[&str], resources: &[&str]) -> Policy {
+        Policy {
+            id: id.to_owned(),
+            description: String::new(),
+            statements: vec![Statement {
+                effect: Effect::Allow,
+                subjects: subjects.iter().map(|v| v.to_string()).collect(),
+                actions: vec!["get".to_owned()],
+                resources: resources.iter().map(|v| v.to_string()).collect(),
+                conditions: None,
+                meta: None,
+                obligations: None,
+                not_subjects: Vec::new(),
+                not_actions: Vec::new(),
+                not_resources: Vec::new(),
+                subject_matcher: None,
+                action_matcher: None,
+                resource_matcher: None,
+                compiled: Default::default(),
+            }],
+            meta: None,
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite() {
+        let path = std::env::temp_dir().join(format!("ope-sql-{}.db", std::process::id()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let store = SqlStore::connect(&url).await.unwrap();
+        store.migrate().await.unwrap();
+        store.migrate().await.unwrap();
+
+        store
+            .create(policy("a", &["peter"], &["articles:<[0-9]+>"]))
+            .await
+            .unwrap();
+        store
+            .create(policy("b", &["users:<.*>"], &["files:1"]))
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.create(policy("a", &["max"], &["x"])).await,
+            Err(Error::PolicyAlreadyExists(_))
+        ));
+
+        let ids = |v: Vec<Arc<Policy>>| v.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store
+                .find_candidates("peter", "articles:9", "get")
+                .await
+                .unwrap()),
+            vec!["a"]
+        );
+        assert_eq!(
+            ids(store
+                .find_candidates("users:ken", "files:1", "get")
+                .await
+                .unwrap()),
+            vec!["b"]
+        );
+        assert!(store
+            .find_candidates("max", "files:1", "get")
+            .await
+            .unwrap()
+            .is_empty());
+
+        store
+            .update(policy("a", &["max"], &["files:1"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            ids(store
+                .find_candidates("max", "files:1", "get")
+                .await
+                .unwrap()),
+            vec!["a"]
+        );
+        assert_eq!(store.all().await.unwrap().len(), 2);
+        store.delete("a").await.unwrap();
+        assert!(matches!(
+            store.get("a").await,
+            Err(Error::PolicyNotFound(_))
+        ));
+        let _ = std::fs::remove_file(path);
+    }
+}
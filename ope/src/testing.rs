@@ -0,0 +1,192 @@
+use serde::Deserialize;
+
+use crate::context::Context;
+use crate::enforcer::Enforcer;
+use crate::matcher::Matcher;
+use crate::policy::Format;
+use crate::req::Request;
+use crate::store::PolicyStore;
+use crate::{Error, Result};
+
+#[macro_export]
+macro_rules! assert_allowed {
+    ($enforcer:expr, $subject:expr, $action:expr, $resource:expr $(, $key:expr => $value:expr)* $(,)?) => {{
+        let input = $crate::__request!($subject, $action, $resource $(, $key => $value)*);
+        if let Err(err) = $enforcer.is_allowed(&input) {
+            panic!("expected {:?} to be allowed, got: {}", input, err);
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! assert_denied {
+    ($enforcer:expr, $subject:expr, $action:expr, $resource:expr $(, $key:expr => $value:expr)* $(,)?) => {{
+        let input = $crate::__request!($subject, $action, $resource $(, $key => $value)*);
+        match $enforcer.is_allowed(&input) {
+            Err($crate::Error::Deny { .. }) => {}
+            Ok(()) => panic!("expected {:?} to be denied, got allowed", input),
+            Err(err) => panic!("expected {:?} to be denied, got: {}", input, err),
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! assert_not_matched {
+    ($enforcer:expr, $subject:expr, $action:expr, $resource:expr $(, $key:expr => $value:expr)* $(,)?) => {{
+        let input = $crate::__request!($subject, $action, $resource $(, $key => $value)*);
+        match $enforcer.is_allowed(&input) {
+            Err($crate::Error::NotMatched { .. }) => {}
+            Ok(()) => panic!("expected {:?} to match no policy, got allowed", input),
+            Err(err) => panic!("expected {:?} to match no policy, got: {}", input, err),
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __request {
+    ($subject:expr, $action:expr, $resource:expr $(, $key:expr => $value:expr)*) => {{
+        #[allow(unused_mut)]
+        let mut context = $crate::Context::new();
+        $(context.insert($key, $value);)*
+        $crate::Request {
+            resource: ::std::string::ToString::to_string(&$resource),
+            action: ::std::string::ToString::to_string(&$action),
+            subject: ::std::string::ToString::to_string(&$subject),
+            context,
+        }
+    }};
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Expect {
+    Allow,
+    Deny,
+    NotMatched,
+}
+
+impl Expect {
+    fn accepts(self, decision: &Result<()>) -> bool {
+        matches!(
+            (self, decision),
+            (Expect::Allow, Ok(()))
+                | (
+                    Expect::Deny,
+                    Err(Error::Deny { .. } | Error::NotMatched { .. })
+                )
+                | (Expect::NotMatched, Err(Error::NotMatched { .. }))
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FixtureCase {
+    #[serde(default)]
+    pub name: String,
+    pub subject: String,
+    pub action: String,
+    pub resource: String,
+    #[serde(default)]
+    pub context: Context,
+    pub expect: Expect,
+}
+
+impl FixtureCase {
+    pub fn request(&self) -> Request {
+        Request {
+            resource: self.resource.clone(),
+            action: self.action.clone(),
+            subject: self.subject.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Fixture {
+    pub cases: Vec<FixtureCase>,
+}
+
+#[derive(Debug)]
+pub struct CaseOutcome<'a> {
+    pub case: &'a FixtureCase,
+    pub decision: Result<()>,
+    pub passed: bool,
+}
+
+impl Fixture {
+    pub fn parse(content: &str, format: Format) -> Result<Self> {
+        Ok(serde_json::from_str(&format.to_json(content)?)?)
+    }
+
+    pub fn run<M: Matcher, S: PolicyStore>(
+        &self,
+        enforcer: &Enforcer<M, S>,
+    ) -> Vec<CaseOutcome<'_>> {
+        self.cases
+            .iter()
+            .map(|case| {
+                let decision = enforcer.is_allowed(&case.request());
+                CaseOutcome {
+                    case,
+                    passed: case.expect.accepts(&decision),
+                    decision,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PolicyBuilder, Regexp};
+
+    fn enforcer() -> Enforcer<Regexp> {
+        let policy = PolicyBuilder::new("articles")
+            .allow()
+            .subject("<peter|ken>")
+            .action("get")
+            .resource("articles:<[0-9]+>")
+            .condition(
+                "clientIP",
+                "CIDR",
+                serde_json::json!({"cidr": ["10.0.0.0/8"]}),
+            )
+            .deny()
+            .subject("ken")
+            .action("get")
+            .resource("articles:0")
+            .build()
+            .unwrap();
+        Enforcer::new(Regexp::new(16).unwrap(), vec![policy])
+    }
+
+    #[test]
+    fn macros() {
+        let enforcer = enforcer();
+        crate::assert_allowed!(enforcer, "peter", "get", "articles:1", "clientIP" => "10.1.2.3");
+        crate::assert_not_matched!(enforcer, "peter", "get", "articles:1");
+        crate::assert_not_matched!(enforcer, "peter", "get", "articles:1", "clientIP" => "192.168.0.1");
+        crate::assert_denied!(enforcer, "ken", "get", "articles:0");
+        crate::assert_not_matched!(enforcer, "max", "get", "articles:1");
+    }
+
+    #[test]
+    fn fixture() {
+        let fixture = Fixture::parse(
+            r#"{"cases": [
+                {"name": "peter reads", "subject": "peter", "action": "get", "resource": "articles:1", "context": {"clientIP": "10.1.2.3"}, "expect": "allow"},
+                {"subject": "ken", "action": "get", "resource": "articles:0", "expect": "deny"},
+                {"subject": "max", "action": "get", "resource": "articles:1", "expect": "not_matched"},
+                {"subject": "peter", "action": "get", "resource": "articles:1", "context": {"clientIP": "1.1.1.1"}, "expect": "allow"}
+            ]}"#,
+            Format::Json,
+        )
+        .unwrap();
+        let enforcer = enforcer();
+        let passed: Vec<_> = fixture.run(&enforcer).iter().map(|v| v.passed).collect();
+        assert_eq!(passed, vec![true, true, true, false]);
+    }
+}
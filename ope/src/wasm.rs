@@ -0,0 +1,68 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Enforcer, Error, Policy, PolicyStore, Regexp, Request};
+
+#[wasm_bindgen]
+pub struct WasmEnforcer {
+    inner: Enforcer<Regexp>,
+}
+
+#[wasm_bindgen]
+impl WasmEnforcer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(policies: &str) -> Result<WasmEnforcer, JsError> {
+        let enforcer = Enforcer::new(Regexp::new(256)?, Vec::new());
+        let wasm = WasmEnforcer { inner: enforcer };
+        wasm.load_policies(policies)?;
+        Ok(wasm)
+    }
+
+    #[wasm_bindgen(js_name = loadPolicies)]
+    pub fn load_policies(&self, policies: &str) -> Result<(), JsError> {
+        let policies: Vec<Policy> = serde_json::from_str(policies)?;
+        self.inner.store().replace(policies)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isAllowed)]
+    pub fn is_allowed(&self, request: &str) -> Result<bool, JsError> {
+        let request: Request = serde_json::from_str(request)?;
+        match self.inner.is_allowed(&request) {
+            Ok(()) => Ok(true),
+            Err(Error::Deny { .. } | Error::NotMatched { .. } | Error::MultipleApplicable(_)) => {
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_enforcer() {
+        let enforcer = WasmEnforcer::new(
+            r#"[{"id": "readers", "statements": [{"effect": "Allow", "subjects": ["peter"],
+                "actions": ["get"], "resources": ["articles:<[0-9]+>"], "conditions": null,
+                "meta": null}], "meta": null}]"#,
+        )
+        .unwrap();
+        let request = |resource: &str| {
+            format!(
+                r#"{{"subject": "peter", "action": "get", "resource": "{resource}", "context": {{}}}}"#
+            )
+        };
+        assert!(enforcer.is_allowed(&request("articles:1")).unwrap());
+        assert!(!enforcer.is_allowed(&request("articles:x")).unwrap());
+        let duplicated: Vec<Policy> = serde_json::from_str(
+            r#"[{"id": "a", "statements": []}, {"id": "a", "statements": []}]"#,
+        )
+        .unwrap();
+        assert!(enforcer.inner.store().replace(duplicated).is_err());
+        assert!(enforcer.is_allowed(&request("articles:1")).unwrap());
+        enforcer.load_policies("[]").unwrap();
+        assert!(!enforcer.is_allowed(&request("articles:1")).unwrap());
+    }
+}